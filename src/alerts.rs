@@ -0,0 +1,198 @@
+use std::process::Command;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicU32, Ordering};
+use std::time::{Duration, Instant};
+
+use serde_json::Value;
+
+use crate::config::{AlertNotify, AlertRule, Condition, Threshold};
+use crate::exec;
+use crate::script_context::ScriptContext;
+
+#[derive(Default)]
+struct AlertState {
+    /// When the conditions first started holding in the current streak.
+    pending_since: Option<Instant>,
+    firing: bool,
+    last_fired: Option<Instant>,
+    /// Id of the alert's notification on screen, replaced by its next one
+    /// instead of stacking up; 0 for none.
+    notification: Arc<AtomicU32>,
+}
+
+/// Evaluates `[[alerts]]` rules against each refresh's `ScriptContext`.
+pub struct AlertEngine {
+    states: Vec<AlertState>,
+}
+
+impl AlertEngine {
+    pub fn new(rules: &[AlertRule]) -> Self {
+        for rule in rules {
+            if rule.notify.is_none() && rule.action.is_none() {
+                eprintln!(
+                    "rustky: alert '{}' has neither notify nor action",
+                    rule.name
+                );
+            }
+        }
+        Self {
+            states: rules.iter().map(|_| AlertState::default()).collect(),
+        }
+    }
+
+    pub fn evaluate(&mut self, rules: &[AlertRule], ctx: &ScriptContext) {
+        let metrics = match serde_json::to_value(ctx) {
+            Ok(v) => v,
+            Err(_) => return,
+        };
+        let now = Instant::now();
+
+        for (rule, state) in rules.iter().zip(self.states.iter_mut()) {
+            // While firing, conditions are relaxed by the hysteresis margin so
+            // a value hovering around the threshold doesn't flap.
+            let margin = if state.firing { rule.hysteresis } else { 0.0 };
+            let conditions = rule.conditions();
+            let holds = !conditions.is_empty()
+                && conditions
+                    .iter()
+                    .all(|c| condition_holds(c, &metrics, margin).unwrap_or(false));
+
+            if !holds {
+                state.pending_since = None;
+                state.firing = false;
+                continue;
+            }
+
+            let since = *state.pending_since.get_or_insert(now);
+            if state.firing || now.duration_since(since) < Duration::from_secs(rule.for_secs) {
+                continue;
+            }
+
+            state.firing = true;
+            let cooled_down = state
+                .last_fired
+                .is_none_or(|t| now.duration_since(t) >= Duration::from_secs(rule.cooldown_secs));
+            if cooled_down {
+                state.last_fired = Some(now);
+                fire(rule, &conditions, &metrics, &state.notification);
+            }
+        }
+    }
+}
+
+/// Looks up a numeric metric by name; dotted paths index into arrays and
+/// maps (e.g. `disks.0.available_bytes`).
+pub fn metric_value(metrics: &Value, name: &str) -> Option<f64> {
+    let mut v = metrics;
+    for part in name.split('.') {
+        v = match part.parse::<usize>() {
+            Ok(i) => v.get(i)?,
+            Err(_) => v.get(part)?,
+        };
+    }
+    v.as_f64()
+}
+
+fn threshold_value(t: &Threshold, metrics: &Value) -> Option<f64> {
+    match t {
+        Threshold::Value(v) => Some(*v),
+        Threshold::Metric(name) => metric_value(metrics, name),
+    }
+}
+
+fn condition_holds(c: &Condition, metrics: &Value, margin: f64) -> Option<bool> {
+    let value = metric_value(metrics, &c.metric)?;
+    if let Some(ref above) = c.above
+        && value <= threshold_value(above, metrics)? - margin
+    {
+        return Some(false);
+    }
+    if let Some(ref below) = c.below
+        && value >= threshold_value(below, metrics)? + margin
+    {
+        return Some(false);
+    }
+    Some(c.above.is_some() || c.below.is_some())
+}
+
+fn fire(
+    rule: &AlertRule,
+    conditions: &[Condition],
+    metrics: &Value,
+    notification: &Arc<AtomicU32>,
+) {
+    let metric = conditions.first().map(|c| c.metric.as_str()).unwrap_or("");
+    let value = metric_value(metrics, metric)
+        .map(|v| format!("{v:.1}"))
+        .unwrap_or_default();
+    eprintln!("rustky: alert '{}' fired", rule.name);
+    if let Some(notify) = &rule.notify {
+        let fill = |text: &str| {
+            text.replace("{name}", &rule.name)
+                .replace("{metric}", metric)
+                .replace("{value}", &value)
+        };
+        let summary = fill(&notify.summary);
+        let body = fill(&notify.body);
+        show_notification(&rule.name, notify, summary, body, notification.clone());
+    }
+    if let Some(action) = &rule.action {
+        let env = [("RUSTKY_ALERT", rule.name.clone()), ("RUSTKY_VALUE", value)];
+        if let Err(e) = exec::spawn(action, &env) {
+            eprintln!("rustky: alert '{}' action failed: {e}", rule.name);
+        }
+    }
+}
+
+/// Sends a notification over org.freedesktop.Notifications, off the
+/// render path since the call waits for the notification daemon.
+fn show_notification(
+    name: &str,
+    notify: &AlertNotify,
+    summary: String,
+    body: String,
+    id: Arc<AtomicU32>,
+) {
+    let name = name.to_string();
+    let icon = notify.icon.clone();
+    let urgency = (notify.urgency as u8).to_string();
+    std::thread::spawn(move || {
+        let replaces = id.load(Ordering::Relaxed).to_string();
+        let output = Command::new("busctl")
+            .args([
+                "--user",
+                "call",
+                "org.freedesktop.Notifications",
+                "/org/freedesktop/Notifications",
+                "org.freedesktop.Notifications",
+                "Notify",
+                "susssasa{sv}i",
+                "rustky",
+                &replaces,
+                &icon,
+                &summary,
+                &body,
+                "0",
+                "1",
+                "urgency",
+                "y",
+                &urgency,
+                "-1",
+            ])
+            .output();
+        match output {
+            // The reply is "u <id>"
+            Ok(output) if output.status.success() => {
+                let reply = String::from_utf8_lossy(&output.stdout);
+                if let Some(new) = reply.split_whitespace().nth(1).and_then(|s| s.parse().ok()) {
+                    id.store(new, Ordering::Relaxed);
+                }
+            }
+            Ok(output) => eprintln!(
+                "rustky: alert '{name}' notification: {}",
+                String::from_utf8_lossy(&output.stderr).trim()
+            ),
+            Err(e) => eprintln!("rustky: alert '{name}' notification: busctl: {e}"),
+        }
+    });
+}
@@ -0,0 +1,109 @@
+use std::time::{Duration, Instant};
+
+use crate::config::General;
+use crate::render::Renderer;
+use crate::styled::{Element, StyledLine};
+
+/// Eases bar, graph and gauge values from what is on screen to each
+/// refresh's over `animation_ms`, and optionally fades in lines that
+/// weren't there before.
+pub struct Animator {
+    duration: Duration,
+    fade_in: bool,
+    /// Lines on screen when `target` arrived.
+    from: Vec<StyledLine>,
+    /// Lines of the last redraw, as the modules produced them.
+    target: Vec<StyledLine>,
+    start: Instant,
+}
+
+impl Animator {
+    pub fn new(general: &General) -> Self {
+        Self {
+            duration: Duration::from_millis(general.animation_ms),
+            fade_in: general.fade_in,
+            from: Vec::new(),
+            target: Vec::new(),
+            start: Instant::now(),
+        }
+    }
+
+    /// Takes a redraw's lines and returns the ones to draw now: partway
+    /// there when they differ from the last redraw's.
+    pub fn update(&mut self, lines: Vec<StyledLine>, renderer: &Renderer) -> Vec<StyledLine> {
+        if self.duration.is_zero() {
+            return lines;
+        }
+        if lines != self.target {
+            let now = Instant::now();
+            self.from = self.lines(now, renderer);
+            self.target = lines;
+            self.start = now;
+        }
+        self.lines(Instant::now(), renderer)
+    }
+
+    pub fn is_active(&self) -> bool {
+        self.start.elapsed() < self.duration
+    }
+
+    /// The lines as they stand at `now`.
+    pub fn lines(&self, now: Instant, renderer: &Renderer) -> Vec<StyledLine> {
+        let elapsed = now.duration_since(self.start).as_secs_f32();
+        let progress = (elapsed / self.duration.as_secs_f32()).min(1.0);
+        if progress >= 1.0 {
+            return self.target.clone();
+        }
+        // Ease out: quick at first, settling onto the new value
+        let t = 1.0 - (1.0 - progress).powi(3);
+        // Lines only pair up by position when none came or went, as on a
+        // plain refresh
+        let paired = self.from.len() == self.target.len();
+        self.target
+            .iter()
+            .enumerate()
+            .map(|(i, line)| {
+                let mut line = line.clone();
+                if paired {
+                    ease(&self.from[i].element, &mut line.element, t);
+                } else if self.fade_in && !self.from.iter().any(|old| old.text == line.text) {
+                    renderer.fade(&mut line, t);
+                }
+                line
+            })
+            .collect()
+    }
+}
+
+/// Moves `to`'s values back towards `from`'s, to `t` of the way between.
+fn ease(from: &Element, to: &mut Element, t: f32) {
+    let lerp = |a: f32, b: f32| a + (b - a) * t;
+    match (from, to) {
+        (Element::Bar(from), Element::Bar(to)) => to.fraction = lerp(from.fraction, to.fraction),
+        (Element::Gauges(from), Element::Gauges(to)) if from.len() == to.len() => {
+            for (from, to) in from.iter().zip(to) {
+                to.fraction = lerp(from.fraction, to.fraction);
+            }
+        }
+        (Element::Graph(from), Element::Graph(to)) => {
+            ease_newest(&from.samples, &mut to.samples, t)
+        }
+        (Element::Sparkline(from), Element::Sparkline(to)) => {
+            ease_newest(&from.samples, &mut to.samples, t)
+        }
+        (Element::Heatmap(from), Element::Heatmap(to)) if from.values.len() == to.values.len() => {
+            for (from, to) in from.values.iter().zip(&mut to.values) {
+                *to = lerp(*from, *to);
+            }
+        }
+        _ => {}
+    }
+}
+
+/// Eases the newest sample of a history from the newest on screen; the
+/// older ones have scrolled in already.
+fn ease_newest(from: &[Option<f64>], to: &mut [Option<f64>], t: f32) {
+    if let (Some(Some(from)), Some(Some(to))) = (from.last(), to.last_mut()) {
+        *to = *from + (*to - *from) * f64::from(t);
+    }
+}
@@ -0,0 +1,63 @@
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+/// Runs a slow fetch (network, package managers, D-Bus tools) on its own
+/// thread every `interval`, so drawing only ever reads the latest result.
+pub struct Poller<T> {
+    latest: Arc<Mutex<Option<Result<T, String>>>>,
+}
+
+impl<T: Clone + Send + 'static> Poller<T> {
+    pub fn spawn<F>(interval: Duration, fetch: F) -> Self
+    where
+        F: Fn() -> Result<T, String> + Send + 'static,
+    {
+        let latest = Arc::new(Mutex::new(None));
+        let shared = Arc::clone(&latest);
+        std::thread::spawn(move || {
+            loop {
+                let result = fetch();
+                *shared.lock().unwrap_or_else(|e| e.into_inner()) = Some(result);
+                std::thread::sleep(interval);
+            }
+        });
+        Self { latest }
+    }
+
+    /// Like `spawn`, but once a fetch has succeeded, failures keep the last
+    /// good result (logging the error under `name`) instead of replacing it.
+    pub fn spawn_cached<F>(interval: Duration, name: &'static str, fetch: F) -> Self
+    where
+        F: Fn() -> Result<T, String> + Send + 'static,
+    {
+        let last: Mutex<Option<T>> = Mutex::new(None);
+        Self::spawn(interval, move || {
+            let mut last = last.lock().unwrap_or_else(|e| e.into_inner());
+            match (fetch(), &*last) {
+                (Ok(value), _) => {
+                    *last = Some(value.clone());
+                    Ok(value)
+                }
+                (Err(e), Some(value)) => {
+                    eprintln!("rustky: {name}: {e}");
+                    Ok(value.clone())
+                }
+                (Err(e), None) => Err(e),
+            }
+        })
+    }
+
+    /// Replaces the latest result ahead of the next fetch, e.g. right after
+    /// the user changed the underlying state.
+    pub fn publish(&self, result: Result<T, String>) {
+        *self.latest.lock().unwrap_or_else(|e| e.into_inner()) = Some(result);
+    }
+
+    /// The most recent result, or `None` before the first fetch finishes.
+    pub fn latest(&self) -> Option<Result<T, String>> {
+        self.latest
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .clone()
+    }
+}
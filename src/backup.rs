@@ -0,0 +1,102 @@
+use std::collections::HashMap;
+use std::path::Path;
+use std::sync::{Mutex, OnceLock};
+use std::time::{Duration, UNIX_EPOCH};
+
+use serde_json::Value;
+
+use crate::background::Poller;
+use crate::{config, countdown, exec};
+
+type Registry = Mutex<HashMap<(Option<String>, Option<String>), Poller<i64>>>;
+
+static POLLERS: OnceLock<Registry> = OnceLock::new();
+
+/// Unix time of the most recent backup, taken from `path` or `command`
+/// (exactly one must be set) every `interval`.
+pub fn last_backup(
+    path: Option<&str>,
+    command: Option<&str>,
+    interval: Duration,
+) -> Option<Result<i64, String>> {
+    let mut pollers = POLLERS
+        .get_or_init(Default::default)
+        .lock()
+        .unwrap_or_else(|e| e.into_inner());
+    pollers
+        .entry((path.map(String::from), command.map(String::from)))
+        .or_insert_with(|| {
+            let (path, command) = (path.map(String::from), command.map(String::from));
+            Poller::spawn_cached(interval, "backup", move || {
+                match (path.as_deref(), command.as_deref()) {
+                    (Some(path), None) => newest_mtime(Path::new(&config::shellexpand(path))),
+                    (None, Some(command)) => probe(command),
+                    _ => Err("set either path or command".into()),
+                }
+            })
+        })
+        .latest()
+}
+
+/// Newest mtime of `path` and, for a directory, its direct entries: a
+/// stamp file, a restic repository (`snapshots/` changes per snapshot) or a
+/// borg repository (a new `index.N` per archive).
+fn newest_mtime(path: &Path) -> Result<i64, String> {
+    let mtime = |meta: std::fs::Metadata| {
+        meta.modified()
+            .ok()?
+            .duration_since(UNIX_EPOCH)
+            .ok()
+            .map(|d| d.as_secs() as i64)
+    };
+    let meta = std::fs::metadata(path).map_err(|e| format!("{}: {e}", path.display()))?;
+    let is_dir = meta.is_dir();
+    let mut newest = mtime(meta).unwrap_or_default();
+    if is_dir {
+        let entries = std::fs::read_dir(path).map_err(|e| format!("{}: {e}", path.display()))?;
+        for entry in entries.flatten() {
+            if let Some(t) = entry.metadata().ok().and_then(mtime) {
+                newest = newest.max(t);
+            }
+        }
+    }
+    Ok(newest)
+}
+
+/// Runs `command` through the exec gate. JSON output (`restic snapshots
+/// --json`, `borg list --json`) yields the newest "time" field anywhere in
+/// it; other output is read as a single timestamp or Unix time.
+fn probe(command: &str) -> Result<i64, String> {
+    let output = exec::run(command, &[])?;
+    if !output.status.success() {
+        return Err(format!("command exited with {}", output.status));
+    }
+    let text = String::from_utf8_lossy(&output.stdout);
+    let text = text.trim();
+    if let Ok(json) = serde_json::from_str::<Value>(text)
+        && !json.is_number()
+    {
+        return newest_time(&json).ok_or_else(|| "no \"time\" in output".into());
+    }
+    let line = text.lines().last().unwrap_or_default().trim();
+    if let Ok(secs) = line.parse::<i64>() {
+        return Ok(secs);
+    }
+    countdown::parse_datetime(line).map(|t| t.timestamp())
+}
+
+fn newest_time(value: &Value) -> Option<i64> {
+    match value {
+        Value::Array(items) => items.iter().filter_map(newest_time).max(),
+        Value::Object(map) => map
+            .iter()
+            .filter_map(|(key, value)| match value.as_str() {
+                Some(s) if key == "time" => {
+                    countdown::parse_datetime(s).ok().map(|t| t.timestamp())
+                }
+                _ => newest_time(value),
+            })
+            .max(),
+        _ => None,
+    }
+}
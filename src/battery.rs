@@ -0,0 +1,106 @@
+use std::fs;
+use std::path::Path;
+
+use serde::{Deserialize, Serialize};
+
+const POWER_SUPPLY: &str = "/sys/class/power_supply";
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BatteryInfo {
+    /// Power supply name, e.g. "BAT0".
+    pub name: String,
+    pub percent: f64,
+    /// Kernel status string: "Charging", "Discharging", "Full",
+    /// "Not charging" or "Unknown".
+    pub status: String,
+    pub time_to_empty_secs: Option<u64>,
+    pub time_to_full_secs: Option<u64>,
+    /// Current charge/discharge rate.
+    pub power_w: Option<f64>,
+}
+
+/// Reads every system battery from sysfs. Peripheral batteries (mice,
+/// headsets) report `scope=Device` and are skipped.
+pub fn read_all() -> Vec<BatteryInfo> {
+    let Ok(entries) = fs::read_dir(POWER_SUPPLY) else {
+        return Vec::new();
+    };
+    let mut batteries: Vec<BatteryInfo> = entries
+        .flatten()
+        .filter_map(|e| read_battery(&e.path()))
+        .collect();
+    batteries.sort_by(|a, b| a.name.cmp(&b.name));
+    batteries
+}
+
+fn read_battery(dir: &Path) -> Option<BatteryInfo> {
+    if read_str(&dir.join("type"))? != "Battery" {
+        return None;
+    }
+    if read_str(&dir.join("scope")).is_some_and(|s| s == "Device") {
+        return None;
+    }
+    let name = dir.file_name()?.to_string_lossy().into_owned();
+    let status = read_str(&dir.join("status")).unwrap_or_else(|| "Unknown".into());
+
+    // Drivers report either energy (µWh, power in µW) or charge (µAh,
+    // current in µA); the ratios work out the same.
+    let (now, full, rate) = match read_u64(&dir.join("energy_now")) {
+        Some(now) => (
+            Some(now),
+            read_u64(&dir.join("energy_full")),
+            read_u64(&dir.join("power_now")),
+        ),
+        None => (
+            read_u64(&dir.join("charge_now")),
+            read_u64(&dir.join("charge_full")),
+            read_u64(&dir.join("current_now")),
+        ),
+    };
+
+    let percent = match read_u64(&dir.join("capacity")) {
+        Some(capacity) => capacity as f64,
+        None => match (now, full) {
+            (Some(now), Some(full)) if full > 0 => now as f64 / full as f64 * 100.0,
+            _ => return None,
+        },
+    };
+
+    let secs_at_rate = |amount: u64| match rate {
+        Some(rate) if rate > 0 => Some((amount as f64 / rate as f64 * 3600.0) as u64),
+        _ => None,
+    };
+    let (time_to_empty_secs, time_to_full_secs) = match status.as_str() {
+        "Discharging" => (now.and_then(secs_at_rate), None),
+        "Charging" => (
+            None,
+            now.zip(full)
+                .and_then(|(now, full)| secs_at_rate(full.saturating_sub(now))),
+        ),
+        _ => (None, None),
+    };
+
+    let power_w = match read_u64(&dir.join("power_now")) {
+        Some(uw) => Some(uw as f64 / 1_000_000.0),
+        None => read_u64(&dir.join("current_now"))
+            .zip(read_u64(&dir.join("voltage_now")))
+            .map(|(ua, uv)| ua as f64 * uv as f64 / 1e12),
+    };
+
+    Some(BatteryInfo {
+        name,
+        percent,
+        status,
+        time_to_empty_secs,
+        time_to_full_secs,
+        power_w,
+    })
+}
+
+fn read_str(path: &Path) -> Option<String> {
+    Some(fs::read_to_string(path).ok()?.trim().to_string())
+}
+
+fn read_u64(path: &Path) -> Option<u64> {
+    read_str(path)?.parse().ok()
+}
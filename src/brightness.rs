@@ -0,0 +1,121 @@
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use std::sync::{Mutex, OnceLock};
+
+const BACKLIGHT: &str = "/sys/class/backlight";
+
+/// Scroll steps not yet applied, per device. A device with an entry has a
+/// thread draining it.
+static PENDING: OnceLock<Mutex<HashMap<String, f64>>> = OnceLock::new();
+
+#[derive(Debug, Clone)]
+pub struct Backlight {
+    /// Device name under /sys/class/backlight, e.g. "intel_backlight".
+    pub device: String,
+    pub brightness: u64,
+    pub max: u64,
+}
+
+impl Backlight {
+    pub fn percent(&self) -> f64 {
+        if self.max == 0 {
+            return 0.0;
+        }
+        self.brightness as f64 / self.max as f64 * 100.0
+    }
+}
+
+/// Reads `device`, or the first backlight device when `None`.
+pub fn read(device: Option<&str>) -> Option<Backlight> {
+    let dir = match device {
+        Some(device) => Path::new(BACKLIGHT).join(device),
+        None => first_device()?,
+    };
+    // actual_brightness is what the hardware reports; brightness is the
+    // last requested value.
+    let brightness =
+        read_u64(&dir.join("actual_brightness")).or_else(|| read_u64(&dir.join("brightness")))?;
+    Some(Backlight {
+        device: dir.file_name()?.to_string_lossy().into_owned(),
+        brightness,
+        max: read_u64(&dir.join("max_brightness"))?,
+    })
+}
+
+/// Changes the brightness by `delta_pct` percent of the maximum in the
+/// background. Steps arriving while a change is under way are added up and
+/// applied together.
+pub fn adjust(device: &str, delta_pct: f64) {
+    let pending = PENDING.get_or_init(Default::default);
+    let mut steps = pending.lock().unwrap_or_else(|e| e.into_inner());
+    if let Some(sum) = steps.get_mut(device) {
+        *sum += delta_pct;
+        return;
+    }
+    steps.insert(device.to_string(), delta_pct);
+    let device = device.to_string();
+    std::thread::spawn(move || {
+        loop {
+            let delta = {
+                let mut steps = pending.lock().unwrap_or_else(|e| e.into_inner());
+                match steps.get_mut(&device) {
+                    Some(sum) if *sum != 0.0 => std::mem::take(sum),
+                    _ => {
+                        steps.remove(&device);
+                        return;
+                    }
+                }
+            };
+            if let Err(e) = apply(&device, delta) {
+                eprintln!("rustky: {e}");
+            }
+        }
+    });
+}
+
+/// Sets the new level through logind so no write access to sysfs is needed.
+fn apply(device: &str, delta_pct: f64) -> Result<(), String> {
+    let current = read(Some(device)).ok_or_else(|| format!("backlight {device}: not found"))?;
+    let step = current.max as f64 * delta_pct / 100.0;
+    // Never go fully dark from a scroll; 1 is the lowest visible level.
+    let target = (current.brightness as f64 + step)
+        .round()
+        .clamp(1.0, current.max as f64) as u64;
+    let output = Command::new("busctl")
+        .args([
+            "call",
+            "org.freedesktop.login1",
+            "/org/freedesktop/login1/session/auto",
+            "org.freedesktop.login1.Session",
+            "SetBrightness",
+            "ssu",
+            "backlight",
+            device,
+            &target.to_string(),
+        ])
+        .output()
+        .map_err(|e| format!("busctl: {e}"))?;
+    if !output.status.success() {
+        return Err(format!(
+            "SetBrightness: {}",
+            String::from_utf8_lossy(&output.stderr).trim()
+        ));
+    }
+    Ok(())
+}
+
+fn first_device() -> Option<PathBuf> {
+    let mut dirs: Vec<PathBuf> = fs::read_dir(BACKLIGHT)
+        .ok()?
+        .flatten()
+        .map(|e| e.path())
+        .collect();
+    dirs.sort();
+    dirs.into_iter().next()
+}
+
+fn read_u64(path: &Path) -> Option<u64> {
+    fs::read_to_string(path).ok()?.trim().parse().ok()
+}
@@ -0,0 +1,209 @@
+use std::collections::HashMap;
+use std::sync::{Mutex, OnceLock};
+use std::time::Duration;
+
+use chrono::{Local, NaiveDate, NaiveDateTime, TimeZone, Utc};
+
+use crate::background::Poller;
+use crate::config;
+use crate::http;
+use crate::recurrence::{self, Rule};
+
+/// A VEVENT as parsed, before recurrence expansion. Times are local.
+#[derive(Debug, Clone)]
+pub struct EventSpec {
+    pub summary: String,
+    pub start: NaiveDateTime,
+    pub duration: chrono::Duration,
+    pub all_day: bool,
+    pub rule: Option<Rule>,
+    pub exdates: Vec<NaiveDateTime>,
+    uid: String,
+    /// Set on a modified instance of a series; that instance is then
+    /// dropped from the series' own expansion.
+    recurrence_id: Option<NaiveDateTime>,
+}
+
+#[derive(Debug, Clone)]
+pub struct Event {
+    pub summary: String,
+    pub start: NaiveDateTime,
+    pub end: NaiveDateTime,
+    pub all_day: bool,
+}
+
+type Registry = Mutex<HashMap<String, Poller<Vec<EventSpec>>>>;
+
+static POLLERS: OnceLock<Registry> = OnceLock::new();
+
+/// Events of the calendar at `source` (a path or http(s) URL), refetched
+/// every `interval` in the background.
+pub fn events(source: &str, interval: Duration) -> Option<Result<Vec<EventSpec>, String>> {
+    let mut pollers = POLLERS
+        .get_or_init(Default::default)
+        .lock()
+        .unwrap_or_else(|e| e.into_inner());
+    pollers
+        .entry(source.to_string())
+        .or_insert_with(|| {
+            let source = source.to_string();
+            Poller::spawn_cached(interval, "calendar", move || {
+                let text = if source.starts_with("http://") || source.starts_with("https://") {
+                    http::get(&source)?
+                } else {
+                    let path = config::shellexpand(&source);
+                    std::fs::read_to_string(&path).map_err(|e| format!("{path}: {e}"))?
+                };
+                Ok(parse(&text))
+            })
+        })
+        .latest()
+}
+
+/// Occurrences that haven't ended yet and start before `now + horizon`,
+/// soonest first.
+pub fn upcoming(specs: &[EventSpec], now: NaiveDateTime, horizon: chrono::Duration) -> Vec<Event> {
+    let to = now + horizon;
+    let mut events: Vec<Event> = specs
+        .iter()
+        .flat_map(|spec| {
+            // Widen the window so an occurrence already in progress counts.
+            let from = now - spec.duration;
+            let starts = match &spec.rule {
+                Some(rule) => recurrence::occurrences(spec.start, rule, &spec.exdates, from, to),
+                None if spec.start >= from && spec.start < to => vec![spec.start],
+                None => Vec::new(),
+            };
+            starts.into_iter().map(|start| Event {
+                summary: spec.summary.clone(),
+                start,
+                end: start + spec.duration,
+                all_day: spec.all_day,
+            })
+        })
+        .filter(|e| e.end > now)
+        .collect();
+    events.sort_by_key(|e| e.start);
+    events
+}
+
+/// Extracts VEVENTs. Events whose RRULE uses unsupported parts are kept as
+/// single events; TZID times are taken as local time.
+pub fn parse(text: &str) -> Vec<EventSpec> {
+    let mut specs = parse_events(text);
+    let overrides: Vec<(String, NaiveDateTime)> = specs
+        .iter()
+        .filter_map(|s| Some((s.uid.clone(), s.recurrence_id?)))
+        .collect();
+    for (uid, at) in overrides {
+        for series in specs
+            .iter_mut()
+            .filter(|s| s.uid == uid && s.rule.is_some())
+        {
+            series.exdates.push(at);
+        }
+    }
+    specs
+}
+
+fn parse_events(text: &str) -> Vec<EventSpec> {
+    let mut specs = Vec::new();
+    let mut current: Option<Vec<(String, String, String)>> = None;
+    for line in unfold(text) {
+        let Some((head, value)) = line.split_once(':') else {
+            continue;
+        };
+        let (name, params) = head.split_once(';').unwrap_or((head, ""));
+        match (name, value) {
+            ("BEGIN", "VEVENT") => current = Some(Vec::new()),
+            ("END", "VEVENT") => {
+                if let Some(spec) = current.take().and_then(|props| build(&props)) {
+                    specs.push(spec);
+                }
+            }
+            _ => {
+                if let Some(props) = current.as_mut() {
+                    props.push((name.to_string(), params.to_string(), value.to_string()));
+                }
+            }
+        }
+    }
+    specs
+}
+
+/// Joins continuation lines (starting with a space or tab) to the line
+/// before.
+fn unfold(text: &str) -> Vec<String> {
+    let mut lines: Vec<String> = Vec::new();
+    for line in text.lines() {
+        let line = line.trim_end_matches('\r');
+        match (line.strip_prefix([' ', '\t']), lines.last_mut()) {
+            (Some(rest), Some(last)) => last.push_str(rest),
+            _ => lines.push(line.to_string()),
+        }
+    }
+    lines
+}
+
+fn build(props: &[(String, String, String)]) -> Option<EventSpec> {
+    let prop = |name: &str| props.iter().find(|(n, _, _)| n == name);
+    // Cancelled instances of a series still show up as VEVENTs.
+    if prop("STATUS").is_some_and(|(_, _, v)| v == "CANCELLED") {
+        return None;
+    }
+    let (_, params, value) = prop("DTSTART")?;
+    let (start, all_day) = parse_time(params, value)?;
+    let end = prop("DTEND")
+        .and_then(|(_, p, v)| parse_time(p, v))
+        .map(|(t, _)| t);
+    let duration = match end {
+        Some(end) if end > start => end - start,
+        _ if all_day => chrono::Duration::days(1),
+        _ => chrono::Duration::zero(),
+    };
+    let exdates = props
+        .iter()
+        .filter(|(n, _, _)| n == "EXDATE")
+        .flat_map(|(_, p, v)| v.split(',').filter_map(|d| Some(parse_time(p, d)?.0)))
+        .collect();
+    Some(EventSpec {
+        summary: prop("SUMMARY")
+            .map(|(_, _, v)| unescape(v))
+            .unwrap_or_default(),
+        start,
+        duration,
+        all_day,
+        rule: prop("RRULE").and_then(|(_, _, v)| recurrence::parse(v)),
+        exdates,
+        uid: prop("UID").map(|(_, _, v)| v.clone()).unwrap_or_default(),
+        recurrence_id: prop("RECURRENCE-ID").and_then(|(_, p, v)| Some(parse_time(p, v)?.0)),
+    })
+}
+
+/// DATE or DATE-TIME value as local time, and whether it was a DATE.
+fn parse_time(params: &str, value: &str) -> Option<(NaiveDateTime, bool)> {
+    if params.contains("VALUE=DATE") && !params.contains("VALUE=DATE-TIME") || value.len() == 8 {
+        let date = NaiveDate::parse_from_str(value, "%Y%m%d").ok()?;
+        return Some((date.and_hms_opt(0, 0, 0)?, true));
+    }
+    match value.strip_suffix('Z') {
+        Some(utc) => {
+            let naive = NaiveDateTime::parse_from_str(utc, "%Y%m%dT%H%M%S").ok()?;
+            let local = Utc.from_utc_datetime(&naive).with_timezone(&Local);
+            Some((local.naive_local(), false))
+        }
+        None => Some((
+            NaiveDateTime::parse_from_str(value, "%Y%m%dT%H%M%S").ok()?,
+            false,
+        )),
+    }
+}
+
+fn unescape(value: &str) -> String {
+    value
+        .replace("\\n", " ")
+        .replace("\\N", " ")
+        .replace("\\,", ",")
+        .replace("\\;", ";")
+        .replace("\\\\", "\\")
+}
@@ -0,0 +1,108 @@
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
+
+const CGROUP_ROOT: &str = "/sys/fs/cgroup";
+/// Redraws between refreshes reuse the previous rates instead of computing
+/// them over a few milliseconds.
+const MIN_INTERVAL: Duration = Duration::from_millis(250);
+
+/// Cumulative counters of one cgroup at one point in time.
+#[derive(Clone, Copy)]
+struct Sample {
+    at: Instant,
+    cpu_usec: u64,
+    io_read: u64,
+    io_write: u64,
+}
+
+#[derive(Debug, Clone, Copy, Default)]
+pub struct CgroupUsage {
+    /// Percent of one CPU, so a busy 4-thread service can exceed 100.
+    pub cpu_pct: Option<f64>,
+    pub memory: Option<u64>,
+    pub io_read_per_sec: Option<f64>,
+    pub io_write_per_sec: Option<f64>,
+}
+
+/// Reads cgroup v2 controllers and turns cumulative counters into rates
+/// across calls.
+#[derive(Default)]
+pub struct CgroupReader {
+    prev: HashMap<PathBuf, (Sample, CgroupUsage)>,
+}
+
+impl CgroupReader {
+    /// `path` is relative to `/sys/fs/cgroup` (e.g. "user.slice") unless it
+    /// already starts with it.
+    pub fn read(&mut self, path: &str) -> Result<CgroupUsage, String> {
+        let dir = if Path::new(path).starts_with(CGROUP_ROOT) {
+            PathBuf::from(path)
+        } else {
+            Path::new(CGROUP_ROOT).join(path.trim_start_matches('/'))
+        };
+        if !dir.is_dir() {
+            return Err("not found".into());
+        }
+
+        let memory = read_u64(&dir.join("memory.current"));
+        let (io_read, io_write) = read_io(&dir.join("io.stat"));
+        let sample = Sample {
+            at: Instant::now(),
+            cpu_usec: read_cpu_usec(&dir.join("cpu.stat")).unwrap_or(0),
+            io_read,
+            io_write,
+        };
+
+        let usage = match self.prev.get(&dir) {
+            Some((prev, usage)) if sample.at.duration_since(prev.at) < MIN_INTERVAL => {
+                return Ok(CgroupUsage { memory, ..*usage });
+            }
+            Some((prev, _)) => {
+                let secs = sample.at.duration_since(prev.at).as_secs_f64();
+                let rate = |now: u64, before: u64| Some(now.saturating_sub(before) as f64 / secs);
+                CgroupUsage {
+                    cpu_pct: rate(sample.cpu_usec, prev.cpu_usec).map(|us| us / 10_000.0),
+                    memory,
+                    io_read_per_sec: rate(sample.io_read, prev.io_read),
+                    io_write_per_sec: rate(sample.io_write, prev.io_write),
+                }
+            }
+            None => CgroupUsage {
+                memory,
+                ..Default::default()
+            },
+        };
+        self.prev.insert(dir, (sample, usage));
+        Ok(usage)
+    }
+}
+
+fn read_u64(path: &Path) -> Option<u64> {
+    fs::read_to_string(path).ok()?.trim().parse().ok()
+}
+
+fn read_cpu_usec(path: &Path) -> Option<u64> {
+    fs::read_to_string(path)
+        .ok()?
+        .lines()
+        .find_map(|l| l.strip_prefix("usage_usec "))
+        .and_then(|v| v.trim().parse().ok())
+}
+
+/// Sums `rbytes=` and `wbytes=` over every device in `io.stat`.
+fn read_io(path: &Path) -> (u64, u64) {
+    let Ok(content) = fs::read_to_string(path) else {
+        return (0, 0);
+    };
+    let mut totals = (0, 0);
+    for field in content.split_whitespace() {
+        if let Some(v) = field.strip_prefix("rbytes=") {
+            totals.0 += v.parse::<u64>().unwrap_or(0);
+        } else if let Some(v) = field.strip_prefix("wbytes=") {
+            totals.1 += v.parse::<u64>().unwrap_or(0);
+        }
+    }
+    totals
+}
@@ -0,0 +1,103 @@
+use std::fs::File;
+use std::io::Read;
+use std::os::fd::{FromRawFd, OwnedFd};
+use std::sync::Mutex;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+
+/// Password managers (KeePassXC, KDE Wallet, …) tag secrets with this type;
+/// such selections are never read.
+const SENSITIVE_MIME: &str = "x-kde-passwordManagerHint";
+const TEXT_MIMES: &[&str] = &[
+    "text/plain;charset=utf-8",
+    "UTF8_STRING",
+    "text/plain",
+    "STRING",
+    "TEXT",
+];
+/// Only this much of a selection is read; the preview is trimmed anyway.
+const MAX_BYTES: u64 = 64 * 1024;
+
+#[derive(Debug, Clone, Default)]
+pub enum Selection {
+    #[default]
+    Empty,
+    /// Marked as secret by its source; contents were not read.
+    Sensitive,
+    Text {
+        text: String,
+        mimes: Vec<String>,
+    },
+    /// No text representation, e.g. an image.
+    Other {
+        mime: String,
+    },
+}
+
+/// Current selection, written by the data-control device in wayland.rs.
+static SELECTION: Mutex<Selection> = Mutex::new(Selection::Empty);
+/// Bumped on every selection change so a slow read can't overwrite a newer
+/// selection.
+static GENERATION: AtomicU64 = AtomicU64::new(0);
+static REVEALED: AtomicBool = AtomicBool::new(false);
+
+pub fn current() -> Selection {
+    SELECTION.lock().unwrap_or_else(|e| e.into_inner()).clone()
+}
+
+pub fn set(selection: Selection) {
+    GENERATION.fetch_add(1, Ordering::Relaxed);
+    *SELECTION.lock().unwrap_or_else(|e| e.into_inner()) = selection;
+}
+
+/// Whether private previews are currently shown in full.
+pub fn revealed() -> bool {
+    REVEALED.load(Ordering::Relaxed)
+}
+
+pub fn toggle_revealed() {
+    REVEALED.fetch_xor(true, Ordering::Relaxed);
+}
+
+pub fn is_sensitive(mimes: &[String]) -> bool {
+    mimes.iter().any(|m| m == SENSITIVE_MIME)
+}
+
+/// The preferred text type among those offered.
+pub fn text_mime(mimes: &[String]) -> Option<&str> {
+    TEXT_MIMES
+        .iter()
+        .find(|t| mimes.iter().any(|m| m == *t))
+        .copied()
+}
+
+/// Creates the pipe a selection is received through: `(read, write)`.
+pub fn pipe() -> std::io::Result<(OwnedFd, OwnedFd)> {
+    let mut fds = [0; 2];
+    // SAFETY: fds is a valid two-element array for pipe2 to fill.
+    if unsafe { libc::pipe2(fds.as_mut_ptr(), libc::O_CLOEXEC) } != 0 {
+        return Err(std::io::Error::last_os_error());
+    }
+    // SAFETY: pipe2 succeeded, so both fds are open and owned by us.
+    Ok(unsafe { (OwnedFd::from_raw_fd(fds[0]), OwnedFd::from_raw_fd(fds[1])) })
+}
+
+/// Reads the selection's text from `read` on a thread, since the source
+/// client writes it at its own pace, then publishes it.
+pub fn read_text(read: OwnedFd, mimes: Vec<String>) {
+    let generation = GENERATION.fetch_add(1, Ordering::Relaxed) + 1;
+    std::thread::spawn(move || {
+        let mut bytes = Vec::new();
+        if File::from(read)
+            .take(MAX_BYTES)
+            .read_to_end(&mut bytes)
+            .is_err()
+        {
+            return;
+        }
+        let text = String::from_utf8_lossy(&bytes).into_owned();
+        let mut selection = SELECTION.lock().unwrap_or_else(|e| e.into_inner());
+        if GENERATION.load(Ordering::Relaxed) == generation {
+            *selection = Selection::Text { text, mimes };
+        }
+    });
+}
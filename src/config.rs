@@ -1,25 +1,82 @@
 use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
 use std::path::PathBuf;
+use strum::IntoStaticStr;
 
-use crate::styled::LineStyle;
+use crate::hwmon::SensorKind;
+use crate::pressure::Resource;
+use crate::styled::{Align, Clicks, LineStyle, Overflow};
 
 #[derive(Debug, Serialize, Deserialize)]
 #[serde(default)]
 pub struct Config {
     pub general: General,
-    pub window: Window,
-    pub modules: Vec<Module>,
+    pub window: Windows,
+    pub exec: ExecPolicy,
+    pub gpu: GpuConfig,
+    pub modules: Vec<ModuleEntry>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub pages: Vec<Page>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub alerts: Vec<AlertRule>,
+    /// Icon glyph drawn before the first line of each module of a type,
+    /// e.g. `cpu = "\u{f4bc}"`.
+    #[serde(skip_serializing_if = "HashMap::is_empty")]
+    pub icons: HashMap<String, String>,
+    pub theme: Theme,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
 #[serde(default)]
 pub struct General {
     pub update_interval_ms: u64,
+    /// Family looked up through fontconfig, or a font file path.
     pub font: String,
     pub font_size: f32,
+    /// Row height as a multiple of the font size.
+    pub line_height_factor: f32,
+    /// Extra space after every character, in pixels.
+    pub letter_spacing: f32,
+    /// Distance between tab stops, in pixels from the start of the text.
+    pub tab_width: f32,
     pub fg_color: String,
     pub bg_color: String,
+    /// Prefixes for byte sizes and rates.
+    pub byte_units: ByteUnits,
+    /// Decimal places of formatted sizes, rates and the like.
+    pub precision: usize,
     pub scripts_dir: Option<String>,
+    /// Opt-in switch for everything that spawns shell commands (exec
+    /// modules, alert actions, click commands).
+    pub allow_exec: bool,
+    /// Multiplies font sizes, paddings and the window size. Overridden by
+    /// `--scale` / `RUSTKY_SCALE` and adjustable via `rustky msg scale`.
+    pub scale: f32,
+    /// Replace theme colors with a white-on-black, black-on-yellow palette.
+    pub high_contrast: bool,
+    /// Font file for `icon` glyphs, e.g. a Nerd Font; glyphs it lacks come
+    /// from the main font.
+    pub icon_font: Option<String>,
+    /// Fonts (families or files) tried in order for glyphs the main font
+    /// lacks, such as emoji or CJK, before asking fontconfig.
+    pub fallback_fonts: Vec<String>,
+    /// How frames reach the compositor.
+    pub render_backend: RenderBackend,
+    /// Time bar, graph and gauge values take to ease to a refresh's; 0
+    /// jumps straight there.
+    pub animation_ms: u64,
+    /// Frames per second drawn while values ease.
+    pub animation_fps: u32,
+    /// Fade in lines that appear, such as a group's when it expands.
+    pub fade_in: bool,
+    /// Multiplies the distance wheels and touchpads scroll.
+    pub scroll_speed: f32,
+    /// Ease each wheel notch over a few frames instead of jumping.
+    pub smooth_scroll: bool,
+    /// Seconds without input after which drawing pauses until the next
+    /// input, while alerts keep firing; 0 never pauses. Needs
+    /// ext-idle-notify-v1.
+    pub idle_pause_s: u64,
     #[cfg(feature = "rhai-scripting")]
     pub on_draw_rhai: Option<String>,
     #[cfg(feature = "python-scripting")]
@@ -36,28 +93,495 @@ pub struct Window {
     pub transparent: bool,
     pub always_on_top: bool,
     pub decoration: bool,
+    /// Accept keyboard focus on demand, for `/` search and key bindings.
+    pub keyboard: bool,
+    /// Holding this modifier while dragging with the left button moves the
+    /// widget. Needs keyboard focus, which is then requested on demand.
+    pub drag_modifier: Option<DragModifier>,
+    /// Restore the last dragged-to position from the state file instead of
+    /// `x`/`y`.
+    pub remember_position: bool,
+    /// Output to open on, by connector name ("DP-2") or part of its
+    /// description; default: the compositor's choice.
+    pub output: Option<String>,
+    /// Screen corner, edge or centre the window sits at; `x` and `y` are
+    /// measured from the edges it touches.
+    pub anchor: WindowAnchor,
+    /// Margins from each edge, in place of `x` and `y`. Only those of the
+    /// edges anchored to take effect.
+    pub margin: Option<Margins>,
+    /// Let pointer input fall through to the windows beneath, for a purely
+    /// decorative overlay. Also disables scrolling, clicks and dragging.
+    pub click_through: bool,
+    /// Stack lines top to bottom, or lay them out left to right as a bar.
+    pub orientation: Orientation,
+    /// Reserve the window's height, or width on a side edge, so tiling
+    /// compositors keep other windows clear of it. Needs an edge `anchor`.
+    pub exclusive: bool,
+    /// Slide the window off its anchored edge while the pointer is away.
+    pub auto_hide: Option<AutoHide>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct AutoHide {
+    /// Pixels left on screen to hover; 0 hides the window completely.
+    pub peek: u32,
+    /// Time after the pointer leaves before sliding out.
+    pub hide_delay_ms: u64,
+    /// Time the pointer rests on the edge before sliding in.
+    pub reveal_delay_ms: u64,
+    /// Duration of the slide; 0 jumps.
+    pub slide_ms: u64,
+}
+
+impl Default for AutoHide {
+    fn default() -> Self {
+        Self {
+            peek: 2,
+            hide_delay_ms: 1000,
+            reveal_delay_ms: 200,
+            slide_ms: 150,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Orientation {
+    #[default]
+    Vertical,
+    /// One row, modules grouped left, centre and right by their `align`;
+    /// with `width = 0` it spans the output.
+    Horizontal,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum WindowAnchor {
+    TopLeft,
+    Top,
+    #[default]
+    TopRight,
+    Left,
+    Center,
+    Right,
+    BottomLeft,
+    Bottom,
+    BottomRight,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Margins {
+    pub top: i32,
+    pub right: i32,
+    pub bottom: i32,
+    pub left: i32,
+}
+
+/// A single `[window]` table, or a `[[window]]` array of layer surfaces
+/// managed by one process.
+#[derive(Debug, Serialize)]
+#[serde(untagged)]
+pub enum Windows {
+    One(Window),
+    Many(Vec<WindowEntry>),
+}
+
+/// Picks the shape by hand, so mistakes inside either are reported as such
+/// rather than as matching neither.
+impl<'de> Deserialize<'de> for Windows {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        use serde::de::Error;
+        match toml::Value::deserialize(deserializer)? {
+            value @ toml::Value::Array(_) => value.try_into().map(Windows::Many),
+            value => value.try_into().map(Windows::One),
+        }
+        .map_err(D::Error::custom)
+    }
+}
+
+impl Default for Windows {
+    fn default() -> Self {
+        Windows::One(Window::default())
+    }
+}
+
+/// One `[[window]]` entry: window settings and, optionally, modules of its
+/// own in place of the top-level `modules` and `pages`.
+#[derive(Debug, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct WindowEntry {
+    #[serde(flatten)]
+    pub window: Window,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub modules: Vec<ModuleEntry>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DragModifier {
+    Alt,
+    Ctrl,
+    Shift,
+    Super,
+}
+
+/// Containment applied to every spawned command.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ExecPolicy {
+    /// Scheduling niceness for child processes (0 = inherit).
+    pub nice: i32,
+    /// cgroup `MemoryMax=` for a transient systemd scope, e.g. "128M".
+    pub memory_max: Option<String>,
+    /// cgroup `CPUQuota=` for a transient systemd scope, e.g. "20%".
+    pub cpu_quota: Option<String>,
+    /// Clear the environment except for a few session variables.
+    pub minimal_env: bool,
+}
+
+/// Named colors (`primary`, `ok`, `warn`, `critical`, `muted`, `fg`, `bg`
+/// and any others) usable wherever a color is.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Theme {
+    /// Built-in palette to start from; default "default".
+    pub name: Option<String>,
+    /// TOML file of `name = "#rrggbb"` pairs layered over the built-in
+    /// palette.
+    pub file: Option<String>,
+    /// Layered over both.
+    #[serde(skip_serializing_if = "HashMap::is_empty")]
+    pub colors: HashMap<String, String>,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct GpuConfig {
+    pub backend: GpuBackend,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RenderBackend {
+    /// Frames copied into wl_shm buffers.
+    #[default]
+    Shm,
+    /// Frames uploaded to and presented by the GPU (Vulkan or GLES through
+    /// wgpu); needs the `gpu` feature.
+    Gpu,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum GpuBackend {
+    /// NVML for NVIDIA cards (when built with `nvml`), sysfs for the rest.
+    #[default]
+    Auto,
+    Nvml,
+    Amdgpu,
+    /// i915 and xe.
+    Intel,
+}
+
+/// A `modules` list entry: the module, and what clicking its lines does
+/// where they don't say themselves.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ModuleEntry {
+    #[serde(flatten)]
+    pub module: Module,
+    #[serde(flatten)]
+    pub clicks: Clicks,
+    /// Group on a horizontal bar: left (default), center or right.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub align: Option<Align>,
+}
+
+impl From<Module> for ModuleEntry {
+    fn from(module: Module) -> Self {
+        Self {
+            module,
+            clicks: Clicks::default(),
+            align: None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, IntoStaticStr)]
 #[serde(tag = "type", rename_all = "snake_case")]
+#[strum(serialize_all = "snake_case")]
 pub enum Module {
     Cpu {
         #[serde(default = "default_label")]
         label: String,
         #[serde(default)]
         show_per_core: bool,
+        /// Draw usage as a bar after the text.
+        #[serde(default)]
+        bar: Option<BarStyle>,
+        /// Styles applied past limits, e.g. `[{ above = 80, fg_color = "critical" }]`.
+        #[serde(default)]
+        thresholds: Vec<ThresholdStyle>,
+    },
+    /// Per-core usage as a grid of cells, `columns` to a row, each colored
+    /// along `stops`.
+    CpuHeatmap {
+        #[serde(default = "default_label")]
+        label: String,
+        #[serde(default = "default_heatmap_columns")]
+        columns: usize,
+        #[serde(default = "default_heatmap_cell_size")]
+        cell_size: f32,
+        /// Colors blend between stops by percentage.
+        #[serde(default = "default_gauge_stops")]
+        stops: Vec<ColorStop>,
+    },
+    /// Core clock min/avg/max and the scaling governor.
+    CpuFreq {
+        #[serde(default = "default_label_cpu_freq")]
+        label: String,
+        #[serde(default)]
+        show_per_core: bool,
+    },
+    /// Pressure stall information (avg10/avg60) from /proc/pressure.
+    Pressure {
+        /// Default: cpu, memory and io.
+        #[serde(default)]
+        resources: Vec<Resource>,
+        /// Add the "full" stall figures next to "some".
+        #[serde(default)]
+        show_full: bool,
+        /// avg10 percentages at which a line turns orange/red.
+        #[serde(default = "default_pressure_warn")]
+        warn: f64,
+        #[serde(default = "default_pressure_critical")]
+        critical: f64,
     },
     Memory {
         #[serde(default = "default_label_mem")]
         label: String,
+        #[serde(default)]
+        display: DisplayMode,
+        /// Draw the used share as a bar after the text.
+        #[serde(default)]
+        bar: Option<BarStyle>,
+        /// Styles applied past limits, e.g. `[{ above = 80, fg_color = "critical" }]`.
+        #[serde(default)]
+        thresholds: Vec<ThresholdStyle>,
     },
     Disk {
         #[serde(default = "default_mount")]
         mount_point: String,
+        #[serde(default)]
+        display: DisplayMode,
+        /// Draw the used share as a bar after the text.
+        #[serde(default)]
+        bar: Option<BarStyle>,
+        /// Styles applied past limits, e.g. `[{ above = 80, fg_color = "critical" }]`.
+        #[serde(default)]
+        thresholds: Vec<ThresholdStyle>,
     },
+    /// Scrolling line chart of a metric, one sample per refresh.
+    Graph {
+        metric: GraphMetric,
+        /// Interface for `net_rx` / `net_tx`.
+        #[serde(default = "default_iface")]
+        interface: String,
+        #[serde(default)]
+        label: Option<String>,
+        /// Samples across the width, up to 600.
+        #[serde(default = "default_graph_samples")]
+        samples: usize,
+        #[serde(default = "default_graph_height")]
+        height: f32,
+        #[serde(default = "default_graph_color")]
+        color: String,
+        /// Top of the scale; default 100 for percentages, the largest
+        /// sample shown for rates.
+        #[serde(default)]
+        max: Option<f64>,
+    },
+    /// Radial dials, `columns` to a row.
+    Gauge {
+        metrics: Vec<GaugeMetric>,
+        #[serde(default = "default_gauge_radius")]
+        radius: f32,
+        #[serde(default = "default_gauge_thickness")]
+        thickness: f32,
+        /// Each stop's color applies from its `at` percentage upward.
+        #[serde(default = "default_gauge_stops")]
+        stops: Vec<ColorStop>,
+        #[serde(default = "default_gauge_columns")]
+        columns: usize,
+    },
+    /// A PNG, JPEG, GIF, WebP or SVG file, redrawn when it changes. With
+    /// only one of `width`/`height` the other keeps the aspect ratio;
+    /// with neither the image's own size is used.
+    Image {
+        path: String,
+        #[serde(default)]
+        width: Option<f32>,
+        #[serde(default)]
+        height: Option<f32>,
+    },
+    /// Receive/transmit rates of one interface.
     Network {
         #[serde(default = "default_iface")]
         interface: String,
+        #[serde(default)]
+        unit: RateUnit,
+        /// Append the highest rates of the last minute or so.
+        #[serde(default)]
+        show_peak: bool,
+    },
+    /// SSID, signal, band and bitrate of a wireless interface (via `iw`).
+    Wifi {
+        /// Default: the first interface in /proc/net/wireless.
+        #[serde(default)]
+        interface: Option<String>,
+    },
+    /// Next events from an iCalendar file or URL, with relative times.
+    Calendar {
+        ics_path_or_url: String,
+        /// How far ahead to look.
+        #[serde(default = "default_horizon_hours")]
+        horizon_hours: u64,
+        #[serde(default = "default_max_events")]
+        max_events: usize,
+        #[serde(default = "default_calendar_interval")]
+        interval_secs: u64,
+    },
+    /// Crypto or stock prices with the 24h change, green/red.
+    Ticker {
+        /// CoinGecko coin ids ("bitcoin") or Yahoo tickers ("AAPL").
+        symbols: Vec<String>,
+        #[serde(default)]
+        provider: TickerProvider,
+        /// Quote currency for CoinGecko; Yahoo quotes in the listing's own.
+        #[serde(default = "default_ticker_currency")]
+        currency: String,
+        #[serde(default = "default_ticker_interval")]
+        interval_secs: u64,
+    },
+    /// Current conditions and a daily forecast from Open-Meteo.
+    Weather {
+        latitude: f64,
+        longitude: f64,
+        #[serde(default)]
+        units: Units,
+        #[serde(default = "default_weather_interval")]
+        interval_secs: u64,
+        /// Days of forecast below the current conditions; 0 hides it.
+        #[serde(default = "default_forecast_days")]
+        forecast_days: usize,
+    },
+    /// Polls a REST endpoint and formats fields of its JSON response.
+    Http {
+        url: String,
+        #[serde(default = "default_http_interval")]
+        interval_ms: u64,
+        /// Dot path or JSONPath selecting the part of the response that
+        /// `format` paths are relative to.
+        #[serde(default)]
+        jsonpath: Option<String>,
+        /// `{path}` placeholders, `{}` for the selected value, `{path:.1}`
+        /// to round numbers.
+        #[serde(default = "default_http_format")]
+        format: String,
+    },
+    /// Unread GitHub notifications and the latest CI run per repository.
+    Github {
+        /// Command printing an API token (needs `allow_exec`), e.g.
+        /// "gh auth token" or "pass show github/token".
+        token_cmd: String,
+        /// "owner/name" repositories to show CI status for.
+        #[serde(default)]
+        repos: Vec<String>,
+        #[serde(default = "default_github_interval")]
+        interval_secs: u64,
+    },
+    /// Result of a PromQL instant query: the scalar or the first sample.
+    Prometheus {
+        /// Server base URL, e.g. "http://prometheus.lan:9090".
+        endpoint: String,
+        query: String,
+        /// `{value}` (or `{value:.2}`) and `{metric.<label>}` placeholders.
+        #[serde(default = "default_prometheus_format")]
+        format: String,
+        #[serde(default = "default_prometheus_interval")]
+        interval_secs: u64,
+    },
+    /// External IP address (and country) from an HTTP endpoint.
+    PublicIp {
+        /// Plain-text or JSON (`ip`, `country`) endpoint.
+        #[serde(default = "default_public_ip_endpoint")]
+        endpoint: String,
+        #[serde(default = "default_public_ip_interval")]
+        interval_secs: u64,
+        #[serde(default)]
+        show_country: bool,
+    },
+    /// WireGuard/OpenVPN tunnel state; red while down.
+    Vpn {
+        interface: String,
+        #[serde(default)]
+        label: Option<String>,
+    },
+    /// Time left until `target`, updated every second.
+    Countdown {
+        /// "2026-12-24 18:00", RFC 3339, or a date.
+        target: String,
+        #[serde(default = "default_label_countdown")]
+        label: String,
+    },
+    /// Time elapsed since `since` (default: since rustky started), updated
+    /// every second.
+    Stopwatch {
+        #[serde(default)]
+        since: Option<String>,
+        #[serde(default = "default_label_stopwatch")]
+        label: String,
+    },
+    /// Work/break timer driven by clicks on its line; the timer lives in
+    /// the state file so it survives restarts.
+    Pomodoro {
+        #[serde(default = "default_label_pomodoro")]
+        label: String,
+        #[serde(default = "default_pomodoro_work")]
+        work_mins: u64,
+        #[serde(default = "default_pomodoro_short_break")]
+        short_break_mins: u64,
+        #[serde(default = "default_pomodoro_long_break")]
+        long_break_mins: u64,
+        #[serde(default = "default_pomodoro_long_break_every")]
+        long_break_every: u32,
+    },
+    /// Title and app id of the focused window (wlr foreign toplevel
+    /// management), cut to the widget width.
+    WindowTitle {
+        #[serde(default = "default_label_window")]
+        label: String,
+        #[serde(default = "default_true")]
+        show_app_id: bool,
+        #[serde(default = "default_overflow_ellipsize")]
+        overflow: Overflow,
+    },
+    /// Active XKB layout, e.g. "us" / "de".
+    KeyboardLayout {
+        #[serde(default = "default_label_keyboard")]
+        label: String,
+        /// Layout code instead of the description ("German").
+        #[serde(default = "default_true")]
+        short: bool,
+    },
+    /// Kernel counters from /proc through one format string: `{entropy}`,
+    /// `{fds}`, `{fds_max}`, `{ctxt_rate}`, `{forks_rate}`, `{intr_rate}`,
+    /// `{procs_running}`, `{procs_blocked}` (`{ctxt_rate:.0}` rounds).
+    Kernel {
+        #[serde(default = "default_kernel_format")]
+        format: String,
     },
     Uptime,
     Hostname,
@@ -67,12 +591,336 @@ pub enum Module {
     },
     Text {
         content: String,
+        /// Break long content onto further rows at spaces.
+        #[serde(default)]
+        wrap: bool,
+        /// Unless wrapped: clip, ellipsize or marquee.
+        #[serde(default)]
+        overflow: Overflow,
+    },
+    /// A horizontal rule between modules.
+    Separator {
+        #[serde(default)]
+        style: SeparatorStyle,
+    },
+    /// Raw hwmon readings (temperatures, fans, power, voltages, currents).
+    Sensors {
+        /// Restrict to these kinds; all kinds when empty.
+        #[serde(default)]
+        kinds: Vec<SensorKind>,
+        /// Only chips whose name contains this string.
+        #[serde(default)]
+        chip: Option<String>,
+        /// Only sensors whose label contains this string.
+        #[serde(default)]
+        label: Option<String>,
+    },
+    /// Temperatures (CPU, GPU, NVMe, …) from hwmon, colored by thresholds.
+    Temperature {
+        /// Only sensors whose "chip label" contains one of these strings,
+        /// e.g. "coretemp Package", "nvme", "amdgpu edge".
+        #[serde(default)]
+        sensors: Vec<String>,
+        /// °C above which the line turns orange/red, before `thresholds`;
+        /// defaults to each sensor's own max/crit limits.
+        #[serde(default)]
+        warn: Option<f64>,
+        #[serde(default)]
+        critical: Option<f64>,
+        /// Styles applied past limits in °C, e.g. `[{ above = 70, bg_color = "warn" }]`.
+        #[serde(default)]
+        thresholds: Vec<ThresholdStyle>,
+    },
+    Processes {
+        #[serde(default = "default_process_count")]
+        count: usize,
+        #[serde(default)]
+        sort_by: ProcessSort,
+    },
+    /// CPU/memory totals across processes matching a name or pid.
+    ProcWatch {
+        /// Process id, or a substring of the process name.
+        #[serde(rename = "match")]
+        pattern: String,
+        #[serde(default)]
+        label: Option<String>,
+        #[serde(default = "default_watch_metrics")]
+        metrics: Vec<WatchMetric>,
+    },
+    /// CPU, memory and IO of a cgroup v2 slice or scope.
+    Cgroup {
+        /// Relative to /sys/fs/cgroup, e.g. "user.slice" or
+        /// "system.slice/docker-<id>.scope".
+        path: String,
+        #[serde(default)]
+        label: Option<String>,
+    },
+    /// Pending Flatpak (and optionally fwupd firmware) updates.
+    Flatpak {
+        /// How often to check; checks hit the network.
+        #[serde(default = "default_update_interval")]
+        interval_secs: u64,
+        #[serde(default)]
+        firmware: bool,
+        /// List each pending update below the summary.
+        #[serde(default)]
+        show_list: bool,
+    },
+    /// Trimmed preview of the clipboard selection (wlr data control).
+    Clipboard {
+        #[serde(default = "default_clipboard_chars")]
+        max_chars: usize,
+        /// Mask the preview until the line is clicked.
+        #[serde(default)]
+        private: bool,
+        /// Extra MIME types whose selections are never previewed.
+        #[serde(default)]
+        ignore_mime: Vec<String>,
+    },
+    /// Do-not-disturb state of the notification daemon; click to toggle.
+    Dnd {
+        #[serde(default)]
+        daemon: DndDaemon,
+    },
+    /// Latest desktop notifications seen on the session bus, with an unread
+    /// count that a click clears.
+    Notifications {
+        #[serde(default = "default_label_notifications")]
+        label: String,
+        #[serde(default = "default_notification_count")]
+        count: usize,
+        #[serde(default = "default_notification_chars")]
+        max_chars: usize,
+        #[serde(default = "default_true")]
+        show_app: bool,
+    },
+    /// Indicator for active xdg-desktop-portal screen cast / remote desktop
+    /// sessions.
+    ScreenShare {
+        /// Render nothing while no session is active.
+        #[serde(default)]
+        hide_idle: bool,
+    },
+    /// GPU utilization, VRAM, temperature and power from the GPU backends.
+    Gpu {
+        /// Which GPU to show; all detected GPUs when unset.
+        #[serde(default)]
+        index: Option<usize>,
+        /// List processes holding GPU memory below each GPU.
+        #[serde(default)]
+        show_processes: bool,
+    },
+    /// Total size of a directory tree, recomputed on a slow schedule.
+    DirSize {
+        path: String,
+        /// Default: the directory's name.
+        #[serde(default)]
+        label: Option<String>,
+        #[serde(default = "default_dir_size_interval")]
+        interval_secs: u64,
+    },
+    /// Age of the most recent backup; red once older than `max_age_hours`.
+    BackupStatus {
+        /// Stamp file or restic/borg repository directory, by mtime.
+        #[serde(default)]
+        path: Option<String>,
+        /// Alternative to `path`: prints a timestamp, Unix time, or restic /
+        /// borg JSON with "time" fields. Needs `allow_exec`.
+        #[serde(default)]
+        command: Option<String>,
+        #[serde(default = "default_label_backup")]
+        label: String,
+        #[serde(default = "default_backup_max_age_hours")]
+        max_age_hours: f64,
+        #[serde(default = "default_backup_interval")]
+        interval_secs: u64,
+    },
+    /// UPS charge, load and runtime from NUT or apcupsd; colored while on
+    /// battery.
+    Ups {
+        #[serde(default)]
+        backend: UpsBackend,
+        /// NUT "name@host" or apcupsd "host:port"; default: the local UPS.
+        #[serde(default)]
+        target: Option<String>,
+        #[serde(default = "default_label_ups")]
+        label: String,
+        #[serde(default = "default_ups_interval")]
+        interval_secs: u64,
+    },
+    /// SMART overall health, temperature and reallocated sectors of a disk,
+    /// read with `smartctl`.
+    Smart {
+        /// e.g. "/dev/sda" or "/dev/nvme0".
+        device: String,
+        #[serde(default = "default_smart_interval")]
+        interval_secs: u64,
+    },
+    /// ZFS pool and btrfs filesystem health, scrub progress and devices
+    /// that are degraded or have errors.
+    PoolStatus {
+        /// ZFS pool names.
+        #[serde(default)]
+        zpools: Vec<String>,
+        /// btrfs mount points.
+        #[serde(default)]
+        btrfs: Vec<String>,
+        #[serde(default = "default_pool_interval")]
+        interval_secs: u64,
+    },
+    /// libvirt domains with their state and CPU/memory use, via `virsh`.
+    Vms {
+        #[serde(default = "default_libvirt_uri")]
+        uri: String,
+        /// Hide domains that are shut off.
+        #[serde(default)]
+        running_only: bool,
+    },
+    /// Active/failed state of systemd units, colored by state.
+    Systemd {
+        #[serde(default)]
+        units: Vec<String>,
+        /// Query the user manager instead of the system one.
+        #[serde(default)]
+        user: bool,
+        /// Add a line with the number of failed units manager-wide.
+        #[serde(default = "default_true")]
+        show_failed: bool,
+    },
+    /// Last journal messages, followed live by a background `journalctl`.
+    Journal {
+        /// Only this unit; default: the whole journal.
+        #[serde(default)]
+        unit: Option<String>,
+        #[serde(default = "default_journal_lines")]
+        lines: usize,
+        /// Maximum syslog priority shown, 0 (emerg) to 7 (debug).
+        #[serde(default = "default_journal_priority")]
+        priority: u8,
+        /// Messages are cut to this many characters.
+        #[serde(default = "default_journal_chars")]
+        max_chars: usize,
+    },
+    /// Unread counts per mailbox over IMAP (implicit TLS) or from a Maildir.
+    Mail {
+        #[serde(default = "default_label_mail")]
+        label: String,
+        #[serde(default)]
+        backend: MailBackend,
+        #[serde(default)]
+        host: String,
+        #[serde(default = "default_imap_port")]
+        port: u16,
+        #[serde(default)]
+        user: String,
+        /// Command printing the password (needs `allow_exec`), e.g.
+        /// "pass show mail/work".
+        #[serde(default)]
+        password_command: Option<String>,
+        /// Environment variable holding the password.
+        #[serde(default)]
+        password_env: Option<String>,
+        /// Maildir root for the maildir backend.
+        #[serde(default)]
+        path: Option<String>,
+        #[serde(default = "default_mailboxes")]
+        mailboxes: Vec<String>,
+        #[serde(default = "default_mail_interval")]
+        interval_secs: u64,
+    },
+    /// Default audio sink volume and mute state (PulseAudio/PipeWire).
+    Volume {
+        #[serde(default = "default_label_volume")]
+        label: String,
+        /// Left-click toggles mute.
+        #[serde(default)]
+        click_to_mute: bool,
+    },
+    /// Backlight level from /sys/class/backlight.
+    Brightness {
+        #[serde(default = "default_label_brightness")]
+        label: String,
+        /// Default: the first backlight device.
+        #[serde(default)]
+        device: Option<String>,
+        /// Percent per scroll notch over this line; scrolling the line
+        /// scrolls the widget instead when unset.
+        #[serde(default)]
+        scroll_step: Option<f64>,
+    },
+    /// Charge, charging state and time estimate for each laptop battery.
+    Battery {
+        #[serde(default = "default_label_battery")]
+        label: String,
+        /// Only this power supply (e.g. "BAT1"); default: every battery.
+        #[serde(default)]
+        name: Option<String>,
+        #[serde(default)]
+        display: DisplayMode,
+        /// Draw the charge as a bar after the text; `warn` and `critical`
+        /// apply at or below the charge.
+        #[serde(default)]
+        bar: Option<BarStyle>,
+        /// E.g. `[{ below = 20, fg_color = "warn" }]`.
+        #[serde(default)]
+        thresholds: Vec<ThresholdStyle>,
+    },
+    /// CPU package power from RAPL energy counters, plus the battery
+    /// discharge rate on laptops.
+    Power {
+        #[serde(default = "default_label_power")]
+        label: String,
+        /// One line per RAPL zone (core, uncore, dram, …) below the total.
+        #[serde(default)]
+        show_zones: bool,
+        /// Append the total battery discharge rate while discharging.
+        #[serde(default = "default_true")]
+        show_battery: bool,
+    },
+    /// Rolling round-trip-time sparkline for an ICMP-pinged host.
+    LatencyGraph {
+        host: String,
+        #[serde(default)]
+        label: Option<String>,
+        /// Milliseconds between probes, at least 100.
+        #[serde(default = "default_ping_interval")]
+        interval_ms: u64,
+        /// Number of samples shown in the graph.
+        #[serde(default = "default_graph_width")]
+        width: usize,
     },
     Exec {
         command: String,
         label: Option<String>,
+        /// Boxed to keep `Module` small; most exec modules have none.
+        #[serde(default)]
+        style: Option<Box<LineStyle>>,
+        #[serde(default)]
+        protocol: ExecProtocol,
+        /// Passed to i3blocks blocklets as `BLOCK_INSTANCE`.
+        #[serde(default)]
+        instance: Option<String>,
+        /// Read `<fg=#ff0000>hot</fg>` markup in the output.
+        #[serde(default)]
+        markup: bool,
+    },
+    Group {
+        title: String,
+        #[serde(default)]
+        collapsed: bool,
+        #[serde(default)]
+        modules: Vec<ModuleEntry>,
+    },
+    /// Child modules drawn inside a titled box.
+    Section {
+        title: String,
+        #[serde(default)]
+        modules: Vec<ModuleEntry>,
+        /// Default: the text color, faint.
         #[serde(default)]
-        style: Option<LineStyle>,
+        border_color: Option<String>,
+        #[serde(default)]
+        bg_color: Option<String>,
     },
     #[cfg(feature = "rhai-scripting")]
     Rhai {
@@ -87,18 +935,528 @@ pub enum Module {
     },
 }
 
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Page {
+    pub name: String,
+    #[serde(default)]
+    pub icon: Option<String>,
+    #[serde(default)]
+    pub modules: Vec<ModuleEntry>,
+}
+
+/// A cross-cutting condition on `ScriptContext` metrics that shows a
+/// desktop notification and/or runs a command when it holds, independent of
+/// which modules are displayed.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AlertRule {
+    pub name: String,
+    /// Shorthand for a single condition; combined with `all` if both are set.
+    #[serde(default)]
+    pub metric: Option<String>,
+    #[serde(default)]
+    pub above: Option<Threshold>,
+    #[serde(default)]
+    pub below: Option<Threshold>,
+    /// Every condition must hold for the alert to fire.
+    #[serde(default)]
+    pub all: Vec<Condition>,
+    /// How long the conditions must hold before firing.
+    #[serde(default)]
+    pub for_secs: u64,
+    /// Margin a value must move back past its threshold before the alert clears.
+    #[serde(default)]
+    pub hysteresis: f64,
+    /// Minimum time between two firings of the same alert.
+    #[serde(default = "default_alert_cooldown")]
+    pub cooldown_secs: u64,
+    /// Desktop notification shown when the alert fires.
+    #[serde(default)]
+    pub notify: Option<AlertNotify>,
+    /// Shell command run when the alert fires.
+    #[serde(default)]
+    pub action: Option<String>,
+}
+
+/// An alert's freedesktop notification; `{name}`, `{metric}` and `{value}`
+/// in the texts are replaced by the alert's.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct AlertNotify {
+    pub summary: String,
+    pub body: String,
+    pub urgency: Urgency,
+    /// Freedesktop icon name or path.
+    pub icon: String,
+}
+
+impl Default for AlertNotify {
+    fn default() -> Self {
+        Self {
+            summary: "rustky: {name}".into(),
+            body: "{metric} is {value}".into(),
+            urgency: Urgency::default(),
+            icon: "dialog-warning".into(),
+        }
+    }
+}
+
+/// Notification urgency levels, numbered as the spec's `urgency` hint.
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Urgency {
+    Low,
+    #[default]
+    Normal,
+    Critical,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Condition {
+    pub metric: String,
+    #[serde(default)]
+    pub above: Option<Threshold>,
+    #[serde(default)]
+    pub below: Option<Threshold>,
+}
+
+/// A literal number or the name of another metric (e.g. `above = "cpu_count"`).
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum Threshold {
+    Value(f64),
+    Metric(String),
+}
+
+impl AlertRule {
+    pub fn conditions(&self) -> Vec<Condition> {
+        let mut conditions = self.all.clone();
+        if let Some(ref metric) = self.metric {
+            conditions.push(Condition {
+                metric: metric.clone(),
+                above: self.above.clone(),
+                below: self.below.clone(),
+            });
+        }
+        conditions
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ExecProtocol {
+    /// Every output line is shown as-is.
+    #[default]
+    Plain,
+    /// i3blocks blocklet: full_text / short_text / color lines, clicks
+    /// delivered via `BLOCK_BUTTON` and friends.
+    I3blocks,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DndDaemon {
+    /// Whichever of swaync, dunst and mako answers first.
+    #[default]
+    Auto,
+    Mako,
+    Dunst,
+    Swaync,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum WatchMetric {
+    Cpu,
+    Mem,
+    Count,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum UpsBackend {
+    /// Network UPS Tools, via `upsc`.
+    #[default]
+    Nut,
+    /// via `apcaccess`.
+    Apcupsd,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum MailBackend {
+    #[default]
+    Imap,
+    Maildir,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TickerProvider {
+    #[default]
+    Coingecko,
+    Yahoo,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Units {
+    /// °C, km/h
+    #[default]
+    Metric,
+    /// °F, mph
+    Imperial,
+}
+
+/// Binary (KiB = 1024 B) or decimal (KB = 1000 B) prefixes.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ByteUnits {
+    #[default]
+    Iec,
+    Si,
+}
+
+/// Display unit for network rates; the fixed ones use decimal prefixes.
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RateUnit {
+    /// Bytes per second with whichever `byte_units` prefix fits.
+    #[default]
+    Auto,
+    /// Kb/s
+    Kbit,
+    /// Mb/s
+    Mbit,
+    /// KB/s
+    Kb,
+    /// MB/s
+    Mb,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum GraphMetric {
+    /// Total CPU usage in percent.
+    Cpu,
+    /// Used memory in percent.
+    Memory,
+    /// Receive rate of `interface`.
+    NetRx,
+    /// Transmit rate of `interface`.
+    NetTx,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum GaugeMetric {
+    Cpu,
+    Memory,
+    Swap,
+    /// Charge of the first battery.
+    Battery,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ColorStop {
+    pub at: f64,
+    pub color: String,
+}
+
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ProcessSort {
+    #[default]
+    Cpu,
+    Memory,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DisplayMode {
+    /// The full text, plus a bar when `bar` is set.
+    #[default]
+    Text,
+    /// Label and percentage with a bar, styled by `bar` or the default.
+    Bar,
+}
+
+/// Look of a module's bar, e.g. `bar = { width = 80, warn = 70, critical = 90 }`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct BarStyle {
+    pub width: f32,
+    pub fill_color: String,
+    /// Percentages past which the fill switches to `warn_color` /
+    /// `critical_color`.
+    pub warn: Option<f64>,
+    pub critical: Option<f64>,
+    pub warn_color: String,
+    pub critical_color: String,
+}
+
+/// A style for while a module's value is past a limit, e.g.
+/// `{ above = 80, fg_color = "#ff5555" }`; with both limits, outside them.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ThresholdStyle {
+    pub above: Option<f64>,
+    pub below: Option<f64>,
+    pub fg_color: Option<String>,
+    pub bg_color: Option<String>,
+}
+
+impl ThresholdStyle {
+    /// A `warn`/`critical` style limit as a threshold coloring text (or a
+    /// bar's fill) `color` above it, or below it when `falling`.
+    pub fn limit(value: Option<f64>, falling: bool, color: &str) -> Option<Self> {
+        let value = value?;
+        Some(ThresholdStyle {
+            above: (!falling).then_some(value),
+            below: falling.then_some(value),
+            fg_color: Some(color.into()),
+            bg_color: None,
+        })
+    }
+
+    pub fn crossed(&self, value: f64) -> bool {
+        self.above.is_some_and(|above| value > above)
+            || self.below.is_some_and(|below| value < below)
+    }
+}
+
+/// Look of a separator, e.g. `style = { thickness = 2, color = "muted" }`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SeparatorStyle {
+    /// In pixels.
+    pub thickness: f32,
+    /// Default: the text color, faint.
+    pub color: Option<String>,
+    /// Space above and below the rule.
+    pub padding: f32,
+}
+
 fn default_label() -> String {
     "CPU".into()
 }
 fn default_label_mem() -> String {
     "MEM".into()
 }
+fn default_public_ip_endpoint() -> String {
+    "https://ipinfo.io/json".into()
+}
+fn default_public_ip_interval() -> u64 {
+    600
+}
+fn default_journal_lines() -> usize {
+    5
+}
+fn default_journal_priority() -> u8 {
+    6
+}
+fn default_journal_chars() -> usize {
+    60
+}
+fn default_label_mail() -> String {
+    "MAIL".into()
+}
+fn default_imap_port() -> u16 {
+    993
+}
+fn default_mailboxes() -> Vec<String> {
+    vec!["INBOX".into()]
+}
+fn default_mail_interval() -> u64 {
+    300
+}
+fn default_true() -> bool {
+    true
+}
+fn default_overflow_ellipsize() -> Overflow {
+    Overflow::Ellipsize
+}
+fn default_horizon_hours() -> u64 {
+    24
+}
+fn default_max_events() -> usize {
+    5
+}
+fn default_calendar_interval() -> u64 {
+    900
+}
+fn default_ticker_currency() -> String {
+    "usd".into()
+}
+fn default_ticker_interval() -> u64 {
+    300
+}
+fn default_label_countdown() -> String {
+    "COUNTDOWN".into()
+}
+fn default_label_stopwatch() -> String {
+    "ELAPSED".into()
+}
+fn default_http_interval() -> u64 {
+    60_000
+}
+fn default_http_format() -> String {
+    "{}".into()
+}
+fn default_github_interval() -> u64 {
+    300
+}
+fn default_prometheus_format() -> String {
+    "{value}".into()
+}
+fn default_prometheus_interval() -> u64 {
+    30
+}
+fn default_libvirt_uri() -> String {
+    "qemu:///system".into()
+}
+fn default_label_backup() -> String {
+    "BACKUP".into()
+}
+fn default_backup_max_age_hours() -> f64 {
+    26.0
+}
+fn default_backup_interval() -> u64 {
+    300
+}
+fn default_label_ups() -> String {
+    "UPS".into()
+}
+fn default_ups_interval() -> u64 {
+    10
+}
+fn default_dir_size_interval() -> u64 {
+    900
+}
+fn default_pool_interval() -> u64 {
+    60
+}
+fn default_smart_interval() -> u64 {
+    3600
+}
+fn default_label_notifications() -> String {
+    "NOTIF".into()
+}
+fn default_notification_count() -> usize {
+    5
+}
+fn default_notification_chars() -> usize {
+    50
+}
+fn default_kernel_format() -> String {
+    "FDS {fds}/{fds_max} | CTXT {ctxt_rate:.0}/s | RUN {procs_running} | ENTROPY {entropy}".into()
+}
+fn default_label_keyboard() -> String {
+    "KBD".into()
+}
+fn default_label_window() -> String {
+    "WINDOW".into()
+}
+fn default_label_pomodoro() -> String {
+    "POMODORO".into()
+}
+fn default_pomodoro_work() -> u64 {
+    25
+}
+fn default_pomodoro_short_break() -> u64 {
+    5
+}
+fn default_pomodoro_long_break() -> u64 {
+    15
+}
+fn default_pomodoro_long_break_every() -> u32 {
+    4
+}
+fn default_weather_interval() -> u64 {
+    1800
+}
+fn default_forecast_days() -> usize {
+    3
+}
+fn default_label_volume() -> String {
+    "VOL".into()
+}
+fn default_label_brightness() -> String {
+    "BRI".into()
+}
+fn default_label_battery() -> String {
+    "BAT".into()
+}
+fn default_label_cpu_freq() -> String {
+    "FREQ".into()
+}
+fn default_pressure_warn() -> f64 {
+    10.0
+}
+fn default_pressure_critical() -> f64 {
+    40.0
+}
+fn default_label_power() -> String {
+    "PWR".into()
+}
 fn default_mount() -> String {
     "/".into()
 }
 fn default_iface() -> String {
     "eth0".into()
 }
+fn default_gauge_radius() -> f32 {
+    24.0
+}
+fn default_gauge_thickness() -> f32 {
+    5.0
+}
+fn default_gauge_stops() -> Vec<ColorStop> {
+    [(0.0, "ok"), (70.0, "warn"), (90.0, "critical")]
+        .into_iter()
+        .map(|(at, color)| ColorStop {
+            at,
+            color: color.into(),
+        })
+        .collect()
+}
+fn default_gauge_columns() -> usize {
+    4
+}
+fn default_heatmap_columns() -> usize {
+    8
+}
+fn default_heatmap_cell_size() -> f32 {
+    14.0
+}
+fn default_graph_samples() -> usize {
+    120
+}
+fn default_graph_height() -> f32 {
+    40.0
+}
+fn default_graph_color() -> String {
+    "primary".into()
+}
+fn default_alert_cooldown() -> u64 {
+    300
+}
+fn default_process_count() -> usize {
+    10
+}
+fn default_ping_interval() -> u64 {
+    1000
+}
+fn default_graph_width() -> usize {
+    40
+}
+fn default_watch_metrics() -> Vec<WatchMetric> {
+    vec![WatchMetric::Cpu, WatchMetric::Mem, WatchMetric::Count]
+}
+fn default_update_interval() -> u64 {
+    3600
+}
+fn default_clipboard_chars() -> usize {
+    60
+}
 fn default_time_format() -> String {
     "%Y-%m-%d %H:%M:%S".into()
 }
@@ -107,8 +1465,10 @@ impl Default for Config {
     fn default() -> Self {
         Self {
             general: General::default(),
-            window: Window::default(),
-            modules: vec![
+            window: Windows::default(),
+            exec: ExecPolicy::default(),
+            gpu: GpuConfig::default(),
+            modules: [
                 Module::Hostname,
                 Module::Uptime,
                 Module::Time {
@@ -117,14 +1477,29 @@ impl Default for Config {
                 Module::Cpu {
                     label: default_label(),
                     show_per_core: false,
+                    bar: None,
+                    thresholds: Vec::new(),
                 },
                 Module::Memory {
                     label: default_label_mem(),
+                    display: DisplayMode::Text,
+                    bar: None,
+                    thresholds: Vec::new(),
                 },
                 Module::Disk {
                     mount_point: default_mount(),
+                    display: DisplayMode::Text,
+                    bar: None,
+                    thresholds: Vec::new(),
                 },
-            ],
+            ]
+            .into_iter()
+            .map(ModuleEntry::from)
+            .collect(),
+            pages: Vec::new(),
+            alerts: Vec::new(),
+            icons: HashMap::new(),
+            theme: Theme::default(),
         }
     }
 }
@@ -135,9 +1510,26 @@ impl Default for General {
             update_interval_ms: 1000,
             font: "monospace".into(),
             font_size: 12.0,
-            fg_color: "#ffffff".into(),
-            bg_color: "#000000aa".into(),
+            line_height_factor: 1.4,
+            letter_spacing: 0.0,
+            tab_width: 48.0,
+            fg_color: "fg".into(),
+            bg_color: "bg".into(),
+            byte_units: ByteUnits::default(),
+            precision: 1,
             scripts_dir: None,
+            allow_exec: false,
+            scale: 1.0,
+            high_contrast: false,
+            icon_font: None,
+            fallback_fonts: Vec::new(),
+            render_backend: RenderBackend::default(),
+            animation_ms: 0,
+            animation_fps: 30,
+            fade_in: false,
+            scroll_speed: 1.0,
+            smooth_scroll: true,
+            idle_pause_s: 0,
             #[cfg(feature = "rhai-scripting")]
             on_draw_rhai: None,
             #[cfg(feature = "python-scripting")]
@@ -156,11 +1548,178 @@ impl Default for Window {
             transparent: true,
             always_on_top: true,
             decoration: false,
+            keyboard: false,
+            drag_modifier: None,
+            remember_position: false,
+            output: None,
+            anchor: WindowAnchor::default(),
+            margin: None,
+            click_through: false,
+            orientation: Orientation::default(),
+            exclusive: false,
+            auto_hide: None,
+        }
+    }
+}
+
+impl WindowAnchor {
+    /// Which way `x` and `y` move the window on screen: 1 right or down, -1
+    /// left or up, 0 for a centred axis where they do nothing.
+    pub fn directions(self) -> (i32, i32) {
+        use WindowAnchor::*;
+        let x = match self {
+            TopLeft | Left | BottomLeft => 1,
+            TopRight | Right | BottomRight => -1,
+            Top | Center | Bottom => 0,
+        };
+        let y = match self {
+            TopLeft | Top | TopRight => 1,
+            BottomLeft | Bottom | BottomRight => -1,
+            Left | Center | Right => 0,
+        };
+        (x, y)
+    }
+
+    /// `margins` with those of the anchored edges set to `(x, y)`.
+    pub fn place(self, mut margins: Margins, (x, y): (i32, i32)) -> Margins {
+        match self.directions().0 {
+            1 => margins.left = x,
+            -1 => margins.right = x,
+            _ => {}
+        }
+        match self.directions().1 {
+            1 => margins.top = y,
+            -1 => margins.bottom = y,
+            _ => {}
+        }
+        margins
+    }
+
+    /// The `(x, y)` `margins` put the window at, from the anchored edges.
+    pub fn position(self, margins: Margins) -> (i32, i32) {
+        let (x, y) = self.directions();
+        let x = match x {
+            1 => margins.left,
+            -1 => margins.right,
+            _ => 0,
+        };
+        let y = match y {
+            1 => margins.top,
+            -1 => margins.bottom,
+            _ => 0,
+        };
+        (x, y)
+    }
+}
+
+impl DisplayMode {
+    /// The bar style to draw with, if any.
+    pub fn bar_style(self, bar: &Option<BarStyle>) -> Option<BarStyle> {
+        match self {
+            DisplayMode::Text => bar.clone(),
+            DisplayMode::Bar => Some(bar.clone().unwrap_or_default()),
+        }
+    }
+}
+
+impl Default for BarStyle {
+    fn default() -> Self {
+        Self {
+            width: 100.0,
+            fill_color: "ok".into(),
+            warn: None,
+            critical: None,
+            warn_color: "warn".into(),
+            critical_color: "critical".into(),
         }
     }
 }
 
+impl Default for SeparatorStyle {
+    fn default() -> Self {
+        Self {
+            thickness: 1.0,
+            color: None,
+            padding: 6.0,
+        }
+    }
+}
+
+impl Module {
+    /// The `type` key the module was configured with, e.g. "cpu".
+    pub fn type_name(&self) -> &'static str {
+        self.into()
+    }
+
+    /// Whether the module shows seconds and so needs a redraw every second
+    /// even when `update_interval_ms` is longer.
+    pub fn ticks_every_second(&self) -> bool {
+        match self {
+            Module::Countdown { .. } | Module::Stopwatch { .. } | Module::Pomodoro { .. } => true,
+            Module::Group { modules, .. } | Module::Section { modules, .. } => modules
+                .iter()
+                .any(|entry| entry.module.ticks_every_second()),
+            _ => false,
+        }
+    }
+}
+
+impl Window {
+    /// Whether anything needs keyboard focus (search, drag modifier).
+    pub fn wants_keyboard(&self) -> bool {
+        self.keyboard || self.drag_modifier.is_some()
+    }
+
+    /// A horizontal bar without a width, stretched across the output.
+    pub fn spans_output(&self) -> bool {
+        self.orientation == Orientation::Horizontal && self.width == 0
+    }
+
+    /// Layer-shell exclusive zone: with `exclusive`, the window's extent
+    /// away from the edge it sits on; otherwise -1, neither pushing other
+    /// surfaces aside nor being pushed by them.
+    pub fn exclusive_zone(&self, scale: f32) -> Result<i32, String> {
+        if !self.exclusive {
+            return Ok(-1);
+        }
+        let (width, height) = self.scaled_size(scale);
+        let spans = self.spans_output();
+        match self.anchor.directions() {
+            (x, y) if y != 0 && (x == 0 || spans) => Ok(height as i32),
+            (x, 0) if x != 0 && !spans => Ok(width as i32),
+            _ => Err("exclusive needs the window anchored to an edge".to_string()),
+        }
+    }
+
+    /// Margins of the configured position: `margin`, or `x` and `y` from
+    /// the anchored edges.
+    pub fn margins(&self) -> Margins {
+        self.margin
+            .unwrap_or_else(|| self.anchor.place(Margins::default(), (self.x, self.y)))
+    }
+
+    /// Window size with the accessibility scale applied.
+    pub fn scaled_size(&self, scale: f32) -> (u32, u32) {
+        (
+            (self.width as f32 * scale).round() as u32,
+            (self.height as f32 * scale).round() as u32,
+        )
+    }
+}
+
 impl Config {
+    /// Every window with its own modules, empty where it shows the
+    /// top-level `modules` and `pages`.
+    pub fn windows(&self) -> Vec<(&Window, &[ModuleEntry])> {
+        match &self.window {
+            Windows::One(window) => vec![(window, &[])],
+            Windows::Many(entries) => entries
+                .iter()
+                .map(|entry| (&entry.window, entry.modules.as_slice()))
+                .collect(),
+        }
+    }
+
     pub fn config_path() -> PathBuf {
         dirs::config_dir()
             .unwrap_or_else(|| PathBuf::from("~/.config"))
@@ -168,6 +1727,28 @@ impl Config {
             .join("config.toml")
     }
 
+    /// Every configured module, including those that only appear on pages
+    /// or inside groups and sections.
+    pub fn all_modules(&self) -> Vec<&Module> {
+        fn walk<'a>(modules: &'a [ModuleEntry], out: &mut Vec<&'a Module>) {
+            for ModuleEntry { module, .. } in modules {
+                out.push(module);
+                if let Module::Group { modules, .. } | Module::Section { modules, .. } = module {
+                    walk(modules, out);
+                }
+            }
+        }
+        let mut out = Vec::new();
+        walk(&self.modules, &mut out);
+        for page in &self.pages {
+            walk(&page.modules, &mut out);
+        }
+        for (_, modules) in self.windows() {
+            walk(modules, &mut out);
+        }
+        out
+    }
+
     #[allow(dead_code)]
     pub fn scripts_dir(&self) -> PathBuf {
         if let Some(ref dir) = self.general.scripts_dir {
@@ -194,14 +1775,11 @@ impl Config {
     pub fn load() -> Self {
         let path = Self::config_path();
         match std::fs::read_to_string(&path) {
-            Ok(contents) => match toml::from_str(&contents) {
-                Ok(cfg) => cfg,
-                Err(e) => {
-                    eprintln!("warning: failed to parse {}: {e}", path.display());
-                    eprintln!("falling back to defaults");
-                    Self::default()
-                }
-            },
+            Ok(_) => Self::try_load().unwrap_or_else(|e| {
+                eprintln!("warning: {e}");
+                eprintln!("falling back to defaults");
+                Self::default()
+            }),
             Err(_) => {
                 eprintln!("no config found at {}, using defaults", path.display());
                 Self::default()
@@ -209,17 +1787,73 @@ impl Config {
         }
     }
 
+    /// Like `load`, but reports problems instead of falling back to defaults.
+    pub fn try_load() -> Result<Self, String> {
+        let path = Self::config_path();
+        let contents = std::fs::read_to_string(&path)
+            .map_err(|e| format!("failed to read {}: {e}", path.display()))?;
+        toml::from_str(&contents).map_err(|e| format!("failed to parse {}: {e}", path.display()))
+    }
+
+    /// Script files referenced by modules and hooks, resolved.
+    pub fn script_paths(&self) -> Vec<PathBuf> {
+        #[allow(unused_mut)]
+        let mut paths = Vec::new();
+        for module in self.all_modules() {
+            match module {
+                #[cfg(feature = "rhai-scripting")]
+                Module::Rhai {
+                    file: Some(file), ..
+                } => paths.push(self.resolve_script_path(file)),
+                #[cfg(feature = "python-scripting")]
+                Module::Python { file, .. } => paths.push(self.resolve_script_path(file)),
+                _ => {}
+            }
+        }
+        #[cfg(feature = "rhai-scripting")]
+        if let Some(ref hook) = self.general.on_draw_rhai {
+            paths.push(self.resolve_script_path(hook));
+        }
+        #[cfg(feature = "python-scripting")]
+        if let Some(ref hook) = self.general.on_draw_python {
+            paths.push(self.resolve_script_path(hook));
+        }
+        paths
+    }
+
     pub fn generate_default_toml() -> String {
         toml::to_string_pretty(&Config::default()).expect("failed to serialize default config")
     }
 }
 
-#[allow(dead_code)]
-fn shellexpand(s: &str) -> String {
-    if let Some(rest) = s.strip_prefix("~/") {
-        if let Some(home) = dirs::home_dir() {
-            return home.join(rest).to_string_lossy().into_owned();
+/// Bounds on `scale` when set from outside the config file.
+pub const MIN_SCALE: f32 = 0.5;
+pub const MAX_SCALE: f32 = 4.0;
+
+/// Accessibility settings from the command line and environment, applied
+/// over `[general]` on every load.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Overrides {
+    pub scale: Option<f32>,
+    pub high_contrast: bool,
+}
+
+impl Overrides {
+    pub fn apply(&self, cfg: &mut Config) {
+        if let Some(scale) = self.scale {
+            cfg.general.scale = scale;
+        }
+        if self.high_contrast {
+            cfg.general.high_contrast = true;
         }
     }
+}
+
+pub fn shellexpand(s: &str) -> String {
+    if let Some(rest) = s.strip_prefix("~/")
+        && let Some(home) = dirs::home_dir()
+    {
+        return home.join(rest).to_string_lossy().into_owned();
+    }
     s.to_string()
 }
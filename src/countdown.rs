@@ -0,0 +1,54 @@
+use std::sync::OnceLock;
+use std::time::Instant;
+
+use chrono::{DateTime, Local, NaiveDate, NaiveDateTime, TimeZone};
+
+static STARTED: OnceLock<Instant> = OnceLock::new();
+
+/// Called at startup so stopwatches without `since` count from launch.
+pub fn mark_start() {
+    STARTED.get_or_init(Instant::now);
+}
+
+/// Seconds since launch.
+pub fn uptime_secs() -> i64 {
+    STARTED.get_or_init(Instant::now).elapsed().as_secs() as i64
+}
+
+/// Parses "2026-12-24 18:00[:00[.fff]]", the same with a `T`, RFC 3339, or
+/// a bare date (midnight); times without an offset are local.
+pub fn parse_datetime(s: &str) -> Result<DateTime<Local>, String> {
+    if let Ok(t) = DateTime::parse_from_rfc3339(s) {
+        return Ok(t.with_timezone(&Local));
+    }
+    let naive = [
+        "%Y-%m-%d %H:%M:%S%.f",
+        "%Y-%m-%d %H:%M",
+        "%Y-%m-%dT%H:%M:%S%.f",
+        "%Y-%m-%dT%H:%M",
+    ]
+    .iter()
+    .find_map(|f| NaiveDateTime::parse_from_str(s, f).ok())
+    .or_else(|| {
+        NaiveDate::parse_from_str(s, "%Y-%m-%d")
+            .ok()?
+            .and_hms_opt(0, 0, 0)
+    })
+    .ok_or_else(|| format!("invalid date/time {s:?}"))?;
+    Local
+        .from_local_datetime(&naive)
+        .earliest()
+        .ok_or_else(|| format!("{s:?} does not exist in the local time zone"))
+}
+
+/// 93784 → "1d 02:03:04", 3723 → "01:02:03".
+pub fn format_hms(secs: i64) -> String {
+    let secs = secs.max(0);
+    let (days, rest) = (secs / 86_400, secs % 86_400);
+    let hms = format!("{:02}:{:02}:{:02}", rest / 3600, rest / 60 % 60, rest % 60);
+    if days > 0 {
+        format!("{days}d {hms}")
+    } else {
+        hms
+    }
+}
@@ -0,0 +1,66 @@
+use std::collections::{HashMap, HashSet};
+use std::os::unix::fs::MetadataExt;
+use std::path::{Path, PathBuf};
+use std::sync::{Mutex, OnceLock};
+use std::time::Duration;
+
+use crate::background::Poller;
+use crate::config;
+
+#[derive(Debug, Clone, Copy)]
+pub struct Usage {
+    /// Allocated size on disk, like `du`.
+    pub bytes: u64,
+    pub files: u64,
+}
+
+type Registry = Mutex<HashMap<String, Poller<Usage>>>;
+
+static POLLERS: OnceLock<Registry> = OnceLock::new();
+
+/// Disk usage of the tree at `path`, recomputed every `interval` on a
+/// background thread.
+pub fn usage(path: &str, interval: Duration) -> Option<Result<Usage, String>> {
+    let mut pollers = POLLERS
+        .get_or_init(Default::default)
+        .lock()
+        .unwrap_or_else(|e| e.into_inner());
+    pollers
+        .entry(path.to_string())
+        .or_insert_with(|| {
+            let root = PathBuf::from(config::shellexpand(path));
+            Poller::spawn_cached(interval, "dir size", move || walk(&root))
+        })
+        .latest()
+}
+
+/// Sums the tree without following symlinks or crossing into other
+/// filesystems; hard links count once and unreadable entries are skipped.
+fn walk(root: &Path) -> Result<Usage, String> {
+    let meta = std::fs::symlink_metadata(root).map_err(|e| format!("{}: {e}", root.display()))?;
+    let device = meta.dev();
+    let mut usage = Usage { bytes: 0, files: 0 };
+    let mut seen = HashSet::new();
+    let mut stack = vec![(root.to_path_buf(), meta)];
+    while let Some((path, meta)) = stack.pop() {
+        if meta.nlink() > 1 && !meta.is_dir() && !seen.insert((meta.dev(), meta.ino())) {
+            continue;
+        }
+        usage.bytes += meta.blocks() * 512;
+        if !meta.is_dir() {
+            usage.files += 1;
+            continue;
+        }
+        let Ok(entries) = std::fs::read_dir(&path) else {
+            continue;
+        };
+        for entry in entries.flatten() {
+            if let Ok(meta) = entry.metadata()
+                && meta.dev() == device
+            {
+                stack.push((entry.path(), meta));
+            }
+        }
+    }
+    Ok(usage)
+}
@@ -0,0 +1,94 @@
+use std::collections::HashMap;
+use std::process::Command;
+use std::sync::{Mutex, OnceLock};
+use std::time::Duration;
+
+use crate::background::Poller;
+use crate::config::DndDaemon;
+
+const POLL_INTERVAL: Duration = Duration::from_secs(2);
+/// mako has no built-in DND; this is the conventional mode name.
+const MAKO_MODE: &str = "do-not-disturb";
+
+/// Pollers keyed by the configured daemon.
+type Registry = Mutex<HashMap<DndDaemon, Poller<(DndDaemon, bool)>>>;
+
+static POLLERS: OnceLock<Registry> = OnceLock::new();
+
+/// The daemon in use and whether DND is on, polled in the background.
+pub fn state(daemon: DndDaemon) -> Option<Result<(DndDaemon, bool), String>> {
+    let mut pollers = POLLERS
+        .get_or_init(Default::default)
+        .lock()
+        .unwrap_or_else(|e| e.into_inner());
+    pollers
+        .entry(daemon)
+        .or_insert_with(|| Poller::spawn(POLL_INTERVAL, move || query(daemon)))
+        .latest()
+}
+
+/// Flips DND in the background, as the daemon's CLI can be slow to answer.
+pub fn toggle(daemon: DndDaemon) {
+    std::thread::spawn(move || {
+        if let Err(e) = flip(daemon) {
+            eprintln!("rustky: {e}");
+        }
+    });
+}
+
+fn flip(configured: DndDaemon) -> Result<(), String> {
+    let (daemon, enabled) = query(configured)?;
+    let (program, args): (&str, &[&str]) = match daemon {
+        DndDaemon::Mako => ("makoctl", &["mode", "-t", MAKO_MODE]),
+        DndDaemon::Dunst => ("dunstctl", &["set-paused", "toggle"]),
+        DndDaemon::Swaync => ("swaync-client", &["--toggle-dnd", "--skip-wait"]),
+        DndDaemon::Auto => return Err("no notification daemon found".into()),
+    };
+    run(program, args)?;
+    let pollers = POLLERS
+        .get_or_init(Default::default)
+        .lock()
+        .unwrap_or_else(|e| e.into_inner());
+    if let Some(poller) = pollers.get(&configured) {
+        poller.publish(Ok((daemon, !enabled)));
+    }
+    Ok(())
+}
+
+fn query(daemon: DndDaemon) -> Result<(DndDaemon, bool), String> {
+    let candidates: &[DndDaemon] = match daemon {
+        DndDaemon::Auto => &[DndDaemon::Swaync, DndDaemon::Dunst, DndDaemon::Mako],
+        _ => std::slice::from_ref(&daemon),
+    };
+    for &candidate in candidates {
+        let enabled = match candidate {
+            DndDaemon::Mako => run("makoctl", &["mode"])
+                .map(|out| out.lines().any(|mode| mode.trim() == MAKO_MODE)),
+            DndDaemon::Dunst => run("dunstctl", &["is-paused"]).map(|out| out.trim() == "true"),
+            DndDaemon::Swaync => {
+                run("swaync-client", &["--get-dnd", "--skip-wait"]).map(|out| out.trim() == "true")
+            }
+            DndDaemon::Auto => continue,
+        };
+        match enabled {
+            Ok(enabled) => return Ok((candidate, enabled)),
+            Err(e) if daemon != DndDaemon::Auto => return Err(e),
+            Err(_) => {}
+        }
+    }
+    Err("no notification daemon found".into())
+}
+
+fn run(program: &str, args: &[&str]) -> Result<String, String> {
+    let output = Command::new(program)
+        .args(args)
+        .output()
+        .map_err(|e| format!("{program}: {e}"))?;
+    if !output.status.success() {
+        return Err(format!(
+            "{program}: {}",
+            String::from_utf8_lossy(&output.stderr).trim()
+        ));
+    }
+    Ok(String::from_utf8_lossy(&output.stdout).into_owned())
+}
@@ -0,0 +1,197 @@
+use std::collections::HashMap;
+use std::os::unix::process::CommandExt;
+use std::process::{Child, Command, Output};
+use std::sync::{Mutex, OnceLock};
+
+use crate::config::ExecPolicy;
+use crate::styled::{LineStyle, StyledLine};
+
+static POLICY: OnceLock<(bool, ExecPolicy)> = OnceLock::new();
+
+/// Output of the latest click on a blocklet, keyed by command and instance.
+type Clicked = Mutex<HashMap<(String, Option<String>), Output>>;
+
+static CLICKED: OnceLock<Clicked> = OnceLock::new();
+
+/// Variables kept when `minimal_env` is set; everything else is cleared.
+const MINIMAL_ENV: &[&str] = &[
+    "PATH",
+    "HOME",
+    "USER",
+    "LANG",
+    "LC_ALL",
+    "XDG_RUNTIME_DIR",
+    "WAYLAND_DISPLAY",
+    "DBUS_SESSION_BUS_ADDRESS",
+];
+
+/// Installs the global exec switch and sandbox policy. Must be called once at
+/// startup, before any module runs a command.
+pub fn init(allow_exec: bool, policy: ExecPolicy) {
+    let _ = POLICY.set((allow_exec, policy));
+}
+
+fn policy() -> &'static (bool, ExecPolicy) {
+    POLICY.get_or_init(|| (false, ExecPolicy::default()))
+}
+
+/// Builds `sh -c command` wrapped according to the sandbox policy.
+fn build(command: &str, env: &[(&str, String)]) -> Result<Command, String> {
+    let (allowed, policy) = policy();
+    if !allowed {
+        return Err("exec disabled (set allow_exec = true in [general])".into());
+    }
+
+    let mut cmd = if policy.memory_max.is_some() || policy.cpu_quota.is_some() {
+        // Transient systemd scope so the kernel enforces cgroup limits.
+        let mut c = Command::new("systemd-run");
+        c.args(["--user", "--scope", "--quiet", "--collect"]);
+        if let Some(ref mem) = policy.memory_max {
+            c.arg("-p").arg(format!("MemoryMax={mem}"));
+        }
+        if let Some(ref cpu) = policy.cpu_quota {
+            c.arg("-p").arg(format!("CPUQuota={cpu}"));
+        }
+        c.args(["--", "sh", "-c", command]);
+        c
+    } else {
+        let mut c = Command::new("sh");
+        c.arg("-c").arg(command);
+        c
+    };
+
+    if policy.minimal_env {
+        cmd.env_clear();
+        for key in MINIMAL_ENV {
+            if let Ok(value) = std::env::var(key) {
+                cmd.env(key, value);
+            }
+        }
+    }
+    for (key, value) in env {
+        cmd.env(key, value);
+    }
+
+    let nice = policy.nice;
+    // SAFETY: only async-signal-safe libc calls run between fork and exec.
+    unsafe {
+        cmd.pre_exec(move || {
+            if nice != 0 {
+                libc::setpriority(libc::PRIO_PROCESS, 0, nice);
+            }
+            // Undo the event loop blocking SIGUSR2, which children inherit
+            let mut set: libc::sigset_t = std::mem::zeroed();
+            libc::sigemptyset(&mut set);
+            libc::sigaddset(&mut set, libc::SIGUSR2);
+            libc::pthread_sigmask(libc::SIG_UNBLOCK, &set, std::ptr::null_mut());
+            // Don't leak the Wayland socket, IPC socket or anything else
+            // beyond stdio into the child. Marking them close-on-exec (rather
+            // than closing) keeps std's exec-error pipe working.
+            let rc = libc::syscall(
+                libc::SYS_close_range,
+                3u32,
+                u32::MAX,
+                libc::CLOSE_RANGE_CLOEXEC,
+            );
+            if rc != 0 {
+                for fd in 3..1024 {
+                    libc::fcntl(fd, libc::F_SETFD, libc::FD_CLOEXEC);
+                }
+            }
+            Ok(())
+        });
+    }
+    Ok(cmd)
+}
+
+/// Runs a module command through `sh -c` with extra environment variables,
+/// waiting for it to finish.
+pub fn run(command: &str, env: &[(&str, String)]) -> Result<Output, String> {
+    build(command, env)?
+        .output()
+        .map_err(|e| format!("exec error: {e}"))
+}
+
+/// Starts a command without waiting for it; a thread reaps it on exit.
+pub fn spawn(command: &str, env: &[(&str, String)]) -> Result<(), String> {
+    let mut child: Child = build(command, env)?
+        .spawn()
+        .map_err(|e| format!("exec error: {e}"))?;
+    std::thread::spawn(move || child.wait());
+    Ok(())
+}
+
+/// Environment i3blocks passes to a blocklet; `button` is 0 for a normal
+/// refresh and the i3bar button number (1 = left, 2 = middle, 3 = right) for
+/// a click.
+pub fn i3blocks_env(
+    name: &str,
+    instance: Option<&str>,
+    button: u32,
+    x: f64,
+    y: f64,
+) -> Vec<(&'static str, String)> {
+    let mut env = vec![
+        ("BLOCK_NAME", name.to_string()),
+        ("BLOCK_INSTANCE", instance.unwrap_or_default().to_string()),
+    ];
+    if button > 0 {
+        env.push(("BLOCK_BUTTON", button.to_string()));
+        env.push(("BLOCK_X", format!("{x:.0}")));
+        env.push(("BLOCK_Y", format!("{y:.0}")));
+    }
+    env
+}
+
+/// Runs a blocklet for a click on its own thread. As in i3blocks, what it
+/// prints becomes the block's text until the next refresh.
+pub fn i3blocks_click(command: String, instance: Option<String>, env: Vec<(&'static str, String)>) {
+    std::thread::spawn(move || match run(&command, &env) {
+        Ok(output) => {
+            CLICKED
+                .get_or_init(Default::default)
+                .lock()
+                .unwrap_or_else(|e| e.into_inner())
+                .insert((command, instance), output);
+        }
+        Err(e) => eprintln!("rustky: {e}"),
+    });
+}
+
+/// Takes the output of a click on the blocklet, if one finished since the
+/// last refresh.
+pub fn take_i3blocks_click(command: &str, instance: Option<&str>) -> Option<Output> {
+    CLICKED
+        .get_or_init(Default::default)
+        .lock()
+        .unwrap_or_else(|e| e.into_inner())
+        .remove(&(command.to_string(), instance.map(String::from)))
+}
+
+/// Interprets blocklet output: line 1 is full_text, line 2 short_text (unused,
+/// the widget is never space-constrained like a bar), line 3 the color. Exit
+/// code 33 marks the block urgent.
+pub fn parse_i3blocks(output: &Output, label: Option<&str>) -> StyledLine {
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let mut lines = stdout.lines();
+    let full_text = lines.next().unwrap_or_default().trim();
+    let _short_text = lines.next();
+    let color = lines
+        .next()
+        .map(str::trim)
+        .filter(|c| c.starts_with('#'))
+        .map(String::from);
+
+    let text = match label {
+        Some(lbl) => format!("{lbl}: {full_text}"),
+        None => full_text.to_string(),
+    };
+    let urgent = output.status.code() == Some(33);
+    let style = LineStyle {
+        fg_color: color,
+        bg_color: urgent.then(|| "#ff000066".to_string()),
+        font_size: None,
+        ..Default::default()
+    };
+    StyledLine::styled(text, style)
+}
@@ -0,0 +1,73 @@
+use smithay_client_toolkit::seat::keyboard::Keysym;
+
+use crate::styled::StyledLine;
+
+/// `/pattern` search over list-style module output, driven by the keyboard.
+#[derive(Default)]
+pub struct Filter {
+    editing: bool,
+    pattern: String,
+}
+
+impl Filter {
+    pub fn is_active(&self) -> bool {
+        self.editing || !self.pattern.is_empty()
+    }
+
+    /// Feeds a key press into the filter. Returns true if the key was consumed.
+    pub fn handle_key(&mut self, keysym: Keysym, utf8: Option<&str>) -> bool {
+        if keysym == Keysym::Escape {
+            let was_active = self.is_active();
+            self.editing = false;
+            self.pattern.clear();
+            return was_active;
+        }
+        if !self.editing {
+            if keysym == Keysym::slash {
+                self.editing = true;
+                self.pattern.clear();
+                return true;
+            }
+            return false;
+        }
+        match keysym {
+            Keysym::Return | Keysym::KP_Enter => self.editing = false,
+            Keysym::BackSpace => {
+                self.pattern.pop();
+            }
+            _ => match utf8 {
+                Some(text) if !text.chars().any(char::is_control) => self.pattern.push_str(text),
+                _ => return false,
+            },
+        }
+        true
+    }
+
+    /// Drops filterable lines that don't match and marks the match range on
+    /// those that do. Other lines pass through untouched. A prompt line is
+    /// prepended while the filter is active.
+    pub fn apply(&self, lines: Vec<StyledLine>) -> Vec<StyledLine> {
+        if !self.is_active() {
+            return lines;
+        }
+        let needle = self.pattern.to_lowercase();
+        let cursor = if self.editing { "▏" } else { "" };
+        let mut out = vec![StyledLine::plain(format!("/{}{cursor}", self.pattern))];
+        for mut line in lines {
+            if !line.filterable || needle.is_empty() {
+                out.push(line);
+                continue;
+            }
+            // Lowercasing can change byte lengths for some scripts; only
+            // highlight when the offsets are still valid in the original.
+            if let Some(start) = line.text.to_lowercase().find(&needle) {
+                let end = start + needle.len();
+                if line.text.is_char_boundary(start) && line.text.is_char_boundary(end) {
+                    line.highlight = Some((start, end));
+                }
+                out.push(line);
+            }
+        }
+        out
+    }
+}
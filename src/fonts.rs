@@ -0,0 +1,108 @@
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::process::Command;
+use std::sync::Arc;
+
+use skia_rs::prelude::Typeface;
+
+use crate::config;
+
+/// Face used when the configured font can't be found or loaded.
+const FALLBACK: &[u8] = include_bytes!("../assets/fonts/DejaVuSansMono.ttf");
+
+/// The face `font` names: a font file path, or a family ("monospace",
+/// "Iosevka", …) resolved through fontconfig. Falls back to the bundled
+/// DejaVu Sans Mono.
+pub fn typeface(font: &str) -> Arc<Typeface> {
+    load(font, false, false).unwrap_or_else(|e| {
+        eprintln!("rustky: font {font:?}: {e}; using the bundled DejaVu Sans Mono");
+        Arc::new(Typeface::from_data(FALLBACK.to_vec()).expect("bundled font is valid"))
+    })
+}
+
+/// Faces tried, in order, for characters a line's own face has no glyph
+/// for: the configured `fallback_fonts`, then whatever fontconfig picks for
+/// the character.
+pub struct Fallback {
+    chain: Vec<Arc<Typeface>>,
+    /// Faces fontconfig picked, by file, so each is loaded once.
+    found: HashMap<PathBuf, Arc<Typeface>>,
+    /// Face for each character looked up so far; `None` when no face has it.
+    chars: HashMap<char, Option<Arc<Typeface>>>,
+}
+
+impl Fallback {
+    pub fn new(fonts: &[String]) -> Self {
+        let chain = fonts
+            .iter()
+            .filter_map(|font| {
+                load(font, false, false)
+                    .map_err(|e| eprintln!("rustky: fallback font {font:?}: {e}"))
+                    .ok()
+            })
+            .collect();
+        Self {
+            chain,
+            found: HashMap::new(),
+            chars: HashMap::new(),
+        }
+    }
+
+    /// A face with a glyph for `c`.
+    pub fn face(&mut self, c: char) -> Option<Arc<Typeface>> {
+        if let Some(face) = self.chars.get(&c) {
+            return face.clone();
+        }
+        let covers = |face: &Typeface| face.char_to_glyph(c) != 0;
+        let face = match self.chain.iter().find(|face| covers(face)) {
+            Some(face) => Some(face.clone()),
+            None => find(&format!(":charset={:x}", c as u32), false, false)
+                .ok()
+                .and_then(|path| match self.found.get(&path) {
+                    Some(face) => Some(face.clone()),
+                    None => {
+                        let face = load(&path.to_string_lossy(), false, false).ok()?;
+                        self.found.insert(path, face.clone());
+                        Some(face)
+                    }
+                })
+                .filter(|face| covers(face)),
+        };
+        self.chars.insert(c, face.clone());
+        face
+    }
+}
+
+/// The bold and/or italic face of `font`. A font file path is loaded as
+/// is, since it names a single face.
+pub fn load(font: &str, bold: bool, italic: bool) -> Result<Arc<Typeface>, String> {
+    let path = find(font, bold, italic)?;
+    let data = std::fs::read(&path).map_err(|e| format!("{}: {e}", path.display()))?;
+    Typeface::from_data(data)
+        .map(Arc::new)
+        .ok_or_else(|| format!("{}: not a font", path.display()))
+}
+
+/// File of the best match for `font` in the given weight and slant, as
+/// `fc-match` picks it.
+fn find(font: &str, bold: bool, italic: bool) -> Result<PathBuf, String> {
+    if font.contains('/') {
+        return Ok(PathBuf::from(config::shellexpand(font)));
+    }
+    let mut pattern = font.to_string();
+    if bold {
+        pattern.push_str(":weight=bold");
+    }
+    if italic {
+        pattern.push_str(":slant=italic");
+    }
+    let output = Command::new("fc-match")
+        .args(["--format=%{file}", &pattern])
+        .output()
+        .map_err(|e| format!("fc-match: {e}"))?;
+    let file = String::from_utf8_lossy(&output.stdout).trim().to_string();
+    if !output.status.success() || file.is_empty() {
+        return Err("fc-match found no font".into());
+    }
+    Ok(PathBuf::from(file))
+}
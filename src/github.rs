@@ -0,0 +1,114 @@
+use std::collections::HashMap;
+use std::sync::{Mutex, OnceLock};
+use std::time::Duration;
+
+use serde_json::Value;
+
+use crate::background::Poller;
+use crate::exec;
+use crate::http;
+
+const API: &str = "https://api.github.com";
+/// Notifications fetched per poll; more than this shows as "50+".
+pub const PAGE_SIZE: usize = 50;
+
+#[derive(Debug, Clone)]
+pub struct Status {
+    pub unread: usize,
+    pub runs: Vec<Run>,
+}
+
+/// Latest workflow run of a repository.
+#[derive(Debug, Clone)]
+pub struct Run {
+    pub repo: String,
+    pub workflow: String,
+    pub branch: String,
+    /// "queued", "in_progress", "completed", …
+    pub status: String,
+    /// Set once completed: "success", "failure", "cancelled", …
+    pub conclusion: Option<String>,
+}
+
+type Registry = Mutex<HashMap<(String, Vec<String>), Poller<Status>>>;
+
+static POLLERS: OnceLock<Registry> = OnceLock::new();
+
+/// Unread notifications and the latest CI run of each of `repos`
+/// ("owner/name"), fetched every `interval`. The token is whatever
+/// `token_cmd` prints, asked for on every poll so rotated tokens are
+/// picked up.
+pub fn status(
+    token_cmd: &str,
+    repos: &[String],
+    interval: Duration,
+) -> Option<Result<Status, String>> {
+    let mut pollers = POLLERS
+        .get_or_init(Default::default)
+        .lock()
+        .unwrap_or_else(|e| e.into_inner());
+    pollers
+        .entry((token_cmd.to_string(), repos.to_vec()))
+        .or_insert_with(|| {
+            let (token_cmd, repos) = (token_cmd.to_string(), repos.to_vec());
+            Poller::spawn_cached(interval, "github", move || fetch(&token_cmd, &repos))
+        })
+        .latest()
+}
+
+fn fetch(token_cmd: &str, repos: &[String]) -> Result<Status, String> {
+    let token = token(token_cmd)?;
+    let auth = format!("Bearer {token}");
+    let get = |path: &str, query: &[(&str, &str)]| -> Result<Value, String> {
+        let body = http::get_with(
+            &format!("{API}{path}"),
+            query,
+            &[
+                ("Authorization", &auth),
+                ("Accept", "application/vnd.github+json"),
+            ],
+        )
+        // ureq's error names the URL, never the headers
+        .map_err(|e| format!("github: {e}"))?;
+        serde_json::from_str(&body).map_err(|e| format!("github: {e}"))
+    };
+
+    let page_size = PAGE_SIZE.to_string();
+    let unread = get("/notifications", &[("per_page", &page_size)])?
+        .as_array()
+        .map_or(0, Vec::len);
+    let mut runs = Vec::new();
+    for repo in repos {
+        let response = get(&format!("/repos/{repo}/actions/runs"), &[("per_page", "1")])?;
+        let Some(run) = response["workflow_runs"].get(0) else {
+            continue;
+        };
+        let text = |key: &str| run[key].as_str().unwrap_or_default().to_string();
+        runs.push(Run {
+            repo: repo.clone(),
+            workflow: text("name"),
+            branch: text("head_branch"),
+            status: text("status"),
+            conclusion: run["conclusion"].as_str().map(String::from),
+        });
+    }
+    Ok(Status { unread, runs })
+}
+
+/// First line of `token_cmd`'s output; runs through the exec gate.
+fn token(token_cmd: &str) -> Result<String, String> {
+    let output = exec::run(token_cmd, &[])?;
+    if !output.status.success() {
+        return Err(format!("token_cmd exited with {}", output.status));
+    }
+    let token = String::from_utf8_lossy(&output.stdout)
+        .lines()
+        .next()
+        .unwrap_or_default()
+        .trim()
+        .to_string();
+    if token.is_empty() {
+        return Err("token_cmd printed nothing".into());
+    }
+    Ok(token)
+}
@@ -0,0 +1,293 @@
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::Instant;
+
+use serde::{Deserialize, Serialize};
+
+use crate::config::GpuBackend;
+use crate::hwmon::{self, SensorKind};
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct GpuInfo {
+    pub index: usize,
+    pub name: String,
+    /// Which backend produced this entry ("nvml", "amdgpu", "i915", "xe").
+    pub backend: String,
+    pub utilization_pct: Option<f64>,
+    pub vram_used: Option<u64>,
+    pub vram_total: Option<u64>,
+    pub temperature_c: Option<f64>,
+    pub power_w: Option<f64>,
+    pub processes: Vec<GpuProcess>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GpuProcess {
+    pub pid: u32,
+    pub name: String,
+    pub vram_used: u64,
+}
+
+/// GPU collection across the available backends. Backends that can't
+/// initialize (no driver, no device) are silently skipped unless they were
+/// explicitly selected.
+pub struct GpuMonitor {
+    backend: GpuBackend,
+    #[cfg(feature = "nvml")]
+    nvml: Option<nvml_wrapper::Nvml>,
+    /// Counters from the previous refresh for metrics only available as
+    /// cumulative totals (Intel idle residency, energy), keyed by card.
+    counters: HashMap<String, Counters>,
+}
+
+struct Counters {
+    at: Instant,
+    idle_ms: Option<u64>,
+    energy_uj: Option<u64>,
+}
+
+impl GpuMonitor {
+    pub fn new(backend: GpuBackend) -> Self {
+        #[cfg(feature = "nvml")]
+        let nvml = match backend {
+            GpuBackend::Auto => nvml_wrapper::Nvml::init().ok(),
+            GpuBackend::Nvml => nvml_wrapper::Nvml::init()
+                .map_err(|e| eprintln!("rustky: NVML unavailable: {e}"))
+                .ok(),
+            _ => None,
+        };
+        #[cfg(not(feature = "nvml"))]
+        if backend == GpuBackend::Nvml {
+            eprintln!("rustky: gpu backend \"nvml\" requires building with --features nvml");
+        }
+
+        Self {
+            backend,
+            #[cfg(feature = "nvml")]
+            nvml,
+            counters: HashMap::new(),
+        }
+    }
+
+    /// Runtime-suspended cards are skipped rather than woken for a reading.
+    pub fn collect(&mut self) -> Vec<GpuInfo> {
+        let cards = drm_cards();
+        let mut gpus = Vec::new();
+        // NVML queries wake the card, so go by the nvidia driver's DRM nodes.
+        #[cfg(feature = "nvml")]
+        if let Some(ref nvml) = self.nvml {
+            let nvidia: Vec<&PathBuf> = cards
+                .iter()
+                .filter(|(_, _, driver)| driver == "nvidia")
+                .map(|(_, device, _)| device)
+                .collect();
+            let asleep = !nvidia.is_empty() && nvidia.iter().all(|d| hwmon::runtime_suspended(d));
+            if !asleep {
+                gpus.extend(collect_nvml(nvml));
+            }
+        }
+
+        for (card, device, driver) in cards {
+            if hwmon::runtime_suspended(&device) {
+                continue;
+            }
+            let wanted = match driver.as_str() {
+                "amdgpu" => matches!(self.backend, GpuBackend::Auto | GpuBackend::Amdgpu),
+                "i915" | "xe" => matches!(self.backend, GpuBackend::Auto | GpuBackend::Intel),
+                _ => false,
+            };
+            if !wanted {
+                continue;
+            }
+            let gpu = if driver == "amdgpu" {
+                collect_amdgpu(&device)
+            } else {
+                self.collect_intel(&card, &device, &driver)
+            };
+            gpus.push(gpu);
+        }
+
+        for (i, gpu) in gpus.iter_mut().enumerate() {
+            gpu.index = i;
+        }
+        gpus
+    }
+
+    fn collect_intel(&mut self, card: &str, device: &Path, driver: &str) -> GpuInfo {
+        let card_dir = Path::new("/sys/class/drm").join(card);
+        // Busy time is the complement of the GT's idle (RC6) residency.
+        let idle_ms = [
+            device.join("tile0/gt0/gtidle/idle_residency_ms"),
+            card_dir.join("gt/gt0/rc6_residency_ms"),
+            card_dir.join("power/rc6_residency_ms"),
+        ]
+        .iter()
+        .find_map(|p| read_u64(p));
+        let hwmon_dir = hwmon_dir(device);
+        let energy_uj = hwmon_dir
+            .as_ref()
+            .and_then(|d| read_u64(&d.join("energy1_input")));
+
+        let now = Instant::now();
+        let prev = self.counters.insert(
+            card.to_string(),
+            Counters {
+                at: now,
+                idle_ms,
+                energy_uj,
+            },
+        );
+
+        let mut utilization_pct = None;
+        let mut power_w = None;
+        if let Some(prev) = prev {
+            let elapsed = now.duration_since(prev.at).as_secs_f64();
+            if elapsed > 0.0 {
+                if let (Some(a), Some(b)) = (prev.idle_ms, idle_ms) {
+                    let idle = b.saturating_sub(a) as f64 / 1000.0 / elapsed;
+                    utilization_pct = Some(((1.0 - idle) * 100.0).clamp(0.0, 100.0));
+                }
+                if let (Some(a), Some(b)) = (prev.energy_uj, energy_uj) {
+                    power_w = Some(b.saturating_sub(a) as f64 / 1_000_000.0 / elapsed);
+                }
+            }
+        }
+
+        GpuInfo {
+            index: 0,
+            name: "Intel GPU".into(),
+            backend: driver.to_string(),
+            utilization_pct,
+            vram_used: None,
+            vram_total: None,
+            temperature_c: hwmon_dir.and_then(|d| first_reading(&d, SensorKind::Temp)),
+            power_w,
+            processes: Vec::new(),
+        }
+    }
+}
+
+fn read_u64(path: &Path) -> Option<u64> {
+    fs::read_to_string(path).ok()?.trim().parse().ok()
+}
+
+/// `(cardN, device dir, kernel driver)` for every DRM card, in card order.
+fn drm_cards() -> Vec<(String, PathBuf, String)> {
+    let Ok(entries) = fs::read_dir("/sys/class/drm") else {
+        return Vec::new();
+    };
+    let mut cards: Vec<(u32, String)> = entries
+        .flatten()
+        .filter_map(|e| {
+            let name = e.file_name().to_string_lossy().into_owned();
+            let n = name.strip_prefix("card")?.parse().ok()?;
+            Some((n, name))
+        })
+        .collect();
+    cards.sort();
+
+    cards
+        .into_iter()
+        .filter_map(|(_, card)| {
+            let device = Path::new("/sys/class/drm").join(&card).join("device");
+            let driver = fs::read_link(device.join("driver")).ok()?;
+            let driver = driver.file_name()?.to_string_lossy().into_owned();
+            Some((card, device, driver))
+        })
+        .collect()
+}
+
+fn hwmon_dir(device: &Path) -> Option<PathBuf> {
+    fs::read_dir(device.join("hwmon"))
+        .ok()?
+        .flatten()
+        .map(|e| e.path())
+        .next()
+}
+
+fn first_reading(hwmon_dir: &Path, kind: SensorKind) -> Option<f64> {
+    hwmon::read_chip(hwmon_dir, "")
+        .into_iter()
+        .find(|r| r.kind == kind)
+        .map(|r| r.value)
+}
+
+fn collect_amdgpu(device: &Path) -> GpuInfo {
+    let hwmon_dir = hwmon_dir(device);
+    let name = fs::read_to_string(device.join("product_name"))
+        .map(|s| s.trim().to_string())
+        .ok()
+        .filter(|s| !s.is_empty())
+        .unwrap_or_else(|| "AMD GPU".into());
+    GpuInfo {
+        index: 0,
+        name,
+        backend: "amdgpu".into(),
+        utilization_pct: read_u64(&device.join("gpu_busy_percent")).map(|v| v as f64),
+        vram_used: read_u64(&device.join("mem_info_vram_used")),
+        vram_total: read_u64(&device.join("mem_info_vram_total")),
+        temperature_c: hwmon_dir
+            .as_ref()
+            .and_then(|d| first_reading(d, SensorKind::Temp)),
+        power_w: hwmon_dir.and_then(|d| first_reading(&d, SensorKind::Power)),
+        processes: Vec::new(),
+    }
+}
+
+#[cfg(feature = "nvml")]
+fn process_name(pid: u32) -> String {
+    std::fs::read_to_string(format!("/proc/{pid}/comm"))
+        .map(|s| s.trim().to_string())
+        .unwrap_or_else(|_| pid.to_string())
+}
+
+#[cfg(feature = "nvml")]
+fn collect_nvml(nvml: &nvml_wrapper::Nvml) -> Vec<GpuInfo> {
+    use nvml_wrapper::enum_wrappers::device::TemperatureSensor;
+    use nvml_wrapper::enums::device::UsedGpuMemory;
+
+    let count = nvml.device_count().unwrap_or(0);
+    (0..count)
+        .filter_map(|i| {
+            let device = nvml.device_by_index(i).ok()?;
+            let memory = device.memory_info().ok();
+
+            let mut procs = device.running_compute_processes().unwrap_or_default();
+            procs.extend(device.running_graphics_processes().unwrap_or_default());
+            // A process in both lists reports the same memory twice, so
+            // the larger figure stands
+            let mut vram: HashMap<u32, u64> = HashMap::new();
+            for p in procs {
+                if let UsedGpuMemory::Used(bytes) = p.used_gpu_memory {
+                    let used = vram.entry(p.pid).or_default();
+                    *used = (*used).max(bytes);
+                }
+            }
+            let mut processes: Vec<GpuProcess> = vram
+                .into_iter()
+                .map(|(pid, vram_used)| GpuProcess {
+                    pid,
+                    name: process_name(pid),
+                    vram_used,
+                })
+                .collect();
+            processes.sort_by_key(|p| std::cmp::Reverse(p.vram_used));
+
+            Some(GpuInfo {
+                index: i as usize,
+                name: device.name().unwrap_or_else(|_| "NVIDIA GPU".into()),
+                backend: "nvml".into(),
+                utilization_pct: device.utilization_rates().ok().map(|u| u.gpu as f64),
+                vram_used: memory.as_ref().map(|m| m.used),
+                vram_total: memory.as_ref().map(|m| m.total),
+                temperature_c: device
+                    .temperature(TemperatureSensor::Gpu)
+                    .ok()
+                    .map(|t| t as f64),
+                power_w: device.power_usage().ok().map(|mw| mw as f64 / 1000.0),
+                processes,
+            })
+        })
+        .collect()
+}
@@ -0,0 +1,58 @@
+use crate::config::{Module, ModuleEntry};
+use crate::state::UiState;
+use crate::styled::{LineAction, SectionBox, StyledLine};
+
+/// A module list entry after group expansion.
+pub enum Visible<'a> {
+    Header(Box<StyledLine>),
+    Module(&'a ModuleEntry),
+}
+
+/// Expands `Group` modules into a header line followed by their children,
+/// dropping the children of collapsed groups, and `Section` modules into
+/// their children between the lines opening and closing the box.
+pub fn flatten<'a>(
+    modules: &'a [ModuleEntry],
+    state: &UiState,
+    depth: usize,
+    out: &mut Vec<Visible<'a>>,
+) {
+    for entry in modules {
+        if let Module::Group {
+            title,
+            collapsed,
+            modules: children,
+        } = &entry.module
+        {
+            let is_collapsed = state.is_collapsed(title, *collapsed);
+            let glyph = if is_collapsed { '▸' } else { '▾' };
+            let indent = "  ".repeat(depth);
+            let mut header = StyledLine::plain(format!("{indent}{glyph} {title}"));
+            header.action = Some(LineAction::ToggleGroup {
+                title: title.clone(),
+                default_collapsed: *collapsed,
+            });
+            out.push(Visible::Header(Box::new(header)));
+            if !is_collapsed {
+                flatten(children, state, depth + 1, out);
+            }
+        } else if let Module::Section {
+            title,
+            modules: children,
+            border_color,
+            bg_color,
+        } = &entry.module
+        {
+            let section = SectionBox {
+                border_color: border_color.clone(),
+                bg_color: bg_color.clone(),
+            };
+            let start = StyledLine::section_start(title.clone(), section);
+            out.push(Visible::Header(Box::new(start)));
+            flatten(children, state, depth, out);
+            out.push(Visible::Header(Box::new(StyledLine::section_end())));
+        } else {
+            out.push(Visible::Module(entry));
+        }
+    }
+}
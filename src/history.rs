@@ -0,0 +1,45 @@
+use std::collections::VecDeque;
+
+/// Fixed-size ring of samples, oldest first. `None` marks a missing sample
+/// (a lost ping, a failed read) so gaps stay visible in graphs.
+pub struct History {
+    samples: VecDeque<Option<f64>>,
+    capacity: usize,
+}
+
+impl History {
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            samples: VecDeque::with_capacity(capacity),
+            capacity,
+        }
+    }
+
+    pub fn push(&mut self, sample: Option<f64>) {
+        if self.samples.len() == self.capacity {
+            self.samples.pop_front();
+        }
+        self.samples.push_back(sample);
+    }
+
+    pub fn latest(&self) -> Option<f64> {
+        self.samples.back().copied().flatten()
+    }
+
+    /// Largest of the newest `n` samples.
+    pub fn peak(&self, n: usize) -> Option<f64> {
+        let skip = self.samples.len().saturating_sub(n);
+        self.samples
+            .iter()
+            .skip(skip)
+            .flatten()
+            .copied()
+            .reduce(f64::max)
+    }
+
+    /// The newest `n` samples, oldest first.
+    pub fn recent(&self, n: usize) -> Vec<Option<f64>> {
+        let skip = self.samples.len().saturating_sub(n);
+        self.samples.iter().skip(skip).copied().collect()
+    }
+}
@@ -0,0 +1,46 @@
+use std::sync::OnceLock;
+use std::time::Duration;
+
+const TIMEOUT: Duration = Duration::from_secs(15);
+
+static AGENT: OnceLock<ureq::Agent> = OnceLock::new();
+
+/// Shared blocking client. Only call this from background threads (see
+/// `background::Poller`), never while drawing.
+pub fn agent() -> &'static ureq::Agent {
+    AGENT.get_or_init(|| {
+        ureq::Agent::config_builder()
+            .timeout_global(Some(TIMEOUT))
+            .user_agent(concat!("rustky/", env!("CARGO_PKG_VERSION")))
+            .build()
+            .into()
+    })
+}
+
+/// GETs `url` and returns the body as text.
+pub fn get(url: &str) -> Result<String, String> {
+    get_with_query(url, &[])
+}
+
+/// Like `get`, appending URL-encoded query parameters.
+pub fn get_with_query(url: &str, query: &[(&str, &str)]) -> Result<String, String> {
+    get_with(url, query, &[])
+}
+
+/// Like `get`, with query parameters and extra request headers.
+pub fn get_with(
+    url: &str,
+    query: &[(&str, &str)],
+    headers: &[(&str, &str)],
+) -> Result<String, String> {
+    let mut request = agent().get(url).query_pairs(query.iter().copied());
+    for (name, value) in headers {
+        request = request.header(*name, *value);
+    }
+    request
+        .call()
+        .map_err(|e| format!("{url}: {e}"))?
+        .body_mut()
+        .read_to_string()
+        .map_err(|e| format!("{url}: {e}"))
+}
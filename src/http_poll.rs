@@ -0,0 +1,32 @@
+use std::collections::HashMap;
+use std::sync::{Mutex, OnceLock};
+use std::time::Duration;
+
+use serde_json::Value;
+
+use crate::background::Poller;
+use crate::http;
+
+type Registry = Mutex<HashMap<(String, u64), Poller<Value>>>;
+
+static POLLERS: OnceLock<Registry> = OnceLock::new();
+
+/// The body of `url`, fetched every `interval_ms` in the background. JSON
+/// bodies are parsed; anything else becomes a string value.
+pub fn latest(url: &str, interval_ms: u64) -> Option<Result<Value, String>> {
+    let mut pollers = POLLERS
+        .get_or_init(Default::default)
+        .lock()
+        .unwrap_or_else(|e| e.into_inner());
+    pollers
+        .entry((url.to_string(), interval_ms))
+        .or_insert_with(|| {
+            let url = url.to_string();
+            Poller::spawn_cached(Duration::from_millis(interval_ms), "http", move || {
+                let body = http::get(&url)?;
+                Ok(serde_json::from_str(&body)
+                    .unwrap_or_else(|_| Value::String(body.trim().to_string())))
+            })
+        })
+        .latest()
+}
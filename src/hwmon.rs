@@ -0,0 +1,141 @@
+use std::fs;
+use std::path::Path;
+
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SensorKind {
+    Temp,
+    Fan,
+    Power,
+    Voltage,
+    Current,
+}
+
+impl SensorKind {
+    /// sysfs attribute prefix and the divisor that converts the raw value
+    /// to the display unit (°C, RPM, W, V, A).
+    fn sysfs(self) -> (&'static str, f64) {
+        match self {
+            SensorKind::Temp => ("temp", 1000.0),
+            SensorKind::Fan => ("fan", 1.0),
+            SensorKind::Power => ("power", 1_000_000.0),
+            SensorKind::Voltage => ("in", 1000.0),
+            SensorKind::Current => ("curr", 1000.0),
+        }
+    }
+
+    pub fn unit(self) -> &'static str {
+        match self {
+            SensorKind::Temp => "°C",
+            SensorKind::Fan => " RPM",
+            SensorKind::Power => " W",
+            SensorKind::Voltage => " V",
+            SensorKind::Current => " A",
+        }
+    }
+
+    const ALL: [SensorKind; 5] = [
+        SensorKind::Temp,
+        SensorKind::Fan,
+        SensorKind::Power,
+        SensorKind::Voltage,
+        SensorKind::Current,
+    ];
+}
+
+#[derive(Debug, Clone)]
+pub struct Reading {
+    /// Driver name from `hwmonN/name`, e.g. "coretemp", "nvme", "amdgpu".
+    pub chip: String,
+    /// `*_label` contents, or the attribute stem (e.g. "temp1") if absent.
+    pub label: String,
+    pub kind: SensorKind,
+    pub value: f64,
+    pub max: Option<f64>,
+    pub crit: Option<f64>,
+}
+
+fn read_f64(path: &Path) -> Option<f64> {
+    fs::read_to_string(path).ok()?.trim().parse().ok()
+}
+
+/// Whether runtime PM has put `device` to sleep. Reading its sensors would
+/// wake it, which keeps an idle dGPU powered on hybrid laptops.
+pub fn runtime_suspended(device: &Path) -> bool {
+    fs::read_to_string(device.join("power/runtime_status")).is_ok_and(|s| s.trim() == "suspended")
+}
+
+/// Reads every sensor under `/sys/class/hwmon`, skipping devices that are
+/// runtime-suspended.
+pub fn read_all() -> Vec<Reading> {
+    let mut readings = Vec::new();
+    let Ok(entries) = fs::read_dir("/sys/class/hwmon") else {
+        return readings;
+    };
+    let mut dirs: Vec<_> = entries.flatten().map(|e| e.path()).collect();
+    dirs.sort();
+
+    for dir in dirs {
+        if runtime_suspended(&dir.join("device")) {
+            continue;
+        }
+        let chip = fs::read_to_string(dir.join("name"))
+            .map(|s| s.trim().to_string())
+            .unwrap_or_else(|_| dir.file_name().unwrap_or_default().to_string_lossy().into());
+        readings.extend(read_chip(&dir, &chip));
+    }
+    readings
+}
+
+/// Reads the sensors of a single hwmon directory.
+pub fn read_chip(dir: &Path, chip: &str) -> Vec<Reading> {
+    let mut readings = Vec::new();
+    let Ok(entries) = fs::read_dir(dir) else {
+        return readings;
+    };
+    let mut files: Vec<String> = entries
+        .flatten()
+        .map(|e| e.file_name().to_string_lossy().into_owned())
+        .collect();
+    files.sort();
+
+    for file in &files {
+        for kind in SensorKind::ALL {
+            let (prefix, divisor) = kind.sysfs();
+            // e.g. temp1_input; power also exposes power1_average on some chips
+            let Some(stem) = file
+                .strip_suffix("_input")
+                .or_else(|| file.strip_suffix("_average"))
+            else {
+                continue;
+            };
+            let Some(index) = stem.strip_prefix(prefix) else {
+                continue;
+            };
+            if index.is_empty() || !index.chars().all(|c| c.is_ascii_digit()) {
+                continue;
+            }
+            // Prefer _input when both _input and _average exist.
+            if file.ends_with("_average") && files.contains(&format!("{stem}_input")) {
+                continue;
+            }
+            let Some(raw) = read_f64(&dir.join(file)) else {
+                continue;
+            };
+            let label = fs::read_to_string(dir.join(format!("{stem}_label")))
+                .map(|s| s.trim().to_string())
+                .unwrap_or_else(|_| stem.to_string());
+            readings.push(Reading {
+                chip: chip.to_string(),
+                label,
+                kind,
+                value: raw / divisor,
+                max: read_f64(&dir.join(format!("{stem}_max"))).map(|v| v / divisor),
+                crit: read_f64(&dir.join(format!("{stem}_crit"))).map(|v| v / divisor),
+            });
+        }
+    }
+    readings
+}
@@ -0,0 +1,80 @@
+use std::collections::HashMap;
+use std::path::Path;
+use std::sync::{Mutex, OnceLock};
+use std::time::{Duration, SystemTime};
+
+use skia_rs_canvas::Surface;
+use skia_rs_codec::Image;
+
+use crate::background::Poller;
+use crate::config;
+
+/// How often the file's mtime is checked; it is only decoded again when
+/// that changed, so a rewritten snapshot or cover shows up within this.
+const RELOAD: Duration = Duration::from_secs(2);
+
+type Registry = Mutex<HashMap<String, Poller<Image>>>;
+
+static POLLERS: OnceLock<Registry> = OnceLock::new();
+
+/// The decoded image at `path` (PNG, JPEG, GIF, WebP or SVG), reloaded on
+/// a background thread whenever the file changes.
+pub fn load(path: &str) -> Option<Result<Image, String>> {
+    let mut pollers = POLLERS
+        .get_or_init(Default::default)
+        .lock()
+        .unwrap_or_else(|e| e.into_inner());
+    pollers
+        .entry(path.to_string())
+        .or_insert_with(|| {
+            let path = config::shellexpand(path);
+            let last: Mutex<Option<(SystemTime, Image)>> = Mutex::new(None);
+            Poller::spawn_cached(RELOAD, "image", move || {
+                let path = Path::new(&path);
+                let mtime = std::fs::metadata(path)
+                    .and_then(|meta| meta.modified())
+                    .map_err(|e| format!("{}: {e}", path.display()))?;
+                let mut last = last.lock().unwrap_or_else(|e| e.into_inner());
+                if let Some((seen, image)) = &*last
+                    && *seen == mtime
+                {
+                    return Ok(image.clone());
+                }
+                let image = decode(path)?;
+                *last = Some((mtime, image.clone()));
+                Ok(image)
+            })
+        })
+        .latest()
+}
+
+fn decode(path: &Path) -> Result<Image, String> {
+    let data = std::fs::read(path).map_err(|e| format!("{}: {e}", path.display()))?;
+    let is_svg = path
+        .extension()
+        .is_some_and(|ext| ext.eq_ignore_ascii_case("svg"));
+    let image = if is_svg {
+        rasterize_svg(&data)
+    } else {
+        skia_rs_codec::decode_image(&data).map_err(|e| e.to_string())
+    };
+    image.map_err(|e| format!("{}: {e}", path.display()))
+}
+
+/// Renders an SVG at its own size onto a transparent surface; the
+/// renderer then scales it like any raster image.
+fn rasterize_svg(data: &[u8]) -> Result<Image, String> {
+    let text = std::str::from_utf8(data).map_err(|e| e.to_string())?;
+    let dom = skia_rs_svg::parse_svg(text).map_err(|e| e.to_string())?;
+    let view_box = dom.get_view_box();
+    let (width, height) = match (dom.width, dom.height) {
+        (w, h) if w > 0.0 && h > 0.0 => (w, h),
+        _ => (view_box.width(), view_box.height()),
+    };
+    let mut surface = Surface::new_raster_n32_premul(width.ceil() as i32, height.ceil() as i32)
+        .ok_or("SVG has no size")?;
+    skia_rs_svg::render_svg(&dom, &mut surface.raster_canvas());
+    surface
+        .make_image_snapshot()
+        .ok_or_else(|| "SVG has no size".into())
+}
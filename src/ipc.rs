@@ -0,0 +1,133 @@
+use std::io::{self, BufRead, BufReader, Read, Write};
+use std::os::unix::net::{UnixListener, UnixStream};
+use std::path::PathBuf;
+
+use crate::pages::PageCommand;
+
+pub enum IpcCommand {
+    Page(PageCommand),
+    Scale(ScaleCommand),
+    /// `None` toggles.
+    HighContrast(Option<bool>),
+    /// Show or hide every widget; `None` toggles.
+    Visibility(Option<bool>),
+}
+
+pub enum ScaleCommand {
+    Set(f32),
+    /// Relative change, e.g. `+0.25`.
+    Adjust(f32),
+}
+
+impl ScaleCommand {
+    fn parse(arg: &str) -> Result<Self, String> {
+        let value: f32 = arg
+            .parse()
+            .map_err(|_| format!("invalid scale '{arg}'"))?;
+        if arg.starts_with(['+', '-']) {
+            Ok(ScaleCommand::Adjust(value))
+        } else if value > 0.0 {
+            Ok(ScaleCommand::Set(value))
+        } else {
+            Err("scale must be positive".into())
+        }
+    }
+}
+
+impl IpcCommand {
+    pub fn parse(line: &str) -> Result<Self, String> {
+        let mut words = line.split_whitespace();
+        match (words.next(), words.next()) {
+            (Some("page"), Some(arg)) => Ok(IpcCommand::Page(PageCommand::parse(arg))),
+            (Some("page"), None) => Err("usage: page next|prev|<name>".into()),
+            (Some("scale"), Some(arg)) => Ok(IpcCommand::Scale(ScaleCommand::parse(arg)?)),
+            (Some("scale"), None) => Err("usage: scale <factor>|+<step>|-<step>".into()),
+            (Some("contrast"), arg) => match arg {
+                Some("on") => Ok(IpcCommand::HighContrast(Some(true))),
+                Some("off") => Ok(IpcCommand::HighContrast(Some(false))),
+                Some("toggle") | None => Ok(IpcCommand::HighContrast(None)),
+                Some(_) => Err("usage: contrast on|off|toggle".into()),
+            },
+            (Some("visibility"), arg) => match arg {
+                Some("show") => Ok(IpcCommand::Visibility(Some(true))),
+                Some("hide") => Ok(IpcCommand::Visibility(Some(false))),
+                Some("toggle") | None => Ok(IpcCommand::Visibility(None)),
+                Some(_) => Err("usage: visibility show|hide|toggle".into()),
+            },
+            (Some(other), _) => Err(format!("unknown command '{other}'")),
+            (None, _) => Err("empty command".into()),
+        }
+    }
+}
+
+pub fn socket_path() -> PathBuf {
+    match dirs::runtime_dir() {
+        Some(dir) => dir.join("rustky.sock"),
+        None => {
+            let user = std::env::var("USER").unwrap_or_else(|_| "default".into());
+            std::env::temp_dir().join(format!("rustky-{user}.sock"))
+        }
+    }
+}
+
+/// Binds the control socket, replacing a stale one left by a previous run.
+pub fn bind() -> std::io::Result<UnixListener> {
+    let path = socket_path();
+    if path.exists() && UnixStream::connect(&path).is_err() {
+        let _ = std::fs::remove_file(&path);
+    }
+    let listener = UnixListener::bind(&path)?;
+    listener.set_nonblocking(true)?;
+    Ok(listener)
+}
+
+/// Longest command line a client may send.
+const MAX_COMMAND_LEN: usize = 4096;
+
+/// Reads what a non-blocking client has sent so far into `buf`. `Ok(None)`
+/// until its command line is complete; an error once it hung up without
+/// one or sent too much.
+pub fn read_command(mut stream: &UnixStream, buf: &mut Vec<u8>) -> io::Result<Option<String>> {
+    let mut chunk = [0; 512];
+    loop {
+        if let Some(end) = buf.iter().position(|&b| b == b'\n') {
+            let line = String::from_utf8_lossy(&buf[..end]);
+            return Ok(Some(line.trim().to_string()));
+        }
+        if buf.len() > MAX_COMMAND_LEN {
+            return Err(io::Error::other("command too long"));
+        }
+        match stream.read(&mut chunk) {
+            // Hanging up ends the line too
+            Ok(0) if !buf.is_empty() => buf.push(b'\n'),
+            Ok(0) => return Err(io::ErrorKind::UnexpectedEof.into()),
+            Ok(n) => buf.extend_from_slice(&chunk[..n]),
+            Err(e) if e.kind() == io::ErrorKind::WouldBlock => return Ok(None),
+            Err(e) if e.kind() == io::ErrorKind::Interrupted => {}
+            Err(e) => return Err(e),
+        }
+    }
+}
+
+pub fn reply(mut stream: &UnixStream, result: Result<(), String>) {
+    let msg = match result {
+        Ok(()) => "ok\n".to_string(),
+        Err(e) => format!("error: {e}\n"),
+    };
+    let _ = stream.write_all(msg.as_bytes());
+}
+
+/// Client side of `rustky msg ...`: sends one command and returns the reply.
+pub fn send(command: &str) -> Result<String, String> {
+    let path = socket_path();
+    let mut stream = UnixStream::connect(&path)
+        .map_err(|e| format!("cannot connect to {}: {e}", path.display()))?;
+    stream
+        .write_all(format!("{command}\n").as_bytes())
+        .map_err(|e| format!("write failed: {e}"))?;
+    let mut response = String::new();
+    BufReader::new(&stream)
+        .read_line(&mut response)
+        .map_err(|e| format!("read failed: {e}"))?;
+    Ok(response.trim().to_string())
+}
@@ -0,0 +1,126 @@
+use std::collections::{HashMap, VecDeque};
+use std::io::{BufRead, BufReader};
+use std::process::{Command, Stdio};
+use std::sync::{Arc, Mutex, OnceLock};
+use std::time::Duration;
+
+use serde_json::Value;
+
+#[derive(Debug, Clone)]
+pub struct Entry {
+    /// Microseconds since the epoch.
+    pub timestamp_us: i64,
+    /// syslog priority, 0 (emerg) to 7 (debug).
+    pub priority: u8,
+    pub identifier: String,
+    pub message: String,
+}
+
+/// Most recent entries, oldest first, capped at the largest `lines` asked
+/// for by any module sharing the follower.
+pub struct Tail {
+    pub entries: VecDeque<Entry>,
+    pub capacity: usize,
+    pub error: Option<String>,
+}
+
+/// Followers keyed by unit filter and maximum priority.
+type Registry = Mutex<HashMap<(Option<String>, u8), Arc<Mutex<Tail>>>>;
+
+static FOLLOWERS: OnceLock<Registry> = OnceLock::new();
+
+/// The tail for `unit`/`priority`, starting a `journalctl --follow` thread
+/// on first use.
+pub fn tail(unit: Option<&str>, priority: u8, lines: usize) -> Arc<Mutex<Tail>> {
+    // An empty tail would have nothing to evict to make room
+    let lines = lines.max(1);
+    let mut followers = FOLLOWERS
+        .get_or_init(Default::default)
+        .lock()
+        .unwrap_or_else(|e| e.into_inner());
+    let tail = followers
+        .entry((unit.map(str::to_string), priority))
+        .or_insert_with(|| {
+            let tail = Arc::new(Mutex::new(Tail {
+                entries: VecDeque::with_capacity(lines),
+                capacity: lines,
+                error: None,
+            }));
+            let shared = Arc::clone(&tail);
+            let unit = unit.map(str::to_string);
+            std::thread::spawn(move || follow(unit.as_deref(), priority, &shared));
+            tail
+        })
+        .clone();
+    let mut state = tail.lock().unwrap_or_else(|e| e.into_inner());
+    state.capacity = state.capacity.max(lines);
+    drop(state);
+    tail
+}
+
+/// Runs `journalctl --follow -o json`, restarting it if it exits.
+fn follow(unit: Option<&str>, priority: u8, tail: &Mutex<Tail>) {
+    loop {
+        let backlog = tail.lock().unwrap_or_else(|e| e.into_inner()).capacity;
+        let mut command = Command::new("journalctl");
+        command
+            .args(["--follow", "--output=json", "--no-pager"])
+            .arg(format!("--lines={backlog}"))
+            .arg(format!("--priority={priority}"));
+        if let Some(unit) = unit {
+            command.arg(format!("--unit={unit}"));
+        }
+        match command.stdout(Stdio::piped()).stderr(Stdio::null()).spawn() {
+            Ok(mut child) => {
+                {
+                    let mut tail = tail.lock().unwrap_or_else(|e| e.into_inner());
+                    tail.entries.clear();
+                    tail.error = None;
+                }
+                if let Some(stdout) = child.stdout.take() {
+                    for line in BufReader::new(stdout).lines() {
+                        let Ok(line) = line else { break };
+                        let Some(entry) = parse(&line) else { continue };
+                        let mut tail = tail.lock().unwrap_or_else(|e| e.into_inner());
+                        while tail.entries.len() >= tail.capacity {
+                            tail.entries.pop_front();
+                        }
+                        tail.entries.push_back(entry);
+                    }
+                }
+                let _ = child.wait();
+            }
+            Err(e) => {
+                tail.lock().unwrap_or_else(|e| e.into_inner()).error =
+                    Some(format!("journalctl: {e}"));
+                return;
+            }
+        }
+        std::thread::sleep(Duration::from_secs(5));
+    }
+}
+
+fn parse(line: &str) -> Option<Entry> {
+    let json: Value = serde_json::from_str(line).ok()?;
+    let text = |key: &str| match json.get(key)? {
+        Value::String(s) => Some(s.clone()),
+        // Non-UTF-8 fields are serialized as byte arrays
+        Value::Array(bytes) => {
+            let bytes: Vec<u8> = bytes
+                .iter()
+                .filter_map(|b| b.as_u64())
+                .map(|b| b as u8)
+                .collect();
+            Some(String::from_utf8_lossy(&bytes).into_owned())
+        }
+        _ => None,
+    };
+    Some(Entry {
+        timestamp_us: text("__REALTIME_TIMESTAMP")?.parse().ok()?,
+        priority: text("PRIORITY").and_then(|p| p.parse().ok()).unwrap_or(6),
+        identifier: text("SYSLOG_IDENTIFIER")
+            .or_else(|| text("_COMM"))
+            .unwrap_or_default(),
+        message: text("MESSAGE")?,
+    })
+}
@@ -0,0 +1,77 @@
+use serde_json::Value;
+
+/// Follows a dot path or simple JSONPath: `$.data.items[0].name`,
+/// `data.items.0.name` and `$['odd key']` all work. `$` or an empty path is
+/// the value itself.
+pub fn select<'a>(value: &'a Value, path: &str) -> Option<&'a Value> {
+    let path = path.trim();
+    let path = path.strip_prefix('$').unwrap_or(path);
+    let mut current = value;
+    let mut rest = path;
+    while !rest.is_empty() {
+        let (key, tail) = if let Some(after) = rest.strip_prefix('[') {
+            let (inner, tail) = after.split_once(']')?;
+            (inner.trim_matches(['\'', '"']), tail)
+        } else {
+            let rest_dot = rest.strip_prefix('.').unwrap_or(rest);
+            let end = rest_dot.find(['.', '[']).unwrap_or(rest_dot.len());
+            (&rest_dot[..end], &rest_dot[end..])
+        };
+        rest = tail;
+        if key.is_empty() {
+            continue;
+        }
+        current = match current {
+            Value::Array(items) => items.get(key.parse::<usize>().ok()?)?,
+            Value::Object(map) => map.get(key)?,
+            _ => return None,
+        };
+    }
+    Some(current)
+}
+
+/// Fills `{path}` placeholders from `root`; `{}` is `root` itself. A
+/// `:.N` suffix rounds numbers (`{temp:.1}`), `{{`/`}}` are literal braces,
+/// and missing values show as "?".
+pub fn fill(template: &str, root: &Value) -> String {
+    let mut out = String::new();
+    let mut chars = template.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '{' if chars.peek() == Some(&'{') => {
+                chars.next();
+                out.push('{');
+            }
+            '}' if chars.peek() == Some(&'}') => {
+                chars.next();
+                out.push('}');
+            }
+            '{' => {
+                let spec: String = chars.by_ref().take_while(|&c| c != '}').collect();
+                let (path, precision) = match spec.rsplit_once(":.") {
+                    Some((path, digits)) => (path, digits.parse::<usize>().ok()),
+                    None => (spec.as_str(), None),
+                };
+                out.push_str(&match (select(root, path), precision) {
+                    (Some(Value::Number(n)), Some(precision)) => match n.as_f64() {
+                        Some(f) => format!("{f:.precision$}"),
+                        None => n.to_string(),
+                    },
+                    (Some(value), _) => display(value),
+                    (None, _) => "?".into(),
+                });
+            }
+            c => out.push(c),
+        }
+    }
+    out
+}
+
+/// Strings without quotes, `null` as "-", anything else as compact JSON.
+pub fn display(value: &Value) -> String {
+    match value {
+        Value::String(s) => s.clone(),
+        Value::Null => "-".into(),
+        other => other.to_string(),
+    }
+}
@@ -0,0 +1,108 @@
+use std::sync::Mutex;
+use std::time::Instant;
+
+use serde_json::{Map, Value, json};
+
+use crate::jsonpath;
+
+/// Cumulative /proc/stat counters behind the `*_rate` metrics.
+#[derive(Clone, Copy)]
+struct Counters {
+    ctxt: u64,
+    forks: u64,
+    intr: u64,
+}
+
+/// Last sample and the rates computed from it; rates are only recomputed
+/// after half a second so several modules drawing together don't divide by
+/// a near-zero interval.
+struct Rates {
+    at: Instant,
+    counters: Counters,
+    per_sec: (f64, f64, f64),
+}
+
+static RATES: Mutex<Option<Rates>> = Mutex::new(None);
+
+/// Fills `format`'s placeholders (`{entropy}`, `{fds}`, `{fds_max}`,
+/// `{ctxt_rate}`, `{forks_rate}`, `{intr_rate}`, `{procs_running}`,
+/// `{procs_blocked}`), reading only the files the format refers to.
+pub fn fill(format: &str) -> String {
+    let wanted = |name: &str| format.contains(&format!("{{{name}"));
+    let mut values = Map::new();
+    if wanted("entropy")
+        && let Some(v) = read_u64("/proc/sys/kernel/random/entropy_avail")
+    {
+        values.insert("entropy".into(), json!(v));
+    }
+    if wanted("fds") {
+        // "allocated  free  max"
+        if let Ok(text) = std::fs::read_to_string("/proc/sys/fs/file-nr") {
+            let fields: Vec<u64> = text
+                .split_whitespace()
+                .filter_map(|f| f.parse().ok())
+                .collect();
+            if let [allocated, free, max] = fields[..] {
+                values.insert("fds".into(), json!(allocated - free.min(allocated)));
+                values.insert("fds_max".into(), json!(max));
+            }
+        }
+    }
+    let rates = ["ctxt_rate", "forks_rate", "intr_rate"];
+    let procs = ["procs_running", "procs_blocked"];
+    if rates.iter().chain(&procs).any(|name| wanted(name))
+        && let Ok(stat) = std::fs::read_to_string("/proc/stat")
+    {
+        let field = |key: &str| {
+            stat.lines()
+                .find_map(|l| l.strip_prefix(key)?.strip_prefix(' '))
+                .and_then(|rest| rest.split_whitespace().next()?.parse::<u64>().ok())
+        };
+        for name in procs {
+            if let Some(v) = field(name) {
+                values.insert(name.into(), json!(v));
+            }
+        }
+        if let (Some(ctxt), Some(forks), Some(intr)) =
+            (field("ctxt"), field("processes"), field("intr"))
+        {
+            let (ctxt_rate, forks_rate, intr_rate) = rates_per_sec(Counters { ctxt, forks, intr });
+            values.insert("ctxt_rate".into(), json!(ctxt_rate));
+            values.insert("forks_rate".into(), json!(forks_rate));
+            values.insert("intr_rate".into(), json!(intr_rate));
+        }
+    }
+    jsonpath::fill(format, &Value::Object(values))
+}
+
+fn rates_per_sec(counters: Counters) -> (f64, f64, f64) {
+    let mut rates = RATES.lock().unwrap_or_else(|e| e.into_inner());
+    let now = Instant::now();
+    match rates.as_mut() {
+        Some(last) if now.duration_since(last.at).as_secs_f64() >= 0.5 => {
+            let secs = now.duration_since(last.at).as_secs_f64();
+            let rate = |new: u64, old: u64| new.saturating_sub(old) as f64 / secs;
+            last.per_sec = (
+                rate(counters.ctxt, last.counters.ctxt),
+                rate(counters.forks, last.counters.forks),
+                rate(counters.intr, last.counters.intr),
+            );
+            last.at = now;
+            last.counters = counters;
+            last.per_sec
+        }
+        Some(last) => last.per_sec,
+        None => {
+            *rates = Some(Rates {
+                at: now,
+                counters,
+                per_sec: (0.0, 0.0, 0.0),
+            });
+            (0.0, 0.0, 0.0)
+        }
+    }
+}
+
+fn read_u64(path: &str) -> Option<u64> {
+    std::fs::read_to_string(path).ok()?.trim().parse().ok()
+}
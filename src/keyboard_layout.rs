@@ -0,0 +1,220 @@
+use std::collections::HashMap;
+use std::io::{BufRead, BufReader};
+use std::os::unix::net::UnixStream;
+use std::path::PathBuf;
+use std::process::{Command, Stdio};
+use std::sync::{Mutex, Once, OnceLock};
+use std::time::Duration;
+
+const RULES: &str = "/usr/share/X11/xkb/rules/evdev.xml";
+
+/// Description of the active layout as XKB names it, e.g. "German".
+static LAYOUT: Mutex<Option<String>> = Mutex::new(None);
+/// Group names from the seat's keymap, indexed by group.
+static SEAT_GROUPS: Mutex<Vec<String>> = Mutex::new(Vec::new());
+static WATCH: Once = Once::new();
+
+/// The active layout's description. Sway and Hyprland are followed over
+/// their IPC; elsewhere the seat keymap is used, whose group changes only
+/// reach rustky while it has keyboard focus.
+pub fn current() -> Option<String> {
+    WATCH.call_once(|| {
+        if std::env::var_os("SWAYSOCK").is_some() {
+            std::thread::spawn(watch_sway);
+        } else if let Some(signature) = std::env::var_os("HYPRLAND_INSTANCE_SIGNATURE") {
+            let signature = signature.to_string_lossy().into_owned();
+            std::thread::spawn(move || watch_hyprland(&signature));
+        }
+    });
+    LAYOUT.lock().unwrap_or_else(|e| e.into_inner()).clone()
+}
+
+fn set(name: String) {
+    *LAYOUT.lock().unwrap_or_else(|e| e.into_inner()) = Some(name);
+}
+
+/// Takes the group names from a keymap in XKB text format, e.g.
+/// `name[Group2]="German";`, and shows the first group.
+pub fn seat_keymap(keymap: &str) {
+    let groups: Vec<String> = keymap
+        .lines()
+        .filter_map(|line| {
+            let rest = line.trim().strip_prefix("name[")?;
+            let (group, value) = rest.split_once("]=")?;
+            if !group.to_ascii_lowercase().starts_with("group") {
+                return None;
+            }
+            Some(value.trim_end_matches(';').trim_matches('"').to_string())
+        })
+        .collect();
+    if let Some(first) = groups.first() {
+        set(first.clone());
+    }
+    *SEAT_GROUPS.lock().unwrap_or_else(|e| e.into_inner()) = groups;
+}
+
+/// The seat switched to keymap group `index`.
+pub fn seat_group(index: u32) {
+    let groups = SEAT_GROUPS.lock().unwrap_or_else(|e| e.into_inner());
+    if let Some(name) = groups.get(index as usize) {
+        set(name.clone());
+    }
+}
+
+/// Layout code for a description ("German (no dead keys)" → "de"), from
+/// the XKB rules; `None` for descriptions the rules don't list.
+pub fn short_name(description: &str) -> Option<&'static str> {
+    static CODES: OnceLock<HashMap<String, String>> = OnceLock::new();
+    CODES
+        .get_or_init(|| {
+            std::fs::read_to_string(RULES)
+                .map(|xml| parse_rules(&xml))
+                .unwrap_or_default()
+        })
+        .get(description)
+        .map(String::as_str)
+}
+
+/// Maps every layout and variant description to its layout's name.
+fn parse_rules(xml: &str) -> HashMap<String, String> {
+    let tag = |block: &str, name: &str| -> Vec<String> {
+        let open = format!("<{name}>");
+        let close = format!("</{name}>");
+        block
+            .split(&open)
+            .skip(1)
+            .filter_map(|s| Some(unescape(s.split_once(&close)?.0)))
+            .collect()
+    };
+    let mut codes = HashMap::new();
+    for block in xml.split("<layout>").skip(1) {
+        let block = block.split("</layout>").next().unwrap_or_default();
+        let Some(code) = tag(block, "name").into_iter().next() else {
+            continue;
+        };
+        for description in tag(block, "description") {
+            codes.entry(description).or_insert_with(|| code.clone());
+        }
+    }
+    codes
+}
+
+fn unescape(s: &str) -> String {
+    s.replace("&lt;", "<")
+        .replace("&gt;", ">")
+        .replace("&quot;", "\"")
+        .replace("&apos;", "'")
+        .replace("&amp;", "&")
+}
+
+/// Reads the layout from `swaymsg -t get_inputs`, then follows input
+/// events, restarting the subscription if it exits.
+fn watch_sway() {
+    loop {
+        match swaymsg(&["-t", "get_inputs"]) {
+            Ok(inputs) => {
+                let inputs: Vec<serde_json::Value> =
+                    serde_json::from_str(&inputs).unwrap_or_default();
+                if let Some(name) = inputs.iter().find_map(sway_layout) {
+                    set(name);
+                }
+            }
+            Err(e) => {
+                eprintln!("rustky: {e}");
+                return;
+            }
+        }
+        if let Ok(mut child) = Command::new("swaymsg")
+            .args(["-r", "-m", "-t", "subscribe", "[\"input\"]"])
+            .stdout(Stdio::piped())
+            .stderr(Stdio::null())
+            .spawn()
+        {
+            if let Some(stdout) = child.stdout.take() {
+                for line in BufReader::new(stdout).lines() {
+                    let Ok(line) = line else { break };
+                    let Ok(event) = serde_json::from_str::<serde_json::Value>(&line) else {
+                        continue;
+                    };
+                    if let Some(name) = event.get("input").and_then(sway_layout) {
+                        set(name);
+                    }
+                }
+            }
+            let _ = child.wait();
+        }
+        std::thread::sleep(Duration::from_secs(5));
+    }
+}
+
+fn sway_layout(input: &serde_json::Value) -> Option<String> {
+    if input.get("type")?.as_str()? != "keyboard" {
+        return None;
+    }
+    Some(input.get("xkb_active_layout_name")?.as_str()?.to_string())
+}
+
+fn swaymsg(args: &[&str]) -> Result<String, String> {
+    let output = Command::new("swaymsg")
+        .args(["-r"])
+        .args(args)
+        .output()
+        .map_err(|e| format!("swaymsg: {e}"))?;
+    if !output.status.success() {
+        return Err(format!(
+            "swaymsg: {}",
+            String::from_utf8_lossy(&output.stderr).trim()
+        ));
+    }
+    Ok(String::from_utf8_lossy(&output.stdout).into_owned())
+}
+
+/// Reads the main keyboard's keymap from `hyprctl devices -j`, then
+/// follows `activelayout` events on the event socket.
+fn watch_hyprland(signature: &str) {
+    loop {
+        if let Ok(output) = Command::new("hyprctl").args(["devices", "-j"]).output() {
+            let devices: serde_json::Value =
+                serde_json::from_slice(&output.stdout).unwrap_or_default();
+            let keyboards = devices["keyboards"].as_array().cloned().unwrap_or_default();
+            let main = keyboards
+                .iter()
+                .find(|k| k["main"].as_bool() == Some(true))
+                .or(keyboards.first());
+            if let Some(name) = main.and_then(|k| k["active_keymap"].as_str()) {
+                set(name.to_string());
+            }
+        }
+        match hyprland_socket(signature).map(UnixStream::connect) {
+            Some(Ok(stream)) => {
+                for line in BufReader::new(stream).lines() {
+                    let Ok(line) = line else { break };
+                    // "activelayout>>KEYBOARDNAME,LAYOUTNAME"
+                    if let Some((_, name)) = line
+                        .strip_prefix("activelayout>>")
+                        .and_then(|rest| rest.split_once(','))
+                    {
+                        set(name.to_string());
+                    }
+                }
+            }
+            Some(Err(e)) => eprintln!("rustky: hyprland event socket: {e}"),
+            None => {
+                eprintln!("rustky: hyprland event socket not found");
+                return;
+            }
+        }
+        std::thread::sleep(Duration::from_secs(5));
+    }
+}
+
+/// `$XDG_RUNTIME_DIR/hypr/<sig>/.socket2.sock`, or `/tmp/hypr/…` on older
+/// releases.
+fn hyprland_socket(signature: &str) -> Option<PathBuf> {
+    let runtime = std::env::var_os("XDG_RUNTIME_DIR").map(PathBuf::from);
+    [runtime, Some(PathBuf::from("/tmp"))]
+        .into_iter()
+        .flatten()
+        .map(|dir| dir.join("hypr").join(signature).join(".socket2.sock"))
+        .find(|path| path.exists())
+}
@@ -0,0 +1,219 @@
+use std::collections::HashMap;
+use std::io::{BufRead, BufReader, Write};
+use std::net::{TcpStream, ToSocketAddrs};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex, OnceLock};
+use std::time::Duration;
+
+use crate::background::Poller;
+use crate::config::{self, MailBackend};
+use crate::exec;
+
+const TIMEOUT: Duration = Duration::from_secs(15);
+
+/// Where to look and how to log in; the key for the shared poller.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Account {
+    pub backend: MailBackend,
+    pub host: String,
+    pub port: u16,
+    pub user: String,
+    pub password_command: Option<String>,
+    pub password_env: Option<String>,
+    /// Maildir root.
+    pub path: Option<String>,
+    pub mailboxes: Vec<String>,
+}
+
+type Registry = Mutex<HashMap<Account, Poller<Vec<(String, u32)>>>>;
+
+static POLLERS: OnceLock<Registry> = OnceLock::new();
+
+/// Unread count per mailbox, in configured order, polled in the background.
+pub fn unread(account: &Account, interval: Duration) -> Option<Result<Vec<(String, u32)>, String>> {
+    let mut pollers = POLLERS
+        .get_or_init(Default::default)
+        .lock()
+        .unwrap_or_else(|e| e.into_inner());
+    pollers
+        .entry(account.clone())
+        .or_insert_with(|| {
+            let account = account.clone();
+            Poller::spawn_cached(interval, "mail", move || match account.backend {
+                MailBackend::Imap => imap_unread(&account),
+                MailBackend::Maildir => maildir_unread(&account),
+            })
+        })
+        .latest()
+}
+
+/// The password from `password_env` or the first line `password_command`
+/// prints (which goes through the exec gate like any other command).
+fn password(account: &Account) -> Result<String, String> {
+    if let Some(var) = &account.password_env {
+        return std::env::var(var).map_err(|_| format!("${var} is not set"));
+    }
+    let Some(command) = &account.password_command else {
+        return Err("set password_command or password_env".into());
+    };
+    let output = exec::run(command, &[])?;
+    if !output.status.success() {
+        return Err(format!("password_command exited with {}", output.status));
+    }
+    Ok(String::from_utf8_lossy(&output.stdout)
+        .lines()
+        .next()
+        .unwrap_or_default()
+        .to_string())
+}
+
+/// Logs in over implicit TLS and asks `STATUS <mailbox> (UNSEEN)` for each
+/// mailbox.
+fn imap_unread(account: &Account) -> Result<Vec<(String, u32)>, String> {
+    let password = password(account)?;
+    let addr = (account.host.as_str(), account.port)
+        .to_socket_addrs()
+        .map_err(|e| format!("{}: {e}", account.host))?
+        .next()
+        .ok_or_else(|| format!("{}: no address", account.host))?;
+    let tcp = TcpStream::connect_timeout(&addr, TIMEOUT).map_err(|e| e.to_string())?;
+    tcp.set_read_timeout(Some(TIMEOUT))
+        .map_err(|e| e.to_string())?;
+    tcp.set_write_timeout(Some(TIMEOUT))
+        .map_err(|e| e.to_string())?;
+
+    let roots = rustls::RootCertStore::from_iter(webpki_roots::TLS_SERVER_ROOTS.iter().cloned());
+    let config = rustls::ClientConfig::builder_with_provider(Arc::new(
+        rustls::crypto::ring::default_provider(),
+    ))
+    .with_safe_default_protocol_versions()
+    .map_err(|e| e.to_string())?
+    .with_root_certificates(roots)
+    .with_no_client_auth();
+    let server_name = account
+        .host
+        .clone()
+        .try_into()
+        .map_err(|e| format!("{}: {e}", account.host))?;
+    let conn =
+        rustls::ClientConnection::new(Arc::new(config), server_name).map_err(|e| e.to_string())?;
+    let mut session = Session {
+        stream: BufReader::new(rustls::StreamOwned::new(conn, tcp)),
+        tag: 0,
+    };
+
+    let greeting = session.read_line()?;
+    if !greeting.starts_with("* OK") {
+        return Err(format!("imap: unexpected greeting {:?}", greeting.trim()));
+    }
+    session.command(&format!(
+        "LOGIN {} {}",
+        quote(&account.user),
+        quote(&password)
+    ))?;
+    let mut counts = Vec::new();
+    for mailbox in &account.mailboxes {
+        let untagged = session.command(&format!("STATUS {} (UNSEEN)", quote(mailbox)))?;
+        let unseen = untagged
+            .iter()
+            .find_map(|l| {
+                let rest = l.split("UNSEEN ").nth(1)?;
+                rest.trim_end_matches([')', '\r', '\n']).trim().parse().ok()
+            })
+            .ok_or_else(|| format!("imap: no UNSEEN count for {mailbox}"))?;
+        counts.push((mailbox.clone(), unseen));
+    }
+    let _ = session.command("LOGOUT");
+    Ok(counts)
+}
+
+struct Session {
+    stream: BufReader<rustls::StreamOwned<rustls::ClientConnection, TcpStream>>,
+    tag: u32,
+}
+
+impl Session {
+    fn read_line(&mut self) -> Result<String, String> {
+        let mut line = String::new();
+        match self.stream.read_line(&mut line) {
+            Ok(0) => Err("imap: connection closed".into()),
+            Ok(_) => Ok(line),
+            Err(e) => Err(format!("imap: {e}")),
+        }
+    }
+
+    /// Sends a tagged command and returns its untagged responses once the
+    /// tagged OK arrives.
+    fn command(&mut self, command: &str) -> Result<Vec<String>, String> {
+        self.tag += 1;
+        let tag = format!("a{}", self.tag);
+        let stream = self.stream.get_mut();
+        stream
+            .write_all(format!("{tag} {command}\r\n").as_bytes())
+            .and_then(|_| stream.flush())
+            .map_err(|e| format!("imap: {e}"))?;
+        let mut untagged = Vec::new();
+        loop {
+            let line = self.read_line()?;
+            let Some(status) = line.strip_prefix(&format!("{tag} ")) else {
+                untagged.push(line);
+                continue;
+            };
+            if status.starts_with("OK") {
+                return Ok(untagged);
+            }
+            // Never echo the command: LOGIN carries the password.
+            let verb = command.split(' ').next().unwrap_or_default();
+            return Err(format!("imap {verb}: {}", status.trim()));
+        }
+    }
+}
+
+/// IMAP quoted string.
+fn quote(s: &str) -> String {
+    format!("\"{}\"", s.replace('\\', "\\\\").replace('"', "\\\""))
+}
+
+/// Counts messages in `new/` plus those in `cur/` without the Seen flag.
+/// "INBOX" is the root itself; other mailboxes are `<root>/<name>` or the
+/// Maildir++ `<root>/.<name>`.
+fn maildir_unread(account: &Account) -> Result<Vec<(String, u32)>, String> {
+    let root = account
+        .path
+        .as_deref()
+        .ok_or("maildir backend needs path")?;
+    let root = PathBuf::from(config::shellexpand(root));
+    account
+        .mailboxes
+        .iter()
+        .map(|mailbox| {
+            let dir = if mailbox.eq_ignore_ascii_case("INBOX") {
+                root.clone()
+            } else if root.join(mailbox).join("cur").is_dir() {
+                root.join(mailbox)
+            } else {
+                root.join(format!(".{mailbox}"))
+            };
+            Ok((mailbox.clone(), count_unread(&dir)?))
+        })
+        .collect()
+}
+
+fn count_unread(dir: &Path) -> Result<u32, String> {
+    let list = |sub: &str| {
+        std::fs::read_dir(dir.join(sub)).map_err(|e| format!("{}: {e}", dir.join(sub).display()))
+    };
+    let new = list("new")?.flatten().count() as u32;
+    let unseen_cur = list("cur")?
+        .flatten()
+        .filter(|e| {
+            let name = e.file_name();
+            let name = name.to_string_lossy();
+            // "<unique>:2,<flags>", flags sorted ASCII, S = seen
+            !name
+                .rsplit_once(":2,")
+                .is_some_and(|(_, flags)| flags.contains('S'))
+        })
+        .count() as u32;
+    Ok(new + unseen_cur)
+}
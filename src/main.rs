@@ -1,16 +1,74 @@
+mod alerts;
+mod animation;
+mod background;
+mod backup;
+mod battery;
+mod brightness;
+mod calendar;
+mod cgroup;
+mod clipboard;
 mod config;
+mod countdown;
+mod dirsize;
+mod dnd;
+mod exec;
+mod filter;
+mod fonts;
+mod github;
+mod gpu;
+#[cfg(feature = "gpu")]
+mod gpu_present;
+mod groups;
+mod history;
+mod http;
+mod http_poll;
+mod hwmon;
+mod image;
+mod ipc;
+mod journal;
+mod jsonpath;
+mod kernel;
+mod keyboard_layout;
+mod mail;
 mod monitor;
+mod notifications;
+mod pages;
+mod ping;
+mod pomodoro;
+mod pool;
+mod power;
+mod pressure;
+mod preview;
+mod prometheus;
+mod public_ip;
+mod recurrence;
 mod render;
+mod screenshare;
 mod script_context;
+mod smart;
+mod state;
 mod styled;
+mod systemd;
+mod theme;
+mod ticker;
+mod toplevel;
+mod units;
+mod updates;
+mod ups;
+mod vms;
+mod volume;
+mod vpn;
 mod wayland;
+mod weather;
+mod wifi;
 
 #[cfg(any(feature = "rhai-scripting", feature = "python-scripting"))]
 mod scripting;
 
-use config::Config;
+use config::{Config, MAX_SCALE, MIN_SCALE, Overrides};
 use monitor::Monitor;
 use render::Renderer;
+use units::Format;
 
 fn main() {
     let args: Vec<String> = std::env::args().collect();
@@ -20,16 +78,62 @@ fn main() {
         return;
     }
 
-    let cfg = Config::load();
+    if args.get(1).map(String::as_str) == Some("msg") {
+        let command = args[2..].join(" ");
+        match ipc::send(&command) {
+            Ok(reply) => println!("{reply}"),
+            Err(e) => {
+                eprintln!("rustky: {e}");
+                std::process::exit(1);
+            }
+        }
+        return;
+    }
+
+    // --preview: config errors are shown on the surface and fixed live
+    let preview = args.iter().any(|a| a == "--preview");
+    let (mut cfg, config_error) = if preview {
+        match Config::try_load() {
+            Ok(cfg) => (cfg, None),
+            Err(e) => (Config::default(), Some(e)),
+        }
+    } else {
+        (Config::load(), None)
+    };
     eprintln!("rustky: loaded config, {} modules", cfg.modules.len());
 
-    let renderer = Renderer::new(
-        cfg.general.font_size,
-        &cfg.general.fg_color,
-        &cfg.general.bg_color,
-    );
+    let overrides = accessibility_overrides(&args);
+    overrides.apply(&mut cfg);
+
+    countdown::mark_start();
+    exec::init(cfg.general.allow_exec, cfg.exec.clone());
 
-    let monitor = Monitor::new();
+    let renderer = Renderer::new(&cfg.general, &cfg.theme);
 
-    wayland::run(cfg, renderer, monitor);
+    let monitor = Monitor::new(cfg.gpu.backend, Format::new(&cfg.general));
+
+    let preview_errors = preview.then(|| config_error.into_iter().collect());
+    wayland::run(cfg, overrides, renderer, monitor, preview_errors);
+}
+
+/// `RUSTKY_SCALE`, then `--scale <factor>` and `--high-contrast`, take
+/// precedence over `[general]`.
+fn accessibility_overrides(args: &[String]) -> Overrides {
+    let mut overrides = Overrides::default();
+    let env_scale = std::env::var("RUSTKY_SCALE").ok();
+    let arg_scale = args
+        .iter()
+        .position(|a| a == "--scale")
+        .and_then(|i| args.get(i + 1))
+        .cloned();
+    for value in [env_scale, arg_scale].into_iter().flatten() {
+        match value.parse::<f32>() {
+            Ok(scale) if scale > 0.0 => {
+                overrides.scale = Some(scale.clamp(MIN_SCALE, MAX_SCALE));
+            }
+            _ => eprintln!("rustky: ignoring invalid scale '{value}'"),
+        }
+    }
+    overrides.high_contrast = args.iter().any(|a| a == "--high-contrast");
+    overrides
 }
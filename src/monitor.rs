@@ -1,23 +1,109 @@
-use std::process::Command;
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::time::{Duration, Instant};
 
 use sysinfo::{Disks, Networks, System};
 
-use crate::config::Module;
-use crate::script_context::{DiskInfo, NetworkInfo, ScriptContext};
-use crate::styled::StyledLine;
+use crate::backup;
+use crate::battery::{self, BatteryInfo};
+use crate::brightness;
+use crate::calendar;
+use crate::cgroup::CgroupReader;
+use crate::clipboard::{self, Selection};
+use crate::config::{
+    BarStyle, DisplayMode, ExecProtocol, GaugeMetric, GpuBackend, GraphMetric, Module, ProcessSort,
+    RateUnit, ThresholdStyle, Units, WatchMetric,
+};
+use crate::countdown;
+use crate::dirsize;
+use crate::dnd;
+use crate::exec;
+use crate::github;
+use crate::gpu::{GpuInfo, GpuMonitor};
+use crate::history::History;
+use crate::http_poll;
+use crate::hwmon::{self, SensorKind};
+use crate::image;
+use crate::journal;
+use crate::jsonpath;
+use crate::kernel;
+use crate::keyboard_layout;
+use crate::mail::{self, Account};
+use crate::notifications;
+use crate::ping;
+use crate::pool;
+use crate::power::{self, PowerMeter, PowerZone};
+use crate::pressure::{self, Resource};
+use crate::prometheus;
+use crate::public_ip;
+use crate::screenshare::{self, SessionKind};
+use crate::script_context::{DiskInfo, NetworkInfo, ScriptContext, TemperatureInfo};
+use crate::smart;
+use crate::styled::{
+    Align, Bar, Column, Gauge, Graph, Heatmap, LineAction, LineStyle, Picture, Sparkline,
+    StyledLine,
+};
+use crate::systemd;
+use crate::ticker;
+use crate::toplevel;
+use crate::units::{self, Format};
+use crate::updates::{self, Update, UpdateKind};
+use crate::ups;
+use crate::vms;
+use crate::volume;
+use crate::vpn::{self, TunnelKind};
+use crate::weather;
+use crate::wifi;
+
+/// Refreshes of history kept per metric for graphs.
+pub const HISTORY_LEN: usize = 600;
+/// Refreshes the network peak indicator looks back over.
+const NET_PEAK_LEN: usize = 60;
+
+/// Receive/transmit rate history of one interface, in bytes per second.
+struct NetRates {
+    rx: History,
+    tx: History,
+}
 
 pub struct Monitor {
     sys: System,
     disks: Disks,
     networks: Networks,
+    net_rates: HashMap<String, NetRates>,
+    /// Total CPU and used memory percentages, for graphs.
+    cpu_history: History,
+    mem_history: History,
+    last_refresh: Instant,
+    gpu: GpuMonitor,
+    gpus: Vec<GpuInfo>,
+    power: PowerMeter,
+    power_zones: Vec<PowerZone>,
+    batteries: Vec<BatteryInfo>,
+    temperatures: Vec<TemperatureInfo>,
+    /// Rates need the previous counters; collect() only has `&self`.
+    cgroups: RefCell<CgroupReader>,
+    pub format: Format,
 }
 
 impl Monitor {
-    pub fn new() -> Self {
+    pub fn new(gpu_backend: GpuBackend, format: Format) -> Self {
         Self {
             sys: System::new_all(),
             disks: Disks::new_with_refreshed_list(),
             networks: Networks::new_with_refreshed_list(),
+            net_rates: HashMap::new(),
+            cpu_history: History::new(HISTORY_LEN),
+            mem_history: History::new(HISTORY_LEN),
+            last_refresh: Instant::now(),
+            gpu: GpuMonitor::new(gpu_backend),
+            gpus: Vec::new(),
+            power: PowerMeter::new(),
+            power_zones: Vec::new(),
+            batteries: Vec::new(),
+            temperatures: Vec::new(),
+            cgroups: RefCell::default(),
+            format,
         }
     }
 
@@ -25,9 +111,43 @@ impl Monitor {
         self.sys.refresh_all();
         self.disks.refresh(true);
         self.networks.refresh(true);
+        let now = Instant::now();
+        let elapsed = now.duration_since(self.last_refresh).as_secs_f64();
+        self.last_refresh = now;
+        if elapsed > 0.0 {
+            for (name, data) in self.networks.list() {
+                let rates = self
+                    .net_rates
+                    .entry(name.clone())
+                    .or_insert_with(|| NetRates {
+                        rx: History::new(HISTORY_LEN),
+                        tx: History::new(HISTORY_LEN),
+                    });
+                rates.rx.push(Some(data.received() as f64 / elapsed));
+                rates.tx.push(Some(data.transmitted() as f64 / elapsed));
+            }
+        }
+        self.cpu_history
+            .push(Some(f64::from(self.sys.global_cpu_usage())));
+        let total = self.sys.total_memory();
+        self.mem_history
+            .push((total > 0).then(|| self.sys.used_memory() as f64 / total as f64 * 100.0));
+        self.gpus = self.gpu.collect();
+        self.power_zones = self.power.sample();
+        self.batteries = battery::read_all();
+        self.temperatures = hwmon::read_all()
+            .into_iter()
+            .filter(|r| r.kind == SensorKind::Temp)
+            .map(|r| TemperatureInfo {
+                chip: r.chip,
+                label: r.label,
+                celsius: r.value,
+                max: r.max,
+                crit: r.crit,
+            })
+            .collect();
     }
 
-    #[allow(dead_code)]
     pub fn snapshot(&self) -> ScriptContext {
         let cpu_per_core: Vec<f64> = self
             .sys
@@ -51,17 +171,25 @@ impl Monitor {
             .networks
             .list()
             .iter()
-            .map(|(name, data)| NetworkInfo {
-                interface: name.clone(),
-                rx_bytes: data.total_received(),
-                tx_bytes: data.total_transmitted(),
+            .map(|(name, data)| {
+                let rates = self.net_rates.get(name);
+                NetworkInfo {
+                    interface: name.clone(),
+                    rx_bytes: data.total_received(),
+                    tx_bytes: data.total_transmitted(),
+                    rx_rate: rates.and_then(|r| r.rx.latest()).unwrap_or(0.0),
+                    tx_rate: rates.and_then(|r| r.tx.latest()).unwrap_or(0.0),
+                }
             })
             .collect();
 
+        let load = System::load_average();
+
         ScriptContext {
             cpu_usage: self.sys.global_cpu_usage() as f64,
             cpu_count: self.sys.cpus().len(),
             cpu_per_core,
+            cpu_freq_mhz: self.sys.cpus().iter().map(|cpu| cpu.frequency()).collect(),
             mem_used: self.sys.used_memory(),
             mem_total: self.sys.total_memory(),
             mem_usage_pct: if self.sys.total_memory() > 0 {
@@ -71,8 +199,16 @@ impl Monitor {
             },
             swap_used: self.sys.used_swap(),
             swap_total: self.sys.total_swap(),
+            load_avg_1: load.one,
+            load_avg_5: load.five,
+            load_avg_15: load.fifteen,
             disks,
             networks,
+            temperatures: self.temperatures.clone(),
+            gpus: self.gpus.clone(),
+            package_power_w: power::package_watts(&self.power_zones),
+            power_zones: self.power_zones.clone(),
+            batteries: self.batteries.clone(),
             hostname: System::host_name().unwrap_or_else(|| "unknown".into()),
             uptime_seconds: System::uptime(),
             os_name: System::name(),
@@ -85,6 +221,8 @@ impl Monitor {
             Module::Cpu {
                 label,
                 show_per_core,
+                bar,
+                thresholds,
             } => {
                 if *show_per_core {
                     self.sys
@@ -92,56 +230,608 @@ impl Monitor {
                         .iter()
                         .enumerate()
                         .map(|(i, cpu)| {
-                            StyledLine::plain(format!("  core {i}: {:.1}%", cpu.cpu_usage()))
+                            let usage = cpu.cpu_usage();
+                            let line = bar_line(
+                                format!("  core {i}: {usage:.1}%"),
+                                usage,
+                                bar.as_ref(),
+                                false,
+                            );
+                            with_thresholds(line, f64::from(usage), thresholds)
                         })
                         .collect()
                 } else {
                     let avg = self.sys.global_cpu_usage();
-                    vec![StyledLine::plain(format!("{label}: {avg:.1}%"))]
+                    let line = bar_line(format!("{label}: {avg:.1}%"), avg, bar.as_ref(), false);
+                    vec![with_thresholds(line, f64::from(avg), thresholds)]
+                }
+            }
+            Module::CpuHeatmap {
+                label,
+                columns,
+                cell_size,
+                stops,
+            } => {
+                let avg = self.sys.global_cpu_usage();
+                vec![StyledLine::heatmap(
+                    format!("{label}: {avg:.1}%"),
+                    Heatmap {
+                        values: self.sys.cpus().iter().map(|cpu| cpu.cpu_usage()).collect(),
+                        columns: (*columns).max(1),
+                        cell_size: *cell_size,
+                        stops: stops.clone(),
+                    },
+                )]
+            }
+            Module::CpuFreq {
+                label,
+                show_per_core,
+            } => {
+                let freqs: Vec<u64> = self.sys.cpus().iter().map(|cpu| cpu.frequency()).collect();
+                let (Some(min), Some(max)) = (freqs.iter().min(), freqs.iter().max()) else {
+                    return vec![StyledLine::plain(format!("{label}: n/a"))];
+                };
+                let avg = freqs.iter().sum::<u64>() / freqs.len() as u64;
+                let mut text = format!("{label}: {min}/{avg}/{max} MHz");
+                let governors = scaling_governors();
+                if !governors.is_empty() {
+                    text.push_str(&format!(" | {}", governors.join(", ")));
+                }
+                let mut lines = vec![StyledLine::plain(text)];
+                if *show_per_core {
+                    lines.extend(
+                        freqs
+                            .iter()
+                            .enumerate()
+                            .map(|(i, f)| StyledLine::plain(format!("  cpu{i}: {f} MHz"))),
+                    );
                 }
+                lines
             }
-            Module::Memory { label } => {
-                let used = self.sys.used_memory() as f64 / 1_073_741_824.0;
-                let total = self.sys.total_memory() as f64 / 1_073_741_824.0;
+            Module::Pressure {
+                resources,
+                show_full,
+                warn,
+                critical,
+            } => {
+                let resources = if resources.is_empty() {
+                    &Resource::ALL[..]
+                } else {
+                    &resources[..]
+                };
+                resources
+                    .iter()
+                    .map(|&resource| {
+                        let Some(p) = pressure::read(resource) else {
+                            return StyledLine::plain(format!("PSI {}: n/a", resource.name()));
+                        };
+                        let mut text = format!(
+                            "PSI {}: some {:.1}% / {:.1}%",
+                            resource.name(),
+                            p.some.avg10,
+                            p.some.avg60
+                        );
+                        if let (true, Some(full)) = (*show_full, p.full) {
+                            text.push_str(&format!(
+                                " | full {:.1}% / {:.1}%",
+                                full.avg10, full.avg60
+                            ));
+                        }
+                        threshold_line(text, p.some.avg10, Some(*warn), Some(*critical))
+                    })
+                    .collect()
+            }
+            Module::Memory {
+                label,
+                display,
+                bar,
+                thresholds,
+            } => {
+                let used = self.sys.used_memory() as f64;
+                let total = self.sys.total_memory() as f64;
                 let pct = if total > 0.0 {
                     used / total * 100.0
                 } else {
                     0.0
                 };
-                vec![StyledLine::plain(format!(
-                    "{label}: {used:.1}/{total:.1} GiB ({pct:.0}%)"
-                ))]
+                let text = match display {
+                    DisplayMode::Text => {
+                        format!("{label}: {} ({pct:.0}%)", self.format.bytes_of(used, total))
+                    }
+                    DisplayMode::Bar => format!("{label} {pct:.0}%"),
+                };
+                let line = bar_line(text, pct as f32, display.bar_style(bar).as_ref(), false);
+                vec![with_thresholds(line, pct, thresholds)]
             }
-            Module::Disk { mount_point } => {
+            Module::Disk {
+                mount_point,
+                display,
+                bar,
+                thresholds,
+            } => {
                 for disk in self.disks.list() {
                     if disk.mount_point().to_string_lossy() == mount_point.as_str() {
-                        let total = disk.total_space() as f64 / 1_073_741_824.0;
-                        let avail = disk.available_space() as f64 / 1_073_741_824.0;
+                        let total = disk.total_space() as f64;
+                        let avail = disk.available_space() as f64;
                         let used = total - avail;
-                        return vec![StyledLine::plain(format!(
-                            "DISK {mount_point}: {used:.1}/{total:.1} GiB"
-                        ))];
+                        let pct = if total > 0.0 {
+                            used / total * 100.0
+                        } else {
+                            0.0
+                        };
+                        let text = match display {
+                            DisplayMode::Text => {
+                                format!("DISK {mount_point}: {}", self.format.bytes_of(used, total))
+                            }
+                            DisplayMode::Bar => format!("DISK {mount_point} {pct:.0}%"),
+                        };
+                        let line =
+                            bar_line(text, pct as f32, display.bar_style(bar).as_ref(), false);
+                        return vec![with_thresholds(line, pct, thresholds)];
                     }
                 }
                 vec![StyledLine::plain(format!("DISK {mount_point}: not found"))]
             }
-            Module::Network { interface } => {
-                for (name, data) in self.networks.list() {
-                    if name == interface {
-                        let rx = data.total_received() as f64 / 1_048_576.0;
-                        let tx = data.total_transmitted() as f64 / 1_048_576.0;
-                        return vec![StyledLine::plain(format!(
-                            "NET {interface}: rx {rx:.1} MiB / tx {tx:.1} MiB"
-                        ))];
+            Module::Network {
+                interface,
+                unit,
+                show_peak,
+            } => {
+                if !self.networks.list().contains_key(interface) {
+                    return vec![StyledLine::plain(format!("NET {interface}: not found"))];
+                }
+                let Some(rates) = self.net_rates.get(interface) else {
+                    return vec![StyledLine::plain(format!("NET {interface}: …"))];
+                };
+                let rx = rates.rx.latest().unwrap_or(0.0);
+                let tx = rates.tx.latest().unwrap_or(0.0);
+                let mut text = format!(
+                    "NET {interface}: ↓ {} ↑ {}",
+                    self.format.rate(rx, *unit),
+                    self.format.rate(tx, *unit)
+                );
+                if *show_peak {
+                    text.push_str(&format!(
+                        " (peak ↓ {} ↑ {})",
+                        self.format
+                            .rate(rates.rx.peak(NET_PEAK_LEN).unwrap_or(0.0), *unit),
+                        self.format
+                            .rate(rates.tx.peak(NET_PEAK_LEN).unwrap_or(0.0), *unit)
+                    ));
+                }
+                vec![StyledLine::plain(text)]
+            }
+            Module::Graph {
+                metric,
+                interface,
+                label,
+                samples,
+                height,
+                color,
+                max,
+            } => {
+                let (history, default_label, percent) = match metric {
+                    GraphMetric::Cpu => (Some(&self.cpu_history), "CPU".into(), true),
+                    GraphMetric::Memory => (Some(&self.mem_history), "MEM".into(), true),
+                    GraphMetric::NetRx => (
+                        self.net_rates.get(interface).map(|r| &r.rx),
+                        format!("NET {interface} ↓"),
+                        false,
+                    ),
+                    GraphMetric::NetTx => (
+                        self.net_rates.get(interface).map(|r| &r.tx),
+                        format!("NET {interface} ↑"),
+                        false,
+                    ),
+                };
+                let label = label.as_deref().unwrap_or(&default_label);
+                let Some(history) = history else {
+                    return vec![StyledLine::plain(format!("{label}: …"))];
+                };
+                let slots = (*samples).clamp(2, HISTORY_LEN);
+                let shown = history.recent(slots);
+                let current = match history.latest() {
+                    None => "…".into(),
+                    Some(v) if percent => format!("{v:.1}%"),
+                    Some(v) => self.format.rate(v, RateUnit::Auto),
+                };
+                let max = max.unwrap_or_else(|| {
+                    if percent {
+                        100.0
+                    } else {
+                        history.peak(slots).unwrap_or(0.0).max(1.0)
+                    }
+                });
+                vec![StyledLine::graph(
+                    format!("{label}: {current}"),
+                    Graph {
+                        samples: shown,
+                        slots,
+                        max,
+                        height: *height,
+                        color: color.clone(),
+                    },
+                )]
+            }
+            Module::Gauge {
+                metrics,
+                radius,
+                thickness,
+                stops,
+                columns,
+            } => {
+                let percent =
+                    |used: u64, total: u64| (total > 0).then(|| used as f64 / total as f64 * 100.0);
+                let gauges: Vec<Gauge> = metrics
+                    .iter()
+                    .map(|metric| {
+                        let (label, value) = match metric {
+                            GaugeMetric::Cpu => {
+                                ("CPU", Some(f64::from(self.sys.global_cpu_usage())))
+                            }
+                            GaugeMetric::Memory => (
+                                "MEM",
+                                percent(self.sys.used_memory(), self.sys.total_memory()),
+                            ),
+                            GaugeMetric::Swap => {
+                                ("SWAP", percent(self.sys.used_swap(), self.sys.total_swap()))
+                            }
+                            GaugeMetric::Battery => {
+                                ("BAT", self.batteries.first().map(|b| b.percent))
+                            }
+                        };
+                        let pct = value.unwrap_or(0.0);
+                        let color = stops
+                            .iter()
+                            .filter(|stop| pct >= stop.at)
+                            .max_by(|a, b| a.at.total_cmp(&b.at))
+                            .map_or("muted", |stop| &stop.color);
+                        Gauge {
+                            fraction: (pct / 100.0).clamp(0.0, 1.0) as f32,
+                            value: value.map_or("n/a".into(), |v| format!("{v:.0}%")),
+                            label: label.into(),
+                            radius: *radius,
+                            thickness: *thickness,
+                            color: color.into(),
+                        }
+                    })
+                    .collect();
+                gauges
+                    .chunks((*columns).max(1))
+                    .map(|row| StyledLine::gauges(String::new(), row.to_vec()))
+                    .collect()
+            }
+            Module::Image {
+                path,
+                width,
+                height,
+            } => match image::load(path) {
+                None => vec![StyledLine::plain("IMAGE: …".into())],
+                Some(Err(e)) => vec![StyledLine::plain(format!("IMAGE: {e}"))],
+                Some(Ok(image)) => {
+                    let (w, h) = (image.width() as f32, image.height() as f32);
+                    let (width, height) = match (*width, *height) {
+                        (Some(width), Some(height)) => (width, height),
+                        (Some(width), None) => (width, width * h / w),
+                        (None, Some(height)) => (height * w / h, height),
+                        (None, None) => (w, h),
+                    };
+                    vec![StyledLine::image(
+                        String::new(),
+                        Picture {
+                            image,
+                            width,
+                            height,
+                        },
+                    )]
+                }
+            },
+            Module::Wifi { interface } => {
+                let Some(interface) = interface.clone().or_else(wifi::default_interface) else {
+                    return vec![StyledLine::plain("WIFI: no wireless interface".into())];
+                };
+                let link = match wifi::link(&interface) {
+                    None => return vec![StyledLine::plain("WIFI: …".into())],
+                    Some(Err(e)) => return vec![StyledLine::plain(format!("WIFI: {e}"))],
+                    Some(Ok(link)) => link,
+                };
+                let Some(ssid) = &link.ssid else {
+                    return vec![StyledLine::plain(format!("WIFI {interface}: disconnected"))];
+                };
+                let mut parts = vec![format!("WIFI {ssid}")];
+                if let (Some(dbm), Some(pct)) = (link.signal_dbm, link.quality_pct()) {
+                    parts.push(format!("{pct}% ({dbm} dBm)"));
+                }
+                if let Some(freq) = link.freq_mhz {
+                    parts.push(format!("{:.1} GHz", freq / 1000.0));
+                }
+                if let Some(rate) = link.bitrate_mbps {
+                    parts.push(format!("{rate:.0} Mb/s"));
+                }
+                vec![StyledLine::plain(parts.join(" | "))]
+            }
+            Module::Calendar {
+                ics_path_or_url,
+                horizon_hours,
+                max_events,
+                interval_secs,
+            } => {
+                let specs =
+                    match calendar::events(ics_path_or_url, Duration::from_secs(*interval_secs)) {
+                        None => return vec![StyledLine::plain("CAL: …".into())],
+                        Some(Err(e)) => return vec![StyledLine::plain(format!("CAL: {e}"))],
+                        Some(Ok(specs)) => specs,
+                    };
+                let now = chrono::Local::now().naive_local();
+                let horizon = chrono::Duration::hours(*horizon_hours as i64);
+                let events = calendar::upcoming(&specs, now, horizon);
+                if events.is_empty() {
+                    return vec![StyledLine::plain("CAL: nothing upcoming".into())];
+                }
+                events
+                    .iter()
+                    .take(*max_events)
+                    .map(|e| {
+                        let when = relative_time(e, now);
+                        let mut line = StyledLine::plain(format!("{when:<9} {}", e.summary));
+                        line.filterable = true;
+                        line
+                    })
+                    .collect()
+            }
+            Module::Ticker {
+                symbols,
+                provider,
+                currency,
+                interval_secs,
+            } => {
+                let interval = Duration::from_secs(*interval_secs);
+                let quotes = match ticker::quotes(*provider, symbols, currency, interval) {
+                    None => return vec![StyledLine::plain("TICKER: …".into())],
+                    Some(Err(e)) => return vec![StyledLine::plain(format!("TICKER: {e}"))],
+                    Some(Ok(quotes)) => quotes,
+                };
+                quotes
+                    .iter()
+                    .map(|q| {
+                        let precision = if q.price < 1.0 { 4 } else { 2 };
+                        let mut text =
+                            format!("{} {:.*} {}", q.symbol, precision, q.price, q.currency);
+                        let Some(change) = q.change_24h_pct else {
+                            return StyledLine::plain(text);
+                        };
+                        text.push_str(&format!(" {change:+.2}%"));
+                        let color = if change >= 0.0 { "ok" } else { "critical" };
+                        StyledLine::styled(
+                            text,
+                            LineStyle {
+                                fg_color: Some(color.into()),
+                                ..Default::default()
+                            },
+                        )
+                    })
+                    .collect()
+            }
+            Module::Weather {
+                latitude,
+                longitude,
+                units,
+                interval_secs,
+                forecast_days,
+            } => {
+                let forecast = match weather::forecast(
+                    *latitude,
+                    *longitude,
+                    *units,
+                    *forecast_days,
+                    Duration::from_secs(*interval_secs),
+                ) {
+                    None => return vec![StyledLine::plain("WEATHER: …".into())],
+                    Some(Err(e)) => return vec![StyledLine::plain(format!("WEATHER: {e}"))],
+                    Some(Ok(forecast)) => forecast,
+                };
+                let (temp_unit, speed_unit) = match units {
+                    Units::Metric => ("°C", "km/h"),
+                    Units::Imperial => ("°F", "mph"),
+                };
+                let (icon, text) = weather::describe(forecast.weather_code);
+                let mut lines = vec![StyledLine::plain(format!(
+                    "{icon} {:.0}{temp_unit} {text} | wind {:.0} {speed_unit}",
+                    forecast.temperature, forecast.wind_speed
+                ))];
+                lines.extend(forecast.days.iter().map(|day| {
+                    let weekday = chrono::NaiveDate::parse_from_str(&day.date, "%Y-%m-%d")
+                        .map(|d| d.format("%a").to_string())
+                        .unwrap_or_else(|_| day.date.clone());
+                    let (icon, _) = weather::describe(day.weather_code);
+                    StyledLine::plain(format!(
+                        "  {weekday} {icon} {:.0}–{:.0}{temp_unit}",
+                        day.min, day.max
+                    ))
+                }));
+                lines
+            }
+            Module::Http {
+                url,
+                interval_ms,
+                jsonpath,
+                format,
+            } => {
+                let root = match http_poll::latest(url, *interval_ms) {
+                    None => return vec![StyledLine::plain("HTTP: …".into())],
+                    Some(Err(e)) => return vec![StyledLine::plain(format!("HTTP: {e}"))],
+                    Some(Ok(root)) => root,
+                };
+                let selected = match jsonpath {
+                    Some(path) => match jsonpath::select(&root, path) {
+                        Some(value) => value,
+                        None => {
+                            return vec![StyledLine::plain(format!("HTTP: no match for {path}"))];
+                        }
+                    },
+                    None => &root,
+                };
+                vec![StyledLine::plain(jsonpath::fill(format, selected))]
+            }
+            Module::Github {
+                token_cmd,
+                repos,
+                interval_secs,
+            } => {
+                let status =
+                    match github::status(token_cmd, repos, Duration::from_secs(*interval_secs)) {
+                        None => return vec![StyledLine::plain("GITHUB: …".into())],
+                        Some(Err(e)) => return vec![StyledLine::plain(format!("GITHUB: {e}"))],
+                        Some(Ok(status)) => status,
+                    };
+                let style = |color: &str| LineStyle {
+                    fg_color: Some(color.into()),
+                    ..Default::default()
+                };
+                let unread = match status.unread {
+                    n if n >= github::PAGE_SIZE => format!("{n}+"),
+                    n => n.to_string(),
+                };
+                let header = format!("GITHUB: {unread} unread");
+                let mut lines = vec![if status.unread > 0 {
+                    StyledLine::styled(header, style("warn"))
+                } else {
+                    StyledLine::plain(header)
+                }];
+                lines.extend(status.runs.iter().map(|run| {
+                    let (mark, state, color) = match run.conclusion.as_deref() {
+                        Some("success") => ("✓", "success", "ok"),
+                        Some(c @ ("failure" | "timed_out" | "startup_failure")) => {
+                            ("✗", c, "critical")
+                        }
+                        Some(c) => ("○", c, "muted"),
+                        None => ("●", run.status.as_str(), "primary"),
+                    };
+                    StyledLine::styled(
+                        format!(
+                            "  {mark} {} {} ({}): {state}",
+                            run.repo, run.workflow, run.branch
+                        ),
+                        style(color),
+                    )
+                }));
+                lines
+            }
+            Module::Prometheus {
+                endpoint,
+                query,
+                format,
+                interval_secs,
+            } => match prometheus::sample(endpoint, query, Duration::from_secs(*interval_secs)) {
+                None => vec![StyledLine::plain("PROM: …".into())],
+                Some(Err(e)) => vec![StyledLine::plain(format!("PROM: {e}"))],
+                Some(Ok(sample)) => vec![StyledLine::plain(jsonpath::fill(format, &sample))],
+            },
+            Module::PublicIp {
+                endpoint,
+                interval_secs,
+                show_country,
+            } => {
+                let text = match public_ip::lookup(endpoint, Duration::from_secs(*interval_secs)) {
+                    None => "IP: …".into(),
+                    Some(Err(e)) => format!("IP: {e}"),
+                    Some(Ok(addr)) => match (&addr.country, show_country) {
+                        (Some(country), true) => format!("IP: {} ({country})", addr.ip),
+                        _ => format!("IP: {}", addr.ip),
+                    },
+                };
+                vec![StyledLine::plain(text)]
+            }
+            Module::Vpn { interface, label } => {
+                let name = label.as_deref().unwrap_or(interface);
+                let tunnel = vpn::tunnel(interface).filter(|t| t.up);
+                let Some(tunnel) = tunnel else {
+                    return vec![StyledLine::styled(
+                        format!("VPN {name}: down"),
+                        LineStyle {
+                            fg_color: Some("critical".into()),
+                            ..Default::default()
+                        },
+                    )];
+                };
+                let mut parts = vec![format!("VPN {name}: up")];
+                if tunnel.kind == TunnelKind::WireGuard {
+                    // Without CAP_NET_ADMIN `wg` fails; just show "up" then.
+                    if let Some(Ok(peer)) = vpn::wireguard_peer(interface) {
+                        parts.extend(peer.endpoint);
+                        parts.push(match peer.handshake_age_secs {
+                            Some(age) => format!("handshake {} ago", units::age(age)),
+                            None => "no handshake".into(),
+                        });
                     }
                 }
-                vec![StyledLine::plain(format!("NET {interface}: not found"))]
+                vec![StyledLine::plain(parts.join(" | "))]
+            }
+            Module::Countdown { target, label } => {
+                let target = match countdown::parse_datetime(target) {
+                    Ok(target) => target,
+                    Err(e) => return vec![StyledLine::plain(format!("{label}: {e}"))],
+                };
+                let left = (target - chrono::Local::now()).num_seconds();
+                if left <= 0 {
+                    return vec![StyledLine::styled(
+                        format!("{label}: reached"),
+                        LineStyle {
+                            fg_color: Some("ok".into()),
+                            ..Default::default()
+                        },
+                    )];
+                }
+                vec![StyledLine::plain(format!(
+                    "{label}: {}",
+                    countdown::format_hms(left)
+                ))]
+            }
+            Module::Stopwatch { since, label } => {
+                let elapsed = match since {
+                    None => countdown::uptime_secs(),
+                    Some(since) => match countdown::parse_datetime(since) {
+                        Ok(since) => (chrono::Local::now() - since).num_seconds(),
+                        Err(e) => return vec![StyledLine::plain(format!("{label}: {e}"))],
+                    },
+                };
+                vec![StyledLine::plain(format!(
+                    "{label}: {}",
+                    countdown::format_hms(elapsed)
+                ))]
+            }
+            Module::WindowTitle {
+                label,
+                show_app_id,
+                overflow,
+            } => {
+                let text = match toplevel::focused() {
+                    None => format!("{label}: -"),
+                    Some(window) if *show_app_id && !window.app_id.is_empty() => {
+                        format!("{label}: {} | {}", window.app_id, window.title)
+                    }
+                    Some(window) => format!("{label}: {}", window.title),
+                };
+                let mut line = StyledLine::plain(text);
+                line.style.overflow = *overflow;
+                vec![line]
+            }
+            Module::KeyboardLayout { label, short } => {
+                let layout = match keyboard_layout::current() {
+                    None => "…".to_string(),
+                    Some(name) if *short => keyboard_layout::short_name(&name)
+                        .map(str::to_string)
+                        .unwrap_or(name),
+                    Some(name) => name,
+                };
+                vec![StyledLine::plain(format!("{label}: {layout}"))]
             }
+            Module::Kernel { format } => vec![StyledLine::plain(kernel::fill(format))],
             Module::Uptime => {
-                let secs = System::uptime();
-                let h = secs / 3600;
-                let m = (secs % 3600) / 60;
-                vec![StyledLine::plain(format!("UPTIME: {h}h {m}m"))]
+                vec![StyledLine::plain(format!(
+                    "UPTIME: {}",
+                    units::duration(System::uptime())
+                ))]
             }
             Module::Hostname => {
                 let name = System::host_name().unwrap_or_else(|| "unknown".into());
@@ -151,28 +841,894 @@ impl Monitor {
                 let now = chrono::Local::now();
                 vec![StyledLine::plain(now.format(format).to_string())]
             }
-            Module::Text { content } => vec![StyledLine::plain(content.clone())],
+            Module::Text {
+                content,
+                wrap,
+                overflow,
+            } => {
+                let mut line = StyledLine::plain(content.clone());
+                line.style.wrap = *wrap;
+                line.style.overflow = *overflow;
+                vec![line]
+            }
+            Module::Separator { style } => vec![StyledLine::separator(style.clone())],
+            Module::Sensors { kinds, chip, label } => {
+                let lines: Vec<StyledLine> = hwmon::read_all()
+                    .into_iter()
+                    .filter(|r| kinds.is_empty() || kinds.contains(&r.kind))
+                    .filter(|r| chip.as_ref().is_none_or(|c| r.chip.contains(c.as_str())))
+                    .filter(|r| label.as_ref().is_none_or(|l| r.label.contains(l.as_str())))
+                    .map(|r| {
+                        let precision = if r.kind == SensorKind::Fan { 0 } else { 1 };
+                        let text = format!(
+                            "{} {}: {:.*}{}",
+                            r.chip,
+                            r.label,
+                            precision,
+                            r.value,
+                            r.kind.unit()
+                        );
+                        // Color by the chip's own limits when it reports them
+                        threshold_line(text, r.value, r.max, r.crit)
+                    })
+                    .collect();
+                if lines.is_empty() {
+                    vec![StyledLine::plain("SENSORS: none found".into())]
+                } else {
+                    lines
+                }
+            }
+            Module::Temperature {
+                sensors,
+                warn,
+                critical,
+                thresholds,
+            } => {
+                let lines: Vec<StyledLine> = self
+                    .temperatures
+                    .iter()
+                    .filter(|t| {
+                        let name = format!("{} {}", t.chip, t.label);
+                        sensors.is_empty() || sensors.iter().any(|s| name.contains(s.as_str()))
+                    })
+                    .map(|t| {
+                        let mut styles = limits(warn.or(t.max), critical.or(t.crit));
+                        styles.extend(thresholds.iter().cloned());
+                        let text = format!("{} {}: {:.0}°C", t.chip, t.label, t.celsius);
+                        with_thresholds(StyledLine::plain(text), t.celsius, &styles)
+                    })
+                    .collect();
+                if lines.is_empty() {
+                    vec![StyledLine::plain("TEMP: none found".into())]
+                } else {
+                    lines
+                }
+            }
+            Module::Processes { count, sort_by } => {
+                let mut procs: Vec<_> = self.sys.processes().values().collect();
+                match sort_by {
+                    ProcessSort::Cpu => {
+                        procs.sort_by(|a, b| b.cpu_usage().total_cmp(&a.cpu_usage()))
+                    }
+                    ProcessSort::Memory => procs.sort_by_key(|p| std::cmp::Reverse(p.memory())),
+                }
+                let columns = vec![
+                    Column::flex(Align::Left),
+                    Column::fixed(50.0, Align::Right),
+                    Column::fixed(90.0, Align::Right),
+                ];
+                procs
+                    .into_iter()
+                    .take(*count)
+                    .map(|p| {
+                        let cells = vec![
+                            p.name().to_string_lossy().into_owned(),
+                            format!("{:.1}%", p.cpu_usage()),
+                            self.format.bytes(p.memory() as f64),
+                        ];
+                        let mut line = StyledLine::table_row(cells, columns.clone());
+                        line.filterable = true;
+                        line
+                    })
+                    .collect()
+            }
+            Module::ProcWatch {
+                pattern,
+                label,
+                metrics,
+            } => {
+                let label = label.as_deref().unwrap_or(pattern);
+                let pid = pattern.parse::<u32>().ok();
+                let matching: Vec<_> = self
+                    .sys
+                    .processes()
+                    .iter()
+                    .filter(|(p, process)| match pid {
+                        Some(pid) => p.as_u32() == pid,
+                        None => process.name().to_string_lossy().contains(pattern.as_str()),
+                    })
+                    .map(|(_, process)| process)
+                    .collect();
+                if matching.is_empty() {
+                    return vec![StyledLine::styled(
+                        format!("{label}: not running"),
+                        LineStyle {
+                            fg_color: Some("muted".into()),
+                            ..Default::default()
+                        },
+                    )];
+                }
+                let parts: Vec<String> = metrics
+                    .iter()
+                    .map(|m| match m {
+                        WatchMetric::Cpu => {
+                            let cpu: f32 = matching.iter().map(|p| p.cpu_usage()).sum();
+                            format!("{cpu:.1}%")
+                        }
+                        WatchMetric::Mem => {
+                            let mem: u64 = matching.iter().map(|p| p.memory()).sum();
+                            self.format.bytes(mem as f64)
+                        }
+                        WatchMetric::Count => match matching.len() {
+                            1 => "1 proc".to_string(),
+                            n => format!("{n} procs"),
+                        },
+                    })
+                    .collect();
+                vec![StyledLine::plain(format!("{label}: {}", parts.join(" | ")))]
+            }
+            Module::Cgroup { path, label } => {
+                let label = label.as_deref().unwrap_or(path);
+                let usage = match self.cgroups.borrow_mut().read(path) {
+                    Ok(usage) => usage,
+                    Err(e) => return vec![StyledLine::plain(format!("{label}: {e}"))],
+                };
+                let mut parts = Vec::new();
+                if let Some(cpu) = usage.cpu_pct {
+                    parts.push(format!("CPU {cpu:.1}%"));
+                }
+                if let Some(mem) = usage.memory {
+                    parts.push(format!("MEM {}", self.format.bytes(mem as f64)));
+                }
+                if let (Some(r), Some(w)) = (usage.io_read_per_sec, usage.io_write_per_sec) {
+                    parts.push(format!(
+                        "IO r {} w {}",
+                        self.format.rate(r, RateUnit::Auto),
+                        self.format.rate(w, RateUnit::Auto)
+                    ));
+                }
+                if parts.is_empty() {
+                    parts.push("waiting…".into());
+                }
+                vec![StyledLine::plain(format!("{label}: {}", parts.join(" | ")))]
+            }
+            Module::Flatpak {
+                interval_secs,
+                firmware,
+                show_list,
+            } => {
+                let interval = Duration::from_secs(*interval_secs);
+                let mut lines = update_lines("FLATPAK", updates::flatpak(interval), *show_list);
+                if *firmware {
+                    lines.extend(update_lines(
+                        "FIRMWARE",
+                        updates::firmware(interval),
+                        *show_list,
+                    ));
+                }
+                lines
+            }
+            Module::Clipboard {
+                max_chars,
+                private,
+                ignore_mime,
+            } => {
+                let text = match clipboard::current() {
+                    Selection::Empty => "(empty)".to_string(),
+                    Selection::Sensitive => "[sensitive]".to_string(),
+                    Selection::Other { mime } => format!("[{mime}]"),
+                    Selection::Text { mimes, .. }
+                        if mimes.iter().any(|m| ignore_mime.contains(m)) =>
+                    {
+                        "[hidden]".to_string()
+                    }
+                    Selection::Text { text, .. } if *private && !clipboard::revealed() => {
+                        format!("•••• ({} chars)", text.chars().count())
+                    }
+                    Selection::Text { text, .. } => one_line(&text, *max_chars),
+                };
+                let mut line = StyledLine::plain(format!("CLIP: {text}"));
+                if *private {
+                    line.action = Some(LineAction::ToggleClipboard);
+                }
+                vec![line]
+            }
+            Module::Dnd { daemon } => {
+                let mut line = match dnd::state(*daemon) {
+                    None => StyledLine::plain("DND: …".into()),
+                    Some(Err(e)) => StyledLine::plain(format!("DND: {e}")),
+                    Some(Ok((_, false))) => StyledLine::plain("DND: off".into()),
+                    Some(Ok((_, true))) => StyledLine::styled(
+                        "DND: on".into(),
+                        LineStyle {
+                            fg_color: Some("warn".into()),
+                            ..Default::default()
+                        },
+                    ),
+                };
+                line.action = Some(LineAction::ToggleDnd(*daemon));
+                vec![line]
+            }
+            Module::Notifications {
+                label,
+                count,
+                max_chars,
+                show_app,
+            } => {
+                let (recent, unread) = notifications::recent(*count);
+                let mut header = if unread > 0 {
+                    StyledLine::styled(
+                        format!("{label}: {unread} unread"),
+                        LineStyle {
+                            fg_color: Some("warn".into()),
+                            ..Default::default()
+                        },
+                    )
+                } else {
+                    StyledLine::plain(format!("{label}: none unread"))
+                };
+                header.action = Some(LineAction::MarkNotificationsRead);
+                let mut lines = vec![header];
+                lines.extend(recent.iter().map(|n| {
+                    let text = if *show_app && !n.app.is_empty() {
+                        format!("{}: {}", n.app, n.summary)
+                    } else {
+                        n.summary.clone()
+                    };
+                    StyledLine::filterable(format!(
+                        "  {} ({})",
+                        one_line(&text, *max_chars),
+                        units::age(n.received.elapsed().as_secs())
+                    ))
+                }));
+                lines
+            }
+            Module::ScreenShare { hide_idle } => {
+                let sessions = screenshare::active();
+                if sessions.is_empty() {
+                    return if *hide_idle {
+                        Vec::new()
+                    } else {
+                        vec![StyledLine::plain("SHARE: idle".into())]
+                    };
+                }
+                let what = if sessions.contains(&SessionKind::RemoteDesktop) {
+                    "REMOTE CONTROL"
+                } else {
+                    "SCREEN SHARING"
+                };
+                let count = match sessions.len() {
+                    1 => String::new(),
+                    n => format!(" ({n} sessions)"),
+                };
+                vec![StyledLine::styled(
+                    format!("● {what}{count}"),
+                    LineStyle {
+                        fg_color: Some("fg".into()),
+                        bg_color: Some("critical".into()),
+                        ..Default::default()
+                    },
+                )]
+            }
+            Module::Gpu {
+                index,
+                show_processes,
+            } => {
+                let gpus: Vec<&GpuInfo> = self
+                    .gpus
+                    .iter()
+                    .filter(|g| index.is_none_or(|i| g.index == i))
+                    .collect();
+                if gpus.is_empty() {
+                    return vec![StyledLine::plain("GPU: not available".into())];
+                }
+                let mut lines = Vec::new();
+                for g in gpus {
+                    let mut parts = vec![format!("GPU{} {}", g.index, g.name)];
+                    if let Some(util) = g.utilization_pct {
+                        parts.push(format!("{util:.0}%"));
+                    }
+                    if let (Some(used), Some(total)) = (g.vram_used, g.vram_total) {
+                        parts.push(format!(
+                            "VRAM {}",
+                            self.format.bytes_of(used as f64, total as f64)
+                        ));
+                    }
+                    if let Some(temp) = g.temperature_c {
+                        parts.push(format!("{temp:.0}°C"));
+                    }
+                    if let Some(power) = g.power_w {
+                        parts.push(format!("{power:.0} W"));
+                    }
+                    lines.push(StyledLine::plain(parts.join(" | ")));
+                    if *show_processes {
+                        let columns =
+                            vec![Column::flex(Align::Left), Column::fixed(90.0, Align::Right)];
+                        lines.extend(g.processes.iter().map(|p| {
+                            let cells = vec![p.name.clone(), self.format.bytes(p.vram_used as f64)];
+                            let mut line = StyledLine::table_row(cells, columns.clone());
+                            line.filterable = true;
+                            line
+                        }));
+                    }
+                }
+                lines
+            }
+            Module::DirSize {
+                path,
+                label,
+                interval_secs,
+            } => {
+                let label = label.as_deref().unwrap_or_else(|| {
+                    path.trim_end_matches('/')
+                        .rsplit('/')
+                        .next()
+                        .unwrap_or(path)
+                });
+                match dirsize::usage(path, Duration::from_secs(*interval_secs)) {
+                    None => vec![StyledLine::plain(format!("{label}: …"))],
+                    Some(Err(e)) => vec![StyledLine::plain(format!("{label}: {e}"))],
+                    Some(Ok(usage)) => vec![StyledLine::plain(format!(
+                        "{label}: {} | {} files",
+                        self.format.bytes(usage.bytes as f64),
+                        usage.files
+                    ))],
+                }
+            }
+            Module::BackupStatus {
+                path,
+                command,
+                label,
+                max_age_hours,
+                interval_secs,
+            } => match backup::last_backup(
+                path.as_deref(),
+                command.as_deref(),
+                Duration::from_secs(*interval_secs),
+            ) {
+                None => vec![StyledLine::plain(format!("{label}: …"))],
+                Some(Err(e)) => vec![StyledLine::plain(format!("{label}: {e}"))],
+                Some(Ok(at)) => {
+                    let age = (chrono::Utc::now().timestamp() - at).max(0) as u64;
+                    vec![threshold_line(
+                        format!("{label}: {} ago", units::age(age)),
+                        age as f64 / 3600.0,
+                        None,
+                        Some(*max_age_hours),
+                    )]
+                }
+            },
+            Module::Ups {
+                backend,
+                target,
+                label,
+                interval_secs,
+            } => {
+                let ups = match ups::status(
+                    *backend,
+                    target.as_deref(),
+                    Duration::from_secs(*interval_secs),
+                ) {
+                    None => return vec![StyledLine::plain(format!("{label}: …"))],
+                    Some(Err(e)) => return vec![StyledLine::plain(format!("{label}: {e}"))],
+                    Some(Ok(ups)) => ups,
+                };
+                let mut parts = Vec::new();
+                if let Some(charge) = ups.charge {
+                    parts.push(format!("{charge:.0}%"));
+                }
+                if let Some(load) = ups.load {
+                    parts.push(format!("load {load:.0}%"));
+                }
+                if let Some(secs) = ups.runtime_secs {
+                    parts.push(format!("{} left", format_hm(secs)));
+                }
+                parts.push(
+                    if ups.on_battery {
+                        "on battery"
+                    } else {
+                        "on mains"
+                    }
+                    .into(),
+                );
+                let text = format!("{label}: {}", parts.join(" | "));
+                let color = if ups.low_battery {
+                    "critical"
+                } else if ups.on_battery {
+                    "warn"
+                } else {
+                    return vec![StyledLine::plain(text)];
+                };
+                vec![StyledLine::styled(
+                    text,
+                    LineStyle {
+                        fg_color: Some(color.into()),
+                        ..Default::default()
+                    },
+                )]
+            }
+            Module::Smart {
+                device,
+                interval_secs,
+            } => {
+                let name = device.rsplit('/').next().unwrap_or(device);
+                let health = match smart::health(device, Duration::from_secs(*interval_secs)) {
+                    None => return vec![StyledLine::plain(format!("SMART {name}: …"))],
+                    Some(Err(e)) => return vec![StyledLine::plain(format!("SMART {name}: {e}"))],
+                    Some(Ok(health)) => health,
+                };
+                let mut parts = vec![if health.passed { "PASSED" } else { "FAILING" }.to_string()];
+                if let Some(t) = health.temperature {
+                    parts.push(format!("{t}°C"));
+                }
+                let errors = match (health.reallocated, health.media_errors) {
+                    (Some(n), _) => {
+                        parts.push(format!("{n} reallocated"));
+                        n
+                    }
+                    (None, Some(n)) => {
+                        parts.push(format!("{n} media errors"));
+                        n
+                    }
+                    (None, None) => 0,
+                };
+                let color = match (health.passed, errors) {
+                    (false, _) => "critical",
+                    (true, 0) => "ok",
+                    (true, _) => "warn",
+                };
+                vec![StyledLine::styled(
+                    format!("SMART {name}: {}", parts.join(" | ")),
+                    LineStyle {
+                        fg_color: Some(color.into()),
+                        ..Default::default()
+                    },
+                )]
+            }
+            Module::PoolStatus {
+                zpools,
+                btrfs,
+                interval_secs,
+            } => {
+                let pools = match pool::status(zpools, btrfs, Duration::from_secs(*interval_secs)) {
+                    None => return vec![StyledLine::plain("POOL: …".into())],
+                    Some(Err(e)) => return vec![StyledLine::plain(format!("POOL: {e}"))],
+                    Some(Ok(pools)) => pools,
+                };
+                let red = || LineStyle {
+                    fg_color: Some("critical".into()),
+                    ..Default::default()
+                };
+                let mut lines = Vec::new();
+                for pool in pools {
+                    let mut text = format!("POOL {}: {}", pool.name, pool.health);
+                    if let Some(pct) = pool.scrub {
+                        text.push_str(&format!(" | scrub {pct:.1}%"));
+                    }
+                    lines.push(if pool.healthy() {
+                        StyledLine::plain(text)
+                    } else {
+                        StyledLine::styled(text, red())
+                    });
+                    lines.extend(
+                        pool.problems
+                            .iter()
+                            .map(|p| StyledLine::styled(format!("  ⚠ {p}"), red())),
+                    );
+                }
+                lines
+            }
+            Module::Vms { uri, running_only } => {
+                let domains = match vms::domains(uri) {
+                    None => return vec![StyledLine::plain("VMS: …".into())],
+                    Some(Err(e)) => return vec![StyledLine::plain(format!("VMS: {e}"))],
+                    Some(Ok(domains)) => domains,
+                };
+                let domains: Vec<_> = domains
+                    .into_iter()
+                    .filter(|d| !*running_only || d.state != "shut off")
+                    .collect();
+                if domains.is_empty() {
+                    return vec![StyledLine::plain("VMS: none".into())];
+                }
+                let columns = vec![
+                    Column::flex(Align::Left),
+                    Column::fixed(100.0, Align::Center),
+                    Column::fixed(40.0, Align::Right),
+                    Column::fixed(70.0, Align::Right),
+                ];
+                domains
+                    .iter()
+                    .map(|d| {
+                        let cpu = d
+                            .cpu_pct
+                            .map_or_else(|| "-".to_string(), |pct| format!("{pct:.0}%"));
+                        let mem = d
+                            .memory_kib
+                            .filter(|_| d.state != "shut off")
+                            .map_or_else(String::new, |kib| self.format.bytes(kib as f64 * 1024.0));
+                        let color = match d.state {
+                            "running" => "ok",
+                            "paused" | "suspended" | "shutting down" => "warn",
+                            "crashed" => "critical",
+                            _ => "muted",
+                        };
+                        let cells = vec![d.name.clone(), d.state.to_string(), cpu, mem];
+                        let mut line = StyledLine::table_row(cells, columns.clone());
+                        line.style.fg_color = Some(color.into());
+                        line.filterable = true;
+                        line
+                    })
+                    .collect()
+            }
+            Module::Systemd {
+                units,
+                user,
+                show_failed,
+            } => {
+                let status = match systemd::status(units, *user) {
+                    None => return vec![StyledLine::plain("SYSTEMD: …".into())],
+                    Some(Err(e)) => return vec![StyledLine::plain(format!("SYSTEMD: {e}"))],
+                    Some(Ok(status)) => status,
+                };
+                let mut lines: Vec<StyledLine> = status
+                    .units
+                    .iter()
+                    .map(|u| {
+                        let color = match u.active_state.as_str() {
+                            "active" => "ok",
+                            "failed" => "critical",
+                            "activating" | "deactivating" | "reloading" => "warn",
+                            _ => "muted",
+                        };
+                        StyledLine::styled(
+                            format!("{}: {} ({})", u.id, u.active_state, u.sub_state),
+                            LineStyle {
+                                fg_color: Some(color.into()),
+                                ..Default::default()
+                            },
+                        )
+                    })
+                    .collect();
+                if *show_failed {
+                    let text = format!("SYSTEMD: {} failed", status.failed);
+                    lines.push(if status.failed > 0 {
+                        StyledLine::styled(
+                            text,
+                            LineStyle {
+                                fg_color: Some("critical".into()),
+                                ..Default::default()
+                            },
+                        )
+                    } else {
+                        StyledLine::plain(text)
+                    });
+                }
+                lines
+            }
+            Module::Journal {
+                unit,
+                lines,
+                priority,
+                max_chars,
+            } => {
+                let tail = journal::tail(unit.as_deref(), *priority, *lines);
+                let tail = tail.lock().unwrap_or_else(|e| e.into_inner());
+                if let Some(e) = &tail.error {
+                    return vec![StyledLine::plain(format!("JOURNAL: {e}"))];
+                }
+                let skip = tail.entries.len().saturating_sub(*lines);
+                tail.entries
+                    .iter()
+                    .skip(skip)
+                    .map(|e| {
+                        let time = chrono::DateTime::from_timestamp_micros(e.timestamp_us)
+                            .map(|t| t.with_timezone(&chrono::Local).format("%H:%M").to_string())
+                            .unwrap_or_default();
+                        let text = one_line(
+                            &format!("{time} {}: {}", e.identifier, e.message),
+                            *max_chars,
+                        );
+                        // err and worse red, warning orange
+                        let color = match e.priority {
+                            0..=3 => Some("critical"),
+                            4 => Some("warn"),
+                            _ => None,
+                        };
+                        let mut line = match color {
+                            Some(c) => StyledLine::styled(
+                                text,
+                                LineStyle {
+                                    fg_color: Some(c.into()),
+                                    ..Default::default()
+                                },
+                            ),
+                            None => StyledLine::plain(text),
+                        };
+                        line.filterable = true;
+                        line
+                    })
+                    .collect()
+            }
+            Module::Mail {
+                label,
+                backend,
+                host,
+                port,
+                user,
+                password_command,
+                password_env,
+                path,
+                mailboxes,
+                interval_secs,
+            } => {
+                let account = Account {
+                    backend: *backend,
+                    host: host.clone(),
+                    port: *port,
+                    user: user.clone(),
+                    password_command: password_command.clone(),
+                    password_env: password_env.clone(),
+                    path: path.clone(),
+                    mailboxes: mailboxes.clone(),
+                };
+                let counts = match mail::unread(&account, Duration::from_secs(*interval_secs)) {
+                    None => return vec![StyledLine::plain(format!("{label}: …"))],
+                    Some(Err(e)) => return vec![StyledLine::plain(format!("{label}: {e}"))],
+                    Some(Ok(counts)) => counts,
+                };
+                let text = match counts.as_slice() {
+                    [(_, unread)] => format!("{label}: {unread} unread"),
+                    _ => format!(
+                        "{label}: {}",
+                        counts
+                            .iter()
+                            .map(|(mailbox, unread)| format!("{mailbox} {unread}"))
+                            .collect::<Vec<_>>()
+                            .join(" | ")
+                    ),
+                };
+                if counts.iter().any(|(_, unread)| *unread > 0) {
+                    vec![StyledLine::styled(
+                        text,
+                        LineStyle {
+                            fg_color: Some("primary".into()),
+                            ..Default::default()
+                        },
+                    )]
+                } else {
+                    vec![StyledLine::plain(text)]
+                }
+            }
+            Module::Volume {
+                label,
+                click_to_mute,
+            } => {
+                let mut line = match volume::sink() {
+                    None => StyledLine::plain(format!("{label}: …")),
+                    Some(Err(e)) => StyledLine::plain(format!("{label}: {e}")),
+                    Some(Ok(sink)) if sink.muted => StyledLine::styled(
+                        format!("{label}: {}% (muted)", sink.volume_pct),
+                        LineStyle {
+                            fg_color: Some("muted".into()),
+                            ..Default::default()
+                        },
+                    ),
+                    Some(Ok(sink)) => StyledLine::plain(format!("{label}: {}%", sink.volume_pct)),
+                };
+                if *click_to_mute {
+                    line.action = Some(LineAction::ToggleMute);
+                }
+                vec![line]
+            }
+            Module::Brightness {
+                label,
+                device,
+                scroll_step,
+            } => {
+                let Some(backlight) = brightness::read(device.as_deref()) else {
+                    return vec![StyledLine::plain(format!("{label}: n/a"))];
+                };
+                let mut line = StyledLine::plain(format!("{label}: {:.0}%", backlight.percent()));
+                line.action = scroll_step.map(|step| LineAction::AdjustBrightness {
+                    device: backlight.device,
+                    step,
+                });
+                vec![line]
+            }
+            Module::Battery {
+                label,
+                name,
+                display,
+                bar,
+                thresholds,
+            } => {
+                let batteries: Vec<&BatteryInfo> = self
+                    .batteries
+                    .iter()
+                    .filter(|b| name.as_ref().is_none_or(|n| &b.name == n))
+                    .collect();
+                if batteries.is_empty() {
+                    return vec![StyledLine::plain(format!("{label}: n/a"))];
+                }
+                let multiple = batteries.len() > 1;
+                let style = display.bar_style(bar);
+                batteries
+                    .into_iter()
+                    .map(|b| {
+                        if *display == DisplayMode::Bar {
+                            let text = if multiple {
+                                format!("{label} {} {:.0}%", b.name, b.percent)
+                            } else {
+                                format!("{label} {:.0}%", b.percent)
+                            };
+                            let line = bar_line(text, b.percent as f32, style.as_ref(), true);
+                            return with_thresholds(line, b.percent, thresholds);
+                        }
+                        let mut text = if multiple {
+                            format!("{label} {}: {:.0}%", b.name, b.percent)
+                        } else {
+                            format!("{label}: {:.0}%", b.percent)
+                        };
+                        text.push_str(&format!(" {}", b.status.to_lowercase()));
+                        if let Some(secs) = b.time_to_empty_secs {
+                            text.push_str(&format!(", {} left", format_hm(secs)));
+                        } else if let Some(secs) = b.time_to_full_secs {
+                            text.push_str(&format!(", {} to full", format_hm(secs)));
+                        }
+                        let line = bar_line(text, b.percent as f32, style.as_ref(), true);
+                        with_thresholds(line, b.percent, thresholds)
+                    })
+                    .collect()
+            }
+            Module::Power {
+                label,
+                show_zones,
+                show_battery,
+            } => {
+                let mut parts = Vec::new();
+                if let Some(total) = power::package_watts(&self.power_zones) {
+                    parts.push(format!("CPU {total:.1} W"));
+                }
+                let discharging: Vec<f64> = self
+                    .batteries
+                    .iter()
+                    .filter(|b| b.status == "Discharging")
+                    .filter_map(|b| b.power_w)
+                    .collect();
+                if *show_battery && !discharging.is_empty() {
+                    parts.push(format!("battery {:.1} W", discharging.iter().sum::<f64>()));
+                }
+                if parts.is_empty() {
+                    return vec![StyledLine::plain(format!("{label}: n/a"))];
+                }
+                let mut lines = vec![StyledLine::plain(format!("{label}: {}", parts.join(" | ")))];
+                if *show_zones {
+                    lines.extend(
+                        self.power_zones
+                            .iter()
+                            .map(|z| StyledLine::plain(format!("  {}: {:.1} W", z.name, z.watts))),
+                    );
+                }
+                lines
+            }
+            Module::LatencyGraph {
+                host,
+                label,
+                interval_ms,
+                width,
+            } => {
+                let label = label.as_deref().unwrap_or(host);
+                let state = ping::prober(host, Duration::from_millis(*interval_ms));
+                let state = state.lock().unwrap_or_else(|e| e.into_inner());
+                let samples = state.history.recent(*width);
+                let received: Vec<f64> = samples.iter().flatten().copied().collect();
+
+                let summary = match (samples.last(), &state.error) {
+                    (None, _) => "waiting…".to_string(),
+                    (_, Some(e)) => e.clone(),
+                    (Some(last), None) => {
+                        let loss = (samples.len() - received.len()) as f64 / samples.len() as f64;
+                        let avg = received.iter().sum::<f64>() / received.len().max(1) as f64;
+                        let current = last.map_or("timeout".into(), |ms| format!("{ms:.1} ms"));
+                        format!("{current} (avg {avg:.1} ms, loss {:.0}%)", loss * 100.0)
+                    }
+                };
+                let text = format!("PING {label}: {summary}");
+                let header = if samples.last().is_some_and(Option::is_none) {
+                    StyledLine::styled(
+                        text,
+                        LineStyle {
+                            fg_color: Some("critical".into()),
+                            ..Default::default()
+                        },
+                    )
+                } else {
+                    StyledLine::plain(text)
+                };
+                // Leading gaps keep the newest sample at the right edge
+                let mut padded = vec![None; width.saturating_sub(samples.len())];
+                padded.extend(samples);
+                let sparkline = Sparkline {
+                    samples: padded,
+                    width: *width as f32 * 2.0,
+                    color: None,
+                };
+                vec![header, StyledLine::sparkline(String::new(), sparkline)]
+            }
+            Module::Exec {
+                command,
+                label,
+                style,
+                protocol: ExecProtocol::I3blocks,
+                instance,
+                markup,
+            } => {
+                let name = label.as_deref().unwrap_or("exec");
+                let env = exec::i3blocks_env(name, instance.as_deref(), 0, 0.0, 0.0);
+                let output = exec::take_i3blocks_click(command, instance.as_deref())
+                    .map_or_else(|| exec::run(command, &env), Ok);
+                let mut line = match output {
+                    Ok(output) => exec::parse_i3blocks(&output, label.as_deref()),
+                    Err(e) => StyledLine::plain(e),
+                };
+                if let Some(s) = style {
+                    line.style = LineStyle {
+                        fg_color: line.style.fg_color.or(s.fg_color.clone()),
+                        bg_color: line.style.bg_color.or(s.bg_color.clone()),
+                        ..(**s).clone()
+                    };
+                }
+                line.action = Some(LineAction::I3blocksClick {
+                    command: command.clone(),
+                    name: name.to_string(),
+                    instance: instance.clone(),
+                });
+                if *markup {
+                    line = line.with_markup();
+                }
+                vec![line]
+            }
             Module::Exec {
                 command,
                 label,
                 style,
+                markup,
+                ..
             } => {
-                let output = Command::new("sh")
-                    .arg("-c")
-                    .arg(command)
-                    .output()
+                let output = exec::run(command, &[])
                     .map(|o| String::from_utf8_lossy(&o.stdout).trim().to_string())
-                    .unwrap_or_else(|e| format!("exec error: {e}"));
+                    .unwrap_or_else(|e| e);
                 let text = if let Some(lbl) = label {
                     format!("{lbl}: {output}")
                 } else {
                     output
                 };
-                if let Some(s) = style {
-                    vec![StyledLine::styled(text, s.clone())]
+                let line = if let Some(s) = style {
+                    StyledLine::styled(text, (**s).clone())
                 } else {
-                    vec![StyledLine::plain(text)]
-                }
+                    StyledLine::plain(text)
+                };
+                vec![if *markup { line.with_markup() } else { line }]
+            }
+            Module::Group { .. } | Module::Section { .. } => {
+                // Groups and sections are expanded into headers and children
+                // in wayland.rs
+                Vec::new()
+            }
+            Module::Pomodoro { .. } => {
+                // The timer lives in the UI state; wayland.rs renders it
+                Vec::new()
             }
             #[cfg(feature = "rhai-scripting")]
             Module::Rhai { .. } => {
@@ -187,3 +1743,161 @@ impl Monitor {
         }
     }
 }
+
+fn update_lines(
+    label: &str,
+    result: Option<Result<Vec<Update>, String>>,
+    show_list: bool,
+) -> Vec<StyledLine> {
+    let list = match result {
+        None => return vec![StyledLine::plain(format!("{label}: checking…"))],
+        Some(Err(e)) => return vec![StyledLine::plain(format!("{label}: {e}"))],
+        Some(Ok(list)) => list,
+    };
+    if list.is_empty() {
+        return vec![StyledLine::plain(format!("{label}: up to date"))];
+    }
+
+    let count = |kind| list.iter().filter(|u| u.kind == kind).count();
+    let summary: Vec<String> = [
+        (UpdateKind::App, "app"),
+        (UpdateKind::Runtime, "runtime"),
+        (UpdateKind::Firmware, "device"),
+    ]
+    .into_iter()
+    .filter_map(|(kind, noun)| match count(kind) {
+        0 => None,
+        1 => Some(format!("1 {noun}")),
+        n => Some(format!("{n} {noun}s")),
+    })
+    .collect();
+    let mut lines = vec![StyledLine::styled(
+        format!("{label}: {}", summary.join(", ")),
+        LineStyle {
+            fg_color: Some("warn".into()),
+            ..Default::default()
+        },
+    )];
+    if show_list {
+        lines.extend(
+            list.iter()
+                .map(|u| StyledLine::filterable(format!("  {}", u.name))),
+        );
+    }
+    lines
+}
+
+/// 4500 → "1:15".
+fn format_hm(secs: u64) -> String {
+    format!("{}:{:02}", secs / 3600, secs / 60 % 60)
+}
+
+/// Orange above `warn`, red above `crit`.
+fn threshold_line(text: String, value: f64, warn: Option<f64>, crit: Option<f64>) -> StyledLine {
+    with_thresholds(StyledLine::plain(text), value, &limits(warn, crit))
+}
+
+/// `warn` and `crit` as thresholds, for `with_thresholds`.
+fn limits(warn: Option<f64>, crit: Option<f64>) -> Vec<ThresholdStyle> {
+    [
+        ThresholdStyle::limit(warn, false, "warn"),
+        ThresholdStyle::limit(crit, false, "critical"),
+    ]
+    .into_iter()
+    .flatten()
+    .collect()
+}
+
+/// `line` restyled by each threshold `value` is past, later ones winning.
+fn with_thresholds(mut line: StyledLine, value: f64, thresholds: &[ThresholdStyle]) -> StyledLine {
+    for threshold in thresholds.iter().filter(|t| t.crossed(value)) {
+        if let Some(fg) = &threshold.fg_color {
+            line.style.fg_color = Some(fg.clone());
+        }
+        if let Some(bg) = &threshold.bg_color {
+            line.style.bg_color = Some(bg.clone());
+        }
+    }
+    line
+}
+
+/// `text` followed by a bar filled to `percent`, colored by the style's
+/// thresholds (past them: above, or below with `falling`);
+/// plain text without a style.
+fn bar_line(text: String, percent: f32, style: Option<&BarStyle>, falling: bool) -> StyledLine {
+    let Some(style) = style else {
+        return StyledLine::plain(text);
+    };
+    let limits = [
+        ThresholdStyle::limit(style.warn, falling, &style.warn_color),
+        ThresholdStyle::limit(style.critical, falling, &style.critical_color),
+    ];
+    // The most severe limit passed wins
+    let fill_color = limits
+        .iter()
+        .rev()
+        .flatten()
+        .find(|t| t.crossed(f64::from(percent)))
+        .and_then(|t| t.fg_color.as_ref())
+        .unwrap_or(&style.fill_color);
+    StyledLine::bar(
+        text,
+        Bar {
+            fraction: (percent / 100.0).clamp(0.0, 1.0),
+            width: style.width,
+            fill_color: fill_color.clone(),
+        },
+    )
+}
+
+/// Collapses whitespace (including newlines) and cuts to `max_chars`,
+/// ending in "…" when cut.
+fn one_line(text: &str, max_chars: usize) -> String {
+    let flat = text.split_whitespace().collect::<Vec<_>>().join(" ");
+    if flat.chars().count() > max_chars {
+        let cut: String = flat.chars().take(max_chars.saturating_sub(1)).collect();
+        format!("{cut}…")
+    } else {
+        flat
+    }
+}
+
+/// "now", "in 35m", "in 3h 10m", "tomorrow", "Tue 14:00".
+fn relative_time(event: &calendar::Event, now: chrono::NaiveDateTime) -> String {
+    let today = now.date();
+    if event.all_day {
+        return match (event.start.date() - today).num_days() {
+            ..=0 => "today".into(),
+            1 => "tomorrow".into(),
+            _ => event.start.format("%a").to_string(),
+        };
+    }
+    if event.start <= now {
+        return "now".into();
+    }
+    let minutes = (event.start - now).num_minutes().max(1);
+    match minutes {
+        ..60 => format!("in {minutes}m"),
+        60..720 => match minutes % 60 {
+            0 => format!("in {}h", minutes / 60),
+            m => format!("in {}h {m}m", minutes / 60),
+        },
+        _ if event.start.date() == today => event.start.format("%H:%M").to_string(),
+        _ => event.start.format("%a %H:%M").to_string(),
+    }
+}
+
+/// Distinct scaling governors in use across cores, e.g. ["powersave"].
+fn scaling_governors() -> Vec<String> {
+    let Ok(entries) = std::fs::read_dir("/sys/devices/system/cpu/cpufreq") else {
+        return Vec::new();
+    };
+    let mut governors: Vec<String> = entries
+        .flatten()
+        .filter_map(|e| std::fs::read_to_string(e.path().join("scaling_governor")).ok())
+        .map(|g| g.trim().to_string())
+        .collect();
+    governors.sort();
+    governors.dedup();
+    governors
+}
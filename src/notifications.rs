@@ -0,0 +1,135 @@
+use std::collections::VecDeque;
+use std::io::{BufRead, BufReader};
+use std::process::{Command, Stdio};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Mutex, MutexGuard, Once};
+use std::time::{Duration, Instant};
+
+/// Notifications kept for display; older ones are dropped.
+const MAX_HISTORY: usize = 50;
+const MATCH_RULE: &str = "interface='org.freedesktop.Notifications',member='Notify'";
+
+#[derive(Debug, Clone)]
+pub struct Notification {
+    pub app: String,
+    pub summary: String,
+    pub received: Instant,
+}
+
+/// Newest first.
+static HISTORY: Mutex<VecDeque<Notification>> = Mutex::new(VecDeque::new());
+static UNREAD: AtomicUsize = AtomicUsize::new(0);
+static WATCH: Once = Once::new();
+
+/// Up to `count` notifications, newest first, and how many arrived since
+/// the last `mark_read`. Starts the D-Bus watcher on first use, so earlier
+/// notifications are not seen.
+pub fn recent(count: usize) -> (Vec<Notification>, usize) {
+    WATCH.call_once(|| {
+        std::thread::spawn(watch);
+    });
+    let recent = lock().iter().take(count).cloned().collect();
+    (recent, UNREAD.load(Ordering::Relaxed))
+}
+
+pub fn mark_read() {
+    UNREAD.store(0, Ordering::Relaxed);
+}
+
+/// Eavesdrops on `Notify` calls to the notification daemon via
+/// `dbus-monitor`, restarting it if it exits.
+fn watch() {
+    loop {
+        match Command::new("dbus-monitor")
+            .args(["--session", MATCH_RULE])
+            .stdout(Stdio::piped())
+            .stderr(Stdio::null())
+            .spawn()
+        {
+            Ok(mut child) => {
+                if let Some(stdout) = child.stdout.take() {
+                    follow(BufReader::new(stdout));
+                }
+                let _ = child.wait();
+            }
+            Err(e) => {
+                eprintln!("rustky: notification watcher: dbus-monitor: {e}");
+                return;
+            }
+        }
+        std::thread::sleep(Duration::from_secs(5));
+    }
+}
+
+/// Notify's arguments follow the call header one per line:
+/// app_name, replaces_id, app_icon, summary, body, … Strings may span
+/// several lines.
+fn follow(reader: impl BufRead) {
+    let mut args: Option<Vec<String>> = None;
+    let mut open_string: Option<String> = None;
+    for line in reader.lines() {
+        let Ok(line) = line else { break };
+        if line.starts_with("method call ") {
+            args = line.contains("member=Notify").then(Vec::new);
+            open_string = None;
+            continue;
+        }
+        let Some(collected) = args.as_mut() else {
+            continue;
+        };
+
+        if let Some(mut text) = open_string.take() {
+            text.push('\n');
+            match line.strip_suffix('"') {
+                Some(end) => {
+                    text.push_str(end);
+                    collected.push(text);
+                }
+                None => {
+                    text.push_str(&line);
+                    open_string = Some(text);
+                }
+            }
+        } else {
+            let trimmed = line.trim_start();
+            if let Some(rest) = trimmed.strip_prefix("string \"") {
+                match rest.strip_suffix('"') {
+                    Some(value) => collected.push(value.to_string()),
+                    None => open_string = Some(rest.to_string()),
+                }
+            } else if let Some(rest) = trimmed.strip_prefix("uint32 ") {
+                collected.push(rest.to_string());
+            }
+        }
+
+        if collected.len() == 4 {
+            let replaces = collected[1] != "0";
+            record(
+                Notification {
+                    app: collected[0].clone(),
+                    summary: collected[3].clone(),
+                    received: Instant::now(),
+                },
+                replaces,
+            );
+            args = None;
+        }
+    }
+}
+
+/// An update of an earlier notification (non-zero replaces_id) takes that
+/// app's latest entry and isn't counted as unread again.
+fn record(notification: Notification, replaces: bool) {
+    let mut history = lock();
+    if replaces && let Some(pos) = history.iter().position(|n| n.app == notification.app) {
+        history.remove(pos);
+    } else {
+        UNREAD.fetch_add(1, Ordering::Relaxed);
+    }
+    history.push_front(notification);
+    history.truncate(MAX_HISTORY);
+}
+
+fn lock() -> MutexGuard<'static, VecDeque<Notification>> {
+    HISTORY.lock().unwrap_or_else(|e| e.into_inner())
+}
@@ -0,0 +1,70 @@
+use crate::config::{Config, ModuleEntry, Page};
+
+/// Tracks which `[[pages]]` entry is currently shown.
+pub struct Pager {
+    current: usize,
+}
+
+pub enum PageCommand {
+    Next,
+    Prev,
+    Select(String),
+}
+
+impl PageCommand {
+    pub fn parse(arg: &str) -> Self {
+        match arg {
+            "next" => PageCommand::Next,
+            "prev" | "previous" => PageCommand::Prev,
+            name => PageCommand::Select(name.to_string()),
+        }
+    }
+}
+
+impl Pager {
+    pub fn new() -> Self {
+        Self { current: 0 }
+    }
+
+    /// Applies a page command. Returns an error if a named page does not exist.
+    pub fn apply(&mut self, pages: &[Page], cmd: &PageCommand) -> Result<(), String> {
+        if pages.is_empty() {
+            return Err("no pages configured".into());
+        }
+        match cmd {
+            PageCommand::Next => self.current = (self.current + 1) % pages.len(),
+            PageCommand::Prev => self.current = (self.current + pages.len() - 1) % pages.len(),
+            PageCommand::Select(name) => {
+                self.current = pages
+                    .iter()
+                    .position(|p| p.name == *name)
+                    .ok_or_else(|| format!("unknown page '{name}'"))?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Modules to display: the current page's list, or the top-level
+    /// `modules` when no pages are configured.
+    pub fn modules<'a>(&self, cfg: &'a Config) -> &'a [ModuleEntry] {
+        match cfg.pages.get(self.current) {
+            Some(page) => &page.modules,
+            None => &cfg.modules,
+        }
+    }
+
+    /// Labels for the tab strip, one per page.
+    pub fn tabs(&self, pages: &[Page]) -> Vec<(String, bool)> {
+        pages
+            .iter()
+            .enumerate()
+            .map(|(i, p)| {
+                let label = match p.icon {
+                    Some(ref icon) => format!("{icon} {}", p.name),
+                    None => p.name.clone(),
+                };
+                (label, i == self.current)
+            })
+            .collect()
+    }
+}
@@ -0,0 +1,129 @@
+use std::collections::HashMap;
+use std::io;
+use std::net::{IpAddr, SocketAddr, ToSocketAddrs, UdpSocket};
+use std::os::fd::{FromRawFd, OwnedFd};
+use std::sync::{Arc, Mutex, OnceLock};
+use std::time::{Duration, Instant};
+
+use crate::history::History;
+
+/// Samples kept per host, enough for the widest graph.
+const HISTORY_LEN: usize = 300;
+/// Shortest probe interval, so a tiny `interval_ms` can't spin the thread.
+const MIN_INTERVAL: Duration = Duration::from_millis(100);
+
+pub struct ProbeState {
+    pub history: History,
+    /// Why probing isn't working at all (resolution, socket permission).
+    pub error: Option<String>,
+}
+
+type Registry = Mutex<HashMap<String, Arc<Mutex<ProbeState>>>>;
+
+static PROBERS: OnceLock<Registry> = OnceLock::new();
+
+/// Shared state of the prober for `host`, starting a background thread that
+/// pings it every `interval` (at least 100 ms) on first use.
+pub fn prober(host: &str, interval: Duration) -> Arc<Mutex<ProbeState>> {
+    let interval = interval.max(MIN_INTERVAL);
+    let mut probers = PROBERS
+        .get_or_init(Default::default)
+        .lock()
+        .unwrap_or_else(|e| e.into_inner());
+    probers
+        .entry(host.to_string())
+        .or_insert_with(|| {
+            let state = Arc::new(Mutex::new(ProbeState {
+                history: History::new(HISTORY_LEN),
+                error: None,
+            }));
+            let shared = Arc::clone(&state);
+            let host = host.to_string();
+            std::thread::spawn(move || run(&host, interval, &shared));
+            state
+        })
+        .clone()
+}
+
+fn run(host: &str, interval: Duration, state: &Mutex<ProbeState>) {
+    let mut seq: u16 = 0;
+    loop {
+        let started = Instant::now();
+        seq = seq.wrapping_add(1);
+        let timeout = interval.min(Duration::from_secs(2));
+        let result = resolve(host).and_then(|ip| probe(ip, seq, timeout));
+
+        {
+            let mut state = state.lock().unwrap_or_else(|e| e.into_inner());
+            match result {
+                Ok(rtt) => {
+                    state.history.push(rtt.map(|d| d.as_secs_f64() * 1000.0));
+                    state.error = None;
+                }
+                Err(e) => {
+                    state.history.push(None);
+                    state.error = Some(e.to_string());
+                }
+            }
+        }
+
+        std::thread::sleep(interval.saturating_sub(started.elapsed()));
+    }
+}
+
+fn resolve(host: &str) -> io::Result<IpAddr> {
+    (host, 0)
+        .to_socket_addrs()?
+        .next()
+        .map(|a| a.ip())
+        .ok_or_else(|| io::Error::other(format!("cannot resolve {host}")))
+}
+
+/// Sends one ICMP echo request over an unprivileged ping socket (allowed by
+/// `net.ipv4.ping_group_range`). `Ok(None)` means no reply within `timeout`.
+fn probe(ip: IpAddr, seq: u16, timeout: Duration) -> io::Result<Option<Duration>> {
+    let (domain, protocol, request, reply) = match ip {
+        IpAddr::V4(_) => (libc::AF_INET, libc::IPPROTO_ICMP, 8u8, 0u8),
+        IpAddr::V6(_) => (libc::AF_INET6, libc::IPPROTO_ICMPV6, 128u8, 129u8),
+    };
+    // SAFETY: plain socket(2) call; the fd is owned by the OwnedFd below.
+    let fd = unsafe { libc::socket(domain, libc::SOCK_DGRAM | libc::SOCK_CLOEXEC, protocol) };
+    if fd < 0 {
+        return Err(io::Error::last_os_error());
+    }
+    // SAFETY: fd is a freshly created, valid socket we exclusively own.
+    let socket = UdpSocket::from(unsafe { OwnedFd::from_raw_fd(fd) });
+    socket.set_read_timeout(Some(timeout))?;
+
+    // Type, code, checksum, identifier (the kernel fills in checksum and
+    // identifier for ping sockets), sequence, payload.
+    let mut packet = [0u8; 16];
+    packet[0] = request;
+    packet[6..8].copy_from_slice(&seq.to_be_bytes());
+
+    let sent = Instant::now();
+    socket.send_to(&packet, SocketAddr::new(ip, 0))?;
+
+    let mut buf = [0u8; 128];
+    loop {
+        let Some(remaining) = timeout.checked_sub(sent.elapsed()) else {
+            return Ok(None);
+        };
+        socket.set_read_timeout(Some(remaining.max(Duration::from_millis(1))))?;
+        match socket.recv(&mut buf) {
+            Ok(n) if n >= 8 && buf[0] == reply && buf[6..8] == seq.to_be_bytes() => {
+                return Ok(Some(sent.elapsed()));
+            }
+            Ok(_) => continue,
+            Err(e)
+                if matches!(
+                    e.kind(),
+                    io::ErrorKind::WouldBlock | io::ErrorKind::TimedOut
+                ) =>
+            {
+                return Ok(None);
+            }
+            Err(e) => return Err(e),
+        }
+    }
+}
@@ -0,0 +1,157 @@
+use serde::{Deserialize, Serialize};
+
+use crate::styled::{LineAction, LineStyle, StyledLine};
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Phase {
+    #[default]
+    Work,
+    ShortBreak,
+    LongBreak,
+}
+
+impl Phase {
+    fn name(self) -> &'static str {
+        match self {
+            Phase::Work => "Work",
+            Phase::ShortBreak => "Break",
+            Phase::LongBreak => "Long break",
+        }
+    }
+}
+
+/// Phase lengths in seconds, from the module config.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Durations {
+    pub work: i64,
+    pub short_break: i64,
+    pub long_break: i64,
+    /// Every n-th break is a long one.
+    pub long_break_every: u32,
+}
+
+impl Durations {
+    pub fn from_mins(work: u64, short_break: u64, long_break: u64, long_break_every: u32) -> Self {
+        Self {
+            work: work as i64 * 60,
+            short_break: short_break as i64 * 60,
+            long_break: long_break as i64 * 60,
+            long_break_every: long_break_every.max(1),
+        }
+    }
+
+    fn of(&self, phase: Phase) -> i64 {
+        match phase {
+            Phase::Work => self.work,
+            Phase::ShortBreak => self.short_break,
+            Phase::LongBreak => self.long_break,
+        }
+    }
+}
+
+/// The timer, kept in the state file. Times are unix seconds so a running
+/// phase keeps counting while rustky is closed.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Timer {
+    pub phase: Phase,
+    /// When the running phase ends; `None` while paused or not started.
+    ends_at: Option<i64>,
+    /// Seconds left in a paused phase; `None` before it starts.
+    left: Option<i64>,
+    /// Work phases finished since the last reset.
+    pub completed: u32,
+}
+
+impl Timer {
+    pub fn running(&self) -> bool {
+        self.ends_at.is_some()
+    }
+
+    pub fn remaining(&self, durations: &Durations, now: i64) -> i64 {
+        match (self.ends_at, self.left) {
+            (Some(end), _) => (end - now).max(0),
+            (None, Some(left)) => left,
+            (None, None) => durations.of(self.phase),
+        }
+    }
+
+    /// Moves on once the running phase is over. The next phase waits for a
+    /// click. Returns whether anything changed.
+    pub fn advance(&mut self, durations: &Durations, now: i64) -> bool {
+        if self.ends_at.is_none_or(|end| now < end) {
+            return false;
+        }
+        let finished_work = self.phase == Phase::Work;
+        if finished_work {
+            self.completed += 1;
+        }
+        self.next(durations, finished_work);
+        true
+    }
+
+    /// Starts or pauses.
+    pub fn toggle(&mut self, durations: &Durations, now: i64) {
+        match self.ends_at.take() {
+            Some(end) => self.left = Some((end - now).max(0)),
+            None => self.ends_at = Some(now + self.remaining(durations, now)),
+        }
+    }
+
+    /// Ends the current phase early without counting it.
+    pub fn skip(&mut self, durations: &Durations) {
+        self.next(durations, false);
+    }
+
+    pub fn reset(&mut self) {
+        *self = Self::default();
+    }
+
+    /// A long break only follows a work phase that ran to the end.
+    fn next(&mut self, durations: &Durations, counted: bool) {
+        self.phase = match self.phase {
+            Phase::Work if counted && self.completed.is_multiple_of(durations.long_break_every) => {
+                Phase::LongBreak
+            }
+            Phase::Work => Phase::ShortBreak,
+            Phase::ShortBreak | Phase::LongBreak => Phase::Work,
+        };
+        self.ends_at = None;
+        self.left = None;
+    }
+
+    pub fn lines(&self, label: &str, durations: &Durations, now: i64) -> Vec<StyledLine> {
+        let left = self.remaining(durations, now);
+        let mut text = format!(
+            "{label}: {} {:02}:{:02}",
+            self.phase.name(),
+            left / 60,
+            left % 60
+        );
+        if !self.running() {
+            text.push_str(if self.left.is_some() {
+                " (paused)"
+            } else {
+                " (ready)"
+            });
+        }
+        if self.completed > 0 {
+            text.push_str(&format!(" | {} done", self.completed));
+        }
+        let color = match (self.running(), self.phase) {
+            (false, _) => "muted",
+            (true, Phase::Work) => "critical",
+            (true, _) => "ok",
+        };
+        let mut line = StyledLine::styled(
+            text,
+            LineStyle {
+                fg_color: Some(color.into()),
+                ..Default::default()
+            },
+        );
+        line.action = Some(LineAction::Pomodoro(durations.clone()));
+        vec![line]
+    }
+}
@@ -0,0 +1,192 @@
+use std::collections::HashMap;
+use std::process::Command;
+use std::sync::{Mutex, OnceLock};
+use std::time::Duration;
+
+use crate::background::Poller;
+
+#[derive(Debug, Clone)]
+pub struct Pool {
+    /// ZFS pool name or btrfs mount point.
+    pub name: String,
+    /// zpool state ("ONLINE", "DEGRADED", …); for btrfs "OK" or "DEGRADED".
+    pub health: String,
+    /// Progress of a running scrub, in percent.
+    pub scrub: Option<f64>,
+    /// Devices that are not online or have errors, e.g. "sdb FAULTED".
+    pub problems: Vec<String>,
+}
+
+impl Pool {
+    pub fn healthy(&self) -> bool {
+        matches!(self.health.as_str(), "ONLINE" | "OK") && self.problems.is_empty()
+    }
+}
+
+/// Pollers keyed by (zpools, btrfs mount points).
+type Registry = Mutex<HashMap<(Vec<String>, Vec<String>), Poller<Vec<Pool>>>>;
+
+static POLLERS: OnceLock<Registry> = OnceLock::new();
+
+/// State of the given ZFS pools and btrfs filesystems, in that order,
+/// polled with `zpool`/`btrfs` every `interval`.
+pub fn status(
+    zpools: &[String],
+    btrfs: &[String],
+    interval: Duration,
+) -> Option<Result<Vec<Pool>, String>> {
+    let mut pollers = POLLERS
+        .get_or_init(Default::default)
+        .lock()
+        .unwrap_or_else(|e| e.into_inner());
+    pollers
+        .entry((zpools.to_vec(), btrfs.to_vec()))
+        .or_insert_with(|| {
+            let (zpools, btrfs) = (zpools.to_vec(), btrfs.to_vec());
+            Poller::spawn(interval, move || {
+                let mut pools = Vec::new();
+                for name in &zpools {
+                    pools.push(zpool(name)?);
+                }
+                for mount in &btrfs {
+                    pools.push(btrfs_fs(mount)?);
+                }
+                Ok(pools)
+            })
+        })
+        .latest()
+}
+
+fn zpool(name: &str) -> Result<Pool, String> {
+    let out = run("zpool", &["status", name])?;
+    let mut pool = Pool {
+        name: name.to_string(),
+        health: String::new(),
+        scrub: None,
+        problems: Vec::new(),
+    };
+    let mut scrubbing = false;
+    let mut in_config = false;
+    let mut first_row = true;
+    for line in out.lines() {
+        let trimmed = line.trim();
+        if let Some(state) = trimmed.strip_prefix("state:") {
+            pool.health = state.trim().to_string();
+        } else if trimmed.starts_with("scan:") {
+            scrubbing = trimmed.contains("in progress");
+        } else if scrubbing && trimmed.contains("% done") {
+            // "0B repaired, 45.67% done, 00:45:00 to go"
+            pool.scrub = trimmed
+                .split("% done")
+                .next()
+                .and_then(|s| s.rsplit([' ', ',']).next())
+                .and_then(|p| p.parse().ok());
+        } else if trimmed.starts_with("NAME") && trimmed.contains("STATE") {
+            in_config = true;
+        } else if in_config {
+            if trimmed.is_empty() {
+                in_config = false;
+                continue;
+            }
+            // The first row is the pool itself, already covered by state.
+            if std::mem::take(&mut first_row) {
+                continue;
+            }
+            let cols: Vec<&str> = trimmed.split_whitespace().collect();
+            let (Some(device), Some(state)) = (cols.first(), cols.get(1)) else {
+                continue;
+            };
+            let errors: u64 = cols
+                .iter()
+                .skip(2)
+                .take(3)
+                .filter_map(|c| c.parse::<u64>().ok())
+                .sum();
+            if !matches!(*state, "ONLINE" | "AVAIL" | "INUSE") {
+                pool.problems.push(format!("{device} {state}"));
+            } else if errors > 0 {
+                pool.problems.push(format!("{device} {errors} errors"));
+            }
+        }
+    }
+    if pool.health.is_empty() {
+        return Err(format!("zpool {name}: no state in status output"));
+    }
+    Ok(pool)
+}
+
+fn btrfs_fs(mount: &str) -> Result<Pool, String> {
+    let mut problems = Vec::new();
+    if run("btrfs", &["filesystem", "show", mount])?.contains("missing") {
+        problems.push("devices missing".to_string());
+    }
+    // "[/dev/sda].write_io_errs    0", five counters per device
+    let mut errors: Vec<(String, u64)> = Vec::new();
+    for line in run("btrfs", &["device", "stats", mount])?.lines() {
+        let Some((device, count)) = line
+            .strip_prefix('[')
+            .and_then(|rest| rest.split_once("]."))
+            .and_then(|(device, rest)| Some((device, rest.split_whitespace().nth(1)?)))
+        else {
+            continue;
+        };
+        let count: u64 = count.parse().unwrap_or(0);
+        match errors.iter_mut().find(|(d, _)| d == device) {
+            Some((_, total)) => *total += count,
+            None => errors.push((device.to_string(), count)),
+        }
+    }
+    problems.extend(
+        errors
+            .into_iter()
+            .filter(|(_, n)| *n > 0)
+            .map(|(device, n)| format!("{} {n} errors", device.trim_start_matches("/dev/"))),
+    );
+    // Scrub status needs root on most setups; leave it out rather than fail.
+    let scrub = run("btrfs", &["scrub", "status", mount])
+        .ok()
+        .and_then(|out| {
+            if !out
+                .lines()
+                .any(|l| l.trim().starts_with("Status:") && l.contains("running"))
+            {
+                return None;
+            }
+            // "Bytes scrubbed:   1.00TiB  (45.67%)"
+            out.lines()
+                .find(|l| l.trim().starts_with("Bytes scrubbed:"))?
+                .split('(')
+                .nth(1)?
+                .split('%')
+                .next()?
+                .parse()
+                .ok()
+        });
+    Ok(Pool {
+        name: mount.to_string(),
+        health: if problems.is_empty() {
+            "OK"
+        } else {
+            "DEGRADED"
+        }
+        .to_string(),
+        scrub,
+        problems,
+    })
+}
+
+fn run(program: &str, args: &[&str]) -> Result<String, String> {
+    let output = Command::new(program)
+        .args(args)
+        .env("LC_ALL", "C")
+        .output()
+        .map_err(|e| format!("{program}: {e}"))?;
+    if !output.status.success() {
+        return Err(format!(
+            "{program} {}: {}",
+            args.join(" "),
+            String::from_utf8_lossy(&output.stderr).trim()
+        ));
+    }
+    Ok(String::from_utf8_lossy(&output.stdout).into_owned())
+}
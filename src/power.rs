@@ -0,0 +1,109 @@
+use std::collections::HashMap;
+use std::fs;
+use std::path::Path;
+use std::time::Instant;
+
+use serde::{Deserialize, Serialize};
+
+const POWERCAP: &str = "/sys/class/powercap";
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PowerZone {
+    /// RAPL zone name; subzones are qualified by their parent, e.g.
+    /// "package-0/core".
+    pub name: String,
+    pub watts: f64,
+}
+
+struct Sample {
+    at: Instant,
+    energy_uj: u64,
+}
+
+/// Turns the cumulative RAPL energy counters (Intel, and AMD since Zen via
+/// the same powercap interface) into watts over each refresh interval.
+pub struct PowerMeter {
+    prev: HashMap<String, Sample>,
+}
+
+impl PowerMeter {
+    pub fn new() -> Self {
+        Self {
+            prev: HashMap::new(),
+        }
+    }
+
+    /// Reads every RAPL zone; a zone appears from its second sample on.
+    pub fn sample(&mut self) -> Vec<PowerZone> {
+        let Ok(entries) = fs::read_dir(POWERCAP) else {
+            return Vec::new();
+        };
+        let mut dirs: Vec<String> = entries
+            .flatten()
+            .map(|e| e.file_name().to_string_lossy().into_owned())
+            // intel-rapl-mmio duplicates the MSR zones on newer CPUs
+            .filter(|n| n.starts_with("intel-rapl:"))
+            .collect();
+        dirs.sort();
+
+        let now = Instant::now();
+        let mut zones = Vec::new();
+        for dir in dirs {
+            let path = Path::new(POWERCAP).join(&dir);
+            // energy_uj is root-only on kernels with the PLATYPUS mitigation
+            let Some(energy_uj) = read_u64(&path.join("energy_uj")) else {
+                continue;
+            };
+            let Some(name) = zone_name(&dir) else {
+                continue;
+            };
+
+            let prev = self.prev.insert(dir.clone(), Sample { at: now, energy_uj });
+            let Some(prev) = prev else {
+                continue;
+            };
+            let elapsed = now.duration_since(prev.at).as_secs_f64();
+            if elapsed <= 0.0 {
+                continue;
+            }
+            let delta = if energy_uj >= prev.energy_uj {
+                energy_uj - prev.energy_uj
+            } else {
+                // Counter wrapped around max_energy_range_uj
+                let range = read_u64(&path.join("max_energy_range_uj")).unwrap_or(0);
+                range.saturating_sub(prev.energy_uj) + energy_uj
+            };
+            zones.push(PowerZone {
+                name,
+                watts: delta as f64 / 1_000_000.0 / elapsed,
+            });
+        }
+        zones
+    }
+}
+
+/// Total of the CPU package zones, or `None` if no package zone is readable.
+pub fn package_watts(zones: &[PowerZone]) -> Option<f64> {
+    let packages: Vec<f64> = zones
+        .iter()
+        .filter(|z| z.name.starts_with("package") && !z.name.contains('/'))
+        .map(|z| z.watts)
+        .collect();
+    (!packages.is_empty()).then(|| packages.iter().sum())
+}
+
+fn read_u64(path: &Path) -> Option<u64> {
+    fs::read_to_string(path).ok()?.trim().parse().ok()
+}
+
+/// "intel-rapl:0:1" → "package-0/uncore", using each level's `name` file.
+fn zone_name(dir: &str) -> Option<String> {
+    let ids: Vec<&str> = dir.strip_prefix("intel-rapl:")?.split(':').collect();
+    let mut names = Vec::new();
+    for depth in 1..=ids.len() {
+        let zone = format!("intel-rapl:{}", ids[..depth].join(":"));
+        let name = fs::read_to_string(Path::new(POWERCAP).join(zone).join("name")).ok()?;
+        names.push(name.trim().to_string());
+    }
+    Some(names.join("/"))
+}
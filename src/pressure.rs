@@ -0,0 +1,63 @@
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Resource {
+    Cpu,
+    Memory,
+    Io,
+}
+
+impl Resource {
+    pub const ALL: [Resource; 3] = [Resource::Cpu, Resource::Memory, Resource::Io];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Resource::Cpu => "cpu",
+            Resource::Memory => "memory",
+            Resource::Io => "io",
+        }
+    }
+}
+
+/// Share of wall time (percent) in which tasks stalled on a resource,
+/// averaged over 10 and 60 seconds.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Stall {
+    pub avg10: f64,
+    pub avg60: f64,
+}
+
+/// "some": at least one task stalled; "full": all non-idle tasks stalled
+/// at once (not reported for cpu on older kernels).
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Pressure {
+    pub some: Stall,
+    pub full: Option<Stall>,
+}
+
+/// Reads `/proc/pressure/<resource>`; `None` without PSI support
+/// (CONFIG_PSI off or `psi=0`).
+pub fn read(resource: Resource) -> Option<Pressure> {
+    let text = std::fs::read_to_string(format!("/proc/pressure/{}", resource.name())).ok()?;
+    let mut pressure = Pressure::default();
+    // "some avg10=0.00 avg60=0.00 avg300=0.00 total=0"
+    for line in text.lines() {
+        let mut fields = line.split_whitespace();
+        let kind = fields.next();
+        let mut stall = Stall::default();
+        for field in fields {
+            match field.split_once('=') {
+                Some(("avg10", v)) => stall.avg10 = v.parse().ok()?,
+                Some(("avg60", v)) => stall.avg60 = v.parse().ok()?,
+                _ => {}
+            }
+        }
+        match kind {
+            Some("some") => pressure.some = stall,
+            Some("full") => pressure.full = Some(stall),
+            _ => {}
+        }
+    }
+    Some(pressure)
+}
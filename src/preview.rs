@@ -0,0 +1,70 @@
+use std::path::PathBuf;
+use std::time::SystemTime;
+
+use crate::config::Config;
+use crate::styled::{LineStyle, StyledLine};
+
+/// State of `--preview`: the files being watched, errors from the last
+/// reload (shown on the surface instead of stderr) and the grid overlay.
+pub struct Preview {
+    watched: Vec<(PathBuf, Option<SystemTime>)>,
+    pub errors: Vec<String>,
+    pub grid: bool,
+}
+
+fn mtime(path: &PathBuf) -> Option<SystemTime> {
+    std::fs::metadata(path).and_then(|m| m.modified()).ok()
+}
+
+impl Preview {
+    pub fn new(cfg: &Config, errors: Vec<String>) -> Self {
+        let mut preview = Self {
+            watched: Vec::new(),
+            errors,
+            grid: false,
+        };
+        preview.watch(cfg);
+        preview
+    }
+
+    /// Watches the config file and every script it references.
+    pub fn watch(&mut self, cfg: &Config) {
+        let mut paths = vec![Config::config_path()];
+        paths.extend(cfg.script_paths());
+        self.watched = paths
+            .into_iter()
+            .map(|p| {
+                let t = mtime(&p);
+                (p, t)
+            })
+            .collect();
+    }
+
+    /// True once any watched file was saved (or created/removed) since the
+    /// last call.
+    pub fn changed(&mut self) -> bool {
+        let mut changed = false;
+        for (path, seen) in &mut self.watched {
+            let now = mtime(path);
+            if now != *seen {
+                *seen = now;
+                changed = true;
+            }
+        }
+        changed
+    }
+
+    pub fn error_lines(&self) -> Vec<StyledLine> {
+        let style = LineStyle {
+            fg_color: Some("#ffffff".into()),
+            bg_color: Some("#b00020dd".into()),
+            font_size: None,
+            ..Default::default()
+        };
+        self.errors
+            .iter()
+            .flat_map(|e| e.lines())
+            .map(|l| StyledLine::styled(l.to_string(), style.clone()))
+            .collect()
+    }
+}
@@ -0,0 +1,64 @@
+use std::collections::HashMap;
+use std::sync::{Mutex, OnceLock};
+use std::time::Duration;
+
+use serde_json::{Value, json};
+
+use crate::background::Poller;
+use crate::http;
+
+type Registry = Mutex<HashMap<(String, String), Poller<Value>>>;
+
+static POLLERS: OnceLock<Registry> = OnceLock::new();
+
+/// Result of the instant `query` against the Prometheus server at
+/// `endpoint`, run every `interval`: `{"value": …, "metric": {labels}}` for
+/// the scalar or first sample, with a null value when there is no data.
+pub fn sample(endpoint: &str, query: &str, interval: Duration) -> Option<Result<Value, String>> {
+    let mut pollers = POLLERS
+        .get_or_init(Default::default)
+        .lock()
+        .unwrap_or_else(|e| e.into_inner());
+    pollers
+        .entry((endpoint.to_string(), query.to_string()))
+        .or_insert_with(|| {
+            let url = format!("{}/api/v1/query", endpoint.trim_end_matches('/'));
+            let query = query.to_string();
+            Poller::spawn_cached(interval, "prometheus", move || fetch(&url, &query))
+        })
+        .latest()
+}
+
+fn fetch(url: &str, query: &str) -> Result<Value, String> {
+    let body = http::get_with_query(url, &[("query", query)])?;
+    let response: Value = serde_json::from_str(&body).map_err(|e| format!("prometheus: {e}"))?;
+    if response["status"] != "success" {
+        return Err(format!(
+            "prometheus: {}",
+            response["error"].as_str().unwrap_or("query failed")
+        ));
+    }
+    let data = &response["data"];
+    let (value, metric) = match data["resultType"].as_str() {
+        Some("scalar" | "string") => (&data["result"][1], Value::Null),
+        Some("vector") => {
+            let Some(first) = data["result"].get(0) else {
+                return Ok(json!({ "value": null, "metric": {} }));
+            };
+            (&first["value"][1], first["metric"].clone())
+        }
+        other => {
+            return Err(format!(
+                "prometheus: unsupported result type {}",
+                other.unwrap_or("?")
+            ));
+        }
+    };
+    // Sample values are strings ("0.25", "NaN"); numbers format better.
+    let value = value
+        .as_str()
+        .and_then(|s| s.parse::<f64>().ok())
+        .filter(|f| f.is_finite())
+        .map_or_else(|| value.clone(), |f| json!(f));
+    Ok(json!({ "value": value, "metric": metric }))
+}
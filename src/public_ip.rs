@@ -0,0 +1,59 @@
+use std::collections::HashMap;
+use std::sync::{Mutex, OnceLock};
+use std::time::Duration;
+
+use crate::background::Poller;
+use crate::http;
+
+#[derive(Debug, Clone)]
+pub struct PublicIp {
+    pub ip: String,
+    pub country: Option<String>,
+}
+
+static POLLERS: OnceLock<Mutex<HashMap<String, Poller<PublicIp>>>> = OnceLock::new();
+
+/// External address as reported by `endpoint`, refetched every `interval`
+/// in the background; the last good answer survives failed fetches.
+pub fn lookup(endpoint: &str, interval: Duration) -> Option<Result<PublicIp, String>> {
+    let mut pollers = POLLERS
+        .get_or_init(Default::default)
+        .lock()
+        .unwrap_or_else(|e| e.into_inner());
+    pollers
+        .entry(endpoint.to_string())
+        .or_insert_with(|| {
+            let endpoint = endpoint.to_string();
+            Poller::spawn_cached(interval, "public ip", move || fetch(&endpoint))
+        })
+        .latest()
+}
+
+/// Accepts a plain-text address (ifconfig.me, icanhazip.com) or a JSON
+/// object with `ip`/`query` and `country`/`country_name` (ipinfo.io,
+/// ifconfig.co/json, ip-api.com).
+fn fetch(endpoint: &str) -> Result<PublicIp, String> {
+    let body = http::get(endpoint)?;
+    let body = body.trim();
+    if !body.starts_with('{') {
+        if body.is_empty() || body.contains(char::is_whitespace) {
+            return Err(format!("{endpoint}: unexpected response"));
+        }
+        return Ok(PublicIp {
+            ip: body.to_string(),
+            country: None,
+        });
+    }
+    let json: serde_json::Value =
+        serde_json::from_str(body).map_err(|e| format!("{endpoint}: {e}"))?;
+    let field = |names: &[&str]| {
+        names
+            .iter()
+            .find_map(|n| json.get(*n)?.as_str())
+            .map(str::to_string)
+    };
+    Ok(PublicIp {
+        ip: field(&["ip", "query"]).ok_or_else(|| format!("{endpoint}: no ip in response"))?,
+        country: field(&["country", "country_name"]),
+    })
+}
@@ -0,0 +1,165 @@
+use chrono::{Datelike, Days, NaiveDate, NaiveDateTime, Weekday};
+
+/// Periods examined before giving up on a rule, so a daily event from long
+/// ago can't stall a fetch.
+const MAX_PERIODS: u32 = 20_000;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Freq {
+    Daily,
+    Weekly,
+    Monthly,
+    Yearly,
+}
+
+/// The subset of an RFC 5545 RRULE that calendar exports use in practice:
+/// FREQ, INTERVAL, COUNT, UNTIL and BYDAY (plain weekdays, for weekly
+/// rules).
+#[derive(Debug, Clone)]
+pub struct Rule {
+    pub freq: Freq,
+    pub interval: u32,
+    pub count: Option<u32>,
+    pub until: Option<NaiveDateTime>,
+    pub by_day: Vec<Weekday>,
+}
+
+/// Parses an RRULE value, e.g. "FREQ=WEEKLY;INTERVAL=2;BYDAY=MO,WE".
+/// Rules using parts beyond the supported subset return `None` rather than
+/// producing wrong dates.
+pub fn parse(value: &str) -> Option<Rule> {
+    let mut rule = Rule {
+        freq: Freq::Daily,
+        interval: 1,
+        count: None,
+        until: None,
+        by_day: Vec::new(),
+    };
+    let mut freq = None;
+    for part in value.split(';') {
+        let (key, val) = part.split_once('=')?;
+        match key {
+            "FREQ" => {
+                freq = Some(match val {
+                    "DAILY" => Freq::Daily,
+                    "WEEKLY" => Freq::Weekly,
+                    "MONTHLY" => Freq::Monthly,
+                    "YEARLY" => Freq::Yearly,
+                    _ => return None,
+                })
+            }
+            "INTERVAL" => rule.interval = val.parse().ok().filter(|&i| i > 0)?,
+            "COUNT" => rule.count = Some(val.parse().ok()?),
+            "UNTIL" => rule.until = Some(parse_until(val)?),
+            "BYDAY" => {
+                rule.by_day = val
+                    .split(',')
+                    .map(|d| match d {
+                        "MO" => Some(Weekday::Mon),
+                        "TU" => Some(Weekday::Tue),
+                        "WE" => Some(Weekday::Wed),
+                        "TH" => Some(Weekday::Thu),
+                        "FR" => Some(Weekday::Fri),
+                        "SA" => Some(Weekday::Sat),
+                        "SU" => Some(Weekday::Sun),
+                        // ordinal forms like "1MO" or "-1FR"
+                        _ => None,
+                    })
+                    .collect::<Option<_>>()?;
+            }
+            "WKST" => {}
+            _ => return None,
+        }
+    }
+    rule.freq = freq?;
+    if !rule.by_day.is_empty() && rule.freq != Freq::Weekly {
+        return None;
+    }
+    Some(rule)
+}
+
+/// UNTIL is a date or date-time; a trailing Z (UTC) is treated as local
+/// time, which is close enough for an end bound.
+fn parse_until(value: &str) -> Option<NaiveDateTime> {
+    let value = value.trim_end_matches('Z');
+    NaiveDateTime::parse_from_str(value, "%Y%m%dT%H%M%S")
+        .ok()
+        .or_else(|| {
+            NaiveDate::parse_from_str(value, "%Y%m%d")
+                .ok()?
+                .and_hms_opt(23, 59, 59)
+        })
+}
+
+/// Start times of the occurrences of an event starting at `start` that
+/// fall within `[from, to)`, minus `exdates`.
+pub fn occurrences(
+    start: NaiveDateTime,
+    rule: &Rule,
+    exdates: &[NaiveDateTime],
+    from: NaiveDateTime,
+    to: NaiveDateTime,
+) -> Vec<NaiveDateTime> {
+    let mut found = Vec::new();
+    // COUNT counts every generated instance, including excluded ones.
+    let mut generated = 0;
+    for period in 0..MAX_PERIODS {
+        let Some(candidates) = period_candidates(start, rule, period) else {
+            // e.g. the 31st in a 30-day month: that period has no instance
+            continue;
+        };
+        for at in candidates {
+            if at < start {
+                continue;
+            }
+            if at >= to || rule.until.is_some_and(|u| at > u) {
+                return found;
+            }
+            if rule.count.is_some_and(|c| generated >= c) {
+                return found;
+            }
+            generated += 1;
+            if at >= from && !exdates.contains(&at) {
+                found.push(at);
+            }
+        }
+    }
+    found
+}
+
+/// Instances in the `period`-th period after the start, in order.
+fn period_candidates(start: NaiveDateTime, rule: &Rule, period: u32) -> Option<Vec<NaiveDateTime>> {
+    let step = period.checked_mul(rule.interval)?;
+    let time = start.time();
+    let date = start.date();
+    match rule.freq {
+        Freq::Daily => Some(vec![
+            date.checked_add_days(Days::new(step.into()))?
+                .and_time(time),
+        ]),
+        Freq::Weekly => {
+            let week = date.checked_add_days(Days::new(u64::from(step) * 7))?;
+            if rule.by_day.is_empty() {
+                return Some(vec![week.and_time(time)]);
+            }
+            let monday = week - Days::new(week.weekday().num_days_from_monday().into());
+            let mut days: Vec<NaiveDateTime> = rule
+                .by_day
+                .iter()
+                .map(|d| (monday + Days::new(d.num_days_from_monday().into())).and_time(time))
+                .collect();
+            days.sort();
+            Some(days)
+        }
+        Freq::Monthly => {
+            let months = date.month0() + step;
+            let year = date.year() + (months / 12) as i32;
+            let day = NaiveDate::from_ymd_opt(year, months % 12 + 1, date.day())?;
+            Some(vec![day.and_time(time)])
+        }
+        Freq::Yearly => {
+            let day = NaiveDate::from_ymd_opt(date.year() + step as i32, date.month(), date.day())?;
+            Some(vec![day.and_time(time)])
+        }
+    }
+}
@@ -1,16 +1,80 @@
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::ops::Range;
 use std::sync::Arc;
 
+use skia_rs::paint::{TileMode, shaders};
 use skia_rs::prelude::*;
-use skia_rs_canvas::Surface;
+use skia_rs_canvas::{RasterCanvas, Surface};
 
-use crate::styled::StyledLine;
+use crate::config::{self, ColorStop, General, Theme};
+use crate::fonts;
+use crate::styled::{Align, Column, Element, Overflow, Span, StyledLine, TABLE_SEPARATOR};
+use crate::theme;
+
+/// WCAG AAA-contrast palette used by `high_contrast`.
+const HC_FG: Color = Color::WHITE;
+const HC_BG: Color = Color::BLACK;
+/// Lines with their own background (alerts, urgent blocks) become black on
+/// yellow.
+const HC_EMPHASIS: Color = Color::from_argb(255, 255, 255, 0);
+
+/// Extra advance after each character and the interval between tab stops,
+/// in pixels.
+#[derive(Clone, Copy)]
+struct Spacing {
+    letter: f32,
+    tab: f32,
+}
+
+/// The centre and eight unit offsets around it, where shadows and
+/// outlines place copies of the text.
+const RING: [(f32, f32); 9] = [
+    (0.0, 0.0),
+    (1.0, 0.0),
+    (0.707, 0.707),
+    (0.0, 1.0),
+    (-0.707, 0.707),
+    (-1.0, 0.0),
+    (-0.707, -0.707),
+    (0.0, -1.0),
+    (0.707, -0.707),
+];
+
+/// Marquee speed in pixels per second before `scale` is applied.
+const MARQUEE_SPEED: f32 = 30.0;
+/// Seconds a marquee rests at the start of each pass.
+const MARQUEE_PAUSE: f32 = 2.0;
 
 pub struct Renderer {
     pub font: Font,
+    /// Default font size with `scale` applied.
     pub font_size: f32,
     pub fg: Color,
     pub bg: Color,
     pub typeface: Arc<Typeface>,
+    /// `general.font`, the family styles asking only for bold or italic use.
+    family: String,
+    /// Faces of per-line families, weights and slants, loaded on first use.
+    faces: RefCell<HashMap<(String, bool, bool), Arc<Typeface>>>,
+    /// Faces for glyphs a line's own face lacks.
+    fallback: RefCell<fonts::Fallback>,
+    /// `general.icon_font`, when set and loadable.
+    icon_typeface: Option<Arc<Typeface>>,
+    /// Named colors of the configured theme.
+    palette: HashMap<String, Color>,
+    /// Accessibility multiplier for font sizes, paddings and decorations.
+    pub scale: f32,
+    base_font_size: f32,
+    pub high_contrast: bool,
+    /// Pixel grid with rulers over the content (`--preview`, toggled with `g`).
+    pub grid: bool,
+    /// Seconds on the clock marquee lines scroll by.
+    pub marquee_time: f32,
+    /// `[general]` text layout defaults; spacing before `scale` is applied.
+    line_height_factor: f32,
+    letter_spacing: f32,
+    tab_width: f32,
 }
 
 pub fn parse_hex_color(hex: &str) -> Color {
@@ -26,18 +90,714 @@ pub fn parse_hex_color(hex: &str) -> Color {
     }
 }
 
+/// `spec` as a name from `palette`, or else as a hex color.
+fn color(palette: &HashMap<String, Color>, spec: &str) -> Color {
+    palette
+        .get(spec)
+        .copied()
+        .unwrap_or_else(|| parse_hex_color(spec))
+}
+
+/// The longest prefix of `text` that fits in `max_width` together with a
+/// trailing "…", or `text` itself when it fits.
+fn ellipsize<'a>(
+    text: &'a str,
+    measure: impl Fn(&str) -> f32,
+    max_width: f32,
+) -> std::borrow::Cow<'a, str> {
+    if measure(text) <= max_width {
+        return text.into();
+    }
+    let budget = max_width - measure("…");
+    let mut end = 0;
+    for (i, c) in text.char_indices() {
+        if measure(&text[..i + c.len_utf8()]) > budget {
+            break;
+        }
+        end = i + c.len_utf8();
+    }
+    format!("{}…", text[..end].trim_end()).into()
+}
+
+/// `text` split into characters, each with the combining marks, joiners
+/// and variation selectors after it and anything a joiner joins to it.
+fn clusters(text: &str) -> impl Iterator<Item = &str> {
+    let mut rest = text;
+    std::iter::from_fn(move || {
+        let mut chars = rest.char_indices();
+        let (_, first) = chars.next()?;
+        let mut end = first.len_utf8();
+        let mut joined = first == '\u{200d}';
+        for (i, c) in chars {
+            let attaches =
+                matches!(c, '\u{300}'..='\u{36f}' | '\u{200d}' | '\u{fe00}'..='\u{fe0f}');
+            if !joined && !attaches {
+                break;
+            }
+            joined = c == '\u{200d}';
+            end = i + c.len_utf8();
+        }
+        let (cluster, tail) = rest.split_at(end);
+        rest = tail;
+        Some(cluster)
+    })
+}
+
+/// Byte ranges of `text` split into rows no wider than `max_width`,
+/// breaking at spaces; a word wider than a row is broken where it overflows.
+fn wrap(text: &str, measure: impl Fn(&str) -> f32, max_width: f32) -> Vec<Range<usize>> {
+    let mut rows = Vec::new();
+    let mut start = 0;
+    while max_width > 0.0 && measure(&text[start..]) > max_width {
+        let rest = &text[start..];
+        let (mut fit, mut space) = (0, None);
+        for (i, c) in rest.char_indices() {
+            let end = i + c.len_utf8();
+            if measure(&rest[..end]) > max_width {
+                break;
+            }
+            if c == ' ' && i > 0 {
+                space = Some(i);
+            }
+            fit = end;
+        }
+        let first = rest.chars().next().map_or(0, char::len_utf8);
+        let cut = space.unwrap_or(fit.max(first));
+        rows.push(start..start + rest[..cut].trim_end().len());
+        start += cut + (rest[cut..].len() - rest[cut..].trim_start().len());
+    }
+    rows.push(start..text.len());
+    rows
+}
+
+/// Draws `samples` as a filled line chart in `area`, `slots` samples
+/// across with the newest at the right edge, scaled from 0 to `max`.
+/// `None` samples break the line.
+fn draw_series(
+    canvas: &mut RasterCanvas<'_>,
+    samples: &[Option<f64>],
+    slots: usize,
+    max: f64,
+    area: Rect,
+    color: Color,
+) {
+    let step = area.width() / slots.saturating_sub(1).max(1) as f32;
+    let first_slot = slots.saturating_sub(samples.len());
+    let point = |i: usize, v: f64| {
+        let level = (v / max.max(f64::EPSILON)).clamp(0.0, 1.0) as f32;
+        Point::new(
+            area.left + (first_slot + i) as f32 * step,
+            area.bottom - level * area.height(),
+        )
+    };
+    let mut paint = Paint::default();
+    paint.set_anti_alias(true);
+    // Each run of samples between gaps is filled, then outlined
+    let mut i = 0;
+    while i < samples.len() {
+        let run: Vec<Point> = samples[i..]
+            .iter()
+            .map_while(|s| *s)
+            .enumerate()
+            .map(|(j, v)| point(i + j, v))
+            .collect();
+        i += run.len() + 1;
+        let (Some(first), Some(last)) = (run.first(), run.last()) else {
+            continue;
+        };
+        let mut fill = PathBuilder::new();
+        fill.move_to(first.x, area.bottom);
+        for p in &run {
+            fill.line_to(p.x, p.y);
+        }
+        fill.line_to(last.x, area.bottom).close();
+        paint.set_style(Style::Fill);
+        paint.set_color(color.with_alpha(70).into());
+        canvas.draw_path(&fill.build(), &paint);
+        paint.set_color(color.into());
+        for pair in run.windows(2) {
+            canvas.draw_line(pair[0], pair[1], &paint);
+        }
+    }
+    // Samples missing once data arrived (a lost ping, a failed read) are
+    // crossed out along the bottom; leading ones were never taken
+    let Some(first) = samples.iter().position(Option::is_some) else {
+        return;
+    };
+    let arm = (area.height() * 0.15).max(1.0);
+    paint.set_color(color.into());
+    for i in (first..samples.len()).filter(|&i| samples[i].is_none()) {
+        let x = area.left + (first_slot + i) as f32 * step;
+        let (top, bottom) = (area.bottom - 2.0 * arm, area.bottom);
+        let (left, right) = (x - arm, x + arm);
+        canvas.draw_line(Point::new(left, top), Point::new(right, bottom), &paint);
+        canvas.draw_line(Point::new(left, bottom), Point::new(right, top), &paint);
+    }
+}
+
+/// A filled ring segment between `inner` and `outer` radii, from `start`
+/// degrees clockwise over `sweep` degrees (0° points right).
+fn ring_segment(center: Point, outer: f32, inner: f32, start: f32, sweep: f32) -> Path {
+    let steps = (sweep.abs() / 4.0).ceil().max(1.0) as usize;
+    let at = |radius: f32, i: usize| {
+        let angle = (start + sweep * i as f32 / steps as f32).to_radians();
+        (
+            center.x + radius * angle.cos(),
+            center.y + radius * angle.sin(),
+        )
+    };
+    let mut path = PathBuilder::new();
+    let (x, y) = at(outer, 0);
+    path.move_to(x, y);
+    for i in 1..=steps {
+        let (x, y) = at(outer, i);
+        path.line_to(x, y);
+    }
+    for i in (0..=steps).rev() {
+        let (x, y) = at(inner, i);
+        path.line_to(x, y);
+    }
+    path.close();
+    path.build()
+}
+
 impl Renderer {
-    pub fn new(font_size: f32, fg_hex: &str, bg_hex: &str) -> Self {
-        let font_data = include_bytes!("/usr/share/fonts/TTF/DejaVuSansMono.ttf");
-        let typeface =
-            Arc::new(Typeface::from_data(font_data.to_vec()).expect("failed to load font"));
+    pub fn new(general: &General, theme: &Theme) -> Self {
+        let typeface = fonts::typeface(&general.font);
+        let font_size = general.font_size * general.scale;
         let font = Font::new(typeface.clone(), font_size);
+        let icon_typeface = general.icon_font.as_deref().and_then(|path| {
+            let path = config::shellexpand(path);
+            let face = std::fs::read(&path)
+                .map_err(|e| e.to_string())
+                .and_then(|data| Typeface::from_data(data).ok_or("not a font".into()));
+            face.map_err(|e| eprintln!("rustky: icon_font {path}: {e}"))
+                .ok()
+                .map(Arc::new)
+        });
+        let palette: HashMap<String, Color> = theme::palette(theme)
+            .into_iter()
+            .map(|(name, hex)| (name, parse_hex_color(&hex)))
+            .collect();
         Self {
             font,
             font_size,
-            fg: parse_hex_color(fg_hex),
-            bg: parse_hex_color(bg_hex),
+            fg: color(&palette, &general.fg_color),
+            bg: color(&palette, &general.bg_color),
             typeface,
+            family: general.font.clone(),
+            faces: RefCell::default(),
+            fallback: RefCell::new(fonts::Fallback::new(&general.fallback_fonts)),
+            icon_typeface,
+            palette,
+            scale: general.scale,
+            base_font_size: general.font_size,
+            high_contrast: general.high_contrast,
+            grid: false,
+            marquee_time: 0.0,
+            line_height_factor: general.line_height_factor,
+            letter_spacing: general.letter_spacing,
+            tab_width: general.tab_width,
+        }
+    }
+
+    pub fn set_scale(&mut self, scale: f32) {
+        self.scale = scale;
+        self.font_size = self.base_font_size * scale;
+        self.font = Font::new(self.typeface.clone(), self.font_size);
+    }
+
+    /// Effective font size of a line, honouring its override and the scale.
+    fn line_font_size(&self, line: &StyledLine) -> f32 {
+        line.style
+            .font_size
+            .map_or(self.font_size, |size| size * self.scale)
+    }
+
+    /// The face for a family, weight and slant, loaded once per
+    /// combination; the default face when none is asked for or it can't be
+    /// loaded.
+    fn face(&self, family: Option<&str>, bold: bool, italic: bool) -> Arc<Typeface> {
+        if family.is_none() && !bold && !italic {
+            return self.typeface.clone();
+        }
+        let family = family.unwrap_or(&self.family);
+        let key = (family.to_string(), bold, italic);
+        self.faces
+            .borrow_mut()
+            .entry(key)
+            .or_insert_with(|| {
+                fonts::load(family, bold, italic).unwrap_or_else(|e| {
+                    eprintln!("rustky: font {family:?}: {e}");
+                    self.typeface.clone()
+                })
+            })
+            .clone()
+    }
+
+    fn line_face(&self, line: &StyledLine) -> Arc<Typeface> {
+        let style = &line.style;
+        self.face(style.font_family.as_deref(), style.bold, style.italic)
+    }
+
+    /// A span's face; unset family, weight and slant come from its line.
+    fn span_face(&self, line: &StyledLine, span: &Span) -> Arc<Typeface> {
+        let (style, line_style) = (&span.style, &line.style);
+        let family = style
+            .font_family
+            .as_deref()
+            .or(line_style.font_family.as_deref());
+        self.face(
+            family,
+            style.bold || line_style.bold,
+            style.italic || line_style.italic,
+        )
+    }
+
+    /// A line's font at its effective size.
+    fn line_font(&self, line: &StyledLine) -> Font {
+        Font::new(self.line_face(line), self.line_font_size(line))
+    }
+
+    /// A span's font; an unset size is `font`'s, the line's.
+    fn span_font(&self, line: &StyledLine, span: &Span, font: &Font) -> Font {
+        let size = span
+            .style
+            .font_size
+            .map_or(font.size(), |size| size * self.scale);
+        Font::new(self.span_face(line, span), size)
+    }
+
+    /// `text` split into runs, each with `font` or, for glyphs its face
+    /// lacks, a fallback face at the same size. Joiners and variation
+    /// selectors stay with the character before them.
+    fn runs<'t>(&self, text: &'t str, font: &Font) -> Vec<(&'t str, Font)> {
+        let face = font.typeface_ref();
+        let covered = |c: char| c.is_control() || face.char_to_glyph(c) != 0;
+        if text.chars().all(covered) {
+            return vec![(text, font.clone())];
+        }
+        let mut runs: Vec<(Range<usize>, Arc<Typeface>)> = Vec::new();
+        for (i, c) in text.char_indices() {
+            let end = i + c.len_utf8();
+            let joins = matches!(c, '\u{200d}' | '\u{fe00}'..='\u{fe0f}');
+            let run_face = match runs.last_mut() {
+                Some((range, _)) if joins => {
+                    range.end = end;
+                    continue;
+                }
+                _ if covered(c) => face.clone(),
+                _ => self.fallback.borrow_mut().face(c).unwrap_or(face.clone()),
+            };
+            match runs.last_mut() {
+                Some((range, last)) if Arc::ptr_eq(last, &run_face) => range.end = end,
+                _ => runs.push((i..end, run_face)),
+            }
+        }
+        runs.into_iter()
+            .map(|(range, face)| (&text[range], Font::new(face, font.size())))
+            .collect()
+    }
+
+    /// Lays `text` out from 0, handing `place` each piece drawn in one go
+    /// with its x and font, and returns the total advance. Tabs move to
+    /// the next stop; letter spacing places characters one by one.
+    fn lay_out(
+        &self,
+        text: &str,
+        font: &Font,
+        spacing: Spacing,
+        mut place: impl FnMut(&str, f32, &Font),
+    ) -> f32 {
+        let mut x = 0.0;
+        for (i, segment) in text.split('\t').enumerate() {
+            if i > 0 && spacing.tab > 0.0 {
+                x = ((x / spacing.tab).floor() + 1.0) * spacing.tab;
+            }
+            for (run, font) in self.runs(segment, font) {
+                if spacing.letter == 0.0 {
+                    place(run, x, &font);
+                    x += font.measure_text(run);
+                    continue;
+                }
+                for cluster in clusters(run) {
+                    place(cluster, x, &font);
+                    x += font.measure_text(cluster) + spacing.letter;
+                }
+            }
+        }
+        x
+    }
+
+    /// Width of `text` in `font`, fallback runs included.
+    fn measure(&self, text: &str, font: &Font, spacing: Spacing) -> f32 {
+        self.lay_out(text, font, spacing, |_, _, _| {})
+    }
+
+    /// Draws `text` run by run from `x`, returning its width.
+    #[allow(clippy::too_many_arguments)]
+    fn draw_text(
+        &self,
+        canvas: &mut RasterCanvas<'_>,
+        text: &str,
+        x: f32,
+        baseline: f32,
+        font: &Font,
+        spacing: Spacing,
+        paint: &Paint,
+    ) -> f32 {
+        self.lay_out(text, font, spacing, |piece, piece_x, font| {
+            canvas.draw_string(piece, x + piece_x, baseline, font, paint);
+        })
+    }
+
+    /// Draws part of a line's text like `draw_text`, over the line's
+    /// shadow and outline if it has them.
+    #[allow(clippy::too_many_arguments)]
+    fn draw_line_text(
+        &self,
+        canvas: &mut RasterCanvas<'_>,
+        line: &StyledLine,
+        text: &str,
+        x: f32,
+        baseline: f32,
+        font: &Font,
+        paint: &Paint,
+    ) -> f32 {
+        let spacing = self.line_spacing(line);
+        // The high-contrast palette is legible as it is
+        if !self.high_contrast {
+            let mut behind = paint.clone();
+            if let Some(shadow) = &line.style.shadow {
+                let (dx, dy) = (shadow.offset_x * self.scale, shadow.offset_y * self.scale);
+                let blur = shadow.blur * self.scale;
+                // Softened by stacking faint copies around the offset, each
+                // faint enough that all of them give the shadow's alpha
+                let copies: &[(f32, f32)] = if blur > 0.0 { &RING } else { &[(0.0, 0.0)] };
+                let color = self.color(&shadow.color);
+                let alpha =
+                    1.0 - (1.0 - color.alpha() as f32 / 255.0).powf(1.0 / copies.len() as f32);
+                behind.set_color(color.with_alpha((alpha * 255.0).round() as u8).into());
+                for (ox, oy) in copies {
+                    let (sx, sy) = (x + dx + ox * blur, baseline + dy + oy * blur);
+                    self.draw_text(canvas, text, sx, sy, font, spacing, &behind);
+                }
+            }
+            if let Some(outline) = &line.style.outline {
+                let width = outline.width * self.scale;
+                behind.set_color(self.color(&outline.color).into());
+                for (ox, oy) in &RING[1..] {
+                    let (sx, sy) = (x + ox * width, baseline + oy * width);
+                    self.draw_text(canvas, text, sx, sy, font, spacing, &behind);
+                }
+            }
+        }
+        self.draw_text(canvas, text, x, baseline, font, spacing, paint)
+    }
+
+    /// Spacing of text outside any line, such as page tabs.
+    fn base_spacing(&self) -> Spacing {
+        Spacing {
+            letter: self.letter_spacing * self.scale,
+            tab: self.tab_width * self.scale,
+        }
+    }
+
+    fn line_spacing(&self, line: &StyledLine) -> Spacing {
+        let style = &line.style;
+        Spacing {
+            letter: style.letter_spacing.unwrap_or(self.letter_spacing) * self.scale,
+            tab: style.tab_width.unwrap_or(self.tab_width) * self.scale,
+        }
+    }
+
+    /// Distance from the surface edges to a line's background, growing
+    /// with each section box around it.
+    fn inset(&self, line: &StyledLine) -> f32 {
+        8.0 * self.scale * (1 + line.section_depth) as f32
+    }
+
+    /// Horizontal padding of a line's content: its inset plus the
+    /// style's own padding.
+    fn padding_x(&self, line: &StyledLine) -> f32 {
+        self.inset(line) + line.style.padding.unwrap_or(0.0) * self.scale
+    }
+
+    /// Left edge of a line's text: the padding, then the icon if any.
+    fn text_x(&self, line: &StyledLine) -> f32 {
+        let padding_x = self.padding_x(line);
+        match &line.style.icon {
+            Some(icon) => {
+                let size = self.line_font_size(line);
+                let icon_font = Font::new(self.icon_typeface(icon), size);
+                padding_x + icon_font.measure_text(icon) + 0.4 * size
+            }
+            None => padding_x,
+        }
+    }
+
+    /// Byte ranges of the rows a line's text takes in a surface `width`
+    /// wide; only single-style lines with `wrap` set take more than one.
+    fn rows(&self, line: &StyledLine, width: f32) -> Vec<Range<usize>> {
+        if !line.style.wrap
+            || !line.spans.is_empty()
+            || matches!(line.element, Element::TableRow(_))
+        {
+            return std::iter::once(0..line.text.len()).collect();
+        }
+        let font = self.line_font(line);
+        let max_width = width - self.padding_x(line) - self.text_x(line);
+        let spacing = self.line_spacing(line);
+        wrap(
+            &line.text,
+            |text| self.measure(text, &font, spacing),
+            max_width,
+        )
+    }
+
+    /// Room for a line's text on its row, when it's a marquee line whose
+    /// text doesn't fit in it.
+    fn marquee_room(&self, line: &StyledLine, font: &Font, width: f32) -> Option<f32> {
+        if line.style.overflow != Overflow::Marquee
+            || line.style.wrap
+            || !line.spans.is_empty()
+            || line.element != Element::Text
+        {
+            return None;
+        }
+        let room = width - self.padding_x(line) - self.text_x(line);
+        (self.measure(&line.text, font, self.line_spacing(line)) > room).then_some(room)
+    }
+
+    /// Whether any of `lines` scrolls as a marquee, so frames need
+    /// redrawing as `marquee_time` advances.
+    pub fn has_marquee(&self, lines: &[StyledLine], width: u32) -> bool {
+        lines.iter().any(|line| {
+            let font = self.line_font(line);
+            self.marquee_room(line, &font, width as f32).is_some()
+        })
+    }
+
+    /// Height of the rows holding a line's text, fitting its largest span;
+    /// gauge rows may have none.
+    fn text_height(&self, line: &StyledLine, width: f32) -> f32 {
+        match &line.element {
+            Element::Gauges(_) | Element::Image(_) if line.text.is_empty() => 0.0,
+            Element::SectionEnd | Element::Separator(_) => 0.0,
+            _ => {
+                let spans = line.spans.iter().filter_map(|span| span.style.font_size);
+                spans
+                    .map(|size| size * self.scale)
+                    .fold(self.line_font_size(line), f32::max)
+                    * line
+                        .style
+                        .line_height_factor
+                        .unwrap_or(self.line_height_factor)
+                    * self.rows(line, width).len() as f32
+            }
+        }
+    }
+
+    /// Draws a line's spans on `baseline` from the left of `row`, each over
+    /// its own background if it has one, and returns their total width.
+    fn draw_spans(
+        &self,
+        canvas: &mut RasterCanvas<'_>,
+        line: &StyledLine,
+        font: &Font,
+        fg: Color,
+        baseline: f32,
+        row: Rect,
+    ) -> f32 {
+        let mut x = row.left;
+        let mut paint = Paint::default();
+        paint.set_anti_alias(true);
+        let spacing = self.line_spacing(line);
+        for span in &line.spans {
+            let font = &self.span_font(line, span, font);
+            let span_w = self.measure(&span.text, font, spacing);
+            if self.high_contrast {
+                paint.set_color(fg.into());
+            } else {
+                if let Some(bg) = &span.style.bg_color {
+                    paint.set_color(self.color(bg).into());
+                    canvas.draw_rect(&Rect::from_xywh(x, row.top, span_w, row.height()), &paint);
+                }
+                let color = span.style.fg_color.as_deref().map_or(fg, |c| self.color(c));
+                paint.set_color(color.into());
+            }
+            self.draw_line_text(canvas, line, &span.text, x, baseline, font, &paint);
+            x += span_w;
+        }
+        x - row.left
+    }
+
+    /// Left edge and width of each table column between `left` and
+    /// `right`, a gap apart.
+    fn column_spans(
+        &self,
+        columns: &[Column],
+        font_size: f32,
+        left: f32,
+        right: f32,
+    ) -> Vec<(f32, f32)> {
+        let gap = 0.8 * font_size;
+        let fixed: f32 = columns.iter().filter_map(|c| c.width).sum::<f32>() * self.scale;
+        let gaps = gap * columns.len().saturating_sub(1) as f32;
+        let flex = columns.iter().filter(|c| c.width.is_none()).count().max(1);
+        let share = ((right - left - fixed - gaps) / flex as f32).max(0.0);
+        let mut x = left;
+        columns
+            .iter()
+            .map(|column| {
+                let column_w = column.width.map_or(share, |w| w * self.scale);
+                let span = (x, column_w);
+                x += column_w + gap;
+                span
+            })
+            .collect()
+    }
+
+    fn highlight_paint(&self) -> Paint {
+        let mut paint = Paint::default();
+        let color = if self.high_contrast {
+            HC_EMPHASIS.with_alpha(120)
+        } else {
+            self.fg.with_alpha(70)
+        };
+        paint.set_color(color.into());
+        paint
+    }
+
+    /// Width of a line's text as drawn in `rows`.
+    fn text_width(&self, line: &StyledLine, font: &Font, rows: &[Range<usize>]) -> f32 {
+        let spacing = self.line_spacing(line);
+        if line.spans.is_empty() {
+            return rows
+                .iter()
+                .map(|row| self.measure(&line.text[row.clone()], font, spacing))
+                .fold(0.0, f32::max);
+        }
+        line.spans
+            .iter()
+            .map(|span| self.measure(&span.text, &self.span_font(line, span, font), spacing))
+            .sum()
+    }
+
+    /// Height of a line including any element drawn below its text, its
+    /// padding and its margins.
+    fn line_height(&self, line: &StyledLine, width: f32) -> f32 {
+        let font_size = self.line_font_size(line);
+        let text = self.text_height(line, width);
+        let content = match &line.element {
+            Element::Graph(graph) => text + graph.height * self.scale + 0.4 * font_size,
+            Element::Gauges(gauges) => {
+                let radius = gauges.iter().map(|g| g.radius).fold(0.0, f32::max);
+                // dial, then its caption
+                text + 0.3 * font_size + 2.0 * radius * self.scale + font_size * 1.6
+            }
+            Element::Image(picture) => text + picture.height * self.scale + 0.4 * font_size,
+            Element::Heatmap(heatmap) => {
+                let rows = heatmap.values.len().div_ceil(heatmap.columns);
+                text + 0.3 * font_size + rows as f32 * (heatmap.cell_size + 2.0) * self.scale
+            }
+            // gap under the title's separator; padding above the box's edge
+            Element::SectionStart(_) => text + 0.3 * font_size,
+            Element::SectionEnd => 0.5 * font_size,
+            Element::Separator(rule) => (rule.thickness + 2.0 * rule.padding) * self.scale,
+            Element::Text | Element::Bar(_) | Element::Sparkline(_) | Element::TableRow(_) => text,
+        };
+        let style = &line.style;
+        let spacing = 2.0 * style.padding.unwrap_or(0.0) + style.margin_top + style.margin_bottom;
+        content + spacing * self.scale
+    }
+
+    /// The icon font when it has every glyph of `icon`, else the main font
+    /// if that does; the icon font otherwise.
+    fn icon_typeface(&self, icon: &str) -> Arc<Typeface> {
+        let covers = |face: &Typeface| icon.chars().all(|c| face.char_to_glyph(c) != 0);
+        match &self.icon_typeface {
+            Some(face) if covers(face) || !covers(&self.typeface) => face.clone(),
+            _ => self.typeface.clone(),
+        }
+    }
+
+    /// `spec` as a theme color name, or else as a hex color.
+    fn color(&self, spec: &str) -> Color {
+        color(&self.palette, spec)
+    }
+
+    /// The color for `value` along `stops`, blended between the two it
+    /// falls between and held past the first and last.
+    fn stop_color(&self, stops: &[ColorStop], value: f32) -> Color {
+        let value = f64::from(value);
+        let below = stops
+            .iter()
+            .filter(|stop| stop.at <= value)
+            .max_by(|a, b| a.at.total_cmp(&b.at));
+        let above = stops
+            .iter()
+            .filter(|stop| stop.at > value)
+            .min_by(|a, b| a.at.total_cmp(&b.at));
+        match (below, above) {
+            (Some(below), Some(above)) => {
+                let t = (value - below.at) / (above.at - below.at);
+                let (from, to) = (self.color(&below.color), self.color(&above.color));
+                Color4f::from_color(from)
+                    .lerp(&Color4f::from_color(to), t as f32)
+                    .to_color()
+            }
+            (Some(stop), None) | (None, Some(stop)) => self.color(&stop.color),
+            (None, None) => self.fg(),
+        }
+    }
+
+    fn fg(&self) -> Color {
+        if self.high_contrast { HC_FG } else { self.fg }
+    }
+
+    fn bg(&self) -> Color {
+        if self.high_contrast { HC_BG } else { self.bg }
+    }
+
+    /// Foreground and optional background of a line after theme overrides.
+    fn line_colors(&self, line: &StyledLine) -> (Color, Option<Color>) {
+        if self.high_contrast {
+            return match line.style.bg_color {
+                Some(_) => (HC_BG, Some(HC_EMPHASIS)),
+                None => (HC_FG, None),
+            };
+        }
+        let fg = line
+            .style
+            .fg_color
+            .as_deref()
+            .map(|c| self.color(c))
+            .unwrap_or(self.fg);
+        (fg, line.style.bg_color.as_deref().map(|c| self.color(c)))
+    }
+
+    /// Makes `line`'s text, icon and background `opacity` times as opaque,
+    /// for a line fading in.
+    pub fn fade(&self, line: &mut StyledLine, opacity: f32) {
+        let faded = |color: Color| {
+            let alpha = (color.alpha() as f32 * opacity).round() as u8;
+            let (r, g, b) = (color.red(), color.green(), color.blue());
+            format!("#{r:02x}{g:02x}{b:02x}{alpha:02x}")
+        };
+        let (fg, bg) = self.line_colors(line);
+        let icon = line
+            .style
+            .icon_color
+            .as_deref()
+            .map_or(fg, |c| self.color(c));
+        line.style.fg_color = Some(faded(fg));
+        line.style.bg_color = bg.map(faded);
+        line.style.icon_color = Some(faded(icon));
+        for span in &mut line.spans {
+            let span_fg = span.style.fg_color.as_deref().map_or(fg, |c| self.color(c));
+            span.style.fg_color = Some(faded(span_fg));
+            span.style.bg_color = span.style.bg_color.as_deref().map(|c| faded(self.color(c)));
         }
     }
 
@@ -50,18 +810,19 @@ impl Renderer {
 
         {
             let mut canvas = surface.raster_canvas();
-            canvas.clear(self.bg);
+            canvas.clear(self.bg());
 
             let mut paint = Paint::default();
-            paint.set_color(self.fg.into());
+            paint.set_color(self.fg().into());
             paint.set_anti_alias(true);
 
-            let line_height = self.font_size * 1.4;
-            let padding_x = 8.0;
+            let line_height = self.font_size * self.line_height_factor;
+            let padding_x = 8.0 * self.scale;
             let mut y = line_height;
+            let spacing = self.base_spacing();
 
             for line in lines {
-                canvas.draw_string(line, padding_x, y, &self.font, &paint);
+                self.draw_text(&mut canvas, line, padding_x, y, &self.font, spacing, &paint);
                 y += line_height;
             }
         }
@@ -69,15 +830,102 @@ impl Renderer {
         surface.pixels().to_vec()
     }
 
-    pub fn content_height(&self, lines: &[StyledLine]) -> f32 {
+    /// Total height of `lines` laid out `width` wide, wrapped rows included.
+    pub fn content_height(&self, lines: &[StyledLine], width: u32) -> f32 {
         let mut h = 0.0_f32;
         for line in lines {
-            let fs = line.style.font_size.unwrap_or(self.font_size);
-            h += fs * 1.4;
+            h += self.line_height(line, width as f32);
         }
         h
     }
 
+    /// Top and bottom of each line in content coordinates, matching the
+    /// layout used by `render_styled_lines_scroll`.
+    pub fn line_extents(&self, lines: &[StyledLine], width: u32) -> Vec<(f32, f32)> {
+        let mut y = 0.0_f32;
+        lines
+            .iter()
+            .map(|line| {
+                let top = y;
+                y += self.line_height(line, width as f32);
+                (top, y)
+            })
+            .collect()
+    }
+
+    /// Height reserved at the top of the surface for the page tab strip.
+    /// Width a line takes on a horizontal bar: its padding either side,
+    /// icon and text, and any bar or sparkline after the text.
+    pub fn cell_width(&self, line: &StyledLine) -> f32 {
+        if line.element == Element::SectionEnd {
+            return 0.0;
+        }
+        let font = self.line_font(line);
+        // One row: nothing wraps on a bar
+        let row: Vec<_> = std::iter::once(0..line.text.len()).collect();
+        let text_w = self.text_width(line, &font, &row);
+        let gap = 6.0 * self.scale;
+        let after = match &line.element {
+            Element::Bar(bar) => gap + bar.width * self.scale,
+            Element::Sparkline(spark) => gap + spark.width * self.scale,
+            _ => 0.0,
+        };
+        self.text_x(line) + text_w + after + self.padding_x(line)
+    }
+
+    /// Left and right edges of each line on a horizontal bar `width` wide:
+    /// left-aligned lines packed from the left edge, right-aligned ones
+    /// against the right edge and centred ones around the middle.
+    pub fn row_cells(&self, lines: &[StyledLine], width: f32) -> Vec<(f32, f32)> {
+        let widths: Vec<f32> = lines.iter().map(|line| self.cell_width(line)).collect();
+        let total = |align| -> f32 {
+            let aligned = lines.iter().zip(&widths).filter(|(l, _)| l.align == align);
+            aligned.map(|(_, w)| w).sum()
+        };
+        let mut left = 0.0;
+        let mut center = (width - total(Align::Center)) / 2.0;
+        let mut right = width - total(Align::Right);
+        lines
+            .iter()
+            .zip(widths)
+            .map(|(line, w)| {
+                let x = match line.align {
+                    Align::Left => &mut left,
+                    Align::Center => &mut center,
+                    Align::Right => &mut right,
+                };
+                *x += w;
+                (*x - w, *x)
+            })
+            .collect()
+    }
+
+    /// Draws a horizontal bar, each line in its cell from `row_cells` and
+    /// centred vertically.
+    pub fn render_row(&self, surface: &mut Surface, lines: &[StyledLine], cells: &[(f32, f32)]) {
+        let height = surface.height() as u32;
+        let mut canvas = surface.raster_canvas();
+        canvas.clear(self.bg());
+        for (i, (line, &(left, right))) in lines.iter().zip(cells).enumerate() {
+            let width = right - left;
+            let top = (height as f32 - self.line_height(line, width)) / 2.0;
+            canvas.save();
+            canvas.translate(left, top.round());
+            let band = (0.0, height as f32);
+            let (line, cell_w) = (&lines[i..=i], width.ceil() as u32);
+            self.draw_frame(&mut canvas, line, cell_w, height, 0.0, &[], band);
+            canvas.restore();
+        }
+    }
+
+    pub fn tab_strip_height(&self, tabs: &[(String, bool)]) -> f32 {
+        if tabs.is_empty() {
+            0.0
+        } else {
+            self.font_size * 1.8
+        }
+    }
+
     #[allow(dead_code)]
     pub fn render_styled_lines(
         &self,
@@ -85,7 +933,7 @@ impl Renderer {
         width: u32,
         height: u32,
     ) -> Vec<u8> {
-        self.render_styled_lines_scroll(lines, width, height, 0.0)
+        self.render_styled_lines_scroll(lines, width, height, 0.0, &[])
     }
 
     pub fn render_styled_lines_scroll(
@@ -94,66 +942,631 @@ impl Renderer {
         width: u32,
         height: u32,
         scroll_offset: f32,
+        tabs: &[(String, bool)],
     ) -> Vec<u8> {
-        let w = width as i32;
-        let h = height as i32;
-
+        let (w, h) = (width as i32, height as i32);
         let mut surface = Surface::new_raster_n32_premul(w, h).expect("failed to create surface");
+        self.render_into(&mut surface, lines, scroll_offset, tabs);
+        surface.pixels().to_vec()
+    }
 
-        {
-            let mut canvas = surface.raster_canvas();
-            canvas.clear(self.bg);
+    /// Draws a whole frame into `surface`, which callers keep across frames
+    /// to avoid allocating one each time.
+    pub fn render_into(
+        &self,
+        surface: &mut Surface,
+        lines: &[StyledLine],
+        scroll_offset: f32,
+        tabs: &[(String, bool)],
+    ) {
+        let (width, height) = (surface.width() as u32, surface.height() as u32);
+        let mut canvas = surface.raster_canvas();
+        canvas.clear(self.bg());
+        let band = (0.0, height as f32);
+        self.draw_frame(&mut canvas, lines, width, height, scroll_offset, tabs, band);
+    }
 
-            let padding_x = 8.0;
-            let height_f = height as f32;
-            let mut y = -scroll_offset;
+    /// Redraws only the horizontal `strips` (top and bottom, in pixels) of
+    /// `surface`, holding a frame drawn earlier with the same layout.
+    pub fn render_strips(
+        &self,
+        surface: &mut Surface,
+        lines: &[StyledLine],
+        scroll_offset: f32,
+        tabs: &[(String, bool)],
+        strips: &[(f32, f32)],
+    ) {
+        let (width, height) = (surface.width() as u32, surface.height() as u32);
+        let mut canvas = surface.raster_canvas();
+        for &(top, bottom) in strips {
+            canvas.save();
+            canvas.clip_rect(&Rect::from_xywh(0.0, top, width as f32, bottom - top));
+            // Unlike `clear`, honours the clip
+            canvas.draw_color(self.bg(), BlendMode::Src);
+            let band = (top, bottom);
+            self.draw_frame(&mut canvas, lines, width, height, scroll_offset, tabs, band);
+            canvas.restore();
+        }
+    }
 
-            for line in lines {
-                let eff_font_size = line.style.font_size.unwrap_or(self.font_size);
-                let line_height = eff_font_size * 1.4;
-                y += line_height;
+    /// Draws everything over the background, skipping lines outside
+    /// `band`, the rows (top and bottom) being drawn.
+    #[allow(clippy::too_many_arguments)]
+    fn draw_frame(
+        &self,
+        canvas: &mut RasterCanvas<'_>,
+        lines: &[StyledLine],
+        width: u32,
+        height: u32,
+        scroll_offset: f32,
+        tabs: &[(String, bool)],
+        band: (f32, f32),
+    ) {
+        let (w, h) = (width as i32, height as i32);
+        let height_f = height as f32;
+        let strip_h = self.tab_strip_height(tabs);
+        let mut y = strip_h - scroll_offset;
 
-                // Skip lines that are fully above or below the viewport
-                if y < 0.0 {
-                    continue;
+        canvas.save();
+        canvas.clip_rect(&Rect::from_xywh(
+            0.0,
+            strip_h,
+            width as f32,
+            height_f - strip_h,
+        ));
+
+        for (i, line) in lines.iter().enumerate() {
+            let padding_x = self.padding_x(line);
+            let eff_font_size = self.line_font_size(line);
+            let (fg_color, bg_color) = self.line_colors(line);
+            let line_height = self.line_height(line, width as f32);
+            y += line_height;
+            // The background sits between the margins, the content
+            // inside it after the padding
+            let pad = line.style.padding.unwrap_or(0.0) * self.scale;
+            let bg_top = y - line_height + line.style.margin_top * self.scale;
+            let bg_h =
+                line_height - (line.style.margin_top + line.style.margin_bottom) * self.scale;
+            let top = bg_top + pad;
+            // Text sits in the first rows, `baseline` being the last
+            // one's; elements may extend below it
+            let text_height = self.text_height(line, width as f32);
+            let baseline = top + text_height;
+
+            // Section box, drawn before its contents and even when its
+            // title has scrolled out of view
+            if let Element::SectionStart(section) = &line.element {
+                let end = lines[i..]
+                    .iter()
+                    .position(|l| {
+                        l.element == Element::SectionEnd && l.section_depth == line.section_depth
+                    })
+                    .map_or(lines.len(), |j| i + j + 1);
+                let box_h: f32 = lines[i..end]
+                    .iter()
+                    .map(|l| self.line_height(l, width as f32))
+                    .sum();
+                let left = self.inset(line) - 12.0 * self.scale;
+                let top = y - line_height;
+                if top < height_f && top + box_h > strip_h {
+                    let rect = Rect::from_xywh(left, top, width as f32 - 2.0 * left, box_h);
+                    let radius = 4.0 * self.scale;
+                    let mut box_paint = Paint::default();
+                    box_paint.set_anti_alias(true);
+                    if let Some(bg) = &section.bg_color
+                        && !self.high_contrast
+                    {
+                        box_paint.set_color(self.color(bg).into());
+                        canvas.draw_round_rect(&rect, radius, radius, &box_paint);
+                    }
+                    let border = match &section.border_color {
+                        Some(color) if !self.high_contrast => self.color(color),
+                        _ if self.high_contrast => fg_color,
+                        _ => fg_color.with_alpha(90),
+                    };
+                    box_paint.set_color(border.into());
+                    box_paint.set_style(Style::Stroke);
+                    canvas.draw_round_rect(&rect, radius, radius, &box_paint);
+                    // Separator under the title
+                    let rule_y = baseline + 0.3 * eff_font_size;
+                    canvas.draw_line(
+                        Point::new(left, rule_y),
+                        Point::new(width as f32 - left, rule_y),
+                        &box_paint,
+                    );
                 }
-                if y - line_height > height_f {
-                    break;
+            }
+
+            // Skip lines that are fully above or below the viewport
+            // or the band being drawn
+            if y < strip_h.max(band.0) {
+                continue;
+            }
+            if y - line_height > band.1.min(height_f) {
+                break;
+            }
+
+            let rows = self.rows(line, width as f32);
+            let row_h = text_height / rows.len() as f32;
+            let text_x = self.text_x(line);
+
+            // Per-line font: reuse the default one or create a custom one
+            let face = self.line_face(line);
+            let custom_font;
+            let font = if (eff_font_size - self.font_size).abs() < 0.01
+                && Arc::ptr_eq(&face, &self.typeface)
+            {
+                &self.font
+            } else {
+                custom_font = Font::new(face, eff_font_size);
+                &custom_font
+            };
+            let spacing = self.line_spacing(line);
+
+            // Per-line background: a pill around padded text, else the
+            // line's full width (inside a section, its box's)
+            if let Some(bg_color) = bg_color {
+                let mut bg_paint = Paint::default();
+                bg_paint.set_anti_alias(true);
+                bg_paint.set_color(bg_color.into());
+                let (left, right) = match (line.style.padding, &line.element) {
+                    (Some(_), Element::Text) => {
+                        let text_w = self
+                            .text_width(line, font, &rows)
+                            .min(width as f32 - padding_x - text_x);
+                        (self.inset(line), text_x + text_w + pad)
+                    }
+                    (Some(_), _) => (self.inset(line), width as f32 - self.inset(line)),
+                    (None, _) => {
+                        let left = match line.section_depth {
+                            0 => 0.0,
+                            _ => self.inset(line) - 12.0 * self.scale,
+                        };
+                        (left, width as f32 - left)
+                    }
+                };
+                let rect = Rect::from_xywh(left, bg_top, right - left, bg_h);
+                let radius = line.style.corner_radius * self.scale;
+                if radius > 0.0 {
+                    canvas.draw_round_rect(&rect, radius, radius, &bg_paint);
+                } else {
+                    canvas.draw_rect(&rect, &bg_paint);
+                }
+            }
+
+            // Icon before the text, its em box centred on the text's
+            // x-height since icon fonts sit on the baseline differently
+            if let Some(icon) = &line.style.icon {
+                let icon_font = Font::new(self.icon_typeface(icon), eff_font_size);
+                let icon_color = match &line.style.icon_color {
+                    Some(color) if !self.high_contrast => self.color(color),
+                    _ => fg_color,
+                };
+                let mut icon_paint = Paint::default();
+                icon_paint.set_anti_alias(true);
+                icon_paint.set_color(icon_color.into());
+                let first_baseline = baseline - (rows.len() - 1) as f32 * row_h;
+                let icon_baseline = first_baseline
+                    - 0.35 * eff_font_size
+                    - (icon_font.ascent() + icon_font.descent()) / 2.0;
+                canvas.draw_string(icon, padding_x, icon_baseline, &icon_font, &icon_paint);
+            }
+
+            // Search match highlight, split over the rows it spans; table
+            // cells mark their own
+            if let Some((start, end)) = line.highlight
+                && !matches!(line.element, Element::TableRow(_))
+            {
+                let hl_paint = self.highlight_paint();
+                for (i, row) in rows.iter().enumerate() {
+                    let (from, to) = (start.max(row.start), end.min(row.end));
+                    if from >= to {
+                        continue;
+                    }
+                    let x0 = text_x + self.measure(&line.text[row.start..from], font, spacing);
+                    let match_w = self.measure(&line.text[from..to], font, spacing);
+                    let (top, hl_h) = match rows.len() {
+                        1 => (top, bg_h - 2.0 * pad),
+                        _ => (top + i as f32 * row_h, row_h),
+                    };
+                    canvas.draw_rect(&Rect::from_xywh(x0, top, match_w, hl_h), &hl_paint);
                 }
+            }
+
+            let mut paint = Paint::default();
+            paint.set_color(fg_color.into());
+            paint.set_anti_alias(true);
 
-                // Per-line background
-                if let Some(ref bg_hex) = line.style.bg_color {
-                    let bg_color = parse_hex_color(bg_hex);
-                    let mut bg_paint = Paint::default();
-                    bg_paint.set_color(bg_color.into());
-                    canvas.draw_rect(
-                        &Rect::from_xywh(0.0, y - line_height, width as f32, line_height),
-                        &bg_paint,
+            let text_w = if let Element::TableRow(table) = &line.element {
+                let right = width as f32 - padding_x;
+                let spans = self.column_spans(&table.columns, eff_font_size, text_x, right);
+                // Byte offset of each cell in the line's text
+                let mut start = 0;
+                for ((cell, column), (x, column_w)) in
+                    table.cells.iter().zip(&table.columns).zip(spans)
+                {
+                    let text = ellipsize(cell, |text| self.measure(text, font, spacing), column_w);
+                    let text_w = self.measure(&text, font, spacing);
+                    let x = match column.align {
+                        Align::Left => x,
+                        Align::Right => x + column_w - text_w,
+                        Align::Center => x + (column_w - text_w) / 2.0,
+                    };
+                    if let Some((from, to)) = line.highlight {
+                        // Only the part of the cell still shown before any "…"
+                        let shown = match text {
+                            std::borrow::Cow::Borrowed(_) => cell.len(),
+                            std::borrow::Cow::Owned(_) => text.len() - '…'.len_utf8(),
+                        };
+                        let (from, to) = (from.max(start), to.min(start + shown));
+                        if from < to {
+                            let x0 = x + self.measure(&cell[..from - start], font, spacing);
+                            let match_w =
+                                self.measure(&cell[from - start..to - start], font, spacing);
+                            canvas.draw_rect(
+                                &Rect::from_xywh(x0, top, match_w, bg_h - 2.0 * pad),
+                                &self.highlight_paint(),
+                            );
+                        }
+                    }
+                    self.draw_line_text(canvas, line, &text, x, baseline, font, &paint);
+                    start += cell.len() + TABLE_SEPARATOR.len();
+                }
+                right - text_x
+            } else if !line.spans.is_empty() {
+                let row =
+                    Rect::from_xywh(text_x, top, width as f32 - padding_x - text_x, text_height);
+                self.draw_spans(canvas, line, font, fg_color, baseline, row)
+            } else if rows.len() > 1 {
+                let last = rows.len() - 1;
+                for (i, row) in rows.iter().enumerate() {
+                    let row_baseline = baseline - (last - i) as f32 * row_h;
+                    self.draw_line_text(
+                        canvas,
+                        line,
+                        &line.text[row.clone()],
+                        text_x,
+                        row_baseline,
+                        font,
+                        &paint,
                     );
                 }
+                self.measure(&line.text[rows[last].clone()], font, spacing)
+            } else if line.style.overflow == Overflow::Ellipsize {
+                let max_width = width as f32 - padding_x - text_x;
+                let text = ellipsize(
+                    &line.text,
+                    |text| self.measure(text, font, spacing),
+                    max_width,
+                );
+                self.draw_line_text(canvas, line, &text, text_x, baseline, font, &paint)
+            } else if let Some(room) = self.marquee_room(line, font, width as f32) {
+                // The text, then a gap and the text again, moving left
+                // until the second copy reaches the start
+                let pass = self.measure(&line.text, font, spacing) + 2.0 * eff_font_size;
+                let speed = MARQUEE_SPEED * self.scale;
+                let period = MARQUEE_PAUSE + pass / speed;
+                let offset = (self.marquee_time % period - MARQUEE_PAUSE).max(0.0) * speed;
+                canvas.save();
+                canvas.clip_rect(&Rect::from_xywh(text_x, bg_top, room, bg_h));
+                for x in [text_x - offset, text_x - offset + pass] {
+                    self.draw_line_text(canvas, line, &line.text, x, baseline, font, &paint);
+                }
+                canvas.restore();
+                room
+            } else {
+                self.draw_line_text(canvas, line, &line.text, text_x, baseline, font, &paint)
+            };
 
-                // Per-line foreground color
-                let fg_color = line
-                    .style
-                    .fg_color
-                    .as_deref()
-                    .map(parse_hex_color)
-                    .unwrap_or(self.fg);
+            // Bar right-aligned in the line, pushed right of a long label
+            if let Element::Bar(bar) = &line.element {
+                let right = width as f32 - padding_x;
+                let label_end = text_x + text_w + 6.0 * self.scale;
+                let x = (right - bar.width * self.scale).max(label_end);
+                let bar_w = right - x;
+                if bar_w > 0.0 {
+                    let bar_h = eff_font_size * 0.6;
+                    let top = baseline - eff_font_size * 0.35 - bar_h / 2.0;
+                    let radius = 2.0 * self.scale;
+                    let mut bar_paint = Paint::default();
+                    bar_paint.set_anti_alias(true);
+                    bar_paint.set_color(fg_color.with_alpha(50).into());
+                    canvas.draw_round_rect(
+                        &Rect::from_xywh(x, top, bar_w, bar_h),
+                        radius,
+                        radius,
+                        &bar_paint,
+                    );
+                    let fill = if self.high_contrast {
+                        fg_color
+                    } else {
+                        self.color(&bar.fill_color)
+                    };
+                    bar_paint.set_color(fill.into());
+                    canvas.draw_round_rect(
+                        &Rect::from_xywh(x, top, bar_w * bar.fraction, bar_h),
+                        radius,
+                        radius,
+                        &bar_paint,
+                    );
+                }
+            }
 
-                let mut paint = Paint::default();
-                paint.set_color(fg_color.into());
-                paint.set_anti_alias(true);
+            if let Element::Graph(graph) = &line.element {
+                let area = Rect::from_xywh(
+                    padding_x,
+                    baseline + 0.3 * eff_font_size,
+                    width as f32 - 2.0 * padding_x,
+                    graph.height * self.scale,
+                );
+                let mut frame_paint = Paint::default();
+                frame_paint.set_color(fg_color.with_alpha(25).into());
+                canvas.draw_rect(&area, &frame_paint);
+                let color = if self.high_contrast {
+                    fg_color
+                } else {
+                    self.color(&graph.color)
+                };
+                draw_series(canvas, &graph.samples, graph.slots, graph.max, area, color);
+            }
 
-                // Per-line font size: reuse default font or create a custom one
-                if (eff_font_size - self.font_size).abs() < 0.01 {
-                    canvas.draw_string(&line.text, padding_x, y, &self.font, &paint);
+            // Sparkline inline after the text, as tall as the glyphs
+            if let Element::Sparkline(spark) = &line.element {
+                let x = if line.text.is_empty() {
+                    text_x
                 } else {
-                    let custom_font = Font::new(self.typeface.clone(), eff_font_size);
-                    canvas.draw_string(&line.text, padding_x, y, &custom_font, &paint);
+                    text_x + text_w + 6.0 * self.scale
+                };
+                let spark_w = (spark.width * self.scale).min(width as f32 - padding_x - x);
+                if spark_w > 0.0 {
+                    let area = Rect::from_xywh(
+                        x,
+                        baseline - 0.8 * eff_font_size,
+                        spark_w,
+                        0.8 * eff_font_size,
+                    );
+                    let color = match &spark.color {
+                        Some(color) if !self.high_contrast => self.color(color),
+                        _ => fg_color,
+                    };
+                    let max = spark.samples.iter().flatten().copied().fold(0.0, f64::max);
+                    draw_series(
+                        canvas,
+                        &spark.samples,
+                        spark.samples.len(),
+                        max,
+                        area,
+                        color,
+                    );
+                }
+            }
+
+            if let Element::Image(picture) = &line.element {
+                let dst = Rect::from_xywh(
+                    padding_x,
+                    baseline + 0.2 * eff_font_size,
+                    picture.width * self.scale,
+                    picture.height * self.scale,
+                );
+                canvas.draw_image_rect(&picture.image, None, &dst, None);
+            }
+
+            if let Element::Separator(rule) = &line.element {
+                let color = match &rule.color {
+                    Some(color) if !self.high_contrast => self.color(color),
+                    _ if self.high_contrast => fg_color,
+                    _ => fg_color.with_alpha(90),
+                };
+                let mut rule_paint = Paint::default();
+                rule_paint.set_color(color.into());
+                canvas.draw_rect(
+                    &Rect::from_xywh(
+                        padding_x,
+                        top + rule.padding * self.scale,
+                        width as f32 - 2.0 * padding_x,
+                        (rule.thickness * self.scale).max(1.0),
+                    ),
+                    &rule_paint,
+                );
+            }
+
+            // Cells row by row, a small gap apart
+            if let Element::Heatmap(heatmap) = &line.element {
+                let cell = heatmap.cell_size * self.scale;
+                let step = cell + 2.0 * self.scale;
+                let top = baseline + 0.3 * eff_font_size;
+                let radius = 2.0 * self.scale;
+                let mut cell_paint = Paint::default();
+                cell_paint.set_anti_alias(true);
+                for (i, &value) in heatmap.values.iter().enumerate() {
+                    let color = if self.high_contrast {
+                        // Brighter for busier cores
+                        fg_color.with_alpha((40.0 + 2.15 * value.clamp(0.0, 100.0)) as u8)
+                    } else {
+                        self.stop_color(&heatmap.stops, value)
+                    };
+                    cell_paint.set_color(color.into());
+                    let (row, column) = (i / heatmap.columns, i % heatmap.columns);
+                    let rect = Rect::from_xywh(
+                        padding_x + column as f32 * step,
+                        top + row as f32 * step,
+                        cell,
+                        cell,
+                    );
+                    canvas.draw_round_rect(&rect, radius, radius, &cell_paint);
+                }
+            }
+
+            if let Element::Gauges(gauges) = &line.element {
+                let mut x = padding_x;
+                let mut gauge_paint = Paint::default();
+                gauge_paint.set_anti_alias(true);
+                gauge_paint.set_style(Style::Fill);
+                for gauge in gauges {
+                    let outer = gauge.radius * self.scale;
+                    let inner = (outer - gauge.thickness * self.scale).max(0.0);
+                    let caption_w = self.measure(&gauge.label, font, spacing);
+                    let cell_w = (2.0 * outer).max(caption_w) + 12.0 * self.scale;
+                    let center =
+                        Point::new(x + cell_w / 2.0, baseline + 0.3 * eff_font_size + outer);
+
+                    // 270° sweep with the opening at the bottom
+                    gauge_paint.set_color(fg_color.with_alpha(40).into());
+                    canvas.draw_path(
+                        &ring_segment(center, outer, inner, 135.0, 270.0),
+                        &gauge_paint,
+                    );
+                    let color = if self.high_contrast {
+                        fg_color
+                    } else {
+                        self.color(&gauge.color)
+                    };
+                    if gauge.fraction > 0.0 {
+                        gauge_paint.set_color(color.into());
+                        canvas.draw_path(
+                            &ring_segment(center, outer, inner, 135.0, 270.0 * gauge.fraction),
+                            &gauge_paint,
+                        );
+                    }
+
+                    gauge_paint.set_color(fg_color.into());
+                    let value_font =
+                        Font::new(self.typeface.clone(), (inner * 0.6).min(eff_font_size));
+                    let value_w = value_font.measure_text(&gauge.value);
+                    canvas.draw_string(
+                        &gauge.value,
+                        center.x - value_w / 2.0,
+                        center.y + value_font.size() * 0.35,
+                        &value_font,
+                        &gauge_paint,
+                    );
+                    self.draw_text(
+                        canvas,
+                        &gauge.label,
+                        center.x - caption_w / 2.0,
+                        center.y + outer + eff_font_size * 1.2,
+                        font,
+                        spacing,
+                        &gauge_paint,
+                    );
+                    x += cell_w;
                 }
             }
         }
 
-        surface.pixels().to_vec()
+        canvas.restore();
+
+        // Overflowing content: fades at the edges with more beyond them,
+        // and a scrollbar thumb
+        let view_h = height_f - strip_h;
+        let content_h = self.content_height(lines, width);
+        if content_h > view_h {
+            let max_scroll = content_h - view_h;
+            let fade_h = (1.5 * self.font_size).min(view_h / 2.0);
+            let mut edges = Vec::new();
+            if scroll_offset > 0.0 {
+                edges.push((strip_h, strip_h + fade_h));
+            }
+            if scroll_offset < max_scroll {
+                edges.push((height_f, height_f - fade_h));
+            }
+            let bg = Color4f::from_color(self.bg());
+            let clear = Color4f::from_color(self.bg().with_alpha(0));
+            let mut fade_paint = Paint::default();
+            for (edge, inner) in edges {
+                // Shaded fills don't honour the clip, so cut to the band here
+                let top = edge.min(inner).max(band.0);
+                let bottom = edge.max(inner).min(band.1);
+                if top >= bottom {
+                    continue;
+                }
+                fade_paint.set_shader(Some(shaders::linear_gradient(
+                    Point::new(0.0, edge),
+                    Point::new(0.0, inner),
+                    vec![bg, clear],
+                    None,
+                    TileMode::Clamp,
+                )));
+                canvas.draw_rect(
+                    &Rect::from_xywh(0.0, top, width as f32, bottom - top),
+                    &fade_paint,
+                );
+            }
+
+            let thumb_h = (view_h * view_h / content_h).max((2.0 * self.font_size).min(view_h));
+            let thumb_y = strip_h + (view_h - thumb_h) * (scroll_offset / max_scroll).min(1.0);
+            let thumb_w = 3.0 * self.scale;
+            let thumb = Rect::from_xywh(
+                width as f32 - thumb_w - 2.0 * self.scale,
+                thumb_y,
+                thumb_w,
+                thumb_h,
+            );
+            let mut thumb_paint = Paint::default();
+            thumb_paint.set_anti_alias(true);
+            thumb_paint.set_color(self.fg().with_alpha(100).into());
+            canvas.draw_round_rect(&thumb, thumb_w / 2.0, thumb_w / 2.0, &thumb_paint);
+        }
+
+        // Page tab strip, pinned above the scrolled content
+        let mut x = 8.0 * self.scale;
+        for (label, active) in tabs {
+            let text_w = self.measure(label, &self.font, self.base_spacing());
+            let pad = 6.0 * self.scale;
+            let pill = Rect::from_xywh(x, strip_h * 0.15, text_w + 2.0 * pad, strip_h * 0.7);
+            let radius = 4.0 * self.scale;
+            let mut paint = Paint::default();
+            paint.set_anti_alias(true);
+            if *active {
+                paint.set_color(self.fg().with_alpha(60).into());
+                canvas.draw_round_rect(&pill, radius, radius, &paint);
+                paint.set_color(self.fg().into());
+            } else {
+                paint.set_color(self.fg().with_alpha(150).into());
+            }
+            let baseline = strip_h * 0.5 + self.font_size * 0.35;
+            let spacing = self.base_spacing();
+            self.draw_text(
+                canvas,
+                label,
+                x + pad,
+                baseline,
+                &self.font,
+                spacing,
+                &paint,
+            );
+            x += text_w + 3.0 * pad;
+        }
+
+        if self.grid {
+            // 10px minor lines, 50px major lines labelled along the edges
+            let ruler_font = Font::new(self.typeface.clone(), 9.0);
+            let mut paint = Paint::default();
+            paint.set_anti_alias(true);
+            let mut pos = 10;
+            while pos < w.max(h) {
+                let major = pos % 50 == 0;
+                let alpha = if major { 110 } else { 40 };
+                paint.set_color(Color::from_argb(alpha, 0, 200, 255).into());
+                let p = pos as f32;
+                if pos < w {
+                    canvas.draw_rect(&Rect::from_xywh(p, 0.0, 1.0, height_f), &paint);
+                }
+                if pos < h {
+                    canvas.draw_rect(&Rect::from_xywh(0.0, p, width as f32, 1.0), &paint);
+                }
+                if major {
+                    paint.set_color(Color::from_argb(220, 0, 200, 255).into());
+                    let label = pos.to_string();
+                    if pos < w {
+                        canvas.draw_string(&label, p + 2.0, 9.0, &ruler_font, &paint);
+                    }
+                    if pos < h {
+                        canvas.draw_string(&label, 2.0, p - 2.0, &ruler_font, &paint);
+                    }
+                }
+                pos += 10;
+            }
+        }
     }
 }
@@ -0,0 +1,101 @@
+use std::collections::BTreeMap;
+use std::io::{BufRead, BufReader};
+use std::process::{Command, Stdio};
+use std::sync::{Mutex, MutexGuard, Once};
+use std::time::Duration;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SessionKind {
+    ScreenCast,
+    RemoteDesktop,
+}
+
+/// Active portal sessions by object path.
+static SESSIONS: Mutex<BTreeMap<String, SessionKind>> = Mutex::new(BTreeMap::new());
+static WATCH: Once = Once::new();
+
+const MATCH_RULES: &[&str] = &[
+    "interface='org.freedesktop.portal.ScreenCast',member='Start'",
+    "interface='org.freedesktop.portal.RemoteDesktop',member='Start'",
+    "interface='org.freedesktop.portal.Session'",
+];
+
+/// Kinds of the currently active ScreenCast/RemoteDesktop sessions, starting
+/// the D-Bus watcher on first use. Sessions started before rustky are not
+/// seen.
+pub fn active() -> Vec<SessionKind> {
+    WATCH.call_once(|| {
+        std::thread::spawn(watch);
+    });
+    lock().values().copied().collect()
+}
+
+/// Follows portal traffic on the session bus via `dbus-monitor`, restarting
+/// it if it exits.
+fn watch() {
+    loop {
+        match Command::new("dbus-monitor")
+            .arg("--session")
+            .args(MATCH_RULES)
+            .stdout(Stdio::piped())
+            .stderr(Stdio::null())
+            .spawn()
+        {
+            Ok(mut child) => {
+                if let Some(stdout) = child.stdout.take() {
+                    follow(BufReader::new(stdout));
+                }
+                let _ = child.wait();
+            }
+            Err(e) => {
+                eprintln!("rustky: screen share watcher: dbus-monitor: {e}");
+                return;
+            }
+        }
+        lock().clear();
+        std::thread::sleep(Duration::from_secs(5));
+    }
+}
+
+fn follow(reader: impl BufRead) {
+    // Start's first argument (the session handle) arrives on the line after
+    // the method call header.
+    let mut pending_start: Option<SessionKind> = None;
+    for line in reader.lines() {
+        let Ok(line) = line else { break };
+        let line = line.trim();
+
+        if let Some(kind) = pending_start.take() {
+            if let Some(path) = line
+                .strip_prefix("object path \"")
+                .and_then(|rest| rest.strip_suffix('"'))
+            {
+                lock().insert(path.to_string(), kind);
+            }
+            continue;
+        }
+
+        let field = |name: &str| {
+            line.split([' ', ';'])
+                .find_map(|part| part.strip_prefix(name))
+        };
+        match (field("interface="), field("member=")) {
+            (Some("org.freedesktop.portal.ScreenCast"), Some("Start")) => {
+                pending_start = Some(SessionKind::ScreenCast);
+            }
+            (Some("org.freedesktop.portal.RemoteDesktop"), Some("Start")) => {
+                pending_start = Some(SessionKind::RemoteDesktop);
+            }
+            (Some("org.freedesktop.portal.Session"), Some("Close" | "Closed")) => {
+                if let Some(path) = field("path=") {
+                    lock().remove(path);
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
+fn lock() -> MutexGuard<'static, BTreeMap<String, SessionKind>> {
+    SESSIONS.lock().unwrap_or_else(|e| e.into_inner())
+}
@@ -1,18 +1,32 @@
 use serde::{Deserialize, Serialize};
 
+use crate::battery::BatteryInfo;
+use crate::gpu::GpuInfo;
+use crate::power::PowerZone;
+
 #[allow(dead_code)]
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ScriptContext {
     pub cpu_usage: f64,
     pub cpu_count: usize,
     pub cpu_per_core: Vec<f64>,
+    pub cpu_freq_mhz: Vec<u64>,
     pub mem_used: u64,
     pub mem_total: u64,
     pub mem_usage_pct: f64,
     pub swap_used: u64,
     pub swap_total: u64,
+    pub load_avg_1: f64,
+    pub load_avg_5: f64,
+    pub load_avg_15: f64,
     pub disks: Vec<DiskInfo>,
     pub networks: Vec<NetworkInfo>,
+    pub temperatures: Vec<TemperatureInfo>,
+    pub gpus: Vec<GpuInfo>,
+    /// Sum of the RAPL package zones in watts.
+    pub package_power_w: Option<f64>,
+    pub power_zones: Vec<PowerZone>,
+    pub batteries: Vec<BatteryInfo>,
     pub hostname: String,
     pub uptime_seconds: u64,
     pub os_name: Option<String>,
@@ -33,4 +47,17 @@ pub struct NetworkInfo {
     pub interface: String,
     pub rx_bytes: u64,
     pub tx_bytes: u64,
+    /// Bytes per second over the last refresh interval.
+    pub rx_rate: f64,
+    pub tx_rate: f64,
+}
+
+#[allow(dead_code)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TemperatureInfo {
+    pub chip: String,
+    pub label: String,
+    pub celsius: f64,
+    pub max: Option<f64>,
+    pub crit: Option<f64>,
 }
@@ -2,10 +2,14 @@ use std::collections::HashMap;
 use std::ffi::CString;
 
 use pyo3::prelude::*;
-use pyo3::types::{PyDict, PyList, PyString};
+use pyo3::types::{PyCFunction, PyDict, PyList, PyString, PyTuple};
 
+use crate::config::RateUnit;
 use crate::script_context::ScriptContext;
-use crate::styled::{LineStyle, StyledLine};
+use crate::styled::{
+    ClickAction, Clicks, LineStyle, Overflow, Sparkline, StyledLine, TextOutline, TextShadow,
+};
+use crate::units::{self, Format};
 
 pub struct PythonEngine {
     loaded_modules: HashMap<String, Py<PyAny>>,
@@ -27,7 +31,7 @@ fn pyany_to_styled_line(_py: Python<'_>, val: &Bound<'_, PyAny>) -> Vec<StyledLi
         let text = s.to_string();
         return text
             .lines()
-            .map(|l| StyledLine::plain(l.to_string()))
+            .map(|l| StyledLine::plain(l.to_string()).with_markup())
             .collect();
     }
 
@@ -54,27 +58,140 @@ fn pyany_to_styled_line(_py: Python<'_>, val: &Bound<'_, PyAny>) -> Vec<StyledLi
             .flatten()
             .and_then(|v| v.extract::<f32>().ok());
 
+        let item = |key: &str| dict.get_item(key).ok().flatten();
         let style = LineStyle {
             fg_color,
             bg_color,
             font_size,
+            icon: item("icon").and_then(|v| v.extract::<String>().ok()),
+            icon_color: item("icon_color").and_then(|v| v.extract::<String>().ok()),
+            wrap: item("wrap")
+                .and_then(|v| v.extract::<bool>().ok())
+                .unwrap_or_default(),
+            padding: item("padding").and_then(|v| v.extract::<f32>().ok()),
+            margin_top: item("margin_top")
+                .and_then(|v| v.extract::<f32>().ok())
+                .unwrap_or_default(),
+            margin_bottom: item("margin_bottom")
+                .and_then(|v| v.extract::<f32>().ok())
+                .unwrap_or_default(),
+            corner_radius: item("corner_radius")
+                .and_then(|v| v.extract::<f32>().ok())
+                .unwrap_or_default(),
+            font_family: item("font_family").and_then(|v| v.extract::<String>().ok()),
+            bold: item("bold")
+                .and_then(|v| v.extract::<bool>().ok())
+                .unwrap_or_default(),
+            italic: item("italic")
+                .and_then(|v| v.extract::<bool>().ok())
+                .unwrap_or_default(),
+            line_height_factor: item("line_height_factor").and_then(|v| v.extract::<f32>().ok()),
+            letter_spacing: item("letter_spacing").and_then(|v| v.extract::<f32>().ok()),
+            tab_width: item("tab_width").and_then(|v| v.extract::<f32>().ok()),
+            overflow: item("overflow")
+                .and_then(|v| v.extract::<String>().ok())
+                .and_then(|name| Overflow::from_name(&name))
+                .unwrap_or_default(),
+            shadow: item("shadow").and_then(|v| v.cast::<PyDict>().ok().map(shadow_from_dict)),
+            outline: item("outline").and_then(|v| v.cast::<PyDict>().ok().map(outline_from_dict)),
+            clicks: clicks_from_dict(dict),
         };
-        return vec![StyledLine::styled(text, style)];
+        let kind = item("type").and_then(|v| v.extract::<String>().ok());
+        if kind.as_deref() == Some("sparkline") {
+            let samples = item("values")
+                .and_then(|v| v.extract::<Vec<Option<f64>>>().ok())
+                .unwrap_or_default();
+            let sparkline = Sparkline {
+                samples,
+                width: item("width")
+                    .and_then(|v| v.extract::<f32>().ok())
+                    .unwrap_or(Sparkline::default().width),
+                color: item("color").and_then(|v| v.extract::<String>().ok()),
+            };
+            return vec![StyledLine {
+                style,
+                ..StyledLine::sparkline(text, sparkline)
+            }];
+        }
+        return vec![StyledLine::styled(text, style).with_markup()];
     }
 
     vec![StyledLine::plain(val.to_string())]
 }
 
+/// A `shadow` dict; missing keys keep their defaults.
+fn shadow_from_dict(dict: &Bound<'_, PyDict>) -> TextShadow {
+    let default = TextShadow::default();
+    let item = |key: &str| dict.get_item(key).ok().flatten();
+    let number = |key: &str, or: f32| {
+        item(key)
+            .and_then(|v| v.extract::<f32>().ok())
+            .unwrap_or(or)
+    };
+    TextShadow {
+        offset_x: number("offset_x", default.offset_x),
+        offset_y: number("offset_y", default.offset_y),
+        blur: number("blur", default.blur),
+        color: item("color")
+            .and_then(|v| v.extract::<String>().ok())
+            .unwrap_or(default.color),
+    }
+}
+
+/// `on_click` and the like: a command string, or a `{"file", "function"}`
+/// dict naming a script function.
+fn clicks_from_dict(dict: &Bound<'_, PyDict>) -> Clicks {
+    let action = |key: &str| {
+        let value = dict.get_item(key).ok().flatten()?;
+        if let Ok(d) = value.cast::<PyDict>() {
+            let get = |k: &str| {
+                d.get_item(k)
+                    .ok()
+                    .flatten()
+                    .and_then(|v| v.extract::<String>().ok())
+            };
+            return Some(ClickAction::Script {
+                file: get("file")?,
+                function: get("function")?,
+            });
+        }
+        value.extract::<String>().ok().map(ClickAction::Command)
+    };
+    Clicks {
+        on_click: action("on_click"),
+        on_right_click: action("on_right_click"),
+        on_middle_click: action("on_middle_click"),
+    }
+}
+
+/// An `outline` dict; missing keys keep their defaults.
+fn outline_from_dict(dict: &Bound<'_, PyDict>) -> TextOutline {
+    let default = TextOutline::default();
+    let item = |key: &str| dict.get_item(key).ok().flatten();
+    TextOutline {
+        width: item("width")
+            .and_then(|v| v.extract::<f32>().ok())
+            .unwrap_or(default.width),
+        color: item("color")
+            .and_then(|v| v.extract::<String>().ok())
+            .unwrap_or(default.color),
+    }
+}
+
 fn context_to_pydict<'py>(py: Python<'py>, ctx: &ScriptContext) -> Bound<'py, PyDict> {
     let dict = PyDict::new(py);
     let _ = dict.set_item("cpu_usage", ctx.cpu_usage);
     let _ = dict.set_item("cpu_count", ctx.cpu_count);
     let _ = dict.set_item("cpu_per_core", &ctx.cpu_per_core);
+    let _ = dict.set_item("cpu_freq_mhz", &ctx.cpu_freq_mhz);
     let _ = dict.set_item("mem_used", ctx.mem_used);
     let _ = dict.set_item("mem_total", ctx.mem_total);
     let _ = dict.set_item("mem_usage_pct", ctx.mem_usage_pct);
     let _ = dict.set_item("swap_used", ctx.swap_used);
     let _ = dict.set_item("swap_total", ctx.swap_total);
+    let _ = dict.set_item("load_avg_1", ctx.load_avg_1);
+    let _ = dict.set_item("load_avg_5", ctx.load_avg_5);
+    let _ = dict.set_item("load_avg_15", ctx.load_avg_15);
     let _ = dict.set_item("hostname", &ctx.hostname);
     let _ = dict.set_item("uptime_seconds", ctx.uptime_seconds);
     let _ = dict.set_item("os_name", &ctx.os_name);
@@ -101,11 +218,87 @@ fn context_to_pydict<'py>(py: Python<'py>, ctx: &ScriptContext) -> Bound<'py, Py
             let _ = nd.set_item("interface", &n.interface);
             let _ = nd.set_item("rx_bytes", n.rx_bytes);
             let _ = nd.set_item("tx_bytes", n.tx_bytes);
+            let _ = nd.set_item("rx_rate", n.rx_rate);
+            let _ = nd.set_item("tx_rate", n.tx_rate);
             nd
         })
         .collect();
     let _ = dict.set_item("networks", networks);
 
+    let temperatures: Vec<Bound<'py, PyDict>> = ctx
+        .temperatures
+        .iter()
+        .map(|t| {
+            let td = PyDict::new(py);
+            let _ = td.set_item("chip", &t.chip);
+            let _ = td.set_item("label", &t.label);
+            let _ = td.set_item("celsius", t.celsius);
+            let _ = td.set_item("max", t.max);
+            let _ = td.set_item("crit", t.crit);
+            td
+        })
+        .collect();
+    let _ = dict.set_item("temperatures", temperatures);
+
+    let gpus: Vec<Bound<'py, PyDict>> = ctx
+        .gpus
+        .iter()
+        .map(|g| {
+            let gd = PyDict::new(py);
+            let _ = gd.set_item("index", g.index);
+            let _ = gd.set_item("name", &g.name);
+            let _ = gd.set_item("backend", &g.backend);
+            let _ = gd.set_item("utilization_pct", g.utilization_pct);
+            let _ = gd.set_item("vram_used", g.vram_used);
+            let _ = gd.set_item("vram_total", g.vram_total);
+            let _ = gd.set_item("temperature_c", g.temperature_c);
+            let _ = gd.set_item("power_w", g.power_w);
+            let processes: Vec<Bound<'py, PyDict>> = g
+                .processes
+                .iter()
+                .map(|p| {
+                    let pd = PyDict::new(py);
+                    let _ = pd.set_item("pid", p.pid);
+                    let _ = pd.set_item("name", &p.name);
+                    let _ = pd.set_item("vram_used", p.vram_used);
+                    pd
+                })
+                .collect();
+            let _ = gd.set_item("processes", processes);
+            gd
+        })
+        .collect();
+    let _ = dict.set_item("gpus", gpus);
+
+    let _ = dict.set_item("package_power_w", ctx.package_power_w);
+    let power_zones: Vec<Bound<'py, PyDict>> = ctx
+        .power_zones
+        .iter()
+        .map(|z| {
+            let zd = PyDict::new(py);
+            let _ = zd.set_item("name", &z.name);
+            let _ = zd.set_item("watts", z.watts);
+            zd
+        })
+        .collect();
+    let _ = dict.set_item("power_zones", power_zones);
+
+    let batteries: Vec<Bound<'py, PyDict>> = ctx
+        .batteries
+        .iter()
+        .map(|b| {
+            let bd = PyDict::new(py);
+            let _ = bd.set_item("name", &b.name);
+            let _ = bd.set_item("percent", b.percent);
+            let _ = bd.set_item("status", &b.status);
+            let _ = bd.set_item("time_to_empty_secs", b.time_to_empty_secs);
+            let _ = bd.set_item("time_to_full_secs", b.time_to_full_secs);
+            let _ = bd.set_item("power_w", b.power_w);
+            bd
+        })
+        .collect();
+    let _ = dict.set_item("batteries", batteries);
+
     dict
 }
 
@@ -124,18 +317,125 @@ fn styled_lines_to_pylist<'py>(py: Python<'py>, lines: &[StyledLine]) -> Bound<'
             if let Some(fs) = l.style.font_size {
                 let _ = d.set_item("font_size", fs);
             }
+            if let Some(icon) = &l.style.icon {
+                let _ = d.set_item("icon", icon);
+            }
+            if let Some(color) = &l.style.icon_color {
+                let _ = d.set_item("icon_color", color);
+            }
+            if let Some(family) = &l.style.font_family {
+                let _ = d.set_item("font_family", family);
+            }
+            for (key, set) in [
+                ("wrap", l.style.wrap),
+                ("bold", l.style.bold),
+                ("italic", l.style.italic),
+            ] {
+                if set {
+                    let _ = d.set_item(key, true);
+                }
+            }
+            if l.style.overflow != Overflow::Clip {
+                let _ = d.set_item("overflow", l.style.overflow.name());
+            }
+            if let Some(shadow) = &l.style.shadow {
+                let s = PyDict::new(py);
+                let _ = s.set_item("offset_x", shadow.offset_x);
+                let _ = s.set_item("offset_y", shadow.offset_y);
+                let _ = s.set_item("blur", shadow.blur);
+                let _ = s.set_item("color", &shadow.color);
+                let _ = d.set_item("shadow", s);
+            }
+            if let Some(outline) = &l.style.outline {
+                let o = PyDict::new(py);
+                let _ = o.set_item("width", outline.width);
+                let _ = o.set_item("color", &outline.color);
+                let _ = d.set_item("outline", o);
+            }
+            for (key, action) in [
+                ("on_click", &l.style.clicks.on_click),
+                ("on_right_click", &l.style.clicks.on_right_click),
+                ("on_middle_click", &l.style.clicks.on_middle_click),
+            ] {
+                match action {
+                    Some(ClickAction::Command(command)) => {
+                        let _ = d.set_item(key, command);
+                    }
+                    Some(ClickAction::Script { file, function }) => {
+                        let s = PyDict::new(py);
+                        let _ = s.set_item("file", file);
+                        let _ = s.set_item("function", function);
+                        let _ = d.set_item(key, s);
+                    }
+                    None => {}
+                }
+            }
+            for (key, value) in [
+                ("padding", l.style.padding),
+                ("line_height_factor", l.style.line_height_factor),
+                ("letter_spacing", l.style.letter_spacing),
+                ("tab_width", l.style.tab_width),
+            ] {
+                if let Some(value) = value {
+                    let _ = d.set_item(key, value);
+                }
+            }
+            for (key, value) in [
+                ("margin_top", l.style.margin_top),
+                ("margin_bottom", l.style.margin_bottom),
+                ("corner_radius", l.style.corner_radius),
+            ] {
+                if value != 0.0 {
+                    let _ = d.set_item(key, value);
+                }
+            }
             d
         })
         .collect();
     PyList::new(py, &items).expect("failed to create PyList")
 }
 
+/// A `rustky` module scripts can import, formatting sizes, rates and
+/// durations as the built-in modules do.
+fn register_helpers(py: Python<'_>, format: Format) -> PyResult<()> {
+    let module = PyModule::new(py, "rustky")?;
+    let format_bytes = move |args: &Bound<'_, PyTuple>, _: Option<&Bound<'_, PyDict>>| {
+        args.extract::<(f64,)>().map(|(bytes,)| format.bytes(bytes))
+    };
+    let format_rate = move |args: &Bound<'_, PyTuple>, _: Option<&Bound<'_, PyDict>>| {
+        args.extract::<(f64,)>()
+            .map(|(rate,)| format.rate(rate, RateUnit::Auto))
+    };
+    let format_duration = |args: &Bound<'_, PyTuple>, _: Option<&Bound<'_, PyDict>>| {
+        args.extract::<(u64,)>()
+            .map(|(secs,)| units::duration(secs))
+    };
+    module.add(
+        "format_bytes",
+        PyCFunction::new_closure(py, Some(c"format_bytes"), None, format_bytes)?,
+    )?;
+    module.add(
+        "format_rate",
+        PyCFunction::new_closure(py, Some(c"format_rate"), None, format_rate)?,
+    )?;
+    module.add(
+        "format_duration",
+        PyCFunction::new_closure(py, Some(c"format_duration"), None, format_duration)?,
+    )?;
+    py.import("sys")?
+        .getattr("modules")?
+        .set_item("rustky", module)
+}
+
 fn to_cstring(s: &str) -> CString {
     CString::new(s).unwrap_or_else(|_| CString::new("rustky_script").unwrap())
 }
 
 impl PythonEngine {
-    pub fn new() -> Self {
+    pub fn new(format: Format) -> Self {
+        if let Err(e) = Python::attach(|py| register_helpers(py, format)) {
+            eprintln!("rustky: python: failed to register the rustky module: {e}");
+        }
         Self {
             loaded_modules: HashMap::new(),
             on_draw_module: None,
@@ -212,6 +512,22 @@ impl PythonEngine {
         })
     }
 
+    /// Calls `function` in the script at `path`, loaded on first use, with
+    /// the button that clicked.
+    pub fn call_click(&mut self, path: &str, function: &str, button: u32) -> Result<(), String> {
+        if !self.loaded_modules.contains_key(path) {
+            self.load_file(path)?;
+        }
+        let module = &self.loaded_modules[path];
+        Python::attach(|py| {
+            module
+                .bind(py)
+                .call_method1(function, (button,))
+                .map(drop)
+                .map_err(|e| format!("python error in {function}: {e}"))
+        })
+    }
+
     pub fn run_on_draw_hook(
         &self,
         lines: Vec<StyledLine>,
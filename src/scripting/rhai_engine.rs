@@ -2,8 +2,12 @@ use std::collections::HashMap;
 
 use rhai::{Array, Dynamic, Engine, Map, Scope, AST};
 
+use crate::config::RateUnit;
 use crate::script_context::ScriptContext;
-use crate::styled::{LineStyle, StyledLine};
+use crate::styled::{
+    ClickAction, Clicks, LineStyle, Overflow, Sparkline, StyledLine, TextOutline, TextShadow,
+};
+use crate::units::{self, Format};
 
 pub struct RhaiEngine {
     engine: Engine,
@@ -24,7 +28,10 @@ fn dynamic_to_styled_lines(val: Dynamic) -> Vec<StyledLine> {
 fn dynamic_to_styled_line(val: Dynamic) -> Vec<StyledLine> {
     if val.is_string() {
         let s = val.into_string().unwrap_or_default();
-        return s.lines().map(|l| StyledLine::plain(l.to_string())).collect();
+        return s
+            .lines()
+            .map(|l| StyledLine::plain(l.to_string()).with_markup())
+            .collect();
     }
 
     if val.is_map() {
@@ -47,13 +54,146 @@ fn dynamic_to_styled_line(val: Dynamic) -> Vec<StyledLine> {
             fg_color,
             bg_color,
             font_size,
+            icon: map.get("icon").and_then(|v| v.clone().into_string().ok()),
+            icon_color: map
+                .get("icon_color")
+                .and_then(|v| v.clone().into_string().ok()),
+            wrap: map
+                .get("wrap")
+                .and_then(|v| v.as_bool().ok())
+                .unwrap_or_default(),
+            padding: map.get("padding").and_then(as_number).map(|p| p as f32),
+            margin_top: map
+                .get("margin_top")
+                .and_then(as_number)
+                .unwrap_or_default() as f32,
+            margin_bottom: map
+                .get("margin_bottom")
+                .and_then(as_number)
+                .unwrap_or_default() as f32,
+            corner_radius: map
+                .get("corner_radius")
+                .and_then(as_number)
+                .unwrap_or_default() as f32,
+            font_family: map
+                .get("font_family")
+                .and_then(|v| v.clone().into_string().ok()),
+            bold: map
+                .get("bold")
+                .and_then(|v| v.as_bool().ok())
+                .unwrap_or_default(),
+            italic: map
+                .get("italic")
+                .and_then(|v| v.as_bool().ok())
+                .unwrap_or_default(),
+            line_height_factor: map
+                .get("line_height_factor")
+                .and_then(as_number)
+                .map(|f| f as f32),
+            letter_spacing: map
+                .get("letter_spacing")
+                .and_then(as_number)
+                .map(|s| s as f32),
+            tab_width: map.get("tab_width").and_then(as_number).map(|w| w as f32),
+            overflow: map
+                .get("overflow")
+                .and_then(|v| v.clone().into_string().ok())
+                .and_then(|name| Overflow::from_name(&name))
+                .unwrap_or_default(),
+            shadow: map
+                .get("shadow")
+                .and_then(|v| v.clone().try_cast::<Map>())
+                .map(|m| shadow_from_map(&m)),
+            outline: map
+                .get("outline")
+                .and_then(|v| v.clone().try_cast::<Map>())
+                .map(|m| outline_from_map(&m)),
+            clicks: clicks_from_map(&map),
         };
-        return vec![StyledLine::styled(text, style)];
+        let kind = map.get("type").and_then(|v| v.clone().into_string().ok());
+        if kind.as_deref() == Some("sparkline") {
+            let samples = map
+                .get("values")
+                .and_then(|v| v.clone().into_array().ok())
+                .unwrap_or_default()
+                .iter()
+                .map(as_number)
+                .collect();
+            let sparkline = Sparkline {
+                samples,
+                width: map
+                    .get("width")
+                    .and_then(as_number)
+                    .map_or(Sparkline::default().width, |w| w as f32),
+                color: map.get("color").and_then(|v| v.clone().into_string().ok()),
+            };
+            return vec![StyledLine {
+                style,
+                ..StyledLine::sparkline(text, sparkline)
+            }];
+        }
+        return vec![StyledLine::styled(text, style).with_markup()];
     }
 
     vec![StyledLine::plain(val.to_string())]
 }
 
+/// Rhai keeps integers and floats apart; scripts may write either.
+fn as_number(v: &Dynamic) -> Option<f64> {
+    v.as_float().ok().or_else(|| v.as_int().ok().map(|i| i as f64))
+}
+
+/// A `shadow` map; missing keys keep their defaults.
+fn shadow_from_map(map: &Map) -> TextShadow {
+    let default = TextShadow::default();
+    let number = |key: &str, or: f32| map.get(key).and_then(as_number).map_or(or, |n| n as f32);
+    TextShadow {
+        offset_x: number("offset_x", default.offset_x),
+        offset_y: number("offset_y", default.offset_y),
+        blur: number("blur", default.blur),
+        color: map
+            .get("color")
+            .and_then(|v| v.clone().into_string().ok())
+            .unwrap_or(default.color),
+    }
+}
+
+/// An `outline` map; missing keys keep their defaults.
+fn outline_from_map(map: &Map) -> TextOutline {
+    let default = TextOutline::default();
+    TextOutline {
+        width: map
+            .get("width")
+            .and_then(as_number)
+            .map_or(default.width, |w| w as f32),
+        color: map
+            .get("color")
+            .and_then(|v| v.clone().into_string().ok())
+            .unwrap_or(default.color),
+    }
+}
+
+/// `on_click` and the like: a command string, or a `#{ file, function }`
+/// map naming a script function.
+fn clicks_from_map(map: &Map) -> Clicks {
+    let action = |key: &str| {
+        let value = map.get(key)?.clone();
+        if let Some(m) = value.clone().try_cast::<Map>() {
+            let get = |k: &str| m.get(k).and_then(|v| v.clone().into_string().ok());
+            return Some(ClickAction::Script {
+                file: get("file")?,
+                function: get("function")?,
+            });
+        }
+        value.into_string().ok().map(ClickAction::Command)
+    };
+    Clicks {
+        on_click: action("on_click"),
+        on_right_click: action("on_right_click"),
+        on_middle_click: action("on_middle_click"),
+    }
+}
+
 fn context_to_scope(ctx: &ScriptContext) -> Scope<'static> {
     let mut scope = Scope::new();
     scope.push("cpu_usage", ctx.cpu_usage);
@@ -65,11 +205,21 @@ fn context_to_scope(ctx: &ScriptContext) -> Scope<'static> {
             .map(|&v| Dynamic::from(v))
             .collect::<Array>(),
     );
+    scope.push(
+        "cpu_freq_mhz",
+        ctx.cpu_freq_mhz
+            .iter()
+            .map(|&f| Dynamic::from(f as i64))
+            .collect::<Array>(),
+    );
     scope.push("mem_used", ctx.mem_used as i64);
     scope.push("mem_total", ctx.mem_total as i64);
     scope.push("mem_usage_pct", ctx.mem_usage_pct);
     scope.push("swap_used", ctx.swap_used as i64);
     scope.push("swap_total", ctx.swap_total as i64);
+    scope.push("load_avg_1", ctx.load_avg_1);
+    scope.push("load_avg_5", ctx.load_avg_5);
+    scope.push("load_avg_15", ctx.load_avg_15);
     scope.push("hostname", ctx.hostname.clone());
     scope.push("uptime_seconds", ctx.uptime_seconds as i64);
     scope.push(
@@ -107,16 +257,96 @@ fn context_to_scope(ctx: &ScriptContext) -> Scope<'static> {
             m.insert("interface".into(), Dynamic::from(n.interface.clone()));
             m.insert("rx_bytes".into(), Dynamic::from(n.rx_bytes as i64));
             m.insert("tx_bytes".into(), Dynamic::from(n.tx_bytes as i64));
+            m.insert("rx_rate".into(), Dynamic::from(n.rx_rate));
+            m.insert("tx_rate".into(), Dynamic::from(n.tx_rate));
             Dynamic::from(m)
         })
         .collect();
     scope.push("networks", networks);
 
+    // Temperatures as array of maps; missing limits are ()
+    let temperatures: Array = ctx
+        .temperatures
+        .iter()
+        .map(|t| {
+            let mut m = Map::new();
+            m.insert("chip".into(), Dynamic::from(t.chip.clone()));
+            m.insert("label".into(), Dynamic::from(t.label.clone()));
+            m.insert("celsius".into(), Dynamic::from(t.celsius));
+            m.insert("max".into(), t.max.map(Dynamic::from).unwrap_or(Dynamic::UNIT));
+            m.insert("crit".into(), t.crit.map(Dynamic::from).unwrap_or(Dynamic::UNIT));
+            Dynamic::from(m)
+        })
+        .collect();
+    scope.push("temperatures", temperatures);
+
+    // GPUs as array of maps; fields a backend can't report are ()
+    let opt_f64 = |v: Option<f64>| v.map(Dynamic::from).unwrap_or(Dynamic::UNIT);
+    let opt_u64 = |v: Option<u64>| v.map(|x| Dynamic::from(x as i64)).unwrap_or(Dynamic::UNIT);
+    let gpus: Array = ctx
+        .gpus
+        .iter()
+        .map(|g| {
+            let mut m = Map::new();
+            m.insert("index".into(), Dynamic::from(g.index as i64));
+            m.insert("name".into(), Dynamic::from(g.name.clone()));
+            m.insert("backend".into(), Dynamic::from(g.backend.clone()));
+            m.insert("utilization_pct".into(), opt_f64(g.utilization_pct));
+            m.insert("vram_used".into(), opt_u64(g.vram_used));
+            m.insert("vram_total".into(), opt_u64(g.vram_total));
+            m.insert("temperature_c".into(), opt_f64(g.temperature_c));
+            m.insert("power_w".into(), opt_f64(g.power_w));
+            let processes: Array = g
+                .processes
+                .iter()
+                .map(|p| {
+                    let mut pm = Map::new();
+                    pm.insert("pid".into(), Dynamic::from(p.pid as i64));
+                    pm.insert("name".into(), Dynamic::from(p.name.clone()));
+                    pm.insert("vram_used".into(), Dynamic::from(p.vram_used as i64));
+                    Dynamic::from(pm)
+                })
+                .collect();
+            m.insert("processes".into(), Dynamic::from(processes));
+            Dynamic::from(m)
+        })
+        .collect();
+    scope.push("gpus", gpus);
+
+    scope.push("package_power_w", opt_f64(ctx.package_power_w));
+    let power_zones: Array = ctx
+        .power_zones
+        .iter()
+        .map(|z| {
+            let mut m = Map::new();
+            m.insert("name".into(), Dynamic::from(z.name.clone()));
+            m.insert("watts".into(), Dynamic::from(z.watts));
+            Dynamic::from(m)
+        })
+        .collect();
+    scope.push("power_zones", power_zones);
+
+    let batteries: Array = ctx
+        .batteries
+        .iter()
+        .map(|b| {
+            let mut m = Map::new();
+            m.insert("name".into(), Dynamic::from(b.name.clone()));
+            m.insert("percent".into(), Dynamic::from(b.percent));
+            m.insert("status".into(), Dynamic::from(b.status.clone()));
+            m.insert("time_to_empty_secs".into(), opt_u64(b.time_to_empty_secs));
+            m.insert("time_to_full_secs".into(), opt_u64(b.time_to_full_secs));
+            m.insert("power_w".into(), opt_f64(b.power_w));
+            Dynamic::from(m)
+        })
+        .collect();
+    scope.push("batteries", batteries);
+
     scope
 }
 
 impl RhaiEngine {
-    pub fn new() -> Self {
+    pub fn new(format: Format) -> Self {
         let mut engine = Engine::new();
 
         // Register a `styled(text, style_map)` helper
@@ -143,6 +373,19 @@ impl RhaiEngine {
             Dynamic::from(m)
         });
 
+        // Sizes, rates and durations formatted as the built-in modules do
+        engine.register_fn("format_bytes", move |bytes: f64| format.bytes(bytes));
+        engine.register_fn("format_bytes", move |bytes: i64| format.bytes(bytes as f64));
+        engine.register_fn("format_rate", move |bytes_per_sec: f64| {
+            format.rate(bytes_per_sec, RateUnit::Auto)
+        });
+        engine.register_fn("format_rate", move |bytes_per_sec: i64| {
+            format.rate(bytes_per_sec as f64, RateUnit::Auto)
+        });
+        engine.register_fn("format_duration", |secs: i64| {
+            units::duration(secs.max(0) as u64)
+        });
+
         Self {
             engine,
             compiled_files: HashMap::new(),
@@ -207,6 +450,19 @@ impl RhaiEngine {
         }
     }
 
+    /// Calls `function` in the script at `path`, compiled on first use,
+    /// with the button that clicked.
+    pub fn call_click(&mut self, path: &str, function: &str, button: u32) -> Result<(), String> {
+        if !self.compiled_files.contains_key(path) {
+            self.compile_file(path)?;
+        }
+        let ast = &self.compiled_files[path];
+        self.engine
+            .call_fn::<Dynamic>(&mut Scope::new(), ast, function, (button as i64,))
+            .map(drop)
+            .map_err(|e| format!("rhai error in {function}: {e}"))
+    }
+
     pub fn run_on_draw_hook(
         &self,
         lines: Vec<StyledLine>,
@@ -233,6 +489,80 @@ impl RhaiEngine {
                 if let Some(fs) = l.style.font_size {
                     m.insert("font_size".into(), Dynamic::from(fs as f64));
                 }
+                if let Some(ref icon) = l.style.icon {
+                    m.insert("icon".into(), Dynamic::from(icon.clone()));
+                }
+                if let Some(ref color) = l.style.icon_color {
+                    m.insert("icon_color".into(), Dynamic::from(color.clone()));
+                }
+                if let Some(ref family) = l.style.font_family {
+                    m.insert("font_family".into(), Dynamic::from(family.clone()));
+                }
+                for (key, set) in [
+                    ("wrap", l.style.wrap),
+                    ("bold", l.style.bold),
+                    ("italic", l.style.italic),
+                ] {
+                    if set {
+                        m.insert(key.into(), Dynamic::from(true));
+                    }
+                }
+                if l.style.overflow != Overflow::Clip {
+                    m.insert(
+                        "overflow".into(),
+                        Dynamic::from(l.style.overflow.name().to_string()),
+                    );
+                }
+                if let Some(ref shadow) = l.style.shadow {
+                    let mut s = Map::new();
+                    s.insert("offset_x".into(), Dynamic::from(shadow.offset_x as f64));
+                    s.insert("offset_y".into(), Dynamic::from(shadow.offset_y as f64));
+                    s.insert("blur".into(), Dynamic::from(shadow.blur as f64));
+                    s.insert("color".into(), Dynamic::from(shadow.color.clone()));
+                    m.insert("shadow".into(), Dynamic::from(s));
+                }
+                if let Some(ref outline) = l.style.outline {
+                    let mut o = Map::new();
+                    o.insert("width".into(), Dynamic::from(outline.width as f64));
+                    o.insert("color".into(), Dynamic::from(outline.color.clone()));
+                    m.insert("outline".into(), Dynamic::from(o));
+                }
+                for (key, action) in [
+                    ("on_click", &l.style.clicks.on_click),
+                    ("on_right_click", &l.style.clicks.on_right_click),
+                    ("on_middle_click", &l.style.clicks.on_middle_click),
+                ] {
+                    let value = match action {
+                        Some(ClickAction::Command(command)) => Dynamic::from(command.clone()),
+                        Some(ClickAction::Script { file, function }) => {
+                            let mut s = Map::new();
+                            s.insert("file".into(), Dynamic::from(file.clone()));
+                            s.insert("function".into(), Dynamic::from(function.clone()));
+                            Dynamic::from(s)
+                        }
+                        None => continue,
+                    };
+                    m.insert(key.into(), value);
+                }
+                for (key, value) in [
+                    ("padding", l.style.padding),
+                    ("line_height_factor", l.style.line_height_factor),
+                    ("letter_spacing", l.style.letter_spacing),
+                    ("tab_width", l.style.tab_width),
+                ] {
+                    if let Some(value) = value {
+                        m.insert(key.into(), Dynamic::from(value as f64));
+                    }
+                }
+                for (key, value) in [
+                    ("margin_top", l.style.margin_top),
+                    ("margin_bottom", l.style.margin_bottom),
+                    ("corner_radius", l.style.corner_radius),
+                ] {
+                    if value != 0.0 {
+                        m.insert(key.into(), Dynamic::from(value as f64));
+                    }
+                }
                 Dynamic::from(m)
             })
             .collect();
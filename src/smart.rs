@@ -0,0 +1,76 @@
+use std::collections::HashMap;
+use std::process::Command;
+use std::sync::{Mutex, OnceLock};
+use std::time::Duration;
+
+use serde_json::Value;
+
+use crate::background::Poller;
+
+/// ATA attribute 5, Reallocated_Sector_Ct.
+const REALLOCATED_SECTORS: u64 = 5;
+
+#[derive(Debug, Clone)]
+pub struct Health {
+    /// Overall self-assessment.
+    pub passed: bool,
+    pub temperature: Option<i64>,
+    /// ATA only; NVMe drives report media errors instead.
+    pub reallocated: Option<u64>,
+    pub media_errors: Option<u64>,
+}
+
+type Registry = Mutex<HashMap<String, Poller<Health>>>;
+
+static POLLERS: OnceLock<Registry> = OnceLock::new();
+
+/// SMART health of `device` (e.g. "/dev/sda"), read with `smartctl` every
+/// `interval`. Reading SMART data usually needs root or the disk group.
+pub fn health(device: &str, interval: Duration) -> Option<Result<Health, String>> {
+    let mut pollers = POLLERS
+        .get_or_init(Default::default)
+        .lock()
+        .unwrap_or_else(|e| e.into_inner());
+    pollers
+        .entry(device.to_string())
+        .or_insert_with(|| {
+            let device = device.to_string();
+            Poller::spawn_cached(interval, "smart", move || query(&device))
+        })
+        .latest()
+}
+
+fn query(device: &str) -> Result<Health, String> {
+    let output = Command::new("smartctl")
+        .args(["--json", "--health", "--attributes", device])
+        .output()
+        .map_err(|e| format!("smartctl: {e}"))?;
+    // The exit status is a bit mask; only bits 0 and 1 (bad arguments,
+    // device open failed) mean there is no data. The rest describe the
+    // disk.
+    let json: Value =
+        serde_json::from_slice(&output.stdout).map_err(|e| format!("smartctl: {e}"))?;
+    if output.status.code().is_none_or(|c| c & 0b11 != 0) {
+        let message = json["smartctl"]["messages"][0]["string"]
+            .as_str()
+            .unwrap_or("failed");
+        return Err(format!("smartctl {device}: {message}"));
+    }
+    let passed = json["smart_status"]["passed"]
+        .as_bool()
+        .ok_or_else(|| format!("smartctl {device}: no health status"))?;
+    let reallocated = json["ata_smart_attributes"]["table"]
+        .as_array()
+        .and_then(|table| {
+            table
+                .iter()
+                .find(|a| a["id"].as_u64() == Some(REALLOCATED_SECTORS))
+        })
+        .and_then(|a| a["raw"]["value"].as_u64());
+    Ok(Health {
+        passed,
+        temperature: json["temperature"]["current"].as_i64(),
+        reallocated,
+        media_errors: json["nvme_smart_health_information_log"]["media_errors"].as_u64(),
+    })
+}
@@ -0,0 +1,88 @@
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::path::PathBuf;
+
+use crate::pomodoro;
+
+/// UI state that survives restarts, stored next to (but separate from) the
+/// user's config so rustky never rewrites hand-edited TOML.
+#[derive(Debug, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct UiState {
+    /// Group title -> collapsed.
+    pub groups: BTreeMap<String, bool>,
+    /// Last dragged-to `(x, y)` margins of the first window.
+    pub position: Option<(i32, i32)>,
+    /// The same for further `[[window]]` entries, by index.
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    pub positions: BTreeMap<String, (i32, i32)>,
+    pub pomodoro: pomodoro::Timer,
+}
+
+impl UiState {
+    /// `None` without a home directory, when nothing is persisted.
+    pub fn path() -> Option<PathBuf> {
+        let dir = dirs::state_dir().or_else(dirs::cache_dir)?;
+        Some(dir.join("rustky").join("state.toml"))
+    }
+
+    pub fn load() -> Self {
+        let Some(path) = Self::path() else {
+            return Self::default();
+        };
+        match std::fs::read_to_string(&path) {
+            Ok(contents) => toml::from_str(&contents).unwrap_or_else(|e| {
+                eprintln!(
+                    "rustky: ignoring invalid state file {}: {e}",
+                    path.display()
+                );
+                Self::default()
+            }),
+            Err(_) => Self::default(),
+        }
+    }
+
+    pub fn save(&self) {
+        let Some(path) = Self::path() else {
+            return;
+        };
+        if let Some(parent) = path.parent() {
+            let _ = std::fs::create_dir_all(parent);
+        }
+        match toml::to_string_pretty(self) {
+            Ok(contents) => {
+                if let Err(e) = std::fs::write(&path, contents) {
+                    eprintln!("rustky: failed to write {}: {e}", path.display());
+                }
+            }
+            Err(e) => eprintln!("rustky: failed to serialize state: {e}"),
+        }
+    }
+
+    pub fn is_collapsed(&self, group: &str, default: bool) -> bool {
+        self.groups.get(group).copied().unwrap_or(default)
+    }
+
+    pub fn toggle_group(&mut self, group: &str, default: bool) {
+        let collapsed = self.is_collapsed(group, default);
+        self.groups.insert(group.to_string(), !collapsed);
+        self.save();
+    }
+
+    pub fn position(&self, window: usize) -> Option<(i32, i32)> {
+        match window {
+            0 => self.position,
+            _ => self.positions.get(&window.to_string()).copied(),
+        }
+    }
+
+    pub fn set_position(&mut self, window: usize, x: i32, y: i32) {
+        match window {
+            0 => self.position = Some((x, y)),
+            _ => {
+                self.positions.insert(window.to_string(), (x, y));
+            }
+        }
+        self.save();
+    }
+}
@@ -1,16 +1,410 @@
 use serde::{Deserialize, Serialize};
 
-#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+use crate::config::{ColorStop, DndDaemon, SeparatorStyle};
+use crate::pomodoro::Durations;
+
+/// Between a table row's cells in its text.
+pub const TABLE_SEPARATOR: &str = "  ";
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
 pub struct LineStyle {
     pub fg_color: Option<String>,
     pub bg_color: Option<String>,
     pub font_size: Option<f32>,
+    /// Glyph drawn before the text, from `general.icon_font`.
+    #[serde(default)]
+    pub icon: Option<String>,
+    /// Default: the text color.
+    #[serde(default)]
+    pub icon_color: Option<String>,
+    /// Break onto further rows at spaces instead of running off the right
+    /// edge. Lines with markup spans stay on one row.
+    #[serde(default)]
+    pub wrap: bool,
+    /// Space between the text and its background, which then hugs the
+    /// text as a pill instead of spanning the line.
+    #[serde(default)]
+    pub padding: Option<f32>,
+    /// Space above and below the line, outside its background.
+    #[serde(default)]
+    pub margin_top: f32,
+    #[serde(default)]
+    pub margin_bottom: f32,
+    /// Rounds the corners of the line's background.
+    #[serde(default)]
+    pub corner_radius: f32,
+    /// Family (or font file) for this line instead of `general.font`.
+    #[serde(default)]
+    pub font_family: Option<String>,
+    #[serde(default)]
+    pub bold: bool,
+    #[serde(default)]
+    pub italic: bool,
+    /// Overrides of the `[general]` settings of the same names.
+    #[serde(default)]
+    pub line_height_factor: Option<f32>,
+    #[serde(default)]
+    pub letter_spacing: Option<f32>,
+    #[serde(default)]
+    pub tab_width: Option<f32>,
+    /// Drawn behind the text so it stays legible over any wallpaper.
+    #[serde(default)]
+    pub shadow: Option<TextShadow>,
+    #[serde(default)]
+    pub outline: Option<TextOutline>,
+    /// What a single-row line does when its text is wider than the surface.
+    #[serde(default)]
+    pub overflow: Overflow,
+    #[serde(flatten)]
+    pub clicks: Clicks,
+}
+
+/// Commands or script functions run by pointer buttons over a line, ahead
+/// of any built-in action of the same button.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Clicks {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub on_click: Option<ClickAction>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub on_right_click: Option<ClickAction>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub on_middle_click: Option<ClickAction>,
+}
+
+impl Clicks {
+    pub fn is_empty(&self) -> bool {
+        self == &Self::default()
+    }
+
+    /// The action of i3bar button `button` (1 = left, 2 = middle, 3 = right).
+    pub fn get(&self, button: u32) -> Option<&ClickAction> {
+        match button {
+            1 => self.on_click.as_ref(),
+            2 => self.on_middle_click.as_ref(),
+            3 => self.on_right_click.as_ref(),
+            _ => None,
+        }
+    }
+
+    /// Fills the buttons left unset from `other`.
+    pub fn or(&mut self, other: &Clicks) {
+        for (mine, theirs) in [
+            (&mut self.on_click, &other.on_click),
+            (&mut self.on_right_click, &other.on_right_click),
+            (&mut self.on_middle_click, &other.on_middle_click),
+        ] {
+            if mine.is_none() {
+                *mine = theirs.clone();
+            }
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum ClickAction {
+    /// Run through `sh -c` (needs `allow_exec`), with the button number in
+    /// `$BUTTON`.
+    Command(String),
+    /// A function in a Rhai or Python file (by extension), called with the
+    /// button number.
+    Script { file: String, function: String },
+}
+
+/// A copy of the text behind it, offset and softened by `blur` pixels.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct TextShadow {
+    pub offset_x: f32,
+    pub offset_y: f32,
+    pub blur: f32,
+    pub color: String,
+}
+
+impl Default for TextShadow {
+    fn default() -> Self {
+        Self {
+            offset_x: 1.0,
+            offset_y: 1.0,
+            blur: 2.0,
+            color: "#000000b0".into(),
+        }
+    }
+}
+
+/// A `width` pixels wide edge around the text's glyphs.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct TextOutline {
+    pub width: f32,
+    pub color: String,
+}
+
+impl Default for TextOutline {
+    fn default() -> Self {
+        Self {
+            width: 1.0,
+            color: "#000000".into(),
+        }
+    }
+}
+
+/// Handling of text wider than its line.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Overflow {
+    /// Runs off the edge.
+    #[default]
+    Clip,
+    /// Cut with a trailing "…".
+    Ellipsize,
+    /// Scrolls sideways through the whole text, pausing at the start.
+    Marquee,
+}
+
+#[cfg(any(feature = "rhai-scripting", feature = "python-scripting"))]
+impl Overflow {
+    /// As written in config and scripts.
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Clip => "clip",
+            Self::Ellipsize => "ellipsize",
+            Self::Marquee => "marquee",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        [Self::Clip, Self::Ellipsize, Self::Marquee]
+            .into_iter()
+            .find(|o| o.name() == name)
+    }
+}
+
+/// A run of a line's text with its own style; unset fields fall back to
+/// the line's.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Span {
+    pub text: String,
+    pub style: LineStyle,
+}
+
+/// Something that happens when the pointer clicks a line.
+#[derive(Debug, Clone, PartialEq)]
+pub enum LineAction {
+    ToggleGroup {
+        title: String,
+        default_collapsed: bool,
+    },
+    /// Re-run an i3blocks blocklet with the click in its environment; its
+    /// output replaces the line until the next refresh.
+    I3blocksClick {
+        command: String,
+        name: String,
+        instance: Option<String>,
+    },
+    /// Show or hide a private clipboard preview.
+    ToggleClipboard,
+    ToggleDnd(DndDaemon),
+    /// Reset the notification unread count.
+    MarkNotificationsRead,
+    /// Mute or unmute the default audio sink.
+    ToggleMute,
+    /// Scrolling over the line changes the backlight by `step` percent per
+    /// notch.
+    AdjustBrightness {
+        device: String,
+        step: f64,
+    },
+    /// Left click starts or pauses the pomodoro timer, middle click skips
+    /// the phase, right click resets.
+    Pomodoro(Durations),
+}
+
+/// A horizontal bar filled to `fraction` (0–1).
+#[derive(Debug, Clone, PartialEq)]
+pub struct Bar {
+    pub fraction: f32,
+    /// Width before `scale` is applied.
+    pub width: f32,
+    pub fill_color: String,
+}
+
+/// A line chart drawn below the text, scaled from 0 to `max`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Graph {
+    /// Oldest first; `None` leaves a gap.
+    pub samples: Vec<Option<f64>>,
+    /// Sample slots across the width; the newest sample sits at the right
+    /// edge so a short history fills in from the right.
+    pub slots: usize,
+    pub max: f64,
+    /// Height before `scale` is applied.
+    pub height: f32,
+    pub color: String,
+}
+
+/// A small trend drawn inline after the text, glyph-high and scaled from 0
+/// to the largest sample.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Sparkline {
+    /// Oldest first; `None` leaves a gap.
+    pub samples: Vec<Option<f64>>,
+    /// Width before `scale` is applied.
+    pub width: f32,
+    /// Default: the line's text color.
+    pub color: Option<String>,
+}
+
+impl Default for Sparkline {
+    fn default() -> Self {
+        Self {
+            samples: Vec::new(),
+            width: 60.0,
+            color: None,
+        }
+    }
+}
+
+/// A 270° dial filled to `fraction`, with `value` in the middle and
+/// `label` underneath.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Gauge {
+    pub fraction: f32,
+    pub value: String,
+    pub label: String,
+    /// Radius and ring thickness before `scale` is applied.
+    pub radius: f32,
+    pub thickness: f32,
+    pub color: String,
+}
+
+/// A grid of cells below the text, each colored by its percentage along
+/// `stops`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Heatmap {
+    /// 0–100, filled row by row.
+    pub values: Vec<f32>,
+    pub columns: usize,
+    /// Cell edge before `scale` is applied.
+    pub cell_size: f32,
+    pub stops: Vec<ColorStop>,
+}
+
+/// A decoded image drawn below the text at `width` × `height`.
+#[derive(Debug, Clone)]
+pub struct Picture {
+    pub image: skia_rs_codec::Image,
+    /// Size before `scale` is applied.
+    pub width: f32,
+    pub height: f32,
+}
+
+impl PartialEq for Picture {
+    fn eq(&self, other: &Self) -> bool {
+        self.image.unique_id() == other.image.unique_id()
+            && self.width == other.width
+            && self.height == other.height
+    }
+}
+
+/// Where a table cell's text sits within its column, or a line on a
+/// horizontal bar.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Align {
+    #[default]
+    Left,
+    Right,
+    Center,
+}
+
+/// A table column; every row of a table carries the same columns so they
+/// line up.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Column {
+    /// Width before `scale` is applied; `None` shares out the space the
+    /// fixed columns leave.
+    pub width: Option<f32>,
+    pub align: Align,
+}
+
+impl Column {
+    pub fn fixed(width: f32, align: Align) -> Self {
+        Self {
+            width: Some(width),
+            align,
+        }
+    }
+
+    pub fn flex(align: Align) -> Self {
+        Self { width: None, align }
+    }
+}
+
+/// One row of a table, each cell cut with "…" to fit its column.
+#[derive(Debug, Clone, PartialEq)]
+pub struct TableRow {
+    pub columns: Vec<Column>,
+    pub cells: Vec<String>,
+}
+
+/// Colors of a section's box; unset falls back to the text color, faint.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct SectionBox {
+    pub border_color: Option<String>,
+    pub bg_color: Option<String>,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+/// What a line draws besides, or instead of, plain text.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub enum Element {
+    #[default]
+    Text,
+    /// The text as a label, followed by a bar right-aligned in the line.
+    Bar(Bar),
+    /// The text on top, then a graph spanning the line's width.
+    Graph(Graph),
+    Sparkline(Sparkline),
+    /// A row of dials below the text.
+    Gauges(Vec<Gauge>),
+    Heatmap(Heatmap),
+    Image(Picture),
+    /// Cells laid out in columns in place of the text, which holds them
+    /// joined for search.
+    TableRow(TableRow),
+    /// A section's title row; the box is drawn from here down to the
+    /// matching `SectionEnd`.
+    SectionStart(SectionBox),
+    /// Bottom padding closing a section's box.
+    SectionEnd,
+    /// A horizontal rule across the line, in place of text.
+    Separator(SeparatorStyle),
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct StyledLine {
     pub text: String,
     pub style: LineStyle,
+    #[serde(skip)]
+    pub action: Option<LineAction>,
+    /// List-style output that the `/` search filter applies to.
+    #[serde(skip)]
+    pub filterable: bool,
+    /// Byte range of the current search match, drawn highlighted.
+    #[serde(skip)]
+    pub highlight: Option<(usize, usize)>,
+    #[serde(skip)]
+    pub element: Element,
+    /// Number of section boxes around the line, each insetting it further.
+    #[serde(skip)]
+    pub section_depth: usize,
+    /// Which group of a horizontal bar the line joins.
+    #[serde(skip)]
+    pub align: Align,
+    /// Styled runs making up `text`; empty for a single-style line.
+    #[serde(skip)]
+    pub spans: Vec<Span>,
 }
 
 impl StyledLine {
@@ -18,11 +412,117 @@ impl StyledLine {
         Self {
             text,
             style: LineStyle::default(),
+            action: None,
+            filterable: false,
+            highlight: None,
+            element: Element::Text,
+            section_depth: 0,
+            align: Align::Left,
+            spans: Vec::new(),
+        }
+    }
+
+    pub fn filterable(text: String) -> Self {
+        Self {
+            filterable: true,
+            ..Self::plain(text)
         }
     }
 
     pub fn styled(text: String, style: LineStyle) -> Self {
-        Self { text, style }
+        Self {
+            text,
+            style,
+            action: None,
+            filterable: false,
+            highlight: None,
+            element: Element::Text,
+            section_depth: 0,
+            align: Align::Left,
+            spans: Vec::new(),
+        }
+    }
+
+    pub fn bar(text: String, bar: Bar) -> Self {
+        Self {
+            element: Element::Bar(bar),
+            ..Self::plain(text)
+        }
+    }
+
+    pub fn graph(text: String, graph: Graph) -> Self {
+        Self {
+            element: Element::Graph(graph),
+            ..Self::plain(text)
+        }
+    }
+
+    pub fn sparkline(text: String, sparkline: Sparkline) -> Self {
+        Self {
+            element: Element::Sparkline(sparkline),
+            ..Self::plain(text)
+        }
+    }
+
+    pub fn gauges(text: String, gauges: Vec<Gauge>) -> Self {
+        Self {
+            element: Element::Gauges(gauges),
+            ..Self::plain(text)
+        }
+    }
+
+    pub fn heatmap(text: String, heatmap: Heatmap) -> Self {
+        Self {
+            element: Element::Heatmap(heatmap),
+            ..Self::plain(text)
+        }
+    }
+
+    pub fn image(text: String, picture: Picture) -> Self {
+        Self {
+            element: Element::Image(picture),
+            ..Self::plain(text)
+        }
+    }
+
+    pub fn table_row(cells: Vec<String>, columns: Vec<Column>) -> Self {
+        Self {
+            element: Element::TableRow(TableRow {
+                columns,
+                cells: cells.clone(),
+            }),
+            ..Self::plain(cells.join(TABLE_SEPARATOR))
+        }
+    }
+
+    /// Title row opening a section box.
+    pub fn section_start(title: String, section: SectionBox) -> Self {
+        Self {
+            element: Element::SectionStart(section),
+            ..Self::plain(title)
+        }
+    }
+
+    pub fn section_end() -> Self {
+        Self {
+            element: Element::SectionEnd,
+            ..Self::plain(String::new())
+        }
+    }
+
+    pub fn separator(style: SeparatorStyle) -> Self {
+        Self {
+            element: Element::Separator(style),
+            ..Self::plain(String::new())
+        }
+    }
+
+    /// Reads `<fg=#ff0000>hot</fg>` markup in the text into spans.
+    pub fn with_markup(mut self) -> Self {
+        let (text, spans) = parse_markup(&self.text);
+        self.text = text;
+        self.spans = spans;
+        self
     }
 }
 
@@ -31,3 +531,80 @@ impl From<String> for StyledLine {
         Self::plain(text)
     }
 }
+
+/// Splits `<fg=#rrggbb>`, `<bg=#rrggbb>` and `<size=14>` tags (closed by
+/// `</fg>` etc., nestable) into spans. Anything else, including unknown or
+/// mismatched tags, stays literal. Returns the plain text and the spans, or
+/// no spans when nothing was styled.
+fn parse_markup(input: &str) -> (String, Vec<Span>) {
+    let mut spans = Vec::new();
+    let mut plain = String::new();
+    let mut text = String::new();
+    // Open tags and the style inside each, innermost last
+    let mut stack: Vec<(&str, LineStyle)> = Vec::new();
+    let mut rest = input;
+    while let Some(start) = rest.find('<') {
+        let Some(len) = rest[start..].find('>') else {
+            break;
+        };
+        let tag = &rest[start + 1..start + len];
+        let current = stack
+            .last()
+            .map(|(_, style)| style.clone())
+            .unwrap_or_default();
+        let opened = tag.split_once('=').and_then(|(name, value)| {
+            let mut style = current.clone();
+            match name {
+                "fg" => style.fg_color = Some(value.to_string()),
+                "bg" => style.bg_color = Some(value.to_string()),
+                "size" => style.font_size = Some(value.parse().ok()?),
+                _ => return None,
+            }
+            Some((name, style))
+        });
+        let closes = tag
+            .strip_prefix('/')
+            .is_some_and(|name| stack.last().is_some_and(|(open, _)| *open == name));
+
+        text.push_str(&rest[..start]);
+        if opened.is_none() && !closes {
+            text.push('<');
+            rest = &rest[start + 1..];
+            continue;
+        }
+        if !text.is_empty() {
+            plain.push_str(&text);
+            spans.push(Span {
+                text: std::mem::take(&mut text),
+                style: current,
+            });
+        }
+        match opened {
+            Some(open) => stack.push(open),
+            None => {
+                stack.pop();
+            }
+        }
+        rest = &rest[start + len + 1..];
+    }
+    text.push_str(rest);
+    if !text.is_empty() {
+        plain.push_str(&text);
+        spans.push(Span {
+            text,
+            style: stack
+                .last()
+                .map(|(_, style)| style.clone())
+                .unwrap_or_default(),
+        });
+    }
+    let styled = spans.iter().any(|span| {
+        span.style.fg_color.is_some()
+            || span.style.bg_color.is_some()
+            || span.style.font_size.is_some()
+    });
+    if !styled {
+        spans.clear();
+    }
+    (plain, spans)
+}
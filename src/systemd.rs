@@ -0,0 +1,103 @@
+use std::collections::HashMap;
+use std::process::Command;
+use std::sync::{Mutex, OnceLock};
+use std::time::Duration;
+
+use crate::background::Poller;
+
+const POLL_INTERVAL: Duration = Duration::from_secs(10);
+
+#[derive(Debug, Clone)]
+pub struct UnitState {
+    pub id: String,
+    /// "active", "inactive", "failed", "activating", …
+    pub active_state: String,
+    /// Finer state, e.g. "running", "exited", "dead".
+    pub sub_state: String,
+}
+
+#[derive(Debug, Clone)]
+pub struct Status {
+    pub units: Vec<UnitState>,
+    /// Failed units on the whole manager, not just the listed ones.
+    pub failed: usize,
+}
+
+/// Pollers keyed by unit list and manager.
+type Registry = Mutex<HashMap<(Vec<String>, bool), Poller<Status>>>;
+
+static POLLERS: OnceLock<Registry> = OnceLock::new();
+
+/// States of `units` on the system (or `user`) manager, polled through
+/// `systemctl`, which queries systemd over D-Bus.
+pub fn status(units: &[String], user: bool) -> Option<Result<Status, String>> {
+    let mut pollers = POLLERS
+        .get_or_init(Default::default)
+        .lock()
+        .unwrap_or_else(|e| e.into_inner());
+    pollers
+        .entry((units.to_vec(), user))
+        .or_insert_with(|| {
+            let units = units.to_vec();
+            Poller::spawn(POLL_INTERVAL, move || query(&units, user))
+        })
+        .latest()
+}
+
+fn query(units: &[String], user: bool) -> Result<Status, String> {
+    let scope = if user { "--user" } else { "--system" };
+    let mut states = Vec::new();
+    if !units.is_empty() {
+        // One "Key=value" block per unit, blank-line separated, in order.
+        let out = systemctl(
+            &[scope, "show", "--property=Id,ActiveState,SubState", "--"],
+            units,
+        )?;
+        for block in out.split("\n\n").filter(|b| !b.trim().is_empty()) {
+            let field = |key: &str| {
+                block
+                    .lines()
+                    .find_map(|l| l.strip_prefix(key)?.strip_prefix('='))
+                    .unwrap_or_default()
+                    .to_string()
+            };
+            states.push(UnitState {
+                id: field("Id"),
+                active_state: field("ActiveState"),
+                sub_state: field("SubState"),
+            });
+        }
+    }
+    let failed = systemctl(
+        &[
+            scope,
+            "list-units",
+            "--state=failed",
+            "--no-legend",
+            "--plain",
+        ],
+        &[],
+    )?
+    .lines()
+    .filter(|l| !l.trim().is_empty())
+    .count();
+    Ok(Status {
+        units: states,
+        failed,
+    })
+}
+
+fn systemctl(args: &[&str], units: &[String]) -> Result<String, String> {
+    let output = Command::new("systemctl")
+        .args(args)
+        .args(units)
+        .output()
+        .map_err(|e| format!("systemctl: {e}"))?;
+    if !output.status.success() {
+        return Err(format!(
+            "systemctl: {}",
+            String::from_utf8_lossy(&output.stderr).trim()
+        ));
+    }
+    Ok(String::from_utf8_lossy(&output.stdout).into_owned())
+}
@@ -0,0 +1,110 @@
+use std::collections::HashMap;
+use std::path::PathBuf;
+
+use crate::config::{self, Config, Theme};
+
+/// Built-in palettes. Each defines every name the built-in modules use, so
+/// switching themes recolors them too.
+const BUILTIN: &[(&str, [(&str, &str); 7])] = &[
+    (
+        "default",
+        [
+            ("fg", "#ffffff"),
+            ("bg", "#000000aa"),
+            ("primary", "#8be9fd"),
+            ("ok", "#50fa7b"),
+            ("warn", "#ffb86c"),
+            ("critical", "#ff5555"),
+            ("muted", "#888888"),
+        ],
+    ),
+    (
+        "dracula",
+        [
+            ("fg", "#f8f8f2"),
+            ("bg", "#282a36dd"),
+            ("primary", "#bd93f9"),
+            ("ok", "#50fa7b"),
+            ("warn", "#ffb86c"),
+            ("critical", "#ff5555"),
+            ("muted", "#6272a4"),
+        ],
+    ),
+    (
+        "nord",
+        [
+            ("fg", "#d8dee9"),
+            ("bg", "#2e3440dd"),
+            ("primary", "#88c0d0"),
+            ("ok", "#a3be8c"),
+            ("warn", "#ebcb8b"),
+            ("critical", "#bf616a"),
+            ("muted", "#4c566a"),
+        ],
+    ),
+    (
+        "gruvbox",
+        [
+            ("fg", "#ebdbb2"),
+            ("bg", "#282828dd"),
+            ("primary", "#83a598"),
+            ("ok", "#b8bb26"),
+            ("warn", "#fabd2f"),
+            ("critical", "#fb4934"),
+            ("muted", "#928374"),
+        ],
+    ),
+    (
+        "solarized",
+        [
+            ("fg", "#839496"),
+            ("bg", "#002b36dd"),
+            ("primary", "#268bd2"),
+            ("ok", "#859900"),
+            ("warn", "#b58900"),
+            ("critical", "#dc322f"),
+            ("muted", "#586e75"),
+        ],
+    ),
+];
+
+/// Named colors of `theme`: the built-in palette it names, overridden by
+/// its theme file, overridden in turn by its `colors` table.
+pub fn palette(theme: &Theme) -> HashMap<String, String> {
+    let name = theme.name.as_deref().unwrap_or("default");
+    let (_, base) = BUILTIN
+        .iter()
+        .find(|(builtin, _)| *builtin == name)
+        .unwrap_or_else(|| {
+            let known: Vec<&str> = BUILTIN.iter().map(|(name, _)| *name).collect();
+            eprintln!(
+                "rustky: unknown theme {name:?} (built-in: {}), using default",
+                known.join(", ")
+            );
+            &BUILTIN[0]
+        });
+    let mut colors: HashMap<String, String> = base
+        .iter()
+        .map(|(name, color)| (name.to_string(), color.to_string()))
+        .collect();
+    if let Some(file) = &theme.file {
+        match load_file(file) {
+            Ok(file_colors) => colors.extend(file_colors),
+            Err(e) => eprintln!("rustky: theme file {e}"),
+        }
+    }
+    colors.extend(theme.colors.clone());
+    colors
+}
+
+/// A theme file is a flat TOML table of `name = "#rrggbb"` pairs; relative
+/// paths are looked up next to config.toml.
+fn load_file(path: &str) -> Result<HashMap<String, String>, String> {
+    let mut resolved = PathBuf::from(config::shellexpand(path));
+    if resolved.is_relative() {
+        resolved = Config::config_path().with_file_name(resolved);
+    }
+    let text =
+        std::fs::read_to_string(&resolved).map_err(|e| format!("{}: {e}", resolved.display()))?;
+    toml::from_str(&text).map_err(|e| format!("{}: {e}", resolved.display()))
+}
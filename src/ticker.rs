@@ -0,0 +1,118 @@
+use std::collections::HashMap;
+use std::sync::{Mutex, OnceLock};
+use std::time::Duration;
+
+use serde_json::Value;
+
+use crate::background::Poller;
+use crate::config::TickerProvider;
+use crate::http;
+
+#[derive(Debug, Clone)]
+pub struct Quote {
+    pub symbol: String,
+    pub price: f64,
+    pub change_24h_pct: Option<f64>,
+    /// e.g. "USD"
+    pub currency: String,
+}
+
+/// Pollers keyed by provider, symbol list and quote currency.
+type Registry = Mutex<HashMap<(TickerProvider, Vec<String>, String), Poller<Vec<Quote>>>>;
+
+static POLLERS: OnceLock<Registry> = OnceLock::new();
+
+/// Quotes for `symbols`, fetched every `interval` in the background; the
+/// last good quotes are kept while the provider is failing.
+pub fn quotes(
+    provider: TickerProvider,
+    symbols: &[String],
+    currency: &str,
+    interval: Duration,
+) -> Option<Result<Vec<Quote>, String>> {
+    let mut pollers = POLLERS
+        .get_or_init(Default::default)
+        .lock()
+        .unwrap_or_else(|e| e.into_inner());
+    pollers
+        .entry((provider, symbols.to_vec(), currency.to_string()))
+        .or_insert_with(|| {
+            let symbols = symbols.to_vec();
+            let currency = currency.to_string();
+            Poller::spawn_cached(interval, "ticker", move || match provider {
+                TickerProvider::Coingecko => coingecko(&symbols, &currency),
+                TickerProvider::Yahoo => yahoo(&symbols),
+            })
+        })
+        .latest()
+}
+
+/// `symbols` are CoinGecko coin ids, e.g. "bitcoin", "ethereum".
+fn coingecko(symbols: &[String], currency: &str) -> Result<Vec<Quote>, String> {
+    let currency = currency.to_lowercase();
+    let url = format!(
+        "https://api.coingecko.com/api/v3/simple/price?ids={}&vs_currencies={currency}\
+         &include_24hr_change=true",
+        symbols.join(",")
+    );
+    let json: Value =
+        serde_json::from_str(&http::get(&url)?).map_err(|e| format!("coingecko: {e}"))?;
+    symbols
+        .iter()
+        .map(|id| {
+            let coin = json
+                .get(id)
+                .ok_or_else(|| format!("coingecko: unknown coin {id}"))?;
+            Ok(Quote {
+                symbol: id.clone(),
+                price: coin
+                    .get(&currency)
+                    .and_then(Value::as_f64)
+                    .ok_or_else(|| format!("coingecko: no {currency} price for {id}"))?,
+                change_24h_pct: coin
+                    .get(format!("{currency}_24h_change"))
+                    .and_then(Value::as_f64),
+                currency: currency.to_uppercase(),
+            })
+        })
+        .collect()
+}
+
+/// `symbols` are Yahoo Finance tickers, e.g. "AAPL", "^GSPC", "EURUSD=X".
+/// The change is against the previous close, which is the daily change for
+/// exchange-traded symbols.
+fn yahoo(symbols: &[String]) -> Result<Vec<Quote>, String> {
+    symbols
+        .iter()
+        .map(|symbol| {
+            let url = format!(
+                "https://query1.finance.yahoo.com/v8/finance/chart/{symbol}?range=1d&interval=1d"
+            );
+            let json: Value =
+                serde_json::from_str(&http::get(&url)?).map_err(|e| format!("yahoo: {e}"))?;
+            let meta = json
+                .pointer("/chart/result/0/meta")
+                .ok_or_else(|| format!("yahoo: unknown symbol {symbol}"))?;
+            let price = meta
+                .get("regularMarketPrice")
+                .and_then(Value::as_f64)
+                .ok_or_else(|| format!("yahoo: no price for {symbol}"))?;
+            let previous = meta
+                .get("chartPreviousClose")
+                .or_else(|| meta.get("previousClose"))
+                .and_then(Value::as_f64);
+            Ok(Quote {
+                symbol: symbol.clone(),
+                price,
+                change_24h_pct: previous
+                    .filter(|p| *p > 0.0)
+                    .map(|p| (price - p) / p * 100.0),
+                currency: meta
+                    .get("currency")
+                    .and_then(Value::as_str)
+                    .unwrap_or_default()
+                    .to_string(),
+            })
+        })
+        .collect()
+}
@@ -0,0 +1,47 @@
+use std::sync::Mutex;
+
+#[derive(Debug, Clone, Default)]
+pub struct Window {
+    pub title: String,
+    pub app_id: String,
+}
+
+/// Per-handle state, applied when the compositor sends `done`.
+#[derive(Debug, Default)]
+pub struct Handle {
+    pub window: Window,
+    pub activated: bool,
+}
+
+/// The focused window and the protocol id of its handle, written by the
+/// foreign-toplevel dispatch in wayland.rs.
+static FOCUSED: Mutex<Option<(u32, Window)>> = Mutex::new(None);
+
+pub fn focused() -> Option<Window> {
+    FOCUSED
+        .lock()
+        .unwrap_or_else(|e| e.into_inner())
+        .as_ref()
+        .map(|(_, window)| window.clone())
+}
+
+pub fn set_focused(id: u32, window: Window) {
+    *FOCUSED.lock().unwrap_or_else(|e| e.into_inner()) = Some((id, window));
+}
+
+/// Clears the focus if it is still on handle `id`. Activation and
+/// deactivation of two windows arrive in no particular order, so another
+/// window may already have taken it.
+pub fn unfocus(id: u32) {
+    let mut focused = FOCUSED.lock().unwrap_or_else(|e| e.into_inner());
+    if focused.as_ref().is_some_and(|(current, _)| *current == id) {
+        *focused = None;
+    }
+}
+
+/// Whether a `state` event array contains `activated` (2).
+pub fn is_activated(states: &[u8]) -> bool {
+    states
+        .chunks_exact(4)
+        .any(|s| u32::from_ne_bytes([s[0], s[1], s[2], s[3]]) == 2)
+}
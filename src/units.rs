@@ -0,0 +1,99 @@
+use crate::config::{ByteUnits, General, RateUnit};
+
+const IEC: [&str; 6] = ["B", "KiB", "MiB", "GiB", "TiB", "PiB"];
+const SI: [&str; 6] = ["B", "KB", "MB", "GB", "TB", "PB"];
+
+/// Byte, rate and duration formatting shared by the built-in modules and
+/// scripts, following `[general] byte_units` and `precision`.
+#[derive(Debug, Clone, Copy)]
+pub struct Format {
+    pub units: ByteUnits,
+    pub precision: usize,
+}
+
+impl Format {
+    pub fn new(general: &General) -> Self {
+        Self {
+            units: general.byte_units,
+            precision: general.precision,
+        }
+    }
+
+    fn base(&self) -> f64 {
+        match self.units {
+            ByteUnits::Iec => 1024.0,
+            ByteUnits::Si => 1000.0,
+        }
+    }
+
+    fn names(&self) -> &'static [&'static str; 6] {
+        match self.units {
+            ByteUnits::Iec => &IEC,
+            ByteUnits::Si => &SI,
+        }
+    }
+
+    /// The largest unit `bytes` is at least one of, and `bytes` in it.
+    fn scaled(&self, bytes: f64) -> (f64, usize) {
+        let mut value = bytes;
+        let mut unit = 0;
+        while value.abs() >= self.base() && unit < IEC.len() - 1 {
+            value /= self.base();
+            unit += 1;
+        }
+        (value, unit)
+    }
+
+    /// "512 B", "3.4 MiB" or, with SI units, "3.6 MB".
+    pub fn bytes(&self, bytes: f64) -> String {
+        let (value, unit) = self.scaled(bytes);
+        self.in_unit(value, unit)
+    }
+
+    /// "3.4/15.6 GiB": both in the unit that fits `total`.
+    pub fn bytes_of(&self, used: f64, total: f64) -> String {
+        let (total, unit) = self.scaled(total);
+        let used = used / self.base().powi(unit as i32);
+        let precision = if unit == 0 { 0 } else { self.precision };
+        format!("{used:.precision$}/{}", self.in_unit(total, unit))
+    }
+
+    fn in_unit(&self, value: f64, unit: usize) -> String {
+        // Whole bytes have no fraction to show
+        let precision = if unit == 0 { 0 } else { self.precision };
+        format!("{value:.precision$} {}", self.names()[unit])
+    }
+
+    /// A rate given in bytes per second, in `unit` or whichever fits.
+    pub fn rate(&self, bytes_per_sec: f64, unit: RateUnit) -> String {
+        let precision = self.precision;
+        match unit {
+            RateUnit::Auto => format!("{}/s", self.bytes(bytes_per_sec)),
+            RateUnit::Kbit => format!("{:.0} Kb/s", bytes_per_sec * 8.0 / 1000.0),
+            RateUnit::Mbit => format!("{:.precision$} Mb/s", bytes_per_sec * 8.0 / 1_000_000.0),
+            RateUnit::Kb => format!("{:.0} KB/s", bytes_per_sec / 1000.0),
+            RateUnit::Mb => format!("{:.precision$} MB/s", bytes_per_sec / 1_000_000.0),
+        }
+    }
+}
+
+/// The two largest units of a duration: "42s", "5m 3s", "4h 10m", "3d 4h".
+pub fn duration(secs: u64) -> String {
+    let (d, h, m, s) = (secs / 86400, secs / 3600 % 24, secs / 60 % 60, secs % 60);
+    match secs {
+        0..60 => format!("{s}s"),
+        60..3600 => format!("{m}m {s}s"),
+        3600..86400 => format!("{h}h {m}m"),
+        _ => format!("{d}d {h}h"),
+    }
+}
+
+/// Just the largest unit, for ages: "42s", "2m", "2h", "3d".
+pub fn age(secs: u64) -> String {
+    match secs {
+        0..60 => format!("{secs}s"),
+        60..3600 => format!("{}m", secs / 60),
+        3600..86400 => format!("{}h", secs / 3600),
+        _ => format!("{}d", secs / 86400),
+    }
+}
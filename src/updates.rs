@@ -0,0 +1,92 @@
+use std::process::Command;
+use std::sync::OnceLock;
+use std::time::Duration;
+
+use crate::background::Poller;
+
+#[derive(Debug, Clone)]
+pub struct Update {
+    pub kind: UpdateKind,
+    /// Application id, runtime id or firmware device name.
+    pub name: String,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum UpdateKind {
+    App,
+    Runtime,
+    Firmware,
+}
+
+static FLATPAK: OnceLock<Poller<Vec<Update>>> = OnceLock::new();
+static FIRMWARE: OnceLock<Poller<Vec<Update>>> = OnceLock::new();
+
+/// Pending Flatpak updates, checked every `interval` in the background.
+pub fn flatpak(interval: Duration) -> Option<Result<Vec<Update>, String>> {
+    FLATPAK
+        .get_or_init(|| Poller::spawn(interval, check_flatpak))
+        .latest()
+}
+
+/// Pending firmware updates from fwupd.
+pub fn firmware(interval: Duration) -> Option<Result<Vec<Update>, String>> {
+    FIRMWARE
+        .get_or_init(|| Poller::spawn(interval, check_firmware))
+        .latest()
+}
+
+fn check_flatpak() -> Result<Vec<Update>, String> {
+    let output = Command::new("flatpak")
+        .args(["remote-ls", "--updates", "--columns=ref"])
+        .output()
+        .map_err(|e| format!("flatpak: {e}"))?;
+    if !output.status.success() {
+        return Err(format!(
+            "flatpak: {}",
+            String::from_utf8_lossy(&output.stderr).trim()
+        ));
+    }
+    // Refs look like "app/org.mozilla.firefox/x86_64/stable"
+    Ok(String::from_utf8_lossy(&output.stdout)
+        .lines()
+        .filter_map(|line| {
+            let mut parts = line.trim().split('/');
+            let kind = match parts.next()? {
+                "app" => UpdateKind::App,
+                "runtime" => UpdateKind::Runtime,
+                _ => return None,
+            };
+            Some(Update {
+                kind,
+                name: parts.next()?.to_string(),
+            })
+        })
+        .collect())
+}
+
+fn check_firmware() -> Result<Vec<Update>, String> {
+    let output = Command::new("fwupdmgr")
+        .args(["get-updates", "--json"])
+        .output()
+        .map_err(|e| format!("fwupdmgr: {e}"))?;
+    // Exit code 2 means "nothing to do"
+    if output.status.code() == Some(2) {
+        return Ok(Vec::new());
+    }
+    let json: serde_json::Value = serde_json::from_slice(&output.stdout).map_err(|_| {
+        format!(
+            "fwupdmgr: {}",
+            String::from_utf8_lossy(&output.stderr).trim()
+        )
+    })?;
+    Ok(json["Devices"]
+        .as_array()
+        .into_iter()
+        .flatten()
+        .filter(|d| d["Releases"].as_array().is_some_and(|r| !r.is_empty()))
+        .map(|d| Update {
+            kind: UpdateKind::Firmware,
+            name: d["Name"].as_str().unwrap_or("device").to_string(),
+        })
+        .collect())
+}
@@ -0,0 +1,115 @@
+use std::collections::HashMap;
+use std::process::Command;
+use std::sync::{Mutex, OnceLock};
+use std::time::Duration;
+
+use crate::background::Poller;
+use crate::config::UpsBackend;
+
+#[derive(Debug, Clone, Default)]
+pub struct Ups {
+    /// Battery charge in percent.
+    pub charge: Option<f64>,
+    /// Output load in percent of capacity.
+    pub load: Option<f64>,
+    pub runtime_secs: Option<u64>,
+    pub on_battery: bool,
+    pub low_battery: bool,
+}
+
+type Registry = Mutex<HashMap<(UpsBackend, Option<String>), Poller<Ups>>>;
+
+static POLLERS: OnceLock<Registry> = OnceLock::new();
+
+/// State of the UPS `target` ("name@host" for NUT, "host:port" for
+/// apcupsd; `None` for the local default), polled every `interval`.
+pub fn status(
+    backend: UpsBackend,
+    target: Option<&str>,
+    interval: Duration,
+) -> Option<Result<Ups, String>> {
+    let mut pollers = POLLERS
+        .get_or_init(Default::default)
+        .lock()
+        .unwrap_or_else(|e| e.into_inner());
+    pollers
+        .entry((backend, target.map(String::from)))
+        .or_insert_with(|| {
+            let target = target.map(String::from);
+            Poller::spawn(interval, move || match backend {
+                UpsBackend::Nut => nut(target.as_deref()),
+                UpsBackend::Apcupsd => apcupsd(target.as_deref()),
+            })
+        })
+        .latest()
+}
+
+/// `upsc <ups>` prints "battery.charge: 100" lines; without a target the
+/// first UPS of `upsc -l` is used.
+fn nut(target: Option<&str>) -> Result<Ups, String> {
+    let target = match target {
+        Some(target) => target.to_string(),
+        None => run("upsc", &["-l"])?
+            .lines()
+            .next()
+            .ok_or("upsc: no UPS configured")?
+            .trim()
+            .to_string(),
+    };
+    let out = run("upsc", &[&target])?;
+    let vars: HashMap<&str, &str> = out.lines().filter_map(|l| l.split_once(": ")).collect();
+    let number = |key: &str| vars.get(key).and_then(|v| v.trim().parse::<f64>().ok());
+    // "OL", "OB DISCHRG", "OB LB", "OL CHRG"
+    let flags: Vec<&str> = vars
+        .get("ups.status")
+        .map(|s| s.split_whitespace().collect())
+        .unwrap_or_default();
+    Ok(Ups {
+        charge: number("battery.charge"),
+        load: number("ups.load"),
+        runtime_secs: number("battery.runtime").map(|s| s as u64),
+        on_battery: flags.contains(&"OB"),
+        low_battery: flags.contains(&"LB"),
+    })
+}
+
+/// `apcaccess status` prints "BCHARGE  : 100.0 Percent" lines.
+fn apcupsd(target: Option<&str>) -> Result<Ups, String> {
+    let mut args = vec!["status"];
+    args.extend(target);
+    let out = run("apcaccess", &args)?;
+    let vars: HashMap<&str, &str> = out
+        .lines()
+        .filter_map(|l| {
+            let (key, value) = l.split_once(':')?;
+            Some((key.trim(), value.trim()))
+        })
+        .collect();
+    let number = |key: &str| {
+        vars.get(key)
+            .and_then(|v| v.split_whitespace().next()?.parse::<f64>().ok())
+    };
+    // "ONLINE", "ONBATT", "ONBATT LOWBATT", "COMMLOST"
+    let status = vars.get("STATUS").copied().unwrap_or_default();
+    Ok(Ups {
+        charge: number("BCHARGE"),
+        load: number("LOADPCT"),
+        runtime_secs: number("TIMELEFT").map(|minutes| (minutes * 60.0) as u64),
+        on_battery: status.contains("ONBATT"),
+        low_battery: status.contains("LOWBATT"),
+    })
+}
+
+fn run(program: &str, args: &[&str]) -> Result<String, String> {
+    let output = Command::new(program)
+        .args(args)
+        .output()
+        .map_err(|e| format!("{program}: {e}"))?;
+    if !output.status.success() {
+        return Err(format!(
+            "{program}: {}",
+            String::from_utf8_lossy(&output.stderr).trim()
+        ));
+    }
+    Ok(String::from_utf8_lossy(&output.stdout).into_owned())
+}
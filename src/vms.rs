@@ -0,0 +1,108 @@
+use std::collections::HashMap;
+use std::process::Command;
+use std::sync::{Mutex, OnceLock};
+use std::time::{Duration, Instant};
+
+use crate::background::Poller;
+
+const POLL_INTERVAL: Duration = Duration::from_secs(5);
+
+#[derive(Debug, Clone)]
+pub struct Domain {
+    pub name: String,
+    /// "running", "paused", "shut off", …
+    pub state: &'static str,
+    /// Share of one host CPU since the previous poll; `None` on the first
+    /// poll and for domains that aren't running.
+    pub cpu_pct: Option<f64>,
+    /// Resident memory, or the balloon size when RSS isn't reported.
+    pub memory_kib: Option<u64>,
+}
+
+type Registry = Mutex<HashMap<String, Poller<Vec<Domain>>>>;
+
+static POLLERS: OnceLock<Registry> = OnceLock::new();
+
+/// Domains defined on the libvirt connection `uri`, sorted by name, polled
+/// with `virsh domstats` (which talks to the libvirt socket).
+pub fn domains(uri: &str) -> Option<Result<Vec<Domain>, String>> {
+    let mut pollers = POLLERS
+        .get_or_init(Default::default)
+        .lock()
+        .unwrap_or_else(|e| e.into_inner());
+    pollers
+        .entry(uri.to_string())
+        .or_insert_with(|| {
+            let uri = uri.to_string();
+            // Previous cpu.time (ns) per domain, for the usage delta.
+            let previous: Mutex<HashMap<String, (u64, Instant)>> = Mutex::default();
+            Poller::spawn(POLL_INTERVAL, move || {
+                let mut previous = previous.lock().unwrap_or_else(|e| e.into_inner());
+                query(&uri, &mut previous)
+            })
+        })
+        .latest()
+}
+
+fn query(uri: &str, previous: &mut HashMap<String, (u64, Instant)>) -> Result<Vec<Domain>, String> {
+    let output = Command::new("virsh")
+        .args(["-c", uri, "domstats", "--state", "--cpu-total", "--balloon"])
+        .env("LC_ALL", "C")
+        .output()
+        .map_err(|e| format!("virsh: {e}"))?;
+    if !output.status.success() {
+        return Err(format!(
+            "virsh: {}",
+            String::from_utf8_lossy(&output.stderr).trim()
+        ));
+    }
+    let now = Instant::now();
+    let mut domains = Vec::new();
+    let mut cpu_times = HashMap::new();
+    // "Domain: 'name'" followed by indented "key=value" lines
+    for block in String::from_utf8_lossy(&output.stdout)
+        .split("Domain: ")
+        .skip(1)
+    {
+        let mut lines = block.lines();
+        let name = lines
+            .next()
+            .unwrap_or_default()
+            .trim()
+            .trim_matches('\'')
+            .to_string();
+        let stats: HashMap<&str, &str> = lines.filter_map(|l| l.trim().split_once('=')).collect();
+        let number = |key: &str| stats.get(key).and_then(|v| v.parse::<u64>().ok());
+
+        let state = state_name(number("state.state").unwrap_or(0));
+        let cpu_pct = number("cpu.time").and_then(|ns| {
+            cpu_times.insert(name.clone(), (ns, now));
+            let (last_ns, at) = previous.get(&name)?;
+            let elapsed = now.duration_since(*at).as_nanos() as f64;
+            (elapsed > 0.0).then(|| ns.saturating_sub(*last_ns) as f64 / elapsed * 100.0)
+        });
+        domains.push(Domain {
+            state,
+            cpu_pct: cpu_pct.filter(|_| state == "running"),
+            memory_kib: number("balloon.rss").or_else(|| number("balloon.current")),
+            name,
+        });
+    }
+    *previous = cpu_times;
+    domains.sort_by(|a, b| a.name.cmp(&b.name));
+    Ok(domains)
+}
+
+/// virDomainState.
+fn state_name(state: u64) -> &'static str {
+    match state {
+        1 => "running",
+        2 => "blocked",
+        3 => "paused",
+        4 => "shutting down",
+        5 => "shut off",
+        6 => "crashed",
+        7 => "suspended",
+        _ => "unknown",
+    }
+}
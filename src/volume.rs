@@ -0,0 +1,98 @@
+use std::io::{BufRead, BufReader};
+use std::process::{Command, Stdio};
+use std::sync::{Mutex, Once};
+use std::time::Duration;
+
+/// Default sink, PulseAudio or PipeWire via pipewire-pulse.
+#[derive(Debug, Clone)]
+pub struct Sink {
+    pub volume_pct: u32,
+    pub muted: bool,
+}
+
+static SINK: Mutex<Option<Result<Sink, String>>> = Mutex::new(None);
+static WATCH: Once = Once::new();
+
+/// The default sink's state, starting the event watcher on first use.
+pub fn sink() -> Option<Result<Sink, String>> {
+    WATCH.call_once(|| {
+        std::thread::spawn(watch);
+    });
+    SINK.lock().unwrap_or_else(|e| e.into_inner()).clone()
+}
+
+/// Toggles mute in the background, so a slow sound server can't stall input.
+pub fn toggle_mute() {
+    std::thread::spawn(|| {
+        if let Err(e) = pactl(&["set-sink-mute", "@DEFAULT_SINK@", "toggle"]) {
+            eprintln!("rustky: {e}");
+        }
+        refresh();
+    });
+}
+
+/// Re-queries the sink whenever `pactl subscribe` reports a sink or server
+/// (default sink) change, restarting the subscription if it exits.
+fn watch() {
+    loop {
+        refresh();
+        match Command::new("pactl")
+            .arg("subscribe")
+            .stdout(Stdio::piped())
+            .stderr(Stdio::null())
+            .spawn()
+        {
+            Ok(mut child) => {
+                if let Some(stdout) = child.stdout.take() {
+                    for line in BufReader::new(stdout).lines() {
+                        let Ok(line) = line else { break };
+                        // "Event 'change' on sink #56"
+                        if line.contains(" on sink #") || line.contains(" on server") {
+                            refresh();
+                        }
+                    }
+                }
+                let _ = child.wait();
+            }
+            Err(e) => {
+                publish(Err(format!("pactl: {e}")));
+                return;
+            }
+        }
+        std::thread::sleep(Duration::from_secs(5));
+    }
+}
+
+fn refresh() {
+    publish(query());
+}
+
+fn publish(result: Result<Sink, String>) {
+    *SINK.lock().unwrap_or_else(|e| e.into_inner()) = Some(result);
+}
+
+fn query() -> Result<Sink, String> {
+    // "Volume: front-left: 32768 /  50% / -18.06 dB,   front-right: …"
+    let volume = pactl(&["get-sink-volume", "@DEFAULT_SINK@"])?;
+    let volume_pct = volume
+        .split('/')
+        .find_map(|part| part.trim().strip_suffix('%')?.parse().ok())
+        .ok_or_else(|| format!("pactl: unexpected volume {:?}", volume.trim()))?;
+    let muted = pactl(&["get-sink-mute", "@DEFAULT_SINK@"])?.trim() == "Mute: yes";
+    Ok(Sink { volume_pct, muted })
+}
+
+fn pactl(args: &[&str]) -> Result<String, String> {
+    let output = Command::new("pactl")
+        .args(args)
+        .env("LC_ALL", "C")
+        .output()
+        .map_err(|e| format!("pactl: {e}"))?;
+    if !output.status.success() {
+        return Err(format!(
+            "pactl: {}",
+            String::from_utf8_lossy(&output.stderr).trim()
+        ));
+    }
+    Ok(String::from_utf8_lossy(&output.stdout).into_owned())
+}
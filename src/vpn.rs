@@ -0,0 +1,104 @@
+use std::collections::HashMap;
+use std::fs;
+use std::path::Path;
+use std::process::Command;
+use std::sync::{Mutex, OnceLock};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use crate::background::Poller;
+
+const POLL_INTERVAL: Duration = Duration::from_secs(5);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TunnelKind {
+    WireGuard,
+    /// tun/tap device, as used by OpenVPN and most other VPN clients.
+    Tun,
+    Other,
+}
+
+#[derive(Debug, Clone)]
+pub struct Tunnel {
+    pub kind: TunnelKind,
+    pub up: bool,
+}
+
+/// WireGuard peer details from `wg`, which needs CAP_NET_ADMIN.
+#[derive(Debug, Clone, Default)]
+pub struct Peer {
+    pub endpoint: Option<String>,
+    /// Seconds since the most recent handshake of any peer.
+    pub handshake_age_secs: Option<u64>,
+}
+
+/// Kind and administrative state of `interface`, or `None` if it doesn't
+/// exist (a stopped VPN usually removes its interface).
+pub fn tunnel(interface: &str) -> Option<Tunnel> {
+    let dir = Path::new("/sys/class/net").join(interface);
+    let flags = fs::read_to_string(dir.join("flags")).ok()?;
+    let flags = u32::from_str_radix(flags.trim().trim_start_matches("0x"), 16).ok()?;
+    let uevent = fs::read_to_string(dir.join("uevent")).unwrap_or_default();
+    let kind = if uevent.lines().any(|l| l == "DEVTYPE=wireguard") {
+        TunnelKind::WireGuard
+    } else if dir.join("tun_flags").exists() {
+        TunnelKind::Tun
+    } else {
+        TunnelKind::Other
+    };
+    Some(Tunnel {
+        kind,
+        up: flags & libc::IFF_UP as u32 != 0,
+    })
+}
+
+static PEERS: OnceLock<Mutex<HashMap<String, Poller<Peer>>>> = OnceLock::new();
+
+/// WireGuard peer details, polled in the background.
+pub fn wireguard_peer(interface: &str) -> Option<Result<Peer, String>> {
+    let mut pollers = PEERS
+        .get_or_init(Default::default)
+        .lock()
+        .unwrap_or_else(|e| e.into_inner());
+    pollers
+        .entry(interface.to_string())
+        .or_insert_with(|| {
+            let interface = interface.to_string();
+            Poller::spawn(POLL_INTERVAL, move || query_wg(&interface))
+        })
+        .latest()
+}
+
+/// Parses `wg show <if> dump`: one interface line, then per peer
+/// `key psk endpoint allowed-ips latest-handshake rx tx keepalive`.
+fn query_wg(interface: &str) -> Result<Peer, String> {
+    let output = Command::new("wg")
+        .args(["show", interface, "dump"])
+        .output()
+        .map_err(|e| format!("wg: {e}"))?;
+    if !output.status.success() {
+        return Err(format!(
+            "wg: {}",
+            String::from_utf8_lossy(&output.stderr).trim()
+        ));
+    }
+    let now = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0);
+    let mut peer = Peer::default();
+    let mut latest = 0;
+    for line in String::from_utf8_lossy(&output.stdout).lines().skip(1) {
+        let fields: Vec<&str> = line.split('\t').collect();
+        let (Some(endpoint), Some(handshake)) = (fields.get(2), fields.get(4)) else {
+            continue;
+        };
+        let handshake: u64 = handshake.parse().unwrap_or(0);
+        if peer.endpoint.is_none() || handshake > latest {
+            peer.endpoint = (*endpoint != "(none)").then(|| endpoint.to_string());
+        }
+        latest = latest.max(handshake);
+    }
+    // 0 means no handshake yet
+    peer.handshake_age_secs = (latest > 0).then(|| now.saturating_sub(latest));
+    Ok(peer)
+}
@@ -1,17 +1,21 @@
-use std::time::Duration;
+use std::time::{Duration, Instant};
 
+use calloop::generic::Generic;
+use calloop::signals::{Signal, Signals};
 use calloop::timer::{TimeoutAction, Timer};
-use calloop::EventLoop;
+use calloop::{EventLoop, Interest, LoopHandle, Mode, PostAction, RegistrationToken};
 use calloop_wayland_source::WaylandSource;
 use smithay_client_toolkit::{
-    compositor::{CompositorHandler, CompositorState},
-    delegate_compositor, delegate_layer, delegate_output, delegate_pointer, delegate_registry,
-    delegate_seat, delegate_shm,
+    compositor::{CompositorHandler, CompositorState, Region},
+    delegate_compositor, delegate_keyboard, delegate_layer, delegate_output, delegate_pointer,
+    delegate_registry, delegate_seat, delegate_shm, delegate_touch,
     output::{OutputHandler, OutputState},
     registry::{ProvidesRegistryState, RegistryState},
     registry_handlers,
     seat::{
+        keyboard::{KeyEvent, KeyboardHandler, Keymap, Keysym, Modifiers},
         pointer::{PointerEvent, PointerEventKind, PointerHandler},
+        touch::TouchHandler,
         Capability, SeatHandler, SeatState,
     },
     shell::WaylandSurface,
@@ -24,165 +28,736 @@ use smithay_client_toolkit::{
         Shm, ShmHandler,
     },
 };
+use std::os::fd::AsFd;
+use std::sync::Mutex;
+
+use skia_rs_canvas::Surface;
 use wayland_client::{
-    globals::registry_queue_init,
-    protocol::{wl_output, wl_pointer, wl_seat, wl_shm, wl_surface},
-    Connection, QueueHandle,
+    event_created_child,
+    globals::{registry_queue_init, GlobalList},
+    protocol::{wl_keyboard, wl_output, wl_pointer, wl_seat, wl_shm, wl_surface, wl_touch},
+    Connection, Dispatch, EventQueue, Proxy, QueueHandle,
+};
+use wayland_protocols::ext::idle_notify::v1::client::{
+    ext_idle_notification_v1::{self, ExtIdleNotificationV1},
+    ext_idle_notifier_v1::ExtIdleNotifierV1,
+};
+use wayland_protocols::wp::fractional_scale::v1::client::{
+    wp_fractional_scale_manager_v1::WpFractionalScaleManagerV1,
+    wp_fractional_scale_v1::{self, WpFractionalScaleV1},
+};
+use wayland_protocols::wp::viewporter::client::{
+    wp_viewport::WpViewport, wp_viewporter::WpViewporter,
+};
+use wayland_protocols_wlr::data_control::v1::client::{
+    zwlr_data_control_device_v1::{self, ZwlrDataControlDeviceV1},
+    zwlr_data_control_manager_v1::ZwlrDataControlManagerV1,
+    zwlr_data_control_offer_v1::{self, ZwlrDataControlOfferV1},
+};
+use wayland_protocols_wlr::foreign_toplevel::v1::client::{
+    zwlr_foreign_toplevel_handle_v1::{self, ZwlrForeignToplevelHandleV1},
+    zwlr_foreign_toplevel_manager_v1::{self, ZwlrForeignToplevelManagerV1},
 };
 
-#[cfg(any(feature = "rhai-scripting", feature = "python-scripting"))]
-use crate::config::Module;
-use crate::config::Config;
+use crate::alerts::AlertEngine;
+use crate::animation::Animator;
+use crate::brightness;
+use crate::clipboard::{self, Selection};
+use crate::config::{
+    Config, DragModifier, MAX_SCALE, MIN_SCALE, Margins, Module, ModuleEntry, Orientation,
+    Overrides, RenderBackend, Window, WindowAnchor,
+};
+use crate::dnd;
+use crate::exec;
+use crate::filter::Filter;
+#[cfg(feature = "gpu")]
+use crate::gpu_present::GpuSurface;
+use crate::groups::{self, Visible};
+use crate::ipc::{self, IpcCommand, ScaleCommand};
+use crate::keyboard_layout;
 use crate::monitor::Monitor;
+use crate::notifications;
+use crate::pages::Pager;
+use crate::pomodoro::Durations;
+use crate::preview::Preview;
 use crate::render::Renderer;
-use crate::styled::StyledLine;
+use crate::state::UiState;
+use crate::styled::{ClickAction, Clicks, Element, LineAction, Overflow, StyledLine};
+use crate::toplevel;
+use crate::units::Format;
+use crate::volume;
 
-pub fn run(cfg: Config, renderer: Renderer, monitor: Monitor) {
-    let conn = Connection::connect_to_env().expect("failed to connect to Wayland");
-    let (globals, event_queue) = registry_queue_init(&conn).expect("failed to init registry");
-    let qh: QueueHandle<RustkyState> = event_queue.handle();
+/// `preview_errors` is `Some` in `--preview` mode, holding errors from the
+/// initial config load.
+pub fn run(
+    cfg: Config,
+    overrides: Overrides,
+    renderer: Renderer,
+    monitor: Monitor,
+    preview_errors: Option<Vec<String>>,
+) {
+    // Blocks SIGUSR2 in this thread and those it goes on to spawn, so it
+    // must come before any of them
+    let signals = Signals::new(&[Signal::SIGUSR2])
+        .map_err(|e| eprintln!("rustky: SIGUSR2 unavailable: {e}"))
+        .ok();
+    let mut event_loop: EventLoop<RustkyState> =
+        EventLoop::try_new().expect("failed to create event loop");
+    let loop_handle = event_loop.handle();
 
-    let compositor = CompositorState::bind(&globals, &qh).expect("wl_compositor not available");
-    let layer_shell = LayerShell::bind(&globals, &qh).expect("wlr_layer_shell not available");
-    let shm = Shm::bind(&globals, &qh).expect("wl_shm not available");
-    let seat_state = SeatState::new(&globals, &qh);
+    let retained = Retained::new(cfg, overrides, monitor, preview_errors);
+    let connected = Connected::open().unwrap_or_else(|e| {
+        eprintln!("rustky: {e}");
+        std::process::exit(1);
+    });
+    let mut state = connect(&loop_handle, connected, retained, Some(renderer));
 
-    let surface = compositor.create_surface(&qh);
-    let layer = layer_shell.create_layer_surface(
-        &qh,
-        surface,
-        Layer::Bottom,
-        Some("rustky".to_string()),
-        None,
-    );
-
-    layer.set_anchor(Anchor::TOP | Anchor::RIGHT);
-    layer.set_size(cfg.window.width, cfg.window.height);
-    layer.set_exclusive_zone(-1); // don't push other surfaces
-    layer.set_keyboard_interactivity(KeyboardInteractivity::None);
-    layer.set_margin(cfg.window.y, cfg.window.x, 0, 0);
-    layer.commit();
-
-    let pool = SlotPool::new(
-        (cfg.window.width * cfg.window.height * 4) as usize,
-        &shm,
-    )
-    .expect("failed to create shm pool");
-
-    // Initialize scripting engines
-    #[cfg(feature = "rhai-scripting")]
-    let rhai_engine = {
-        let mut engine = crate::scripting::rhai_engine::RhaiEngine::new();
-        for module in &cfg.modules {
-            if let Module::Rhai {
-                code,
-                file,
-                function,
-            } = module
-            {
-                if let Some(code_str) = code {
-                    let key = format!("inline:{function}");
-                    if let Err(e) = engine.compile_inline(&key, code_str) {
-                        eprintln!("rustky: {e}");
+    match ipc::bind() {
+        Ok(listener) => {
+            let handle = loop_handle.clone();
+            loop_handle
+                .insert_source(
+                    Generic::new(listener, Interest::READ, Mode::Level),
+                    move |_, listener, _| {
+                        while let Ok((stream, _)) = listener.accept() {
+                            // Each client gets its own source, so one that is
+                            // slow to write never holds up the loop
+                            if let Err(e) = stream.set_nonblocking(true) {
+                                eprintln!("rustky: IPC client: {e}");
+                                continue;
+                            }
+                            let mut buf = Vec::new();
+                            let client = Generic::new(stream, Interest::READ, Mode::Level);
+                            let inserted = handle.insert_source(
+                                client,
+                                move |_, stream, state: &mut RustkyState| {
+                                    match ipc::read_command(stream, &mut buf) {
+                                        Ok(None) => return Ok(PostAction::Continue),
+                                        Ok(Some(line)) => {
+                                            let result = state.handle_ipc(&line);
+                                            ipc::reply(stream, result);
+                                        }
+                                        Err(_) => {}
+                                    }
+                                    Ok(PostAction::Remove)
+                                },
+                            );
+                            if let Err(e) = inserted {
+                                eprintln!("rustky: IPC client: {e}");
+                            }
+                        }
+                        Ok(PostAction::Continue)
+                    },
+                )
+                .expect("failed to insert IPC source");
+        }
+        Err(e) => eprintln!("rustky: failed to bind IPC socket: {e}"),
+    }
+
+    // SIGUSR2 shows or hides the widgets, like `rustky msg visibility`
+    if let Some(signals) = signals {
+        loop_handle
+            .insert_source(signals, |_, _, state: &mut RustkyState| {
+                state.set_visible(None);
+            })
+            .expect("failed to insert signal source");
+    }
+
+    let update_ms = state.cfg.general.update_interval_ms;
+    loop_handle
+        .insert_source(
+            Timer::from_duration(Duration::from_millis(update_ms)),
+            |_, _, state: &mut RustkyState| {
+                // Alerts keep firing while idle; only drawing pauses
+                state.refresh();
+                if !state.idle {
+                    state.draw_all();
+                }
+                TimeoutAction::ToDuration(Duration::from_millis(
+                    state.cfg.general.update_interval_ms,
+                ))
+            },
+        )
+        .expect("failed to insert timer");
+
+    // Second ticks for modules showing seconds, aligned to the wall clock,
+    // when the regular update is slower than that.
+    loop_handle
+        .insert_source(
+            Timer::from_duration(until_next_second()),
+            |_, _, state: &mut RustkyState| {
+                if state.cfg.general.update_interval_ms > 1000 && !state.idle {
+                    for i in 0..state.widgets.len() {
+                        let modules = state.widgets[i].modules(&state.cfg);
+                        let ticks = modules.iter().any(|m| m.module.ticks_every_second());
+                        if ticks {
+                            state.draw(i);
+                        }
                     }
                 }
-                if let Some(file_path) = file {
-                    let resolved = cfg.resolve_script_path(file_path);
-                    let resolved_str = resolved.to_string_lossy().to_string();
-                    if let Err(e) = engine.compile_file(&resolved_str) {
-                        eprintln!("rustky: {e}");
+                TimeoutAction::ToDuration(until_next_second())
+            },
+        )
+        .expect("failed to insert second timer");
+
+    if state.preview.is_some() {
+        loop_handle
+            .insert_source(
+                Timer::from_duration(Duration::from_millis(500)),
+                |_, _, state: &mut RustkyState| {
+                    if state.preview.as_mut().is_some_and(Preview::changed) {
+                        state.reload();
+                    }
+                    TimeoutAction::ToDuration(Duration::from_millis(500))
+                },
+            )
+            .expect("failed to insert preview timer");
+    }
+
+    loop {
+        // The first dispatch of a session lets the surfaces be configured
+        let error = match event_loop.dispatch(Some(Duration::from_millis(100)), &mut state) {
+            Ok(()) => {
+                state.draw_all();
+                loop {
+                    if let Err(e) = event_loop.dispatch(Some(Duration::from_millis(16)), &mut state)
+                    {
+                        break e;
                     }
                 }
             }
-        }
-        if let Some(ref hook_path) = cfg.general.on_draw_rhai {
-            let resolved = cfg.resolve_script_path(hook_path);
-            let resolved_str = resolved.to_string_lossy().to_string();
-            if let Err(e) = engine.load_on_draw_hook(&resolved_str) {
-                eprintln!("rustky: {e}");
-            }
-        }
-        engine
-    };
+            Err(e) => e,
+        };
+        eprintln!("rustky: lost the compositor connection: {error}; reconnecting");
+        state = reconnect(&loop_handle, state.disconnect());
+    }
+}
 
+/// What outlives a compositor connection, carried from one to the next.
+struct Retained {
+    cfg: Config,
+    overrides: Overrides,
+    monitor: Monitor,
+    ui_state: UiState,
+    alerts: AlertEngine,
+    preview: Option<Preview>,
+    #[cfg(feature = "rhai-scripting")]
+    rhai_engine: crate::scripting::rhai_engine::RhaiEngine,
     #[cfg(feature = "python-scripting")]
-    let python_engine = {
-        let mut engine = crate::scripting::python_engine::PythonEngine::new();
-        for module in &cfg.modules {
-            if let Module::Python { file, .. } = module {
-                let resolved = cfg.resolve_script_path(file);
-                let resolved_str = resolved.to_string_lossy().to_string();
-                if let Err(e) = engine.load_file(&resolved_str) {
+    python_engine: crate::scripting::python_engine::PythonEngine,
+}
+
+impl Retained {
+    fn new(
+        cfg: Config,
+        overrides: Overrides,
+        monitor: Monitor,
+        preview_errors: Option<Vec<String>>,
+    ) -> Self {
+        // Initialize scripting engines
+        #[allow(unused_mut)]
+        let mut script_errors: Vec<String> = Vec::new();
+        #[cfg(feature = "rhai-scripting")]
+        let rhai_engine = load_rhai(&cfg, &mut script_errors);
+        #[cfg(feature = "python-scripting")]
+        let python_engine = load_python(&cfg, &mut script_errors);
+        let preview = match preview_errors {
+            Some(mut errors) => {
+                errors.extend(script_errors);
+                Some(Preview::new(&cfg, errors))
+            }
+            None => {
+                for e in &script_errors {
                     eprintln!("rustky: {e}");
                 }
+                None
             }
+        };
+
+        let alerts = AlertEngine::new(&cfg.alerts);
+
+        Retained {
+            cfg,
+            overrides,
+            monitor,
+            ui_state: UiState::load(),
+            alerts,
+            preview,
+            #[cfg(feature = "rhai-scripting")]
+            rhai_engine,
+            #[cfg(feature = "python-scripting")]
+            python_engine,
         }
-        if let Some(ref hook_path) = cfg.general.on_draw_python {
-            let resolved = cfg.resolve_script_path(hook_path);
-            let resolved_str = resolved.to_string_lossy().to_string();
-            if let Err(e) = engine.load_on_draw_hook(&resolved_str) {
-                eprintln!("rustky: {e}");
+    }
+}
+
+/// A new connection, with the globals rustky can't do without.
+struct Connected {
+    conn: Connection,
+    globals: GlobalList,
+    event_queue: EventQueue<RustkyState>,
+    compositor: CompositorState,
+    layer_shell: LayerShell,
+    shm: Shm,
+}
+
+impl Connected {
+    fn open() -> Result<Self, String> {
+        let conn = Connection::connect_to_env()
+            .map_err(|e| format!("failed to connect to Wayland: {e}"))?;
+        let (globals, event_queue) =
+            registry_queue_init(&conn).map_err(|e| format!("failed to init registry: {e}"))?;
+        let qh = event_queue.handle();
+        let compositor =
+            CompositorState::bind(&globals, &qh).map_err(|_| "wl_compositor not available")?;
+        let layer_shell =
+            LayerShell::bind(&globals, &qh).map_err(|_| "wlr_layer_shell not available")?;
+        let shm = Shm::bind(&globals, &qh).map_err(|_| "wl_shm not available")?;
+        Ok(Connected {
+            conn,
+            globals,
+            event_queue,
+            compositor,
+            layer_shell,
+            shm,
+        })
+    }
+}
+
+/// Starts a session on the compositor, with a surface per window; the first
+/// reuses `renderer` if given.
+fn connect(
+    loop_handle: &LoopHandle<'static, RustkyState>,
+    connected: Connected,
+    retained: Retained,
+    mut renderer: Option<Renderer>,
+) -> RustkyState {
+    let Connected {
+        conn,
+        globals,
+        event_queue,
+        compositor,
+        layer_shell,
+        shm,
+    } = connected;
+    let Retained {
+        cfg,
+        overrides,
+        monitor,
+        ui_state,
+        alerts,
+        preview,
+        #[cfg(feature = "rhai-scripting")]
+        rhai_engine,
+        #[cfg(feature = "python-scripting")]
+        python_engine,
+    } = retained;
+    let qh: QueueHandle<RustkyState> = event_queue.handle();
+    let seat_state = SeatState::new(&globals, &qh);
+
+    // Fractional scales need both protocols: the compositor's preferred
+    // scale, and a viewport showing the larger buffer at the surface size.
+    let fractional_scale = match (
+        globals.bind::<WpFractionalScaleManagerV1, _, _>(&qh, 1..=1, ()),
+        globals.bind::<WpViewporter, _, _>(&qh, 1..=1, ()),
+    ) {
+        (Ok(manager), Ok(viewporter)) => Some((manager, viewporter)),
+        _ => None,
+    };
+    #[cfg(not(feature = "gpu"))]
+    if cfg.general.render_backend == RenderBackend::Gpu {
+        eprintln!("rustky: render_backend = \"gpu\" needs the `gpu` feature; using shm");
+    }
+
+    let mut widgets = Vec::new();
+    for (index, (window, _)) in cfg.windows().into_iter().enumerate() {
+        let output = window
+            .output
+            .as_deref()
+            .and_then(|wanted| find_output(&conn, &globals, wanted));
+        let surface = compositor.create_surface(&qh);
+        let layer = layer_shell.create_layer_surface(
+            &qh,
+            surface,
+            Layer::Bottom,
+            Some("rustky".to_string()),
+            output.as_ref(),
+        );
+
+        layer.set_anchor(layer_anchor(window));
+        let (width, height) = window.scaled_size(cfg.general.scale);
+        layer.set_size(width, height);
+        layer.set_exclusive_zone(
+            window
+                .exclusive_zone(cfg.general.scale)
+                .unwrap_or_else(|e| {
+                    eprintln!("rustky: {e}");
+                    -1
+                }),
+        );
+        layer.set_keyboard_interactivity(if window.wants_keyboard() || preview.is_some() {
+            KeyboardInteractivity::OnDemand
+        } else {
+            KeyboardInteractivity::None
+        });
+        let margin = match ui_state.position(index) {
+            Some(position) if window.remember_position => {
+                window.anchor.place(window.margins(), position)
             }
-        }
-        engine
+            _ => window.margins(),
+        };
+        layer.set_margin(margin.top, margin.right, margin.bottom, margin.left);
+        set_click_through(&compositor, &layer, window.click_through);
+
+        // The fractional scale object needn't be kept; its events arrive
+        // anyway, carrying the surface they're for.
+        let viewport = fractional_scale.as_ref().map(|(manager, viewporter)| {
+            let surface = layer.wl_surface();
+            manager.get_fractional_scale(surface, &qh, surface.clone());
+            viewporter.get_viewport(surface, &qh, ())
+        });
+        layer.commit();
+
+        #[cfg(feature = "gpu")]
+        let gpu = match cfg.general.render_backend {
+            RenderBackend::Gpu => {
+                let display = conn.backend().display_ptr().cast();
+                let surface = layer.wl_surface().id().as_ptr().cast();
+                // Both outlive it: `disconnect` drops the widgets before
+                // the connection, and `Widget::gpu` drops before `layer`
+                match unsafe { GpuSurface::new(display, surface, width, height) } {
+                    Ok(gpu) => Some(gpu),
+                    Err(e) => {
+                        eprintln!("rustky: gpu renderer: {e}; using shm");
+                        None
+                    }
+                }
+            }
+            RenderBackend::Shm => None,
+        };
+
+        // Sized again on configure; a bar spanning the output has no width
+        // until then
+        let pool = SlotPool::new((width * height * 4).max(4) as usize, &shm)
+            .expect("failed to create shm pool");
+
+        widgets.push(Widget {
+            index,
+            layer,
+            pool,
+            renderer: renderer
+                .take()
+                .unwrap_or_else(|| Renderer::new(&cfg.general, &cfg.theme)),
+            width: 0,
+            height: 0,
+            output_scale: 1.0,
+            viewport,
+            last_frame: None,
+            rendered: None,
+            lines: Vec::new(),
+            #[cfg(feature = "gpu")]
+            gpu,
+            frame_pending: false,
+            redraw_wanted: false,
+            scroll_wanted: false,
+            animating: false,
+            animator: Animator::new(&cfg.general),
+            animation_timer: None,
+            configured: false,
+            buffer: None,
+            scroll_offset: 0.0,
+            content_height: 0.0,
+            pager: Pager::new(),
+            margin,
+            drag: None,
+            pending_press: None,
+            scroll_timer: None,
+            scroll_target: None,
+            // Hidden from the start, until first hovered
+            slide: if window.auto_hide.is_some() { 1.0 } else { 0.0 },
+            slide_timer: None,
+            hide_shift: Margins::default(),
+            hidden: false,
+            brightness_scroll: 0.0,
+            hit_regions: Vec::new(),
+            filter: Filter::default(),
+        });
+    }
+
+    // Only watch the clipboard when something displays it.
+    let clipboard_manager = if cfg
+        .all_modules()
+        .iter()
+        .any(|m| matches!(m, Module::Clipboard { .. }))
+    {
+        globals
+            .bind::<ZwlrDataControlManagerV1, _, _>(&qh, 1..=2, ())
+            .map_err(|_| {
+                eprintln!("rustky: compositor lacks zwlr_data_control_v1; clipboard disabled")
+            })
+            .ok()
+    } else {
+        None
+    };
+
+    // Same for the focused window: the manager announces every toplevel.
+    if cfg
+        .all_modules()
+        .iter()
+        .any(|m| matches!(m, Module::WindowTitle { .. }))
+        && globals
+            .bind::<ZwlrForeignToplevelManagerV1, _, _>(&qh, 1..=3, ())
+            .is_err()
+    {
+        eprintln!(
+            "rustky: compositor lacks zwlr_foreign_toplevel_manager_v1; window title disabled"
+        );
+    }
+
+    // Likewise for idle notifications, only wanted to pause updates
+    let idle_notifier = if cfg.general.idle_pause_s > 0 {
+        globals
+            .bind::<ExtIdleNotifierV1, _, _>(&qh, 1..=1, ())
+            .map_err(|_| {
+                eprintln!("rustky: compositor lacks ext_idle_notify_v1; drawing won't pause")
+            })
+            .ok()
+    } else {
+        None
     };
 
+    let wayland_source = loop_handle
+        .insert_source(WaylandSource::new(conn, event_queue), |_, _, _| Ok(0usize))
+        .expect("failed to insert wayland source");
+
     let mut state = RustkyState {
         registry: RegistryState::new(&globals),
         output: OutputState::new(&globals, &qh),
         seat_state,
         shm,
-        pool,
-        layer,
+        compositor,
+        widgets,
         cfg,
-        renderer,
+        overrides,
         monitor,
-        width: 0,
-        height: 0,
-        configured: false,
-        buffer: None,
-        scroll_offset: 0.0,
-        content_height: 0.0,
+        qh: qh.clone(),
+        loop_handle: loop_handle.clone(),
+        wayland_source,
+        ui_state,
+        modifiers: Modifiers::default(),
+        keyboard_focus: None,
+        touch: None,
+        refreshed_at: None,
+        alerts,
+        clipboard_manager,
+        idle_notifier,
+        idle_notification: None,
+        idle: false,
+        clipboard_device: None,
+        clipboard_offer: None,
+        preview,
         #[cfg(feature = "rhai-scripting")]
         rhai_engine,
         #[cfg(feature = "python-scripting")]
         python_engine,
     };
 
-    let mut event_loop: EventLoop<RustkyState> =
-        EventLoop::try_new().expect("failed to create event loop");
+    let seats: Vec<_> = state.seat_state.seats().collect();
+    for seat in seats {
+        state.watch_clipboard(&qh, &seat);
+        state.watch_idle(&qh, &seat);
+    }
 
-    let loop_handle = event_loop.handle();
+    state
+}
 
-    let wayland_source = WaylandSource::new(conn, event_queue);
-    loop_handle
-        .insert_source(wayland_source, |_, _, _| Ok(0usize))
-        .expect("failed to insert wayland source");
+/// First wait before reconnecting, doubled after each failed attempt.
+const RECONNECT_DELAY: Duration = Duration::from_millis(500);
+const RECONNECT_DELAY_MAX: Duration = Duration::from_secs(30);
 
-    event_loop
-        .dispatch(Some(Duration::from_millis(100)), &mut state)
-        .expect("initial dispatch failed");
+/// Waits for the compositor to come back, e.g. after a crash or a session
+/// restart, and opens a new session on it.
+fn reconnect(loop_handle: &LoopHandle<'static, RustkyState>, retained: Retained) -> RustkyState {
+    let mut delay = RECONNECT_DELAY;
+    loop {
+        std::thread::sleep(delay);
+        match Connected::open() {
+            Ok(connected) => {
+                eprintln!("rustky: reconnected to the compositor");
+                return connect(loop_handle, connected, retained, None);
+            }
+            Err(e) => {
+                delay = (delay * 2).min(RECONNECT_DELAY_MAX);
+                eprintln!("rustky: {e}; retrying in {:.1}s", delay.as_secs_f32());
+            }
+        }
+    }
+}
 
-    let update_ms = state.cfg.general.update_interval_ms;
-    loop_handle
-        .insert_source(
-            Timer::from_duration(Duration::from_millis(update_ms)),
-            |_, _, state: &mut RustkyState| {
-                state.draw();
-                TimeoutAction::ToDuration(Duration::from_millis(
-                    state.cfg.general.update_interval_ms,
-                ))
-            },
-        )
-        .expect("failed to insert timer");
+/// Layer-shell edges of a window anchor; a centred axis touches neither.
+fn layer_anchor(window: &Window) -> Anchor {
+    let (x, y) = window.anchor.directions();
+    let mut edges = Anchor::empty();
+    if window.spans_output() {
+        edges |= Anchor::LEFT | Anchor::RIGHT;
+    }
+    match x {
+        1 => edges |= Anchor::LEFT,
+        -1 => edges |= Anchor::RIGHT,
+        _ => {}
+    }
+    match y {
+        1 => edges |= Anchor::TOP,
+        -1 => edges |= Anchor::BOTTOM,
+        _ => {}
+    }
+    edges
+}
+
+/// An empty input region passes all pointer input through; none restores
+/// the default of the whole surface. Takes effect on the next commit.
+fn set_click_through(compositor: &CompositorState, layer: &LayerSurface, on: bool) {
+    let surface = layer.wl_surface();
+    if !on {
+        surface.set_input_region(None);
+        return;
+    }
+    match Region::new(compositor) {
+        // The region's contents are copied, so it can go right away
+        Ok(region) => surface.set_input_region(Some(region.wl_region())),
+        Err(e) => eprintln!("rustky: click_through: {e}"),
+    }
+}
 
-    state.draw();
+/// Outputs as known before the main state exists, on an event queue of
+/// their own.
+struct OutputProbe {
+    registry: RegistryState,
+    output: OutputState,
+}
 
-    loop {
-        event_loop
-            .dispatch(Some(Duration::from_millis(16)), &mut state)
-            .expect("event loop error");
+/// The output `wanted` names, as its connector name (e.g. "DP-2") or part of
+/// its description (make and model), so the layer surface can open there.
+fn find_output(
+    conn: &Connection,
+    globals: &GlobalList,
+    wanted: &str,
+) -> Option<wl_output::WlOutput> {
+    let mut queue = conn.new_event_queue();
+    let mut probe = OutputProbe {
+        registry: RegistryState::new(globals),
+        output: OutputState::new(globals, &queue.handle()),
+    };
+    // Names and descriptions arrive after the outputs are bound, through
+    // xdg-output on older compositors
+    for _ in 0..2 {
+        if let Err(e) = queue.roundtrip(&mut probe) {
+            eprintln!("rustky: listing outputs: {e}");
+            return None;
+        }
+    }
+    let infos: Vec<_> = probe
+        .output
+        .outputs()
+        .filter_map(|output| Some((probe.output.info(&output)?, output)))
+        .collect();
+    let found = infos.iter().find(|(info, _)| {
+        info.name.as_deref() == Some(wanted)
+            || info
+                .description
+                .as_deref()
+                .is_some_and(|d| d.contains(wanted))
+    });
+    if found.is_none() {
+        let names: Vec<&str> = infos
+            .iter()
+            .filter_map(|(info, _)| info.name.as_deref())
+            .collect();
+        eprintln!(
+            "rustky: no output matches '{wanted}' (have {}); letting the compositor choose",
+            names.join(", ")
+        );
+    }
+    found.map(|(_, output)| output.clone())
+}
+
+/// Copies skia's RGBA (premultiplied) pixels into a wl_shm ARGB8888
+/// buffer, BGRA in little-endian bytes: one word at a time with R and B
+/// swapped, which the compiler vectorizes.
+fn copy_to_argb8888(rgba: &[u8], argb: &mut [u8]) {
+    for (src, dst) in rgba.chunks_exact(4).zip(argb.chunks_exact_mut(4)) {
+        let pixel = u32::from_le_bytes([src[0], src[1], src[2], src[3]]);
+        let swapped = (pixel & 0xff00_ff00) | ((pixel & 0xff) << 16) | ((pixel >> 16) & 0xff);
+        dst.copy_from_slice(&swapped.to_le_bytes());
+    }
+}
+
+fn until_next_second() -> Duration {
+    let now = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .unwrap_or_default();
+    // A few ms late so the draw lands inside the new second.
+    Duration::from_nanos(1_000_000_000 - u64::from(now.subsec_nanos())) + Duration::from_millis(5)
+}
+
+#[cfg(feature = "rhai-scripting")]
+fn load_rhai(cfg: &Config, errors: &mut Vec<String>) -> crate::scripting::rhai_engine::RhaiEngine {
+    let mut engine = crate::scripting::rhai_engine::RhaiEngine::new(Format::new(&cfg.general));
+    for module in cfg.all_modules() {
+        if let Module::Rhai {
+            code,
+            file,
+            function,
+        } = module
+        {
+            if let Some(code_str) = code {
+                let key = format!("inline:{function}");
+                if let Err(e) = engine.compile_inline(&key, code_str) {
+                    errors.push(e);
+                }
+            }
+            if let Some(file_path) = file {
+                let resolved = cfg.resolve_script_path(file_path);
+                let resolved_str = resolved.to_string_lossy().to_string();
+                if let Err(e) = engine.compile_file(&resolved_str) {
+                    errors.push(e);
+                }
+            }
+        }
+    }
+    if let Some(ref hook_path) = cfg.general.on_draw_rhai {
+        let resolved = cfg.resolve_script_path(hook_path);
+        let resolved_str = resolved.to_string_lossy().to_string();
+        if let Err(e) = engine.load_on_draw_hook(&resolved_str) {
+            errors.push(e);
+        }
+    }
+    engine
+}
+
+#[cfg(feature = "python-scripting")]
+fn load_python(
+    cfg: &Config,
+    errors: &mut Vec<String>,
+) -> crate::scripting::python_engine::PythonEngine {
+    let mut engine = crate::scripting::python_engine::PythonEngine::new(Format::new(&cfg.general));
+    for module in cfg.all_modules() {
+        if let Module::Python { file, .. } = module {
+            let resolved = cfg.resolve_script_path(file);
+            let resolved_str = resolved.to_string_lossy().to_string();
+            if let Err(e) = engine.load_file(&resolved_str) {
+                errors.push(e);
+            }
+        }
+    }
+    if let Some(ref hook_path) = cfg.general.on_draw_python {
+        let resolved = cfg.resolve_script_path(hook_path);
+        let resolved_str = resolved.to_string_lossy().to_string();
+        if let Err(e) = engine.load_on_draw_hook(&resolved_str) {
+            errors.push(e);
+        }
     }
+    engine
 }
 
 struct RustkyState {
@@ -190,195 +765,1912 @@ struct RustkyState {
     output: OutputState,
     seat_state: SeatState,
     shm: Shm,
-    pool: SlotPool,
-    layer: LayerSurface,
+    compositor: CompositorState,
+    /// One per `Config::windows` entry, in order.
+    widgets: Vec<Widget>,
     cfg: Config,
-    renderer: Renderer,
+    /// Reapplied over `cfg` on every reload.
+    overrides: Overrides,
     monitor: Monitor,
+    qh: QueueHandle<RustkyState>,
+    loop_handle: LoopHandle<'static, RustkyState>,
+    /// The connection, removed from the loop when it's lost.
+    wayland_source: RegistrationToken,
+    ui_state: UiState,
+    modifiers: Modifiers,
+    /// Surface of the widget keys go to.
+    keyboard_focus: Option<wl_surface::WlSurface>,
+    /// The finger scrolling a widget; others are ignored.
+    touch: Option<TouchScroll>,
+    /// When the monitor last sampled, so extra redraws don't resample.
+    refreshed_at: Option<Instant>,
+    alerts: AlertEngine,
+    clipboard_manager: Option<ZwlrDataControlManagerV1>,
+    idle_notifier: Option<ExtIdleNotifierV1>,
+    idle_notification: Option<ExtIdleNotificationV1>,
+    /// No input for `idle_pause_s`: updates are paused.
+    idle: bool,
+    clipboard_device: Option<ZwlrDataControlDeviceV1>,
+    /// Offer for the current selection, destroyed when replaced.
+    clipboard_offer: Option<ZwlrDataControlOfferV1>,
+    preview: Option<Preview>,
+    #[cfg(feature = "rhai-scripting")]
+    rhai_engine: crate::scripting::rhai_engine::RhaiEngine,
+    #[cfg(feature = "python-scripting")]
+    python_engine: crate::scripting::python_engine::PythonEngine,
+}
+
+/// A layer surface of one window entry and what is drawn on it.
+struct Widget {
+    /// Position in `Config::windows`.
+    index: usize,
+    /// Presents frames in place of wl_shm buffers with `render_backend = "gpu"`.
+    /// Ahead of `layer`, as fields drop in order and it draws to that surface.
+    #[cfg(feature = "gpu")]
+    gpu: Option<GpuSurface>,
+    layer: LayerSurface,
+    pool: SlotPool,
+    renderer: Renderer,
+    /// Surface size in logical pixels.
     width: u32,
     height: u32,
+    /// Scale of the outputs the surface is on; buffers are drawn this many
+    /// times larger than the surface. Fractional with a viewport, else whole.
+    output_scale: f32,
+    /// Shows fractionally scaled buffers at the surface size, when the
+    /// compositor supports fractional scaling.
+    viewport: Option<WpViewport>,
+    /// Inputs of the frame on screen; redraws producing the same are skipped.
+    last_frame: Option<Frame>,
+    /// The frame on screen as the renderer drew it, redrawn in place.
+    rendered: Option<Surface>,
+    /// Module output as last collected, before filtering and easing.
+    lines: Vec<StyledLine>,
+    /// A frame callback is outstanding: the last commit isn't shown yet.
+    frame_pending: bool,
+    /// Something called `draw()` while a frame was pending.
+    redraw_wanted: bool,
+    /// Likewise `draw_scrolled()`.
+    scroll_wanted: bool,
+    /// The frame on screen has marquee lines, moved on each frame callback.
+    animating: bool,
+    animator: Animator,
+    /// Draws eased values at `animation_fps` while an animation runs.
+    animation_timer: Option<RegistrationToken>,
     configured: bool,
     buffer: Option<Buffer>,
     scroll_offset: f32,
     content_height: f32,
-    #[cfg(feature = "rhai-scripting")]
-    rhai_engine: crate::scripting::rhai_engine::RhaiEngine,
-    #[cfg(feature = "python-scripting")]
-    python_engine: crate::scripting::python_engine::PythonEngine,
+    pager: Pager,
+    /// Current layer-surface margins, as placed or dragged to.
+    margin: Margins,
+    /// How far `auto_hide` has slid the surface out: 0 shown, 1 hidden.
+    slide: f32,
+    /// Waits out an auto-hide delay, then moves `slide` along.
+    slide_timer: Option<RegistrationToken>,
+    /// Taken off `margin` to pull the surface past its edge while hidden.
+    hide_shift: Margins,
+    /// Unmapped through SIGUSR2 or `rustky msg visibility`.
+    hidden: bool,
+    drag: Option<Drag>,
+    /// A left press made before the widget had keyboard focus, and so
+    /// before the modifiers were known: held back until they tell a drag
+    /// from a click.
+    pending_press: Option<(f64, f64)>,
+    /// Moves `scroll_offset` on after a touch flick or towards
+    /// `scroll_target`.
+    scroll_timer: Option<RegistrationToken>,
+    /// Where a smooth scroll is heading.
+    scroll_target: Option<f32>,
+    /// Scroll amount over a brightness line not yet turned into a step.
+    brightness_scroll: f64,
+    /// Clickable lines of the last frame.
+    hit_regions: Vec<HitRegion>,
+    filter: Filter,
+}
+
+/// A line with a built-in action or click actions configured, as a
+/// rectangle of the surface in unscrolled coordinates: full width when
+/// lines are stacked, full height on a horizontal bar.
+struct HitRegion {
+    top: f32,
+    bottom: f32,
+    left: f32,
+    right: f32,
+    action: Option<LineAction>,
+    clicks: Clicks,
+}
+
+/// Everything a frame's pixels are drawn from.
+#[derive(Clone, PartialEq)]
+struct Frame {
+    lines: Vec<StyledLine>,
+    tabs: Vec<(String, bool)>,
+    size: (u32, u32),
+    scroll_offset: f32,
+    scale: f32,
+    high_contrast: bool,
+    grid: bool,
+    marquee_time: f32,
+    /// Each line's left and right edge on a horizontal bar; empty when
+    /// lines are stacked.
+    cells: Vec<(f32, f32)>,
+}
+
+/// A finger on a widget, scrolling it once it moves far enough.
+struct TouchScroll {
+    id: i32,
+    surface: wl_surface::WlSurface,
+    start: (f64, f64),
+    last_y: f64,
+    /// Timestamp of the last motion, in milliseconds.
+    last_time: u32,
+    /// Smoothed, in surface pixels per millisecond.
+    velocity: f64,
+    scrolling: bool,
+}
+
+/// Pointer position and margins when a drag-to-move started.
+#[derive(Clone, Copy)]
+struct Drag {
+    pointer: (f64, f64),
+    margin: Margins,
 }
 
 impl RustkyState {
-    fn draw(&mut self) {
-        if !self.configured {
+    /// Ends a session whose connection was lost, keeping what the next one
+    /// needs. The widgets go before the connection, as GPU surfaces use it.
+    fn disconnect(self) -> Retained {
+        let RustkyState {
+            widgets,
+            cfg,
+            overrides,
+            monitor,
+            loop_handle,
+            wayland_source,
+            ui_state,
+            alerts,
+            preview,
+            #[cfg(feature = "rhai-scripting")]
+            rhai_engine,
+            #[cfg(feature = "python-scripting")]
+            python_engine,
+            ..
+        } = self;
+        drop(widgets);
+        loop_handle.remove(wayland_source);
+        Retained {
+            cfg,
+            overrides,
+            monitor,
+            ui_state,
+            alerts,
+            preview,
+            #[cfg(feature = "rhai-scripting")]
+            rhai_engine,
+            #[cfg(feature = "python-scripting")]
+            python_engine,
+        }
+    }
+
+    fn handle_ipc(&mut self, line: &str) -> Result<(), String> {
+        match IpcCommand::parse(line)? {
+            IpcCommand::Page(cmd) => {
+                for widget in &mut self.widgets {
+                    if widget.own_modules(&self.cfg).is_empty() {
+                        widget.pager.apply(&self.cfg.pages, &cmd)?;
+                        widget.scroll_offset = 0.0;
+                        widget.scroll_target = None;
+                    }
+                }
+            }
+            IpcCommand::Scale(cmd) => {
+                let scale = match cmd {
+                    ScaleCommand::Set(scale) => scale,
+                    ScaleCommand::Adjust(step) => self.cfg.general.scale + step,
+                };
+                self.cfg.general.scale = scale.clamp(MIN_SCALE, MAX_SCALE);
+                for widget in &mut self.widgets {
+                    widget.apply_scale(self.cfg.general.scale);
+                    let window = widget.window(&self.cfg);
+                    let (width, height) = window.scaled_size(self.cfg.general.scale);
+                    widget.layer.set_size(width, height);
+                    widget.commit();
+                }
+            }
+            IpcCommand::HighContrast(on) => {
+                self.cfg.general.high_contrast = on.unwrap_or(!self.cfg.general.high_contrast);
+                for widget in &mut self.widgets {
+                    widget.renderer.high_contrast = self.cfg.general.high_contrast;
+                }
+            }
+            IpcCommand::Visibility(on) => self.set_visible(on),
+        }
+        self.draw_all();
+        Ok(())
+    }
+
+    /// The widget drawn on `surface`.
+    fn widget_of(&self, surface: &wl_surface::WlSurface) -> Option<usize> {
+        self.widgets
+            .iter()
+            .position(|widget| widget.layer.wl_surface() == surface)
+    }
+
+    /// Handles a pointer button press on widget `i`; `button` uses i3bar
+    /// numbering (1 = left, 2 = middle, 3 = right).
+    fn click(&mut self, i: usize, button: u32, x: f64, y: f64) {
+        let Some(region) = self.widgets[i].region_at(&self.cfg, x, y) else {
+            return;
+        };
+        let (click, action) = (region.clicks.get(button).cloned(), region.action.clone());
+        if let Some(click) = click {
+            self.run_click(&click, button);
+            self.draw_all();
+            return;
+        }
+        let Some(action) = action else {
+            return;
+        };
+        match action {
+            LineAction::ToggleGroup {
+                title,
+                default_collapsed,
+            } => {
+                if button != 1 {
+                    return;
+                }
+                self.ui_state.toggle_group(&title, default_collapsed);
+            }
+            LineAction::I3blocksClick {
+                command,
+                name,
+                instance,
+            } => {
+                let env = exec::i3blocks_env(&name, instance.as_deref(), button, x, y);
+                exec::i3blocks_click(command, instance, env);
+            }
+            LineAction::ToggleClipboard => {
+                if button != 1 {
+                    return;
+                }
+                clipboard::toggle_revealed();
+            }
+            LineAction::ToggleDnd(daemon) => {
+                if button != 1 {
+                    return;
+                }
+                dnd::toggle(daemon);
+            }
+            LineAction::MarkNotificationsRead => {
+                if button != 1 {
+                    return;
+                }
+                notifications::mark_read();
+            }
+            LineAction::ToggleMute => {
+                if button != 1 {
+                    return;
+                }
+                volume::toggle_mute();
+            }
+            LineAction::Pomodoro(durations) => {
+                let timer = &mut self.ui_state.pomodoro;
+                match button {
+                    1 => timer.toggle(&durations, chrono::Utc::now().timestamp()),
+                    2 => timer.skip(&durations),
+                    3 => timer.reset(),
+                    _ => return,
+                }
+                self.ui_state.save();
+            }
+            LineAction::AdjustBrightness { .. } => return,
+        }
+        // Actions change state any window may show
+        self.draw_all();
+    }
+
+    /// Runs an `on_click`-style action for `button`.
+    fn run_click(&mut self, click: &ClickAction, button: u32) {
+        let result = match click {
+            ClickAction::Command(command) => {
+                exec::spawn(command, &[("BUTTON", button.to_string())])
+            }
+            ClickAction::Script { file, function } => {
+                let path = self.cfg.resolve_script_path(file);
+                let path = path.to_string_lossy();
+                match path.ends_with(".py") {
+                    #[cfg(feature = "python-scripting")]
+                    true => self.python_engine.call_click(&path, function, button),
+                    #[cfg(feature = "rhai-scripting")]
+                    false => self.rhai_engine.call_click(&path, function, button),
+                    #[allow(unreachable_patterns)]
+                    _ => Err(format!("{path}: {function}: scripting is not built in")),
+                }
+            }
+        };
+        if let Err(e) = result {
+            eprintln!("rustky: {e}");
+        }
+    }
+
+    /// `--preview`: re-reads the config and scripts after a save. Errors are
+    /// kept for display and the previous config stays active.
+    fn reload(&mut self) {
+        let mut errors = Vec::new();
+        let loaded = Config::try_load().map(|mut cfg| {
+            self.overrides.apply(&mut cfg);
+            cfg
+        });
+        match loaded {
+            Ok(cfg) if cfg.windows().len() != self.cfg.windows().len() => {
+                errors.push("adding or removing windows needs a restart".to_string());
+            }
+            Ok(cfg) => {
+                let windows = cfg.windows();
+                for widget in &mut self.widgets {
+                    let (window, _) = windows[widget.index];
+                    let size = window.scaled_size(cfg.general.scale);
+                    let old = widget.window(&self.cfg);
+                    if size != old.scaled_size(self.cfg.general.scale) {
+                        widget.layer.set_size(size.0, size.1);
+                    }
+                    // A remembered position stays as far from the anchored
+                    // edges as it was
+                    let old_anchor = widget.window(&self.cfg).anchor;
+                    widget.margin = if window.remember_position {
+                        let position = old_anchor.position(widget.margin);
+                        window.anchor.place(window.margins(), position)
+                    } else {
+                        window.margins()
+                    };
+                    widget.layer.set_anchor(layer_anchor(window));
+                    match window.exclusive_zone(cfg.general.scale) {
+                        Ok(zone) => widget.layer.set_exclusive_zone(zone),
+                        Err(e) => errors.push(e),
+                    }
+                    widget.apply_slide(&cfg);
+                    set_click_through(&self.compositor, &widget.layer, window.click_through);
+                    widget.commit();
+                    widget.renderer = Renderer::new(&cfg.general, &cfg.theme);
+                    widget.animator = Animator::new(&cfg.general);
+                    // The theme or fonts may have changed under the same lines
+                    widget.last_frame = None;
+                    widget.apply_scale(cfg.general.scale);
+                }
+                self.monitor.format = Format::new(&cfg.general);
+                self.alerts = AlertEngine::new(&cfg.alerts);
+                self.cfg = cfg;
+            }
+            Err(e) => errors.push(e),
+        }
+        #[cfg(feature = "rhai-scripting")]
+        {
+            self.rhai_engine = load_rhai(&self.cfg, &mut errors);
+        }
+        #[cfg(feature = "python-scripting")]
+        {
+            self.python_engine = load_python(&self.cfg, &mut errors);
+        }
+        if let Some(preview) = self.preview.as_mut() {
+            preview.errors = errors;
+            preview.watch(&self.cfg);
+        }
+        self.draw_all();
+    }
+
+    fn set_output_scale(&mut self, i: usize, scale: f32) {
+        let widget = &mut self.widgets[i];
+        if scale == widget.output_scale {
+            return;
+        }
+        widget.output_scale = scale;
+        widget.apply_scale(self.cfg.general.scale);
+        widget.reserve_buffer();
+        self.draw(i);
+    }
+
+    /// Starts a drag from, or clicks at, a press held back until the
+    /// modifiers were known.
+    fn settle_press(&mut self, i: usize) {
+        let Some(position) = self.widgets[i].pending_press.take() else {
+            return;
+        };
+        if self.drag_modifier_held(i) {
+            let widget = &mut self.widgets[i];
+            widget.drag = Some(Drag {
+                pointer: position,
+                margin: widget.margin,
+            });
+        } else {
+            self.click(i, 1, position.0, position.1);
+        }
+    }
+
+    fn drag_modifier_held(&self, i: usize) -> bool {
+        match self.widgets[i].window(&self.cfg).drag_modifier {
+            Some(DragModifier::Alt) => self.modifiers.alt,
+            Some(DragModifier::Ctrl) => self.modifiers.ctrl,
+            Some(DragModifier::Shift) => self.modifiers.shift,
+            Some(DragModifier::Super) => self.modifiers.logo,
+            None => false,
+        }
+    }
+
+    /// Starts receiving selection changes for `seat` (the first seat only).
+    fn watch_clipboard(&mut self, qh: &QueueHandle<Self>, seat: &wl_seat::WlSeat) {
+        if let (Some(manager), None) = (&self.clipboard_manager, &self.clipboard_device) {
+            self.clipboard_device = Some(manager.get_data_device(seat, qh, ()));
+        }
+    }
+
+    /// Starts hearing when `seat` (the first seat only) goes idle and back.
+    fn watch_idle(&mut self, qh: &QueueHandle<Self>, seat: &wl_seat::WlSeat) {
+        if let (Some(notifier), None) = (&self.idle_notifier, &self.idle_notification) {
+            let timeout = self.cfg.general.idle_pause_s.saturating_mul(1000);
+            let timeout = timeout.min(u32::MAX as u64) as u32;
+            self.idle_notification = Some(notifier.get_idle_notification(timeout, seat, qh, ()));
+        }
+    }
+
+    fn clipboard_selection(&mut self, offer: Option<ZwlrDataControlOfferV1>) {
+        if let Some(old) = self.clipboard_offer.take() {
+            old.destroy();
+        }
+        let Some(offer) = offer else {
+            clipboard::set(Selection::Empty);
+            return;
+        };
+        let mimes = offer
+            .data::<Mutex<Vec<String>>>()
+            .map(|m| m.lock().unwrap_or_else(|e| e.into_inner()).clone())
+            .unwrap_or_default();
+
+        if clipboard::is_sensitive(&mimes) {
+            clipboard::set(Selection::Sensitive);
+        } else if let Some(mime) = clipboard::text_mime(&mimes).map(String::from) {
+            match clipboard::pipe() {
+                Ok((read, write)) => {
+                    // The fd is duplicated when the request is queued, so the
+                    // write end can be closed right away.
+                    offer.receive(mime, write.as_fd());
+                    drop(write);
+                    clipboard::read_text(read, mimes);
+                }
+                Err(e) => eprintln!("rustky: clipboard pipe: {e}"),
+            }
+        } else {
+            let mime = mimes.first().cloned().unwrap_or_default();
+            clipboard::set(Selection::Other { mime });
+        }
+        self.clipboard_offer = Some(offer);
+    }
+
+    /// Samples the monitor and evaluates alerts, unless that happened less
+    /// than an update interval ago. Runs on the update timer even while no
+    /// frames are drawn, so alerts keep firing behind other windows.
+    fn refresh(&mut self) {
+        // Redraws between updates (clicks, second ticks) reuse the last sample.
+        let interval = Duration::from_millis(self.cfg.general.update_interval_ms);
+        let slack = Duration::from_millis(50);
+        if self
+            .refreshed_at
+            .is_some_and(|t| t.elapsed() + slack < interval)
+        {
+            return;
+        }
+        self.monitor.refresh();
+        self.refreshed_at = Some(Instant::now());
+
+        if !self.cfg.alerts.is_empty() {
+            let snapshot = self.monitor.snapshot();
+            self.alerts.evaluate(&self.cfg.alerts, &snapshot);
+        }
+    }
+
+    /// `draw()` for every widget.
+    fn draw_all(&mut self) {
+        for i in 0..self.widgets.len() {
+            self.draw(i);
+        }
+    }
+
+    /// Draws widget `i` now, or when the compositor next asks for a frame
+    /// if the last one hasn't been shown yet, so nothing is drawn while the
+    /// surface is hidden or the compositor is throttling it.
+    fn draw(&mut self, i: usize) {
+        let widget = &mut self.widgets[i];
+        if widget.frame_pending {
+            widget.redraw_wanted = true;
+            return;
+        }
+        self.draw_now(i);
+    }
+
+    /// Like `draw()`, but lays out the lines collected last again instead
+    /// of running the modules, for scrolling that ticks at the frame rate.
+    fn draw_scrolled(&mut self, i: usize) {
+        let widget = &mut self.widgets[i];
+        if widget.frame_pending {
+            widget.scroll_wanted = true;
+            return;
+        }
+        self.present_lines(i);
+    }
+
+    fn draw_now(&mut self, i: usize) {
+        if !self.widgets[i].configured {
+            return;
+        }
+        let (w, h) = self.widgets[i].buffer_size();
+        if w == 0 || h == 0 {
+            return;
+        }
+
+        self.refresh();
+
+        #[cfg(any(feature = "rhai-scripting", feature = "python-scripting"))]
+        let ctx = self.monitor.snapshot();
+
+        let mut lines: Vec<StyledLine> = self
+            .preview
+            .as_ref()
+            .map(Preview::error_lines)
+            .unwrap_or_default();
+
+        let widget = &mut self.widgets[i];
+        let mut visible = Vec::new();
+        let modules = widget.modules(&self.cfg);
+        groups::flatten(modules, &self.ui_state, 0, &mut visible);
+
+        // Sections nest; every line records how many boxes it sits in
+        let mut section_depth = 0;
+        for entry in visible {
+            let entry = match entry {
+                Visible::Header(mut header) => {
+                    if let Element::SectionStart(_) = header.element {
+                        section_depth += 1;
+                    }
+                    header.section_depth = section_depth;
+                    if let Element::SectionEnd = header.element {
+                        section_depth -= 1;
+                    }
+                    lines.push(*header);
+                    continue;
+                }
+                Visible::Module(entry) => entry,
+            };
+            let module = &entry.module;
+            let mut module_lines = match module {
+                #[cfg(feature = "rhai-scripting")]
+                Module::Rhai {
+                    code,
+                    file,
+                    function,
+                } => {
+                    if let Some(code_str) = code {
+                        let _ = code_str;
+                        let key = format!("inline:{function}");
+                        self.rhai_engine
+                            .execute_module(&key, function, &ctx, false)
+                    } else if let Some(file_path) = file {
+                        let resolved = self.cfg.resolve_script_path(file_path);
+                        let resolved_str = resolved.to_string_lossy().to_string();
+                        self.rhai_engine
+                            .execute_module(&resolved_str, function, &ctx, true)
+                    } else {
+                        vec![StyledLine::plain(
+                            "[rhai: no code or file specified]".into(),
+                        )]
+                    }
+                }
+                #[cfg(feature = "python-scripting")]
+                Module::Python { file, function } => {
+                    let resolved = self.cfg.resolve_script_path(file);
+                    let resolved_str = resolved.to_string_lossy().to_string();
+                    self.python_engine
+                        .execute_module(&resolved_str, function, &ctx)
+                }
+                Module::Pomodoro {
+                    label,
+                    work_mins,
+                    short_break_mins,
+                    long_break_mins,
+                    long_break_every,
+                } => {
+                    let durations = Durations::from_mins(
+                        *work_mins,
+                        *short_break_mins,
+                        *long_break_mins,
+                        *long_break_every,
+                    );
+                    let now = chrono::Utc::now().timestamp();
+                    if self.ui_state.pomodoro.advance(&durations, now) {
+                        self.ui_state.save();
+                    }
+                    self.ui_state.pomodoro.lines(label, &durations, now)
+                }
+                other => self.monitor.collect(other),
+            };
+            if !self.cfg.icons.is_empty()
+                && let Some(icon) = self.cfg.icons.get(module.type_name())
+                && let Some(first) = module_lines.first_mut()
+                && first.style.icon.is_none()
+            {
+                first.style.icon = Some(icon.clone());
+            }
+            for line in &mut module_lines {
+                line.section_depth = section_depth;
+                line.style.clicks.or(&entry.clicks);
+                line.align = entry.align.unwrap_or_default();
+            }
+            lines.extend(module_lines);
+        }
+
+        #[cfg(feature = "rhai-scripting")]
+        let lines = if self.cfg.general.on_draw_rhai.is_some() {
+            self.rhai_engine.run_on_draw_hook(lines, &ctx)
+        } else {
+            lines
+        };
+
+        #[cfg(feature = "python-scripting")]
+        let lines = if self.cfg.general.on_draw_python.is_some() {
+            self.python_engine.run_on_draw_hook(lines, &ctx)
+        } else {
+            lines
+        };
+
+        widget.lines = lines;
+        self.present_lines(i);
+    }
+
+    /// Lays out and presents the lines widget `i` last collected, at its
+    /// current scroll offset.
+    fn present_lines(&mut self, i: usize) {
+        let widget = &mut self.widgets[i];
+        widget.scroll_wanted = false;
+        if !widget.configured {
+            return;
+        }
+        // Everything below is in buffer pixels; scroll offset, content
+        // height and hit regions are kept in surface (logical) pixels
+        let scale = widget.output_scale;
+        let (w, h) = widget.buffer_size();
+        if w == 0 || h == 0 {
+            return;
+        }
+        let lines = widget.filter.apply(widget.lines.clone());
+
+        // A bar has no tab strip and nothing to scroll
+        let horizontal = widget.window(&self.cfg).orientation == Orientation::Horizontal;
+        let (tabs, cells) = if horizontal {
+            (Vec::new(), widget.renderer.row_cells(&lines, w as f32))
+        } else {
+            (widget.tabs(&self.cfg), Vec::new())
+        };
+
+        // Track content height and clamp scroll offset
+        widget.content_height = if horizontal {
+            widget.height as f32
+        } else {
+            (widget.renderer.content_height(&lines, w) + widget.renderer.tab_strip_height(&tabs))
+                / scale
+        };
+        let max_scroll = (widget.content_height - widget.height as f32).max(0.0);
+        widget.scroll_offset = widget.scroll_offset.clamp(0.0, max_scroll);
+
+        let strip_h = widget.renderer.tab_strip_height(&tabs);
+        let rects: Vec<_> = if horizontal {
+            let full_height = (0.0, f32::INFINITY);
+            cells
+                .iter()
+                .map(|&(left, right)| (full_height, (left / scale, right / scale)))
+                .collect()
+        } else {
+            let full_width = (0.0, f32::INFINITY);
+            let surface_y = |y: f32| (y + strip_h) / scale;
+            let extents = widget.renderer.line_extents(&lines, w).into_iter();
+            extents
+                .map(|(top, bottom)| ((surface_y(top), surface_y(bottom)), full_width))
+                .collect()
+        };
+        widget.hit_regions = rects
+            .into_iter()
+            .zip(&lines)
+            .filter(|(_, line)| line.action.is_some() || !line.style.clicks.is_empty())
+            .map(|(((top, bottom), (left, right)), line)| HitRegion {
+                top,
+                bottom,
+                left,
+                right,
+                action: line.action.clone(),
+                clicks: line.style.clicks.clone(),
+            })
+            .collect();
+
+        widget.renderer.grid = self.preview.as_ref().is_some_and(|p| p.grid);
+        let frame = Frame {
+            lines: widget.animator.update(lines, &widget.renderer),
+            tabs,
+            size: (w, h),
+            scroll_offset: widget.scroll_offset,
+            scale: widget.renderer.scale,
+            high_contrast: widget.renderer.high_contrast,
+            grid: widget.renderer.grid,
+            marquee_time: widget.renderer.marquee_time,
+            cells,
+        };
+        widget.present(frame, &self.qh);
+        self.start_animation(i);
+    }
+
+    /// Ticks at `animation_fps` until widget `i`'s animator settles.
+    fn start_animation(&mut self, i: usize) {
+        let widget = &mut self.widgets[i];
+        if widget.animation_timer.is_some() || !widget.animator.is_active() {
+            return;
+        }
+        let fps = self.cfg.general.animation_fps.max(1);
+        let interval = Duration::from_secs_f32(1.0 / fps as f32);
+        // Found by surface each tick, as widgets close
+        let surface = widget.layer.wl_surface().clone();
+        widget.animation_timer = self
+            .loop_handle
+            .insert_source(
+                Timer::from_duration(interval),
+                move |_, _, state: &mut RustkyState| {
+                    let Some(i) = state.widget_of(&surface) else {
+                        return TimeoutAction::Drop;
+                    };
+                    let widget = &mut state.widgets[i];
+                    if widget.animation_frame(&state.qh) {
+                        TimeoutAction::ToDuration(interval)
+                    } else {
+                        widget.animation_timer = None;
+                        TimeoutAction::Drop
+                    }
+                },
+            )
+            .map_err(|e| eprintln!("rustky: failed to insert animation timer: {}", e.error))
+            .ok();
+    }
+
+    /// Keeps widget `i` scrolling after a flick at `velocity` (pixels per
+    /// millisecond), slowing down until it stops or reaches an end.
+    fn start_fling(&mut self, i: usize, mut velocity: f64) {
+        self.stop_scrolling(i);
+        let widget = &mut self.widgets[i];
+        let surface = widget.layer.wl_surface().clone();
+        widget.scroll_timer = self
+            .loop_handle
+            .insert_source(
+                Timer::from_duration(SCROLL_TICK),
+                move |_, _, state: &mut RustkyState| {
+                    let Some(i) = state.widget_of(&surface) else {
+                        return TimeoutAction::Drop;
+                    };
+                    let step = velocity * SCROLL_TICK.as_millis() as f64;
+                    velocity *= FLING_FRICTION;
+                    let moved = state.widgets[i].scroll_by(step as f32);
+                    if moved {
+                        state.draw_scrolled(i);
+                    }
+                    if moved && velocity.abs() > FLING_MIN_VELOCITY {
+                        TimeoutAction::ToDuration(SCROLL_TICK)
+                    } else {
+                        state.widgets[i].scroll_timer = None;
+                        TimeoutAction::Drop
+                    }
+                },
+            )
+            .map_err(|e| eprintln!("rustky: failed to insert scroll timer: {}", e.error))
+            .ok();
+    }
+
+    /// Shows or hides every widget; `None` hides them unless all are
+    /// hidden already.
+    fn set_visible(&mut self, on: Option<bool>) {
+        let visible = on.unwrap_or_else(|| self.widgets.iter().all(|widget| widget.hidden));
+        for widget in &mut self.widgets {
+            widget.set_hidden(!visible);
+        }
+    }
+
+    /// Slides widget `i` out (`hide`) or back in after the configured
+    /// delay, replacing any slide waiting or under way.
+    fn auto_hide(&mut self, i: usize, hide: bool) {
+        let widget = &mut self.widgets[i];
+        let Some(auto_hide) = &widget.window(&self.cfg).auto_hide else {
+            return;
+        };
+        if let Some(token) = widget.slide_timer.take() {
+            self.loop_handle.remove(token);
+        }
+        let target = if hide { 1.0 } else { 0.0 };
+        if widget.slide == target {
+            return;
+        }
+        let delay = if hide {
+            auto_hide.hide_delay_ms
+        } else {
+            auto_hide.reveal_delay_ms
+        };
+        let step = SLIDE_TICK.as_millis() as f32 / auto_hide.slide_ms.max(1) as f32;
+        let surface = widget.layer.wl_surface().clone();
+        widget.slide_timer = self
+            .loop_handle
+            .insert_source(
+                Timer::from_duration(Duration::from_millis(delay)),
+                move |_, _, state: &mut RustkyState| {
+                    let Some(i) = state.widget_of(&surface) else {
+                        return TimeoutAction::Drop;
+                    };
+                    let widget = &mut state.widgets[i];
+                    widget.slide = if hide {
+                        (widget.slide + step).min(target)
+                    } else {
+                        (widget.slide - step).max(target)
+                    };
+                    widget.apply_slide(&state.cfg);
+                    widget.commit();
+                    if widget.slide == target {
+                        widget.slide_timer = None;
+                        TimeoutAction::Drop
+                    } else {
+                        TimeoutAction::ToDuration(SLIDE_TICK)
+                    }
+                },
+            )
+            .map_err(|e| eprintln!("rustky: failed to insert auto-hide timer: {}", e.error))
+            .ok();
+    }
+
+    /// Scrolls widget `i` by a wheel notch or key press worth of `delta`
+    /// pixels: eased with `smooth_scroll`, otherwise at once.
+    fn scroll_stepped(&mut self, i: usize, delta: f32) {
+        if self.cfg.general.smooth_scroll {
+            self.scroll_smoothly(i, delta);
+        } else if self.widgets[i].scroll_by(delta) {
+            self.draw_scrolled(i);
+        }
+    }
+
+    /// Eases widget `i` by `delta` pixels, on top of any smooth scroll
+    /// still under way.
+    fn scroll_smoothly(&mut self, i: usize, delta: f32) {
+        if self.widgets[i].scroll_target.is_none() {
+            // Cuts a flick short
+            self.stop_scrolling(i);
+        }
+        let widget = &mut self.widgets[i];
+        let from = widget.scroll_target.unwrap_or(widget.scroll_offset);
+        widget.scroll_target = Some((from + delta).clamp(0.0, widget.max_scroll()));
+        if widget.scroll_timer.is_some() {
+            return;
+        }
+        let surface = widget.layer.wl_surface().clone();
+        widget.scroll_timer = self
+            .loop_handle
+            .insert_source(Timer::immediate(), move |_, _, state: &mut RustkyState| {
+                let Some(i) = state.widget_of(&surface) else {
+                    return TimeoutAction::Drop;
+                };
+                let widget = &mut state.widgets[i];
+                let Some(target) = widget.scroll_target else {
+                    widget.scroll_timer = None;
+                    return TimeoutAction::Drop;
+                };
+                // The content may have shrunk since
+                let remaining = target.min(widget.max_scroll()) - widget.scroll_offset;
+                let done = remaining.abs() < 0.5;
+                let step = if done {
+                    remaining
+                } else {
+                    remaining * SMOOTH_SCROLL_EASE
+                };
+                widget.scroll_by(step);
+                if done {
+                    widget.scroll_timer = None;
+                    widget.scroll_target = None;
+                }
+                state.draw_scrolled(i);
+                if done {
+                    TimeoutAction::Drop
+                } else {
+                    TimeoutAction::ToDuration(SCROLL_TICK)
+                }
+            })
+            .map_err(|e| eprintln!("rustky: failed to insert scroll timer: {}", e.error))
+            .ok();
+    }
+
+    /// Ends a flick or smooth scroll where it is.
+    fn stop_scrolling(&mut self, i: usize) {
+        let widget = &mut self.widgets[i];
+        widget.scroll_target = None;
+        if let Some(token) = widget.scroll_timer.take() {
+            self.loop_handle.remove(token);
+        }
+    }
+}
+
+impl Widget {
+    /// Modules the window entry lists itself; empty when it shows the
+    /// top-level ones.
+    fn own_modules<'a>(&self, cfg: &'a Config) -> &'a [ModuleEntry] {
+        cfg.windows()[self.index].1
+    }
+
+    fn window<'a>(&self, cfg: &'a Config) -> &'a Window {
+        cfg.windows()[self.index].0
+    }
+
+    /// Modules to display: the window's own, or the current page's.
+    fn modules<'a>(&self, cfg: &'a Config) -> &'a [ModuleEntry] {
+        match self.own_modules(cfg) {
+            [] => self.pager.modules(cfg),
+            modules => modules,
+        }
+    }
+
+    /// Labels for the tab strip; windows with modules of their own have no
+    /// pages.
+    fn tabs(&self, cfg: &Config) -> Vec<(String, bool)> {
+        if self.own_modules(cfg).is_empty() {
+            self.pager.tabs(&cfg.pages)
+        } else {
+            Vec::new()
+        }
+    }
+
+    /// The clickable line under surface-local `x`, `y`, if any.
+    fn region_at(&self, cfg: &Config, x: f64, y: f64) -> Option<&HitRegion> {
+        let tabs = self.tabs(cfg);
+        if (y as f32) < self.renderer.tab_strip_height(&tabs) / self.output_scale {
+            return None;
+        }
+        let (x, content_y) = (x as f32, y as f32 + self.scroll_offset);
+        self.hit_regions.iter().find(|region| {
+            (region.top..region.bottom).contains(&content_y)
+                && (region.left..region.right).contains(&x)
+        })
+    }
+
+    /// Rows of the buffer, as whole-pixel top and bottom, that differ
+    /// between `last` and `next` because some of their lines changed; `None`
+    /// when more than that did and the whole frame needs drawing.
+    fn damage(&self, last: &Frame, next: &Frame) -> Option<Vec<(f32, f32)>> {
+        // Bars are short enough to redraw whole
+        if !next.cells.is_empty() {
+            return None;
+        }
+        if last.lines.len() != next.lines.len()
+            || last.tabs != next.tabs
+            || last.size != next.size
+            || last.scroll_offset != next.scroll_offset
+            || last.scale != next.scale
+            || last.high_contrast != next.high_contrast
+            || last.grid != next.grid
+        {
+            return None;
+        }
+        let width = next.size.0;
+        let extents = self.renderer.line_extents(&next.lines, width);
+        // Lines moving (one grew or shrank) means repainting everything
+        // below them anyway
+        if self.renderer.line_extents(&last.lines, width) != extents {
+            return None;
+        }
+        let strip_h = self.renderer.tab_strip_height(&next.tabs);
+        let scroll = next.scroll_offset * self.output_scale;
+        let height = next.size.1 as f32;
+        let mut strips: Vec<(f32, f32)> = Vec::new();
+        let marquee_moved = last.marquee_time != next.marquee_time;
+        for ((old, new), (top, bottom)) in last.lines.iter().zip(&next.lines).zip(extents) {
+            if old == new && !(marquee_moved && new.style.overflow == Overflow::Marquee) {
+                continue;
+            }
+            // A section's title line carries the box drawn behind the
+            // lines after it
+            if matches!(old.element, Element::SectionStart(_))
+                || matches!(new.element, Element::SectionStart(_))
+            {
+                return None;
+            }
+            let top = (top + strip_h - scroll).floor().max(strip_h.floor());
+            let bottom = (bottom + strip_h - scroll).ceil().min(height);
+            if top >= bottom {
+                continue;
+            }
+            match strips.last_mut() {
+                Some(last) if last.1 >= top => last.1 = bottom,
+                _ => strips.push((top, bottom)),
+            }
+        }
+        Some(strips)
+    }
+
+    /// Renders at the configured scale times the output's, so text stays
+    /// the same logical size but sharp on HiDPI outputs.
+    fn apply_scale(&mut self, scale: f32) {
+        self.renderer.set_scale(scale * self.output_scale);
+    }
+
+    /// Size of the buffer drawn for the surface at the output scale.
+    fn buffer_size(&self) -> (u32, u32) {
+        let scaled = |size: u32| (size as f32 * self.output_scale).round() as u32;
+        (scaled(self.width), scaled(self.height))
+    }
+
+    /// Grows the shm pool to hold a full-size buffer.
+    fn reserve_buffer(&mut self) {
+        let (w, h) = self.buffer_size();
+        let needed = (w * h * 4) as usize;
+        if self.pool.len() < needed {
+            self.pool.resize(needed).expect("failed to resize pool");
+        }
+    }
+
+    fn max_scroll(&self) -> f32 {
+        (self.content_height - self.height as f32).max(0.0)
+    }
+
+    /// Scrolls by `delta` surface pixels within the content; false when
+    /// already at that end.
+    fn scroll_by(&mut self, delta: f32) -> bool {
+        let offset = (self.scroll_offset + delta).clamp(0.0, self.max_scroll());
+        let moved = offset != self.scroll_offset;
+        self.scroll_offset = offset;
+        moved
+    }
+
+    fn set_margin(&self) {
+        let (m, s) = (self.margin, self.hide_shift);
+        self.layer.set_margin(
+            m.top - s.top,
+            m.right - s.right,
+            m.bottom - s.bottom,
+            m.left - s.left,
+        );
+    }
+
+    /// Commits pending surface state, unless hidden: committing would map
+    /// the surface again.
+    fn commit(&self) {
+        if !self.hidden {
+            self.layer.commit();
+        }
+    }
+
+    /// Unmaps the surface, or maps it again; the compositor then sends a
+    /// fresh configure, handled as on startup.
+    fn set_hidden(&mut self, hidden: bool) {
+        if hidden == self.hidden {
+            return;
+        }
+        self.hidden = hidden;
+        let surface = self.layer.wl_surface();
+        if hidden {
+            surface.attach(None, 0, 0);
+            self.configured = false;
+            // No callback comes for a frame that's never shown
+            self.frame_pending = false;
+            self.last_frame = None;
+        }
+        surface.commit();
+    }
+
+    /// Pulls the surface past the edge it's anchored to by `slide` of its
+    /// extent, short of the `peek` pixels left to hover. A window at a
+    /// corner slides up or down; a centred one stays put.
+    fn apply_slide(&mut self, cfg: &Config) {
+        let window = self.window(cfg);
+        let mut shift = Margins::default();
+        if let Some(auto_hide) = &window.auto_hide {
+            let distance =
+                |extent: u32| (extent.saturating_sub(auto_hide.peek) as f32 * self.slide) as i32;
+            match window.anchor.directions() {
+                (_, 1) => shift.top = distance(self.height),
+                (_, -1) => shift.bottom = distance(self.height),
+                (1, _) => shift.left = distance(self.width),
+                (-1, _) => shift.right = distance(self.width),
+                _ => {}
+            }
+        } else {
+            self.slide = 0.0;
+        }
+        self.hide_shift = shift;
+        self.set_margin();
+    }
+
+    /// Moves the surface so the point grabbed at drag start follows the
+    /// pointer. Pointer positions are surface-local, so the distance the
+    /// surface already moved is added back in.
+    fn drag_to(&mut self, anchor: WindowAnchor, drag: Drag, pointer: (f64, f64)) {
+        // Margins from the right or bottom edge grow as the surface moves
+        // left or up
+        let (sx, sy) = anchor.directions();
+        let start = anchor.position(drag.margin);
+        let now = anchor.position(self.margin);
+        let dx = (pointer.0 - drag.pointer.0) as i32 + sx * (now.0 - start.0);
+        let dy = (pointer.1 - drag.pointer.1) as i32 + sy * (now.1 - start.1);
+        let position = ((start.0 + sx * dx).max(0), (start.1 + sy * dy).max(0));
+        let margin = anchor.place(self.margin, position);
+        if margin != self.margin {
+            self.margin = margin;
+            self.set_margin();
+            self.layer.wl_surface().commit();
+        }
+    }
+
+    /// Draws the next step of easing values; false once they've settled.
+    fn animation_frame(&mut self, qh: &QueueHandle<RustkyState>) -> bool {
+        let active = self.animator.is_active();
+        if self.frame_pending {
+            // The compositor shows frames slower than this; the settled one
+            // is drawn when it asks for the next
+            if !active {
+                self.redraw_wanted = true;
+            }
+            return active;
+        }
+        if let Some(last) = &self.last_frame {
+            let frame = Frame {
+                lines: self.animator.lines(Instant::now(), &self.renderer),
+                ..last.clone()
+            };
+            self.present(frame, qh);
+        }
+        active
+    }
+
+    /// Advances marquee lines to `time` (milliseconds, from a frame
+    /// callback), redrawing just their rows of the frame on screen.
+    fn animate(&mut self, time: u32, qh: &QueueHandle<RustkyState>) {
+        let Some(last) = &self.last_frame else {
+            return;
+        };
+        self.renderer.marquee_time = time as f32 / 1000.0;
+        let frame = Frame {
+            marquee_time: self.renderer.marquee_time,
+            ..last.clone()
+        };
+        self.present(frame, qh);
+    }
+
+    /// Draws `frame` and commits it, unless it's the one on screen.
+    fn present(&mut self, frame: Frame, qh: &QueueHandle<RustkyState>) {
+        // Nothing may be attached while hidden, until configured again
+        if !self.configured || self.last_frame.as_ref() == Some(&frame) {
+            return;
+        }
+        let scale = self.output_scale;
+        let (w, h) = frame.size;
+        // Re-render only the rows whose lines changed, when that's all
+        let strips = self
+            .last_frame
+            .as_ref()
+            .and_then(|last| self.damage(last, &frame));
+        let rendered = match self.rendered.take() {
+            Some(surface) if (surface.width(), surface.height()) == (w as i32, h as i32) => surface,
+            _ => Surface::new_raster_n32_premul(w as i32, h as i32)
+                .expect("failed to create surface"),
+        };
+        let rendered = self.rendered.insert(rendered);
+        let scroll_offset = self.scroll_offset * scale;
+        match &strips {
+            None if !frame.cells.is_empty() => {
+                self.renderer
+                    .render_row(rendered, &frame.lines, &frame.cells)
+            }
+            Some(strips) => self.renderer.render_strips(
+                rendered,
+                &frame.lines,
+                scroll_offset,
+                &frame.tabs,
+                strips,
+            ),
+            None => self
+                .renderer
+                .render_into(rendered, &frame.lines, scroll_offset, &frame.tabs),
+        }
+
+        // Set with the buffer it applies to, so no commit pairs the new
+        // scale with a buffer drawn for the old one
+        match &self.viewport {
+            Some(viewport) => viewport.set_destination(self.width as i32, self.height as i32),
+            None => self
+                .layer
+                .wl_surface()
+                .set_buffer_scale(self.output_scale as i32),
+        }
+        let surface = self.layer.wl_surface();
+
+        #[cfg(feature = "gpu")]
+        if let Some(gpu) = self.gpu.as_mut() {
+            // Presenting commits the surface
+            surface.frame(qh, surface.clone());
+            self.frame_pending = true;
+            gpu.present(rendered.pixels(), w, h, strips.as_deref());
+            self.animating = self.renderer.has_marquee(&frame.lines, w);
+            self.last_frame = Some(frame);
+            return;
+        }
+
+        let (buffer, canvas) = self
+            .pool
+            .create_buffer(w as i32, h as i32, (w * 4) as i32, wl_shm::Format::Argb8888)
+            .expect("failed to create buffer");
+        copy_to_argb8888(rendered.pixels(), canvas);
+
+        surface.attach(Some(buffer.wl_buffer()), 0, 0);
+        match strips {
+            Some(strips) => {
+                for (top, bottom) in strips {
+                    surface.damage_buffer(0, top as i32, w as i32, (bottom - top) as i32);
+                }
+            }
+            None => surface.damage_buffer(0, 0, w as i32, h as i32),
+        }
+        surface.frame(qh, surface.clone());
+        self.frame_pending = true;
+        surface.commit();
+
+        self.buffer = Some(buffer);
+        self.animating = self.renderer.has_marquee(&frame.lines, w);
+        self.last_frame = Some(frame);
+    }
+}
+
+// --- Seat + Pointer handling for scroll and clicks ---
+
+const BTN_LEFT: u32 = 0x110;
+const BTN_RIGHT: u32 = 0x111;
+const BTN_MIDDLE: u32 = 0x112;
+
+/// How far a finger moves before a touch scrolls instead of tapping.
+const TOUCH_SLOP: f64 = 8.0;
+/// Interval of smooth and kinetic scrolling.
+const SCROLL_TICK: Duration = Duration::from_millis(16);
+/// Fraction of its speed a flick keeps per tick.
+const FLING_FRICTION: f64 = 0.95;
+/// Speed below which a flick stops, in pixels per millisecond.
+const FLING_MIN_VELOCITY: f64 = 0.02;
+/// Interval of auto-hide slides.
+const SLIDE_TICK: Duration = Duration::from_millis(16);
+/// Rows a wheel notch scrolls.
+const WHEEL_STEP_ROWS: f32 = 3.0;
+/// Fraction of the remaining distance a smooth scroll covers per tick.
+const SMOOTH_SCROLL_EASE: f32 = 0.3;
+
+impl SeatHandler for RustkyState {
+    fn seat_state(&mut self) -> &mut SeatState {
+        &mut self.seat_state
+    }
+
+    fn new_seat(
+        &mut self,
+        _conn: &Connection,
+        qh: &QueueHandle<Self>,
+        seat: wl_seat::WlSeat,
+    ) {
+        self.watch_clipboard(qh, &seat);
+        self.watch_idle(qh, &seat);
+    }
+
+    fn new_capability(
+        &mut self,
+        _conn: &Connection,
+        qh: &QueueHandle<Self>,
+        seat: wl_seat::WlSeat,
+        capability: Capability,
+    ) {
+        if capability == Capability::Pointer {
+            let _ = self.seat_state.get_pointer(qh, &seat);
+        }
+        if capability == Capability::Touch {
+            let _ = self.seat_state.get_touch(qh, &seat);
+        }
+        let wants_keyboard = self
+            .cfg
+            .windows()
+            .iter()
+            .any(|(window, _)| window.wants_keyboard())
+            || self.preview.is_some()
+            || self
+                .cfg
+                .all_modules()
+                .iter()
+                .any(|m| matches!(m, Module::KeyboardLayout { .. }));
+        if capability == Capability::Keyboard && wants_keyboard {
+            let _ = self.seat_state.get_keyboard(qh, &seat, None);
+        }
+    }
+
+    fn remove_capability(
+        &mut self,
+        _conn: &Connection,
+        _qh: &QueueHandle<Self>,
+        _seat: wl_seat::WlSeat,
+        _capability: Capability,
+    ) {
+    }
+
+    fn remove_seat(
+        &mut self,
+        _conn: &Connection,
+        _qh: &QueueHandle<Self>,
+        _seat: wl_seat::WlSeat,
+    ) {
+    }
+}
+
+impl PointerHandler for RustkyState {
+    fn pointer_frame(
+        &mut self,
+        _conn: &Connection,
+        _qh: &QueueHandle<Self>,
+        _pointer: &wl_pointer::WlPointer,
+        events: &[PointerEvent],
+    ) {
+        for event in events {
+            let Some(i) = self.widget_of(&event.surface) else {
+                continue;
+            };
+            let focused = self.keyboard_focus.as_ref() == Some(&event.surface);
+            match event.kind {
+                // The focus a press brings delivers the modifiers after it
+                PointerEventKind::Press {
+                    button: BTN_LEFT, ..
+                } if !focused && self.widgets[i].window(&self.cfg).drag_modifier.is_some() => {
+                    self.widgets[i].pending_press = Some(event.position);
+                    continue;
+                }
+                PointerEventKind::Press {
+                    button: BTN_LEFT, ..
+                } if self.drag_modifier_held(i) => {
+                    let widget = &mut self.widgets[i];
+                    widget.drag = Some(Drag {
+                        pointer: event.position,
+                        margin: widget.margin,
+                    });
+                    continue;
+                }
+                PointerEventKind::Enter { .. } => {
+                    self.auto_hide(i, false);
+                    continue;
+                }
+                PointerEventKind::Leave { .. } if self.widgets[i].drag.is_none() => {
+                    self.auto_hide(i, true);
+                    continue;
+                }
+                PointerEventKind::Motion { .. } => {
+                    let widget = &mut self.widgets[i];
+                    if let Some(drag) = widget.drag {
+                        let anchor = widget.window(&self.cfg).anchor;
+                        widget.drag_to(anchor, drag, event.position);
+                    }
+                    continue;
+                }
+                // No modifiers came before the release: a plain click
+                PointerEventKind::Release {
+                    button: BTN_LEFT, ..
+                } if self.widgets[i].pending_press.is_some() => {
+                    if let Some(position) = self.widgets[i].pending_press.take() {
+                        self.click(i, 1, position.0, position.1);
+                    }
+                    continue;
+                }
+                PointerEventKind::Release {
+                    button: BTN_LEFT, ..
+                } if self.widgets[i].drag.is_some() => {
+                    let widget = &mut self.widgets[i];
+                    widget.drag = None;
+                    let window = widget.window(&self.cfg);
+                    if window.remember_position {
+                        let (x, y) = window.anchor.position(widget.margin);
+                        self.ui_state.set_position(widget.index, x, y);
+                    }
+                    continue;
+                }
+                _ => {}
+            }
+            if let PointerEventKind::Press { button, .. } = event.kind {
+                let button = match button {
+                    BTN_LEFT => 1,
+                    BTN_MIDDLE => 2,
+                    BTN_RIGHT => 3,
+                    _ => 0,
+                };
+                if button > 0 {
+                    self.click(i, button, event.position.0, event.position.1);
+                }
+            }
+            if let PointerEventKind::Axis {
+                vertical, horizontal: _, ..
+            } = &event.kind
+            {
+                let widget = &mut self.widgets[i];
+                let action = widget
+                    .region_at(&self.cfg, event.position.0, event.position.1)
+                    .and_then(|region| region.action.clone());
+                if let Some(LineAction::AdjustBrightness { device, step }) = action {
+                    // Wheels send one discrete step per notch; touchpads
+                    // only continuous amounts, about 10 per notch.
+                    widget.brightness_scroll += if vertical.discrete != 0 {
+                        vertical.discrete as f64 * 10.0
+                    } else {
+                        vertical.absolute
+                    };
+                    let notches = (widget.brightness_scroll / 10.0).trunc();
+                    if notches != 0.0 {
+                        widget.brightness_scroll -= notches * 10.0;
+                        // Scrolling down (positive) dims.
+                        brightness::adjust(&device, -notches * step);
+                        self.draw_all();
+                    }
+                    continue;
+                }
+                // Wheels scroll by rows per notch, touchpads by the
+                // distance moved. The seat is bound below version 8, so
+                // high-resolution wheels arrive as whole notches too.
+                let general = &self.cfg.general;
+                if vertical.discrete != 0 {
+                    let row = general.font_size * general.line_height_factor * general.scale;
+                    let delta = vertical.discrete as f32 * WHEEL_STEP_ROWS * row;
+                    self.scroll_stepped(i, delta * general.scroll_speed);
+                    continue;
+                }
+                let scroll_amount = vertical.absolute as f32 * general.scroll_speed;
+                if scroll_amount.abs() > 0.01 {
+                    // Keeps a smooth scroll from pulling back
+                    self.stop_scrolling(i);
+                    if self.widgets[i].scroll_by(scroll_amount) {
+                        self.draw_scrolled(i);
+                    }
+                }
+            }
+        }
+    }
+}
+
+// --- Touch scrolling ---
+
+impl TouchHandler for RustkyState {
+    fn down(
+        &mut self,
+        _conn: &Connection,
+        _qh: &QueueHandle<Self>,
+        _touch: &wl_touch::WlTouch,
+        _serial: u32,
+        time: u32,
+        surface: wl_surface::WlSurface,
+        id: i32,
+        position: (f64, f64),
+    ) {
+        if self.touch.is_some() {
+            return;
+        }
+        let Some(i) = self.widget_of(&surface) else {
+            return;
+        };
+        // Touching a flicked widget catches it
+        self.stop_scrolling(i);
+        self.touch = Some(TouchScroll {
+            id,
+            surface,
+            start: position,
+            last_y: position.1,
+            last_time: time,
+            velocity: 0.0,
+            scrolling: false,
+        });
+    }
+
+    fn motion(
+        &mut self,
+        _conn: &Connection,
+        _qh: &QueueHandle<Self>,
+        _touch: &wl_touch::WlTouch,
+        time: u32,
+        id: i32,
+        position: (f64, f64),
+    ) {
+        let Some(touch) = self.touch.as_mut().filter(|touch| touch.id == id) else {
             return;
+        };
+        // Not `widget_of`, which would borrow all of `self`
+        let mut widgets = self.widgets.iter();
+        let Some(i) = widgets.position(|w| w.layer.wl_surface() == &touch.surface) else {
+            return;
+        };
+        if !touch.scrolling {
+            let (dx, dy) = (position.0 - touch.start.0, position.1 - touch.start.1);
+            if dx.hypot(dy) < TOUCH_SLOP {
+                return;
+            }
+            touch.scrolling = true;
         }
-        let w = self.width;
-        let h = self.height;
-        if w == 0 || h == 0 {
+        // Content follows the finger, so moving down scrolls up
+        let delta = touch.last_y - position.1;
+        let elapsed = time.wrapping_sub(touch.last_time);
+        if elapsed > 0 {
+            let velocity = delta / elapsed as f64;
+            touch.velocity = 0.6 * velocity + 0.4 * touch.velocity;
+        }
+        touch.last_y = position.1;
+        touch.last_time = time;
+        if self.widgets[i].scroll_by(delta as f32) {
+            self.draw_scrolled(i);
+        }
+    }
+
+    fn up(
+        &mut self,
+        _conn: &Connection,
+        _qh: &QueueHandle<Self>,
+        _touch: &wl_touch::WlTouch,
+        _serial: u32,
+        time: u32,
+        id: i32,
+    ) {
+        let Some(touch) = self.touch.take_if(|touch| touch.id == id) else {
+            return;
+        };
+        let Some(i) = self.widget_of(&touch.surface) else {
             return;
+        };
+        if !touch.scrolling {
+            // A tap is a left click
+            self.click(i, 1, touch.start.0, touch.start.1);
+        } else if time.wrapping_sub(touch.last_time) < 100
+            && touch.velocity.abs() > FLING_MIN_VELOCITY
+        {
+            // Lifted while still moving, rather than after coming to rest
+            self.start_fling(i, touch.velocity);
         }
+    }
 
-        self.monitor.refresh();
+    fn shape(
+        &mut self,
+        _conn: &Connection,
+        _qh: &QueueHandle<Self>,
+        _touch: &wl_touch::WlTouch,
+        _id: i32,
+        _major: f64,
+        _minor: f64,
+    ) {
+    }
 
-        #[cfg(any(feature = "rhai-scripting", feature = "python-scripting"))]
-        let ctx = self.monitor.snapshot();
+    fn orientation(
+        &mut self,
+        _conn: &Connection,
+        _qh: &QueueHandle<Self>,
+        _touch: &wl_touch::WlTouch,
+        _id: i32,
+        _orientation: f64,
+    ) {
+    }
 
-        let mut lines: Vec<StyledLine> = Vec::new();
+    fn cancel(&mut self, _conn: &Connection, _qh: &QueueHandle<Self>, _touch: &wl_touch::WlTouch) {
+        self.touch = None;
+    }
+}
 
-        for module in &self.cfg.modules {
-            let module_lines = match module {
-                #[cfg(feature = "rhai-scripting")]
-                Module::Rhai {
-                    code,
-                    file,
-                    function,
-                } => {
-                    if let Some(code_str) = code {
-                        let _ = code_str;
-                        let key = format!("inline:{function}");
-                        self.rhai_engine
-                            .execute_module(&key, function, &ctx, false)
-                    } else if let Some(file_path) = file {
-                        let resolved = self.cfg.resolve_script_path(file_path);
-                        let resolved_str = resolved.to_string_lossy().to_string();
-                        self.rhai_engine
-                            .execute_module(&resolved_str, function, &ctx, true)
-                    } else {
-                        vec![StyledLine::plain(
-                            "[rhai: no code or file specified]".into(),
-                        )]
-                    }
-                }
-                #[cfg(feature = "python-scripting")]
-                Module::Python { file, function } => {
-                    let resolved = self.cfg.resolve_script_path(file);
-                    let resolved_str = resolved.to_string_lossy().to_string();
-                    self.python_engine
-                        .execute_module(&resolved_str, function, &ctx)
-                }
-                other => self.monitor.collect(other),
-            };
-            lines.extend(module_lines);
+// --- Keyboard handling for search ---
+
+impl KeyboardHandler for RustkyState {
+    fn enter(
+        &mut self,
+        _conn: &Connection,
+        _qh: &QueueHandle<Self>,
+        _keyboard: &wl_keyboard::WlKeyboard,
+        surface: &wl_surface::WlSurface,
+        _serial: u32,
+        _raw: &[u32],
+        _keysyms: &[Keysym],
+    ) {
+        self.keyboard_focus = Some(surface.clone());
+    }
+
+    fn leave(
+        &mut self,
+        _conn: &Connection,
+        _qh: &QueueHandle<Self>,
+        _keyboard: &wl_keyboard::WlKeyboard,
+        surface: &wl_surface::WlSurface,
+        _serial: u32,
+    ) {
+        if self.keyboard_focus.as_ref() == Some(surface) {
+            self.keyboard_focus = None;
         }
+    }
 
-        #[cfg(feature = "rhai-scripting")]
-        let lines = if self.cfg.general.on_draw_rhai.is_some() {
-            self.rhai_engine.run_on_draw_hook(lines, &ctx)
-        } else {
-            lines
+    fn press_key(
+        &mut self,
+        _conn: &Connection,
+        _qh: &QueueHandle<Self>,
+        _keyboard: &wl_keyboard::WlKeyboard,
+        _serial: u32,
+        event: KeyEvent,
+    ) {
+        let Some(i) = self.keyboard_focus.as_ref().and_then(|s| self.widget_of(s)) else {
+            return;
         };
-
-        #[cfg(feature = "python-scripting")]
-        let lines = if self.cfg.general.on_draw_python.is_some() {
-            self.python_engine.run_on_draw_hook(lines, &ctx)
+        let widget = &mut self.widgets[i];
+        if widget.filter.handle_key(event.keysym, event.utf8.as_deref()) {
+            widget.scroll_offset = 0.0;
+            widget.scroll_target = None;
+            self.draw(i);
+        } else if let Some(preview) = self.preview.as_mut()
+            && event.keysym == Keysym::g
+        {
+            preview.grid = !preview.grid;
+            self.draw_all();
         } else {
-            lines
-        };
+            let general = &self.cfg.general;
+            let row = general.font_size * general.line_height_factor * general.scale;
+            // A page keeps a row of the previous one in view
+            let page = (self.widgets[i].height as f32 - row).max(row);
+            match event.keysym {
+                Keysym::Up => self.scroll_stepped(i, -row),
+                Keysym::Down => self.scroll_stepped(i, row),
+                Keysym::Page_Up => self.scroll_stepped(i, -page),
+                Keysym::Page_Down => self.scroll_stepped(i, page),
+                Keysym::r => {
+                    // Samples now, however recently the last update ran
+                    self.refreshed_at = None;
+                    self.refresh();
+                    self.draw_all();
+                }
+                // Back with SIGUSR2 or `rustky msg visibility show`
+                Keysym::Escape => self.widgets[i].set_hidden(true),
+                _ => {}
+            }
+        }
+    }
 
-        // Track content height and clamp scroll offset
-        self.content_height = self.renderer.content_height(&lines);
-        let max_scroll = (self.content_height - h as f32).max(0.0);
-        self.scroll_offset = self.scroll_offset.clamp(0.0, max_scroll);
+    fn release_key(
+        &mut self,
+        _conn: &Connection,
+        _qh: &QueueHandle<Self>,
+        _keyboard: &wl_keyboard::WlKeyboard,
+        _serial: u32,
+        _event: KeyEvent,
+    ) {
+    }
 
-        let pixels =
-            self.renderer
-                .render_styled_lines_scroll(&lines, w, h, self.scroll_offset);
+    fn update_modifiers(
+        &mut self,
+        _conn: &Connection,
+        _qh: &QueueHandle<Self>,
+        _keyboard: &wl_keyboard::WlKeyboard,
+        _serial: u32,
+        modifiers: Modifiers,
+        layout: u32,
+    ) {
+        self.modifiers = modifiers;
+        keyboard_layout::seat_group(layout);
+        if let Some(i) = self.keyboard_focus.as_ref().and_then(|s| self.widget_of(s)) {
+            self.settle_press(i);
+        }
+    }
 
-        let (buffer, canvas) = self
-            .pool
-            .create_buffer(w as i32, h as i32, (w * 4) as i32, wl_shm::Format::Argb8888)
-            .expect("failed to create buffer");
+    fn update_keymap(
+        &mut self,
+        _conn: &Connection,
+        _qh: &QueueHandle<Self>,
+        _keyboard: &wl_keyboard::WlKeyboard,
+        keymap: Keymap<'_>,
+    ) {
+        keyboard_layout::seat_keymap(&keymap.as_string());
+    }
+}
 
-        // skia-rs outputs RGBA (premultiplied), wayland ARGB8888 = BGRA in little-endian bytes
-        for (i, chunk) in pixels.chunks_exact(4).enumerate() {
-            let idx = i * 4;
-            if idx + 3 < canvas.len() {
-                canvas[idx] = chunk[2]; // B
-                canvas[idx + 1] = chunk[1]; // G
-                canvas[idx + 2] = chunk[0]; // R
-                canvas[idx + 3] = chunk[3]; // A
+// --- Clipboard via wlr data control ---
+
+impl Dispatch<ZwlrDataControlManagerV1, ()> for RustkyState {
+    fn event(
+        _state: &mut Self,
+        _manager: &ZwlrDataControlManagerV1,
+        _event: <ZwlrDataControlManagerV1 as Proxy>::Event,
+        _data: &(),
+        _conn: &Connection,
+        _qh: &QueueHandle<Self>,
+    ) {
+    }
+}
+
+impl Dispatch<ZwlrDataControlDeviceV1, ()> for RustkyState {
+    fn event(
+        state: &mut Self,
+        device: &ZwlrDataControlDeviceV1,
+        event: zwlr_data_control_device_v1::Event,
+        _data: &(),
+        _conn: &Connection,
+        _qh: &QueueHandle<Self>,
+    ) {
+        match event {
+            zwlr_data_control_device_v1::Event::Selection { id } => state.clipboard_selection(id),
+            // Primary selection (middle-click paste) isn't shown
+            zwlr_data_control_device_v1::Event::PrimarySelection { id: Some(offer) } => {
+                offer.destroy();
             }
+            zwlr_data_control_device_v1::Event::Finished => {
+                device.destroy();
+                state.clipboard_device = None;
+            }
+            _ => {}
         }
+    }
 
-        self.layer
-            .wl_surface()
-            .attach(Some(buffer.wl_buffer()), 0, 0);
-        self.layer
-            .wl_surface()
-            .damage_buffer(0, 0, w as i32, h as i32);
-        self.layer.wl_surface().commit();
+    event_created_child!(RustkyState, ZwlrDataControlDeviceV1, [
+        zwlr_data_control_device_v1::EVT_DATA_OFFER_OPCODE =>
+            (ZwlrDataControlOfferV1, Mutex::new(Vec::<String>::new())),
+    ]);
+}
 
-        self.buffer = Some(buffer);
+/// Collects the MIME types an offer is available in.
+impl Dispatch<ZwlrDataControlOfferV1, Mutex<Vec<String>>> for RustkyState {
+    fn event(
+        _state: &mut Self,
+        _offer: &ZwlrDataControlOfferV1,
+        event: zwlr_data_control_offer_v1::Event,
+        mimes: &Mutex<Vec<String>>,
+        _conn: &Connection,
+        _qh: &QueueHandle<Self>,
+    ) {
+        if let zwlr_data_control_offer_v1::Event::Offer { mime_type } = event {
+            mimes
+                .lock()
+                .unwrap_or_else(|e| e.into_inner())
+                .push(mime_type);
+        }
     }
 }
 
-// --- Seat + Pointer handling for scroll ---
+// --- Idle notification ---
 
-impl SeatHandler for RustkyState {
-    fn seat_state(&mut self) -> &mut SeatState {
-        &mut self.seat_state
+impl Dispatch<ExtIdleNotifierV1, ()> for RustkyState {
+    fn event(
+        _state: &mut Self,
+        _notifier: &ExtIdleNotifierV1,
+        _event: <ExtIdleNotifierV1 as Proxy>::Event,
+        _data: &(),
+        _conn: &Connection,
+        _qh: &QueueHandle<Self>,
+    ) {
     }
+}
 
-    fn new_seat(
-        &mut self,
+impl Dispatch<ExtIdleNotificationV1, ()> for RustkyState {
+    fn event(
+        state: &mut Self,
+        _notification: &ExtIdleNotificationV1,
+        event: ext_idle_notification_v1::Event,
+        _data: &(),
         _conn: &Connection,
         _qh: &QueueHandle<Self>,
-        _seat: wl_seat::WlSeat,
     ) {
+        match event {
+            ext_idle_notification_v1::Event::Idled => state.idle = true,
+            ext_idle_notification_v1::Event::Resumed => {
+                state.idle = false;
+                // Catch up at once rather than at the next tick
+                state.draw_all();
+            }
+            _ => {}
+        }
     }
+}
 
-    fn new_capability(
-        &mut self,
+// --- Fractional scaling ---
+
+impl Dispatch<WpFractionalScaleManagerV1, ()> for RustkyState {
+    fn event(
+        _state: &mut Self,
+        _manager: &WpFractionalScaleManagerV1,
+        _event: <WpFractionalScaleManagerV1 as Proxy>::Event,
+        _data: &(),
         _conn: &Connection,
-        qh: &QueueHandle<Self>,
-        seat: wl_seat::WlSeat,
-        capability: Capability,
+        _qh: &QueueHandle<Self>,
     ) {
-        if capability == Capability::Pointer {
-            let _ = self.seat_state.get_pointer(qh, &seat);
+    }
+}
+
+impl Dispatch<WpFractionalScaleV1, wl_surface::WlSurface> for RustkyState {
+    fn event(
+        state: &mut Self,
+        _fractional_scale: &WpFractionalScaleV1,
+        event: wp_fractional_scale_v1::Event,
+        surface: &wl_surface::WlSurface,
+        _conn: &Connection,
+        _qh: &QueueHandle<Self>,
+    ) {
+        // The scale comes in 120ths
+        if let wp_fractional_scale_v1::Event::PreferredScale { scale } = event
+            && let Some(i) = state.widget_of(surface)
+        {
+            state.set_output_scale(i, scale as f32 / 120.0);
         }
     }
+}
 
-    fn remove_capability(
-        &mut self,
+impl Dispatch<WpViewporter, ()> for RustkyState {
+    fn event(
+        _state: &mut Self,
+        _viewporter: &WpViewporter,
+        _event: <WpViewporter as Proxy>::Event,
+        _data: &(),
         _conn: &Connection,
         _qh: &QueueHandle<Self>,
-        _seat: wl_seat::WlSeat,
-        _capability: Capability,
     ) {
     }
+}
 
-    fn remove_seat(
-        &mut self,
+impl Dispatch<WpViewport, ()> for RustkyState {
+    fn event(
+        _state: &mut Self,
+        _viewport: &WpViewport,
+        _event: <WpViewport as Proxy>::Event,
+        _data: &(),
         _conn: &Connection,
         _qh: &QueueHandle<Self>,
-        _seat: wl_seat::WlSeat,
     ) {
     }
 }
 
-impl PointerHandler for RustkyState {
-    fn pointer_frame(
-        &mut self,
+// --- Focused window via wlr foreign toplevel management ---
+
+impl Dispatch<ZwlrForeignToplevelManagerV1, ()> for RustkyState {
+    fn event(
+        _state: &mut Self,
+        _manager: &ZwlrForeignToplevelManagerV1,
+        _event: zwlr_foreign_toplevel_manager_v1::Event,
+        _data: &(),
         _conn: &Connection,
         _qh: &QueueHandle<Self>,
-        _pointer: &wl_pointer::WlPointer,
-        events: &[PointerEvent],
     ) {
-        for event in events {
-            if let PointerEventKind::Axis {
-                vertical, horizontal: _, ..
-            } = &event.kind
-            {
-                let scroll_amount = vertical.absolute as f32;
-                if scroll_amount.abs() > 0.01 {
-                    self.scroll_offset += scroll_amount;
-                    let max_scroll =
-                        (self.content_height - self.height as f32).max(0.0);
-                    self.scroll_offset = self.scroll_offset.clamp(0.0, max_scroll);
-                    self.draw();
+    }
+
+    event_created_child!(RustkyState, ZwlrForeignToplevelManagerV1, [
+        zwlr_foreign_toplevel_manager_v1::EVT_TOPLEVEL_OPCODE =>
+            (ZwlrForeignToplevelHandleV1, Mutex::new(toplevel::Handle::default())),
+    ]);
+}
+
+impl Dispatch<ZwlrForeignToplevelHandleV1, Mutex<toplevel::Handle>> for RustkyState {
+    fn event(
+        _state: &mut Self,
+        handle: &ZwlrForeignToplevelHandleV1,
+        event: zwlr_foreign_toplevel_handle_v1::Event,
+        data: &Mutex<toplevel::Handle>,
+        _conn: &Connection,
+        _qh: &QueueHandle<Self>,
+    ) {
+        let id = handle.id().protocol_id();
+        let mut data = data.lock().unwrap_or_else(|e| e.into_inner());
+        match event {
+            zwlr_foreign_toplevel_handle_v1::Event::Title { title } => data.window.title = title,
+            zwlr_foreign_toplevel_handle_v1::Event::AppId { app_id } => {
+                data.window.app_id = app_id;
+            }
+            zwlr_foreign_toplevel_handle_v1::Event::State { state } => {
+                data.activated = toplevel::is_activated(&state);
+            }
+            zwlr_foreign_toplevel_handle_v1::Event::Done => {
+                if data.activated {
+                    toplevel::set_focused(id, data.window.clone());
+                } else {
+                    toplevel::unfocus(id);
                 }
             }
+            zwlr_foreign_toplevel_handle_v1::Event::Closed => {
+                toplevel::unfocus(id);
+                handle.destroy();
+            }
+            _ => {}
         }
     }
 }
@@ -390,9 +2682,15 @@ impl CompositorHandler for RustkyState {
         &mut self,
         _conn: &Connection,
         _qh: &QueueHandle<Self>,
-        _surface: &wl_surface::WlSurface,
-        _new_factor: i32,
+        surface: &wl_surface::WlSurface,
+        new_factor: i32,
     ) {
+        // With a viewport, the fractional scale events take over
+        if let Some(i) = self.widget_of(surface)
+            && self.widgets[i].viewport.is_none()
+        {
+            self.set_output_scale(i, new_factor as f32);
+        }
     }
 
     fn transform_changed(
@@ -408,9 +2706,24 @@ impl CompositorHandler for RustkyState {
         &mut self,
         _conn: &Connection,
         _qh: &QueueHandle<Self>,
-        _surface: &wl_surface::WlSurface,
-        _time: u32,
+        surface: &wl_surface::WlSurface,
+        time: u32,
     ) {
+        let Some(i) = self.widget_of(surface) else {
+            return;
+        };
+        let widget = &mut self.widgets[i];
+        widget.frame_pending = false;
+        if std::mem::take(&mut widget.redraw_wanted) {
+            self.draw_now(i);
+        } else if widget.scroll_wanted {
+            self.present_lines(i);
+        }
+        // A redraw that changed nothing commits no frame of its own
+        let widget = &mut self.widgets[i];
+        if widget.animating && !widget.frame_pending {
+            widget.animate(time, &self.qh);
+        }
     }
 
     fn surface_enter(
@@ -467,37 +2780,55 @@ impl LayerShellHandler for RustkyState {
         &mut self,
         _conn: &Connection,
         _qh: &QueueHandle<Self>,
-        _layer: &LayerSurface,
+        layer: &LayerSurface,
     ) {
-        std::process::exit(0);
+        // e.g. its output was unplugged; the others carry on
+        self.widgets.retain(|widget| {
+            if widget.layer.wl_surface() != layer.wl_surface() {
+                return true;
+            }
+            if let Some(token) = widget.animation_timer {
+                self.loop_handle.remove(token);
+            }
+            false
+        });
+        if self.widgets.is_empty() {
+            std::process::exit(0);
+        }
     }
 
     fn configure(
         &mut self,
         _conn: &Connection,
         _qh: &QueueHandle<Self>,
-        _layer: &LayerSurface,
+        layer: &LayerSurface,
         configure: LayerSurfaceConfigure,
         _serial: u32,
     ) {
-        self.width = if configure.new_size.0 > 0 {
+        let Some(i) = self.widget_of(layer.wl_surface()) else {
+            return;
+        };
+        let widget = &mut self.widgets[i];
+        let window = widget.window(&self.cfg);
+        let (width, height) = window.scaled_size(self.cfg.general.scale);
+        widget.width = if configure.new_size.0 > 0 {
             configure.new_size.0
         } else {
-            self.cfg.window.width
+            width
         };
-        self.height = if configure.new_size.1 > 0 {
+        widget.height = if configure.new_size.1 > 0 {
             configure.new_size.1
         } else {
-            self.cfg.window.height
+            height
         };
 
-        let needed = (self.width * self.height * 4) as usize;
-        if self.pool.len() < needed {
-            self.pool.resize(needed).expect("failed to resize pool");
-        }
-
-        self.configured = true;
-        self.draw();
+        widget.reserve_buffer();
+        // Follows the new size; committed with the frame below
+        widget.apply_slide(&self.cfg);
+        // A configure wants a commit in reply, even for an unchanged frame
+        widget.last_frame = None;
+        widget.configured = true;
+        self.draw_now(i);
     }
 }
 
@@ -514,10 +2845,52 @@ impl ProvidesRegistryState for RustkyState {
     registry_handlers![OutputState, SeatState];
 }
 
+impl OutputHandler for OutputProbe {
+    fn output_state(&mut self) -> &mut OutputState {
+        &mut self.output
+    }
+
+    fn new_output(
+        &mut self,
+        _conn: &Connection,
+        _qh: &QueueHandle<Self>,
+        _output: wl_output::WlOutput,
+    ) {
+    }
+
+    fn update_output(
+        &mut self,
+        _conn: &Connection,
+        _qh: &QueueHandle<Self>,
+        _output: wl_output::WlOutput,
+    ) {
+    }
+
+    fn output_destroyed(
+        &mut self,
+        _conn: &Connection,
+        _qh: &QueueHandle<Self>,
+        _output: wl_output::WlOutput,
+    ) {
+    }
+}
+
+impl ProvidesRegistryState for OutputProbe {
+    fn registry(&mut self) -> &mut RegistryState {
+        &mut self.registry
+    }
+    registry_handlers![OutputState];
+}
+
+delegate_output!(OutputProbe);
+delegate_registry!(OutputProbe);
+
 delegate_compositor!(RustkyState);
 delegate_output!(RustkyState);
 delegate_layer!(RustkyState);
 delegate_shm!(RustkyState);
 delegate_seat!(RustkyState);
 delegate_pointer!(RustkyState);
+delegate_keyboard!(RustkyState);
+delegate_touch!(RustkyState);
 delegate_registry!(RustkyState);
@@ -0,0 +1,139 @@
+use std::collections::HashMap;
+use std::sync::{Mutex, OnceLock};
+use std::time::Duration;
+
+use serde::Deserialize;
+
+use crate::background::Poller;
+use crate::config::Units;
+use crate::http;
+
+const ENDPOINT: &str = "https://api.open-meteo.com/v1/forecast";
+
+#[derive(Debug, Clone)]
+pub struct Forecast {
+    pub temperature: f64,
+    pub weather_code: u32,
+    pub wind_speed: f64,
+    pub days: Vec<Day>,
+}
+
+#[derive(Debug, Clone)]
+pub struct Day {
+    /// ISO date, e.g. "2026-10-16".
+    pub date: String,
+    pub weather_code: u32,
+    pub min: f64,
+    pub max: f64,
+}
+
+#[derive(Deserialize)]
+struct Response {
+    current: Current,
+    daily: Option<Daily>,
+}
+
+#[derive(Deserialize)]
+struct Current {
+    temperature_2m: f64,
+    weather_code: u32,
+    wind_speed_10m: f64,
+}
+
+#[derive(Deserialize)]
+struct Daily {
+    time: Vec<String>,
+    weather_code: Vec<u32>,
+    temperature_2m_min: Vec<f64>,
+    temperature_2m_max: Vec<f64>,
+}
+
+type Registry = Mutex<HashMap<String, Poller<Forecast>>>;
+
+static POLLERS: OnceLock<Registry> = OnceLock::new();
+
+/// Latest forecast for the location, fetched every `interval` on a
+/// background thread. A failed fetch keeps the previous forecast.
+pub fn forecast(
+    latitude: f64,
+    longitude: f64,
+    units: Units,
+    days: usize,
+    interval: Duration,
+) -> Option<Result<Forecast, String>> {
+    let key = format!("{latitude},{longitude},{units:?},{days}");
+    let mut pollers = POLLERS
+        .get_or_init(Default::default)
+        .lock()
+        .unwrap_or_else(|e| e.into_inner());
+    pollers
+        .entry(key)
+        .or_insert_with(|| {
+            Poller::spawn_cached(interval, "weather", move || {
+                fetch(latitude, longitude, units, days)
+            })
+        })
+        .latest()
+}
+
+fn fetch(latitude: f64, longitude: f64, units: Units, days: usize) -> Result<Forecast, String> {
+    let mut url = format!(
+        "{ENDPOINT}?latitude={latitude}&longitude={longitude}\
+         &current=temperature_2m,weather_code,wind_speed_10m&timezone=auto"
+    );
+    if days > 0 {
+        url.push_str(&format!(
+            "&daily=weather_code,temperature_2m_min,temperature_2m_max&forecast_days={days}"
+        ));
+    }
+    if units == Units::Imperial {
+        url.push_str("&temperature_unit=fahrenheit&wind_speed_unit=mph");
+    }
+
+    let response: Response =
+        serde_json::from_str(&http::get(&url)?).map_err(|e| format!("open-meteo: {e}"))?;
+
+    let days = match response.daily {
+        Some(d) => d
+            .time
+            .into_iter()
+            .zip(d.weather_code)
+            .zip(d.temperature_2m_min.into_iter().zip(d.temperature_2m_max))
+            .map(|((date, weather_code), (min, max))| Day {
+                date,
+                weather_code,
+                min,
+                max,
+            })
+            .collect(),
+        None => Vec::new(),
+    };
+    Ok(Forecast {
+        temperature: response.current.temperature_2m,
+        weather_code: response.current.weather_code,
+        wind_speed: response.current.wind_speed_10m,
+        days,
+    })
+}
+
+/// Emoji and short description for a WMO weather interpretation code.
+pub fn describe(code: u32) -> (&'static str, &'static str) {
+    match code {
+        0 => ("☀️", "Clear"),
+        1 => ("🌤️", "Mainly clear"),
+        2 => ("⛅", "Partly cloudy"),
+        3 => ("☁️", "Overcast"),
+        45 | 48 => ("🌫️", "Fog"),
+        51 | 53 | 55 => ("🌦️", "Drizzle"),
+        56 | 57 => ("🌧️", "Freezing drizzle"),
+        61 | 63 | 65 => ("🌧️", "Rain"),
+        66 | 67 => ("🌧️", "Freezing rain"),
+        71 | 73 | 75 => ("🌨️", "Snow"),
+        77 => ("🌨️", "Snow grains"),
+        80..=82 => ("🌦️", "Showers"),
+        85 | 86 => ("🌨️", "Snow showers"),
+        95 => ("⛈️", "Thunderstorm"),
+        96 | 99 => ("⛈️", "Thunderstorm, hail"),
+        _ => ("❔", "Unknown"),
+    }
+}
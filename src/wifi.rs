@@ -0,0 +1,87 @@
+use std::collections::HashMap;
+use std::process::Command;
+use std::sync::{Mutex, OnceLock};
+use std::time::Duration;
+
+use crate::background::Poller;
+
+const POLL_INTERVAL: Duration = Duration::from_secs(5);
+
+#[derive(Debug, Clone, Default)]
+pub struct Link {
+    /// `None` while the interface is not associated.
+    pub ssid: Option<String>,
+    pub signal_dbm: Option<i32>,
+    pub freq_mhz: Option<f64>,
+    /// Transmit bitrate in Mb/s.
+    pub bitrate_mbps: Option<f64>,
+}
+
+impl Link {
+    /// Rough 0–100 quality from the signal, -100 dBm → 0, -50 dBm → 100.
+    pub fn quality_pct(&self) -> Option<u32> {
+        self.signal_dbm
+            .map(|dbm| ((dbm + 100) * 2).clamp(0, 100) as u32)
+    }
+}
+
+static POLLERS: OnceLock<Mutex<HashMap<String, Poller<Link>>>> = OnceLock::new();
+
+/// Link state of `interface`, polled via `iw` in the background.
+pub fn link(interface: &str) -> Option<Result<Link, String>> {
+    let mut pollers = POLLERS
+        .get_or_init(Default::default)
+        .lock()
+        .unwrap_or_else(|e| e.into_inner());
+    pollers
+        .entry(interface.to_string())
+        .or_insert_with(|| {
+            let interface = interface.to_string();
+            Poller::spawn(POLL_INTERVAL, move || query(&interface))
+        })
+        .latest()
+}
+
+/// The first interface listed in `/proc/net/wireless`.
+pub fn default_interface() -> Option<String> {
+    let wireless = std::fs::read_to_string("/proc/net/wireless").ok()?;
+    // Two header lines, then "wlan0: 0000   56.  -54.  -256 …"
+    wireless
+        .lines()
+        .skip(2)
+        .find_map(|l| Some(l.split(':').next()?.trim().to_string()))
+        .filter(|i| !i.is_empty())
+}
+
+fn query(interface: &str) -> Result<Link, String> {
+    let output = Command::new("iw")
+        .args(["dev", interface, "link"])
+        .output()
+        .map_err(|e| format!("iw: {e}"))?;
+    if !output.status.success() {
+        return Err(format!(
+            "iw: {}",
+            String::from_utf8_lossy(&output.stderr).trim()
+        ));
+    }
+    Ok(parse_link(&String::from_utf8_lossy(&output.stdout)))
+}
+
+/// Parses `iw dev <if> link`; "Not connected." yields an empty link.
+fn parse_link(text: &str) -> Link {
+    let mut link = Link::default();
+    for line in text.lines() {
+        let Some((key, value)) = line.trim().split_once(": ") else {
+            continue;
+        };
+        let number = || value.split_whitespace().next()?.parse::<f64>().ok();
+        match key {
+            "SSID" => link.ssid = Some(value.to_string()),
+            "signal" => link.signal_dbm = number().map(|v| v as i32),
+            "freq" => link.freq_mhz = number(),
+            "tx bitrate" => link.bitrate_mbps = number(),
+            _ => {}
+        }
+    }
+    link
+}